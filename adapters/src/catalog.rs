@@ -1,6 +1,8 @@
-use crate::{DeCollectionHandle, DeZSetHandle, SerOutputBatchHandle};
-use dbsp::{algebra::ZRingValue, CollectionHandle, DBData, DBWeight};
-use serde::Deserialize;
+use crate::{
+    seroutput::MaterializedOutputHandle, DeCollectionHandle, DeZSetHandle, SerOutputBatchHandle,
+};
+use dbsp::{algebra::ZRingValue, trace::Batch, CollectionHandle, DBData, DBWeight, OutputHandle};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 /// A catalog of input and output stream handles of a circuit.
@@ -41,6 +43,9 @@ impl Catalog {
     }
 
     /// Add a named output stream handle to the catalog.
+    ///
+    /// Use [`Self::register_materialized_output_batch_handle`] to also keep
+    /// the current contents of the view computed by the stream.
     pub fn register_output_batch_handle<H>(&mut self, name: &str, handle: H)
     where
         H: SerOutputBatchHandle + 'static,
@@ -49,6 +54,32 @@ impl Catalog {
             .insert(name.to_owned(), Box::new(handle));
     }
 
+    /// Add a named output stream handle to the catalog and maintain the
+    /// integral of the stream.
+    ///
+    /// In addition to per-step output batches, the handle keeps the current
+    /// contents of the view computed by the stream, which can be queried
+    /// via [`Controller::snapshot`](`crate::Controller::snapshot`).
+    ///
+    /// This is a separate method rather than an option of
+    /// [`Self::register_output_batch_handle`], because maintaining the
+    /// integral requires the concrete [`OutputHandle`] in order to merge
+    /// output batches and to deserialize key bounds into the key type of the
+    /// stream, while `register_output_batch_handle` accepts any
+    /// type-erased [`SerOutputBatchHandle`].
+    pub fn register_materialized_output_batch_handle<B>(
+        &mut self,
+        name: &str,
+        handle: OutputHandle<B>,
+    ) where
+        B: Batch<Time = ()> + Send + Sync,
+        B::Key: Serialize + DeserializeOwned,
+        B::Val: Serialize,
+        B::R: Into<i64>,
+    {
+        self.register_output_batch_handle(name, MaterializedOutputHandle::new(handle));
+    }
+
     /// Look up an input stream handle by name.
     pub fn input_collection_handle(&self, name: &str) -> Option<&dyn DeCollectionHandle> {
         self.input_collection_handles.get(name).map(|b| &**b)
@@ -58,4 +89,11 @@ impl Catalog {
     pub fn output_batch_handle(&self, name: &str) -> Option<&dyn SerOutputBatchHandle> {
         self.output_batch_handles.get(name).map(|b| &**b)
     }
+
+    /// Iterate over all output stream handles in the catalog.
+    pub fn output_batch_handles(&self) -> impl Iterator<Item = (&str, &dyn SerOutputBatchHandle)> {
+        self.output_batch_handles
+            .iter()
+            .map(|(name, handle)| (name.as_str(), &**handle))
+    }
 }
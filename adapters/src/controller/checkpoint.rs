@@ -0,0 +1,113 @@
+//! Pipeline checkpoints.
+//!
+//! A checkpoint captures the state of the circuit after a step, along with
+//! the positions of all input endpoints (see
+//! [`InputEndpoint::position`](`crate::InputEndpoint::position`)).  Endpoints
+//! that don't track their position are recorded with a `null` position;
+//! a pipeline that includes such endpoints cannot be restored from the
+//! checkpoint.  The checkpoint is stored as a directory with two files:
+//!
+//! * `metadata.json` - the number of steps evaluated by the circuit and input
+//!   endpoint positions, indexed by endpoint name.
+//!
+//! * `circuit.bin` - serialized circuit state (see [`CircuitCheckpoint`]).
+
+use crate::{ControllerError, Step};
+use anyhow::{Error as AnyError, Result as AnyResult};
+use dbsp::CircuitCheckpoint;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::{
+    collections::BTreeMap,
+    fs::{create_dir_all, read, remove_dir_all, rename, write},
+    path::Path,
+};
+
+const METADATA_FILE: &str = "metadata.json";
+const CIRCUIT_FILE: &str = "circuit.bin";
+
+/// Checkpoint metadata stored in `metadata.json`.
+#[derive(Serialize, Deserialize)]
+struct CheckpointMetadata {
+    /// The number of steps evaluated by the circuit at the time of the
+    /// checkpoint.
+    step: Step,
+
+    /// Positions of input endpoints at the time of the checkpoint.
+    input_positions: BTreeMap<String, JsonValue>,
+}
+
+/// Pipeline checkpoint.
+pub(crate) struct Checkpoint {
+    /// The number of steps evaluated by the circuit.
+    pub step: Step,
+
+    /// Input endpoint positions, indexed by endpoint name.
+    pub input_positions: BTreeMap<String, JsonValue>,
+
+    /// Circuit state.
+    pub circuit: CircuitCheckpoint,
+}
+
+impl Checkpoint {
+    /// Write checkpoint to directory `path`.
+    ///
+    /// The checkpoint is first written to a temporary directory, which then
+    /// replaces `path`, so that an existing checkpoint at `path` does not get
+    /// corrupted if the process fails while writing the checkpoint.
+    pub fn write(&self, path: &Path) -> AnyResult<()> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = Path::new(&tmp_path);
+
+        if tmp_path.exists() {
+            remove_dir_all(tmp_path)?;
+        }
+        create_dir_all(tmp_path)?;
+
+        let metadata = CheckpointMetadata {
+            step: self.step,
+            input_positions: self.input_positions.clone(),
+        };
+        write(
+            tmp_path.join(METADATA_FILE),
+            serde_json::to_vec_pretty(&metadata)?,
+        )?;
+        write(
+            tmp_path.join(CIRCUIT_FILE),
+            self.circuit
+                .to_bytes()
+                .map_err(ControllerError::dbsp_error)?,
+        )?;
+
+        if path.exists() {
+            remove_dir_all(path)?;
+        }
+        rename(tmp_path, path)?;
+
+        Ok(())
+    }
+
+    /// Read checkpoint from directory `path`.
+    pub fn read(path: &Path) -> AnyResult<Self> {
+        Self::read_files(path).map_err(|e| {
+            AnyError::msg(format!(
+                "error reading checkpoint from '{}': {e}",
+                path.display()
+            ))
+        })
+    }
+
+    fn read_files(path: &Path) -> AnyResult<Self> {
+        let metadata: CheckpointMetadata =
+            serde_json::from_slice(&read(path.join(METADATA_FILE))?)?;
+        let circuit = CircuitCheckpoint::from_bytes(&read(path.join(CIRCUIT_FILE))?)
+            .map_err(ControllerError::dbsp_error)?;
+
+        Ok(Self {
+            step: metadata.step,
+            input_positions: metadata.input_positions,
+            circuit,
+        })
+    }
+}
@@ -7,7 +7,7 @@
 
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value as YamlValue;
-use std::{borrow::Cow, collections::BTreeMap};
+use std::{borrow::Cow, collections::BTreeMap, path::PathBuf};
 
 /// Default value of `InputEndpointConfig::max_buffered_records`.
 /// It is declared as a function and not as a constant, so it can
@@ -49,6 +49,26 @@ pub struct GlobalControllerConfig {
     /// get buffered by the controller, defaults to 0.
     #[serde(default)]
     pub max_buffering_delay_usecs: u64,
+
+    /// Checkpoint to resume from.
+    ///
+    /// Path to a checkpoint directory created by
+    /// [`Controller::checkpoint`](`crate::Controller::checkpoint`).  When
+    /// specified, the controller restores the state of the circuit from the
+    /// checkpoint and moves input endpoints to the positions recorded in the
+    /// checkpoint before starting to ingest data.  Fails if an input
+    /// endpoint included in the checkpoint cannot resume from its recorded
+    /// position.
+    #[serde(default)]
+    pub restore_from: Option<PathBuf>,
+
+    /// Enable CPU profiling of the circuit.
+    ///
+    /// When `true`, per-operator CPU usage is reported by
+    /// [`Controller::cpu_profile`](`crate::Controller::cpu_profile`).  CPU
+    /// profiling introduces small runtime overhead.  Defaults to `false`.
+    #[serde(default)]
+    pub cpu_profiler: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -70,6 +90,35 @@ pub struct InputEndpointConfig {
     /// The default is 1 million.
     #[serde(default = "default_max_buffered_records")]
     pub max_buffered_records: u64,
+
+    /// What to do with records that the parser fails to parse.
+    ///
+    /// The default is to skip invalid records, reporting each parse error
+    /// without pausing the pipeline.
+    #[serde(default)]
+    pub on_error: InputErrorPolicy,
+}
+
+/// Policy for handling records that an input endpoint fails to parse.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputErrorPolicy {
+    /// Report the error and pause the pipeline.
+    Fail,
+
+    /// Report the error and drop invalid records.  The number of dropped
+    /// records is reported in the endpoint's `num_rejected_records` metric.
+    #[default]
+    Skip,
+
+    /// Drop invalid records, sending each of them along with the error
+    /// message to the specified output transport endpoint.
+    ///
+    /// Rejected records are written as one JSON object per line, of the form
+    /// `{"endpoint": <input endpoint name>, "error": <error message>,
+    /// "record": <raw record>}`.  The number of rejected records is
+    /// reported in the endpoint's `num_rejected_records` metric.
+    DeadLetter(TransportConfig),
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -89,6 +138,39 @@ pub struct OutputEndpointConfig {
     /// The default is 1 million.
     #[serde(default = "default_max_buffered_records")]
     pub max_buffered_records: u64,
+
+    /// Consolidate the outputs of all worker threads into a single batch
+    /// before encoding them.
+    ///
+    /// By default, the output of each worker is encoded separately, so that
+    /// updates to the same record produced by different workers can appear
+    /// in different parts of the output.  Defaults to `false`.
+    #[serde(default)]
+    pub consolidate: bool,
+
+    /// Minimal number of output records to merge before encoding.
+    ///
+    /// The endpoint merges the outputs of consecutive steps until at least
+    /// `min_batch_size_records` records have been accumulated or
+    /// `max_buffering_delay_usecs` microseconds have passed since the first
+    /// of them was produced.  Defaults to 0.
+    #[serde(default)]
+    pub min_batch_size_records: u64,
+
+    /// Maximal delay in microseconds to wait for `min_batch_size_records` to
+    /// get accumulated by the endpoint.  Defaults to 0, i.e., the output of
+    /// each step is encoded separately.
+    #[serde(default)]
+    pub max_buffering_delay_usecs: u64,
+
+    /// Drop updates that cancel each other out.
+    ///
+    /// When `true`, the outputs of all workers and all merged steps are
+    /// consolidated into a single batch, so that, e.g., a record inserted
+    /// and deleted within merged steps does not appear in the output.
+    /// Implies `consolidate`.  Defaults to `false`.
+    #[serde(default)]
+    pub drop_net_zero: bool,
 }
 
 /// Transport endpoint configuration.
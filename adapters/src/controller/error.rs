@@ -14,6 +14,12 @@ pub enum ConfigError {
     /// Output endpoint with this name already exists.
     DuplicateOutputEndpoint { endpoint_name: String },
 
+    /// Input endpoint with this name does not exist.
+    UnknownInputEndpoint { endpoint_name: String },
+
+    /// Output endpoint with this name does not exist.
+    UnknownOutputEndpoint { endpoint_name: String },
+
     /// An output stream cannot be connected to multiple output endpoints.
     DuplicateOutputStreamConsumer {
         stream_name: String,
@@ -44,6 +50,9 @@ impl Display for ConfigError {
             Self::DuplicateInputEndpoint { endpoint_name } => {
                 write!(f, "input endpoint '{endpoint_name}' already exists")
             }
+            Self::UnknownInputEndpoint { endpoint_name } => {
+                write!(f, "unknown input endpoint '{endpoint_name}'")
+            }
             Self::UnknownInputFormat { format_name } => {
                 write!(f, "unknown input format '{format_name}'")
             }
@@ -53,6 +62,9 @@ impl Display for ConfigError {
             Self::DuplicateOutputEndpoint { endpoint_name } => {
                 write!(f, "output endpoint '{endpoint_name}' already exists")
             }
+            Self::UnknownOutputEndpoint { endpoint_name } => {
+                write!(f, "unknown output endpoint '{endpoint_name}'")
+            }
             Self::DuplicateOutputStreamConsumer {
                 stream_name,
                 endpoint_name1,
@@ -80,6 +92,12 @@ impl ConfigError {
         }
     }
 
+    pub fn unknown_input_endpoint(endpoint_name: &str) -> Self {
+        Self::UnknownInputEndpoint {
+            endpoint_name: endpoint_name.to_owned(),
+        }
+    }
+
     pub fn unknown_input_format(format_name: &str) -> Self {
         Self::UnknownInputFormat {
             format_name: format_name.to_owned(),
@@ -98,6 +116,12 @@ impl ConfigError {
         }
     }
 
+    pub fn unknown_output_endpoint(endpoint_name: &str) -> Self {
+        Self::UnknownOutputEndpoint {
+            endpoint_name: endpoint_name.to_owned(),
+        }
+    }
+
     pub fn duplicate_output_stream_consumer(
         stream_name: &str,
         endpoint_name1: &str,
@@ -235,6 +259,12 @@ impl ControllerError {
         }
     }
 
+    pub fn unknown_input_endpoint(endpoint_name: &str) -> Self {
+        Self::Config {
+            config_error: ConfigError::unknown_input_endpoint(endpoint_name),
+        }
+    }
+
     pub fn unknown_input_format(format_name: &str) -> Self {
         Self::Config {
             config_error: ConfigError::unknown_input_format(format_name),
@@ -253,6 +283,12 @@ impl ControllerError {
         }
     }
 
+    pub fn unknown_output_endpoint(endpoint_name: &str) -> Self {
+        Self::Config {
+            config_error: ConfigError::unknown_output_endpoint(endpoint_name),
+        }
+    }
+
     pub fn duplicate_output_stream_consumer(
         stream_name: &str,
         endpoint_name1: &str,
@@ -31,43 +31,66 @@
 //!
 //! The probe passes the data through to the parser, while counting the number
 //! of transmitted bytes and records and updating respective performance
-//! counters in the controller.
+//! counters in the controller.  The probe also handles records that the
+//! parser fails to parse according to the endpoint's [`InputErrorPolicy`].
+//!
+//! Once a step has been evaluated and the output batches it produced have
+//! been pushed to all output endpoints, the controller notifies input
+//! endpoints via [`InputEndpoint::commit`], allowing endpoints that support
+//! replay (e.g., Kafka) to durably record their input position.
+//!
+//! The same mechanism is used to checkpoint the pipeline (see
+//! [`Controller::checkpoint`]): the circuit thread serializes the state of
+//! the circuit between steps, and the checkpoint is complete once the outputs
+//! of all steps it includes have been pushed to output endpoints.
 
 use crate::{
-    Catalog, Encoder, InputConsumer, InputEndpoint, InputFormat, InputTransport, OutputConsumer,
-    OutputEndpoint, OutputFormat, OutputTransport, Parser, PipelineState, SerBatch,
-    SerOutputBatchHandle,
+    Catalog, Encoder, InputConsumer, InputEndpoint, InputFormat, InputTransport, KeyFilter,
+    OutputConsumer, OutputEndpoint, OutputFormat, OutputTransport, Parser, PipelineState, SerBatch,
+    SerOutputBatchHandle, Step,
 };
 use anyhow::{Error as AnyError, Result as AnyResult};
 use crossbeam::{
+    channel::{bounded, Receiver, RecvTimeoutError, Sender},
     queue::SegQueue,
     sync::{Parker, ShardedLock, Unparker},
 };
-use dbsp::DBSPHandle;
+use dbsp::{profile::OperatorProfile, DBSPHandle};
 use num_traits::FromPrimitive;
+use serde_json::Value as JsonValue;
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, HashSet, VecDeque},
+    mem::take,
+    path::Path,
     sync::{
-        atomic::{AtomicU32, Ordering},
-        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
     },
     thread::{spawn, JoinHandle},
     time::{Duration, Instant},
 };
 
+mod checkpoint;
 mod config;
 mod error;
 mod stats;
 
 pub use config::{
-    ControllerConfig, FormatConfig, GlobalControllerConfig, InputEndpointConfig,
-    OutputEndpointConfig,
+    ControllerConfig, FormatConfig, GlobalControllerConfig, InputEndpointConfig, InputErrorPolicy,
+    OutputEndpointConfig, TransportConfig,
 };
-pub use error::ControllerError;
-pub use stats::ControllerStatus;
+pub use error::{ConfigError, ControllerError};
+pub use stats::{
+    ControllerStatus, InputEndpointMetrics, OutputEndpointMetrics, STEP_LATENCY_BUCKETS_USECS,
+};
+
+use checkpoint::Checkpoint;
 
 type EndpointId = u64;
 
+/// Per-worker CPU profile of the circuit (see [`Controller::cpu_profile`]).
+type CpuProfile = Vec<Vec<OperatorProfile>>;
+
 /// Controller that coordinates the creation, reconfiguration, teardown of
 /// input/output adapters, and implements runtime flow control.
 ///
@@ -114,8 +137,13 @@ impl Controller {
     ///   transport or data format.
     ///
     /// * One or more of the endpoints fails to initialize.
+    ///
+    /// * The checkpoint specified in `config.global.restore_from` cannot be
+    ///   read or does not match the circuit, or an input endpoint fails to
+    ///   move to the position recorded in the checkpoint, e.g., because the
+    ///   endpoint does not support resuming from a checkpoint.
     pub fn with_config(
-        circuit: DBSPHandle,
+        mut circuit: DBSPHandle,
         catalog: Catalog,
         config: &ControllerConfig,
         error_cb: Box<dyn Fn(ControllerError) + Send + Sync>,
@@ -126,9 +154,28 @@ impl Controller {
         let backpressure_thread_parker = Parker::new();
         let backpressure_thread_unparker = backpressure_thread_parker.unparker().clone();
 
+        let checkpoint = config
+            .global
+            .restore_from
+            .as_deref()
+            .map(Checkpoint::read)
+            .transpose()?;
+        if let Some(checkpoint) = &checkpoint {
+            circuit
+                .restore(&checkpoint.circuit)
+                .map_err(ControllerError::dbsp_error)?;
+        }
+
+        if config.global.cpu_profiler {
+            circuit
+                .enable_cpu_profiler()
+                .map_err(ControllerError::dbsp_error)?;
+        }
+
         let inner = Arc::new(ControllerInner::new(
             catalog,
             &config.global,
+            checkpoint.as_ref().map_or(0, |checkpoint| checkpoint.step),
             circuit_thread_unparker,
             backpressure_thread_unparker,
             error_cb,
@@ -146,6 +193,13 @@ impl Controller {
 
         for (input_name, input_config) in config.inputs.iter() {
             inner.connect_input(input_name, input_config)?;
+
+            if let Some(position) = checkpoint
+                .as_ref()
+                .and_then(|checkpoint| checkpoint.input_positions.get(input_name.as_ref()))
+            {
+                inner.seek_input(input_name, position)?;
+            }
         }
 
         for (output_name, output_config) in config.outputs.iter() {
@@ -182,6 +236,97 @@ impl Controller {
         self.inner.connect_input(endpoint_name, config)
     }
 
+    /// Connect a new input endpoint created by the caller.
+    ///
+    /// Similar to [`Self::connect_input`], but instead of looking up the
+    /// transport specified in `config`, invokes `create_endpoint` to
+    /// instantiate the endpoint.  `create_endpoint` receives the input
+    /// consumer the endpoint must push data to.  This is used to connect
+    /// endpoints that are managed outside of the controller, e.g., HTTP
+    /// endpoints driven by the server.
+    pub fn connect_input_with<F>(
+        &self,
+        endpoint_name: &str,
+        config: &InputEndpointConfig,
+        create_endpoint: F,
+    ) -> AnyResult<()>
+    where
+        F: FnOnce(Box<dyn InputConsumer>) -> AnyResult<Box<dyn InputEndpoint>>,
+    {
+        self.inner
+            .connect_input_with(endpoint_name, config, create_endpoint)
+    }
+
+    /// Disconnect an input endpoint.
+    ///
+    /// Stops receiving data from the endpoint and destroys it.  Records the
+    /// endpoint has already pushed to the circuit are not discarded and are
+    /// processed by the next step of the circuit.  The endpoint is destroyed
+    /// once this step has been committed, so that it can record the
+    /// position of its last input (e.g., commit Kafka offsets).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the endpoint does not exist.
+    pub fn disconnect_input(&self, endpoint_name: &str) -> AnyResult<()> {
+        self.inner.disconnect_input(endpoint_name)
+    }
+
+    /// Connect a new output endpoint with specified name and configuration.
+    ///
+    /// Creates an endpoint with data transport and format specified by
+    /// `config`.  The endpoint receives outputs of all steps evaluated
+    /// after it has been connected.
+    ///
+    /// # Errors
+    ///
+    /// The method may fail for the following reasons:
+    ///
+    /// * The endpoint configuration is invalid, e.g., specifies an unknown
+    ///   transport, data format, or output stream.
+    ///
+    /// * The output stream is already connected to another endpoint.
+    ///
+    /// * The endpoint fails to initialize.
+    pub fn connect_output(
+        &self,
+        endpoint_name: &str,
+        config: &OutputEndpointConfig,
+    ) -> AnyResult<()> {
+        self.inner.connect_output(endpoint_name, config)
+    }
+
+    /// Connect a new output endpoint created by the caller.
+    ///
+    /// Similar to [`Self::connect_input_with`], but for output endpoints.
+    /// `create_endpoint` receives the callback the endpoint must use to
+    /// report asynchronous errors (see [`OutputTransport::new_endpoint`]).
+    pub fn connect_output_with<F>(
+        &self,
+        endpoint_name: &str,
+        config: &OutputEndpointConfig,
+        create_endpoint: F,
+    ) -> AnyResult<()>
+    where
+        F: FnOnce(Box<dyn Fn(bool, AnyError) + Send + Sync>) -> AnyResult<Box<dyn OutputEndpoint>>,
+    {
+        self.inner
+            .connect_output_with(endpoint_name, config, create_endpoint)
+    }
+
+    /// Disconnect an output endpoint.
+    ///
+    /// The circuit stops sending outputs to the endpoint.  Blocks until
+    /// output batches already queued for the endpoint have been pushed to it
+    /// before destroying the endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the endpoint does not exist.
+    pub fn disconnect_output(&self, endpoint_name: &str) -> AnyResult<()> {
+        self.inner.disconnect_output(endpoint_name)
+    }
+
     /// Change the state of all input endpoints to running.
     ///
     /// Start streaming data through all connected input endpoints.
@@ -199,11 +344,124 @@ impl Controller {
         self.inner.pause();
     }
 
+    /// Checkpoint the pipeline to directory `path`.
+    ///
+    /// Pauses input endpoints, waits for the circuit to finish the current
+    /// step and to consume inputs already buffered by the controller, and
+    /// waits for the outputs of all evaluated steps to be pushed to output
+    /// endpoints.  Writes the state of the circuit, along with the positions
+    /// of input endpoints, to `path`, replacing any existing checkpoint at
+    /// this location.  Resumes the pipeline afterwards if it was running.
+    ///
+    /// Returns the number of steps included in the checkpoint.  To resume
+    /// from the checkpoint, create a new controller for an identical circuit
+    /// with `restore_from` set to `path` in the global config.  Input
+    /// endpoints that don't track their position (see
+    /// [`InputEndpoint::seek`]) must be removed from the config or renamed,
+    /// in which case they ingest their input from the start.
+    ///
+    /// # Errors
+    ///
+    /// Fails if some of the operators in the circuit do not support
+    /// checkpointing or if the checkpoint cannot be written to `path`.
+    pub fn checkpoint(&self, path: &Path) -> AnyResult<Step> {
+        let running = self.inner.state() == PipelineState::Running;
+        self.inner.pause();
+
+        let result = self.inner.checkpoint().and_then(|checkpoint| {
+            checkpoint.write(path)?;
+            Ok(checkpoint.step)
+        });
+
+        if running {
+            self.inner.start();
+        }
+
+        result
+    }
+
+    /// Returns the CPU usage of circuit operators in each worker thread.
+    ///
+    /// The `i`th element of the returned vector contains the profile of
+    /// worker `i`.  Profiles are empty unless CPU profiling is enabled in the
+    /// controller config (see [`GlobalControllerConfig::cpu_profiler`]).
+    pub fn cpu_profile(&self) -> AnyResult<Vec<Vec<OperatorProfile>>> {
+        self.request_cpu_profile().wait()
+    }
+
+    /// Request the CPU profile of the circuit without waiting for it (see
+    /// [`Controller::cpu_profile`]).
+    ///
+    /// The returned request does not borrow the controller, so the caller can
+    /// release any lock protecting the controller before waiting for the
+    /// circuit thread to serve it.
+    pub(crate) fn request_cpu_profile(&self) -> CpuProfileRequest {
+        self.inner.request_cpu_profile()
+    }
+
+    /// Wait until step number `step` has been evaluated by the circuit and
+    /// its outputs have been pushed to all output endpoints.
+    ///
+    /// Steps are numbered sequentially starting from 0.  The number of the
+    /// next step is reported in the `step` global metric (see
+    /// [`ControllerStatus`]).  Inputs received before the circuit starts
+    /// step `n` are reflected in the outputs of step `n` or earlier; hence,
+    /// once the step number read after pushing inputs to the controller
+    /// completes, the outputs reflect these inputs.
+    ///
+    /// Waiting does not force the circuit to evaluate steps without new
+    /// inputs.  If the step hasn't been evaluated yet, the circuit consumes
+    /// the inputs buffered by the controller right away, without waiting for
+    /// more inputs to arrive.  If `step` is the next step to be evaluated and
+    /// the circuit has already consumed all inputs received so far in
+    /// committed steps, the outputs already reflect all inputs received
+    /// before the step, and the method returns without waiting for the step.
+    /// Waits indefinitely if `timeout` is `None`.
+    ///
+    /// Returns `true` if the step has been completed or `false` if `timeout`
+    /// expired first.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the pipeline terminates before the step is completed.
+    pub fn wait_for_step(&self, step: Step, timeout: Option<Duration>) -> AnyResult<bool> {
+        let num_steps = step
+            .checked_add(1)
+            .ok_or_else(|| AnyError::msg(format!("invalid step number {step}")))?;
+        self.inner.wait_for_committed_steps(num_steps, timeout)
+    }
+
     /// Returns controller status.
     pub fn status(&self) -> &ControllerStatus {
         &self.inner.status
     }
 
+    /// Returns a reference-counted handle to controller status, which, unlike
+    /// [`Controller::status`], does not borrow the controller.
+    pub(crate) fn shared_status(&self) -> Arc<ControllerStatus> {
+        self.inner.status.clone()
+    }
+
+    /// Returns the current contents of a materialized output stream.
+    ///
+    /// The stream must be registered in the catalog using
+    /// [`Catalog::register_materialized_output_batch_handle`].  Only keys
+    /// that match `filter` are returned.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the stream does not exist or is not materialized, or if
+    /// `filter` is invalid for the stream's key type.
+    pub fn snapshot(&self, stream_name: &str, filter: &KeyFilter) -> AnyResult<Box<dyn SerBatch>> {
+        self.inner
+            .catalog
+            .lock()
+            .unwrap()
+            .output_batch_handle(stream_name)
+            .ok_or_else(|| ControllerError::unknown_output_stream(stream_name))?
+            .snapshot(filter)
+    }
+
     /// Terminate the controller, stop all input endpoints and destroy the
     /// circuit.
     pub fn stop(self) -> AnyResult<()> {
@@ -234,6 +492,17 @@ impl Controller {
         loop {
             match controller.state() {
                 PipelineState::Running | PipelineState::Paused => {
+                    // Serve profile requests even when the circuit is blocked
+                    // by backpressure.
+                    let cpu_profile_request = controller.cpu_profile_request.lock().unwrap().take();
+                    if let Some(cpu_profile_request) = cpu_profile_request {
+                        let _ = cpu_profile_request.send(
+                            circuit
+                                .cpu_profile()
+                                .map_err(|e| ControllerError::dbsp_error(e).into()),
+                        );
+                    }
+
                     // Backpressure in the output pipeline: wait for room in output buffers to
                     // become available.
                     if controller.output_buffers_full() {
@@ -243,40 +512,33 @@ impl Controller {
 
                     let buffered_records = controller.status.num_buffered_input_records();
 
-                    // We have sufficient buffered inputs or the buffering delay has expired --
-                    // kick the circuit to consume buffered data.  Use strict inequality in case
+                    // Checkpoint requested: consume buffered inputs first, so
+                    // that the checkpoint covers all data received before the
+                    // request.
+                    let checkpoint_request = controller.checkpoint_request.lock().unwrap().take();
+                    if let Some(checkpoint_request) = checkpoint_request {
+                        if buffered_records > 0 {
+                            start = None;
+                            Self::step_circuit(&mut circuit, &controller);
+                        }
+                        let _ =
+                            checkpoint_request.send(controller.checkpoint_circuit(&mut circuit));
+                        continue;
+                    }
+
+                    // We have sufficient buffered inputs, the buffering delay has expired, or
+                    // a client waiting for a step asked to flush buffered inputs -- kick the
+                    // circuit to consume buffered data.  Use strict inequality in case
                     // `min_batch_size_records` is 0.
+                    let flush_requested = controller.flush_requested.swap(false, Ordering::AcqRel);
                     if buffered_records > min_batch_size_records
                         || start
                             .map(|start| start.elapsed() >= max_buffering_delay)
                             .unwrap_or(false)
+                        || (flush_requested && buffered_records > 0)
                     {
                         start = None;
-                        // Reset all counters of buffered records and bytes to 0.
-                        controller.status.consume_buffered_inputs();
-                        // Wake up the backpressure thread to unpause endpoints blocked due to
-                        // backpressure.
-                        controller.unpark_backpressure();
-                        circuit
-                            .step()
-                            .unwrap_or_else(|e| controller.error(ControllerError::dbsp_error(e)));
-
-                        // Push output batches to output pipelines.
-                        let outputs = controller.outputs.read().unwrap();
-                        for (endpoint_id, output) in outputs.iter() {
-                            // TODO: add an endpoint config option to consolidate output batches.
-                            let batch = output.output_handle.take_from_all();
-                            let num_records = batch.iter().map(|b| b.len()).sum();
-
-                            // Increment stats first, so we don't end up with negative counts.
-                            controller.status.enqueue_batch(*endpoint_id, num_records);
-                            output.queue.push(batch);
-
-                            // Wake up the output thread.  We're not trying to be smart here and
-                            // wake up the thread conditionally if it was previously idle, as I
-                            // don't expect this to make any real difference.
-                            output.unparker.unpark();
-                        }
+                        Self::step_circuit(&mut circuit, &controller);
                     } else if buffered_records > 0 {
                         // We have some buffered data, but less than `min_batch_size_records` --
                         // wait up to `max_buffering_delay` for more data to
@@ -299,6 +561,77 @@ impl Controller {
         }
     }
 
+    /// Evaluate a single step of the circuit and push its outputs to output
+    /// pipelines.
+    fn step_circuit(circuit: &mut DBSPHandle, controller: &ControllerInner) {
+        let step = controller.num_steps.load(Ordering::Acquire);
+        // Inputs received from now on may or may not be consumed by this
+        // step, but are guaranteed to be consumed by the next one.
+        controller.status.steps_started(step + 1);
+
+        // Reset all counters of buffered records and bytes to 0.
+        if let Some(first_input) = controller.status.consume_buffered_inputs() {
+            controller
+                .uncommitted_inputs
+                .lock()
+                .unwrap()
+                .push_back((step, first_input));
+        }
+        // Wake up the backpressure thread to unpause endpoints blocked due to
+        // backpressure.
+        controller.unpark_backpressure();
+
+        controller.start_step(step);
+        let start = Instant::now();
+        circuit
+            .step()
+            .unwrap_or_else(|e| controller.error(ControllerError::dbsp_error(e)));
+        controller.status.step_completed(start.elapsed());
+        controller.end_step(step);
+
+        // Push output batches to output pipelines.
+        let outputs = controller.outputs.read().unwrap();
+        for (endpoint_id, output) in outputs.iter() {
+            let batch = if output.consolidate {
+                vec![output.output_handle.consolidate()]
+            } else {
+                output.output_handle.take_from_all()
+            };
+            let num_records = batch.iter().map(|b| b.len()).sum();
+
+            // Increment stats first, so we don't end up with negative counts.
+            controller.status.enqueue_batch(*endpoint_id, num_records);
+            output.queue.push((step, batch));
+
+            // Wake up the output thread.  We're not trying to be smart here and
+            // wake up the thread conditionally if it was previously idle, as I
+            // don't expect this to make any real difference.
+            output.unparker.unpark();
+        }
+
+        // Keep the integrals of materialized output streams that are not
+        // connected to an endpoint up to date.  Streams with endpoints get
+        // updated when reading output batches above.
+        for (stream_name, output_handle) in controller.materialized_outputs.iter() {
+            if !outputs
+                .values()
+                .any(|output| &output.stream_name == stream_name)
+            {
+                output_handle.take_from_all();
+            }
+        }
+
+        // Update the step counter while holding the lock on
+        // `outputs`, so that newly connected output endpoints
+        // observe a consistent value.
+        controller.num_steps.store(step + 1, Ordering::Release);
+        drop(outputs);
+
+        // Commit the step right away if there are no output
+        // endpoints waiting to push its outputs.
+        controller.commit_steps();
+    }
+
     /// Backpressure thread function.
     fn backpressure_thread(controller: Arc<ControllerInner>, parker: Parker) {
         // `global_pause` flag is `true` when the entire controller is paused
@@ -380,21 +713,88 @@ impl Controller {
 /// State tracked by the controller for each input endpoint.
 struct InputEndpointDescr {
     endpoint_name: String,
-    endpoint: Box<dyn InputEndpoint>,
+    endpoint: Arc<dyn InputEndpoint>,
 }
 
 impl InputEndpointDescr {
     pub fn new(endpoint_name: &str, endpoint: Box<dyn InputEndpoint>) -> Self {
         Self {
             endpoint_name: endpoint_name.to_owned(),
-            endpoint,
+            endpoint: endpoint.into(),
         }
     }
 }
 
+/// Input endpoint that has been disconnected, but still has uncommitted
+/// steps.
+struct DisconnectedInputDescr {
+    descr: InputEndpointDescr,
+    /// The step that consumes the last records pushed by the endpoint.
+    /// `None` until this step starts.
+    last_step: Option<Step>,
+}
+
 /// A lock-free queue used to send output batches from the circuit thread
-/// to output endpoint threads.
-type BatchQueue = SegQueue<Vec<Box<dyn SerBatch>>>;
+/// to output endpoint threads.  Each entry is labeled with the step that
+/// produced it.
+type BatchQueue = SegQueue<(Step, Vec<Box<dyn SerBatch>>)>;
+
+/// Outputs of one or more consecutive steps accumulated by an output endpoint
+/// thread before encoding them (see
+/// [`OutputEndpointConfig::min_batch_size_records`]).
+#[derive(Default)]
+struct OutputBuffer {
+    /// Accumulated batches.
+    batches: Vec<Box<dyn SerBatch>>,
+
+    /// The last step whose outputs are in the buffer.
+    step: Option<Step>,
+
+    /// The number of steps whose outputs are in the buffer.
+    num_steps: usize,
+
+    /// The number of records queued for these steps.
+    num_records: usize,
+
+    /// The time when the first step was added to the buffer.
+    start: Option<Instant>,
+}
+
+impl OutputBuffer {
+    fn is_empty(&self) -> bool {
+        self.num_steps == 0
+    }
+
+    /// Time since the first step was added to the buffer.
+    fn elapsed(&self) -> Duration {
+        self.start
+            .map(|start| start.elapsed())
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// Add the outputs of `step` to the buffer.  When `merge` is `true`,
+    /// merge all accumulated batches into one, dropping updates that cancel
+    /// out.
+    fn push(&mut self, step: Step, batches: Vec<Box<dyn SerBatch>>, merge: bool) -> AnyResult<()> {
+        self.num_records += batches.iter().map(|b| b.len()).sum::<usize>();
+        self.num_steps += 1;
+        self.step = Some(step);
+        self.start.get_or_insert_with(Instant::now);
+
+        if merge {
+            for batch in batches {
+                self.batches = vec![match self.batches.pop() {
+                    None => batch,
+                    Some(acc) => acc.merge(&*batch)?,
+                }];
+            }
+        } else {
+            self.batches.extend(batches);
+        }
+
+        Ok(())
+    }
+}
 
 /// State tracked by the controller for each output endpoint.
 struct OutputEndpointDescr {
@@ -415,26 +815,55 @@ struct OutputEndpointDescr {
     /// Handle for the output stream.
     output_handle: Box<dyn SerOutputBatchHandle>,
 
+    /// Consolidate the outputs of all workers into a single batch (see
+    /// [`OutputEndpointConfig::consolidate`]).
+    consolidate: bool,
+
     /// FIFO queue of batches read from the stream.
     queue: Arc<BatchQueue>,
 
     /// Unparker for the endpoint thread.
     unparker: Unparker,
+
+    /// The number of steps whose outputs have been pushed to the endpoint.
+    ///
+    /// An endpoint connected after step `n` has been evaluated starts with
+    /// `n + 1` pushed steps, as it never receives outputs of earlier steps.
+    /// Stops advancing once the endpoint fails to deliver the output of a
+    /// step.
+    pushed_steps: Arc<AtomicU64>,
+
+    /// Set when the endpoint is disconnected.  The endpoint thread exits
+    /// after pushing all batches remaining in `queue`.
+    disconnected: Arc<AtomicBool>,
+
+    /// The endpoint thread handle.
+    thread_handle: JoinHandle<()>,
 }
 
 impl OutputEndpointDescr {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         endpoint_name: &str,
         stream_name: &str,
         output_handle: Box<dyn SerOutputBatchHandle>,
+        consolidate: bool,
+        queue: Arc<BatchQueue>,
         unparker: Unparker,
+        pushed_steps: Arc<AtomicU64>,
+        disconnected: Arc<AtomicBool>,
+        thread_handle: JoinHandle<()>,
     ) -> Self {
         Self {
             endpoint_name: endpoint_name.to_string(),
             stream_name: stream_name.to_string(),
             output_handle,
-            queue: Arc::new(SegQueue::new()),
+            consolidate,
+            queue,
             unparker,
+            pushed_steps,
+            disconnected,
+            thread_handle,
         }
     }
 }
@@ -444,11 +873,36 @@ impl OutputEndpointDescr {
 /// A reference to this struct is held by each input probe and by both
 /// controller threads.
 struct ControllerInner {
-    status: ControllerStatus,
+    status: Arc<ControllerStatus>,
     state: AtomicU32,
     catalog: Arc<Mutex<Catalog>>,
     inputs: Mutex<BTreeMap<EndpointId, InputEndpointDescr>>,
+    /// Disconnected input endpoints, kept until all steps that consumed
+    /// their inputs have been committed.
+    disconnected_inputs: Mutex<BTreeMap<EndpointId, DisconnectedInputDescr>>,
     outputs: ShardedLock<BTreeMap<EndpointId, OutputEndpointDescr>>,
+    /// Handles of materialized output streams, indexed by stream name.
+    materialized_outputs: Vec<(String, Box<dyn SerOutputBatchHandle>)>,
+    /// The number of steps evaluated by the circuit whose outputs have been
+    /// queued to output endpoints.
+    num_steps: AtomicU64,
+    /// The number of steps committed to input endpoints.
+    committed_steps: Mutex<Step>,
+    /// Signaled when `committed_steps` changes or the pipeline terminates.
+    steps_committed: Condvar,
+    /// Set by clients waiting for a step that hasn't been evaluated yet.
+    /// The circuit thread consumes buffered inputs right away, without
+    /// waiting for `min_batch_size_records` records to accumulate or for the
+    /// buffering delay to expire.
+    flush_requested: AtomicBool,
+    /// Steps that consumed inputs and haven't been committed yet, along with
+    /// the time when the first input record consumed by the step was
+    /// received.
+    uncommitted_inputs: Mutex<VecDeque<(Step, Instant)>>,
+    /// Pending checkpoint request to be processed by the circuit thread.
+    checkpoint_request: Mutex<Option<Sender<AnyResult<Checkpoint>>>>,
+    /// Pending CPU profile request to be processed by the circuit thread.
+    cpu_profile_request: Mutex<Option<Sender<AnyResult<CpuProfile>>>>,
     circuit_thread_unparker: Unparker,
     backpressure_thread_unparker: Unparker,
     error_cb: Box<dyn Fn(ControllerError) + Send + Sync>,
@@ -458,19 +912,36 @@ impl ControllerInner {
     fn new(
         catalog: Catalog,
         global_config: &GlobalControllerConfig,
+        num_steps: Step,
         circuit_thread_unparker: Unparker,
         backpressure_thread_unparker: Unparker,
         error_cb: Box<dyn Fn(ControllerError) + Send + Sync>,
     ) -> Self {
-        let status = ControllerStatus::new(global_config);
+        let status = Arc::new(ControllerStatus::new(global_config));
+        status.steps_started(num_steps);
+        status.steps_committed(num_steps);
         let state = AtomicU32::new(PipelineState::Paused as u32);
+        let materialized_outputs = catalog
+            .output_batch_handles()
+            .filter(|(_, handle)| handle.is_materialized())
+            .map(|(name, handle)| (name.to_string(), handle.fork()))
+            .collect();
 
         Self {
             status,
             state,
             catalog: Arc::new(Mutex::new(catalog)),
             inputs: Mutex::new(BTreeMap::new()),
+            disconnected_inputs: Mutex::new(BTreeMap::new()),
             outputs: ShardedLock::new(BTreeMap::new()),
+            materialized_outputs,
+            num_steps: AtomicU64::new(num_steps),
+            committed_steps: Mutex::new(num_steps),
+            steps_committed: Condvar::new(),
+            flush_requested: AtomicBool::new(false),
+            uncommitted_inputs: Mutex::new(VecDeque::new()),
+            checkpoint_request: Mutex::new(None),
+            cpu_profile_request: Mutex::new(None),
             circuit_thread_unparker,
             backpressure_thread_unparker,
             error_cb,
@@ -482,6 +953,26 @@ impl ControllerInner {
         endpoint_name: &str,
         endpoint_config: &InputEndpointConfig,
     ) -> AnyResult<()> {
+        // Lookup transport endpoint.
+        let transport = <dyn InputTransport>::get_transport(&endpoint_config.transport.name)
+            .ok_or_else(|| {
+                ControllerError::unknown_input_transport(&endpoint_config.transport.name)
+            })?;
+
+        self.connect_input_with(endpoint_name, endpoint_config, |probe| {
+            transport.new_endpoint(&endpoint_config.transport.config, probe)
+        })
+    }
+
+    fn connect_input_with<F>(
+        self: &Arc<Self>,
+        endpoint_name: &str,
+        endpoint_config: &InputEndpointConfig,
+        create_endpoint: F,
+    ) -> AnyResult<()>
+    where
+        F: FnOnce(Box<dyn InputConsumer>) -> AnyResult<Box<dyn InputEndpoint>>,
+    {
         let mut inputs = self.inputs.lock().unwrap();
 
         if inputs.values().any(|ep| ep.endpoint_name == endpoint_name) {
@@ -500,24 +991,42 @@ impl ControllerInner {
             .ok_or_else(|| ControllerError::unknown_input_format(&endpoint_config.format.name))?;
         let parser = format.new_parser(&endpoint_config.format.config, &self.catalog)?;
 
-        // Create probe.
         let endpoint_id = inputs.keys().rev().next().map(|k| k + 1).unwrap_or(0);
+
+        // Create dead-letter endpoint.
+        let on_error = match &endpoint_config.on_error {
+            InputErrorPolicy::Fail => RejectAction::Fail,
+            InputErrorPolicy::Skip => RejectAction::Skip,
+            InputErrorPolicy::DeadLetter(transport_config) => {
+                RejectAction::DeadLetter(Arc::new(Mutex::new(self.dead_letter_endpoint(
+                    endpoint_id,
+                    endpoint_name,
+                    transport_config,
+                )?)))
+            }
+        };
+
+        // Create probe.
         let probe = Box::new(InputProbe::new(
             endpoint_id,
             endpoint_name,
             parser,
+            on_error,
             self.clone(),
             self.circuit_thread_unparker.clone(),
             self.backpressure_thread_unparker.clone(),
         ));
 
         // Create transport endpoint.
-        let transport = <dyn InputTransport>::get_transport(&endpoint_config.transport.name)
-            .ok_or_else(|| {
-                ControllerError::unknown_input_transport(&endpoint_config.transport.name)
-            })?;
-
-        let endpoint = transport.new_endpoint(&endpoint_config.transport.config, probe)?;
+        let endpoint = create_endpoint(probe)?;
+        let position = endpoint.position();
+
+        // The backpressure thread only starts endpoints when the pipeline
+        // switches to the running state.  Start the new endpoint right away if
+        // the pipeline is already running.
+        if self.state() == PipelineState::Running {
+            endpoint.start()?;
+        }
 
         inputs.insert(
             endpoint_id,
@@ -526,14 +1035,67 @@ impl ControllerInner {
 
         drop(inputs);
 
-        // Initialize endpoint stats.
+        // Initialize endpoint stats.  The initial position is included in
+        // checkpoints taken before the endpoint sees its first step.
         self.status
             .add_input(&endpoint_id, endpoint_name, endpoint_config);
+        self.status.input_position(endpoint_id, position);
 
         self.unpark_backpressure();
         Ok(())
     }
 
+    /// Create an output transport endpoint to send records rejected by input
+    /// endpoint `endpoint_name` to.
+    fn dead_letter_endpoint(
+        self: &Arc<Self>,
+        endpoint_id: EndpointId,
+        endpoint_name: &str,
+        transport_config: &TransportConfig,
+    ) -> AnyResult<Box<dyn OutputEndpoint>> {
+        let transport = <dyn OutputTransport>::get_transport(&transport_config.name)
+            .ok_or_else(|| ControllerError::unknown_output_transport(&transport_config.name))?;
+
+        let self_weak = Arc::downgrade(self);
+        let endpoint_name_clone = endpoint_name.to_string();
+
+        transport.new_endpoint(
+            &transport_config.config,
+            Box::new(move |fatal, e| {
+                if let Some(controller) = self_weak.upgrade() {
+                    controller.input_transport_error(
+                        endpoint_id,
+                        &endpoint_name_clone,
+                        false,
+                        AnyError::msg(format!(
+                            "dead-letter endpoint error{}: {e}",
+                            if fatal { " (fatal)" } else { "" }
+                        )),
+                    )
+                }
+            }),
+        )
+    }
+
+    /// Move input endpoint `endpoint_name` to `position` recorded in a
+    /// checkpoint.
+    fn seek_input(&self, endpoint_name: &str, position: &JsonValue) -> AnyResult<()> {
+        let inputs = self.inputs.lock().unwrap();
+
+        if let Some((endpoint_id, ep)) = inputs
+            .iter()
+            .find(|(_, ep)| ep.endpoint_name == endpoint_name)
+        {
+            ep.endpoint
+                .seek(position)
+                .map_err(|e| ControllerError::input_transport_error(endpoint_name, true, e))?;
+            self.status
+                .input_position(*endpoint_id, Some(position.clone()));
+        }
+
+        Ok(())
+    }
+
     /// Unpark the circuit thread.
     fn unpark_circuit(&self) {
         self.circuit_thread_unparker.unpark();
@@ -544,11 +1106,62 @@ impl ControllerInner {
         self.backpressure_thread_unparker.unpark();
     }
 
+    fn disconnect_input(&self, endpoint_name: &str) -> AnyResult<()> {
+        let mut inputs = self.inputs.lock().unwrap();
+
+        let endpoint_id = inputs
+            .iter()
+            .find(|(_, ep)| ep.endpoint_name == endpoint_name)
+            .map(|(endpoint_id, _)| *endpoint_id)
+            .ok_or_else(|| ControllerError::unknown_input_endpoint(endpoint_name))?;
+
+        let descr = inputs.remove(&endpoint_id).unwrap();
+        descr.endpoint.disconnect();
+
+        // Keep the endpoint around until the steps that consumed its inputs
+        // have been committed (see `commit_steps`).
+        self.disconnected_inputs.lock().unwrap().insert(
+            endpoint_id,
+            DisconnectedInputDescr {
+                descr,
+                last_step: None,
+            },
+        );
+        drop(inputs);
+
+        // The global counter of buffered records is not affected, so records
+        // received from the endpoint still get processed by the circuit.
+        self.status.remove_input(&endpoint_id);
+
+        self.unpark_backpressure();
+        Ok(())
+    }
+
     fn connect_output(
         self: &Arc<Self>,
         endpoint_name: &str,
         endpoint_config: &OutputEndpointConfig,
     ) -> AnyResult<()> {
+        // Lookup transport endpoint.
+        let transport = <dyn OutputTransport>::get_transport(&endpoint_config.transport.name)
+            .ok_or_else(|| {
+                ControllerError::unknown_output_transport(&endpoint_config.transport.name)
+            })?;
+
+        self.connect_output_with(endpoint_name, endpoint_config, |async_error_callback| {
+            transport.new_endpoint(&endpoint_config.transport.config, async_error_callback)
+        })
+    }
+
+    fn connect_output_with<F>(
+        self: &Arc<Self>,
+        endpoint_name: &str,
+        endpoint_config: &OutputEndpointConfig,
+        create_endpoint: F,
+    ) -> AnyResult<()>
+    where
+        F: FnOnce(Box<dyn Fn(bool, AnyError) + Send + Sync>) -> AnyResult<Box<dyn OutputEndpoint>>,
+    {
         let mut outputs = self.outputs.write().unwrap();
 
         if outputs.values().any(|ep| ep.endpoint_name == endpoint_name) {
@@ -581,31 +1194,18 @@ impl ControllerInner {
             .fork();
 
         // Create transport endpoint.
-        let transport = <dyn OutputTransport>::get_transport(&endpoint_config.transport.name)
-            .ok_or_else(|| {
-                ControllerError::unknown_output_transport(&endpoint_config.transport.name)
-            })?;
-
         let endpoint_id = outputs.keys().rev().next().map(|k| k + 1).unwrap_or(0);
         let endpoint_name_str = endpoint_name.to_string();
 
         let self_weak = Arc::downgrade(self);
-        let endpoint = transport.new_endpoint(
-            &endpoint_config.transport.config,
-            Box::new(move |fatal: bool, e: AnyError| {
-                if let Some(controller) = self_weak.upgrade() {
-                    controller.output_transport_error(endpoint_id, &endpoint_name_str, fatal, e)
-                }
-            }),
-        )?;
+        let endpoint = create_endpoint(Box::new(move |fatal: bool, e: AnyError| {
+            if let Some(controller) = self_weak.upgrade() {
+                controller.output_transport_error(endpoint_id, &endpoint_name_str, fatal, e)
+            }
+        }))?;
 
         // Create probe.
-        let probe = Box::new(OutputProbe::new(
-            endpoint_id,
-            endpoint_name,
-            endpoint,
-            self.clone(),
-        ));
+        let probe = Box::new(OutputProbe::new(endpoint_id, endpoint, self.clone()));
 
         // Create encoder.
         let format = <dyn OutputFormat>::get_format(&endpoint_config.format.name)
@@ -613,29 +1213,48 @@ impl ControllerInner {
         let encoder = format.new_encoder(&endpoint_config.format.config, probe)?;
 
         let parker = Parker::new();
-        let endpoint_state = OutputEndpointDescr::new(
-            endpoint_name,
-            &endpoint_config.stream,
-            collection_handle,
-            parker.unparker().clone(),
-        );
-        let queue = endpoint_state.queue.clone();
-        let controller = self.clone();
+        let unparker = parker.unparker().clone();
+        let queue = Arc::new(SegQueue::new());
+        let pushed_steps = Arc::new(AtomicU64::new(self.num_steps.load(Ordering::Acquire)));
+        let disconnected = Arc::new(AtomicBool::new(false));
 
-        outputs.insert(endpoint_id, endpoint_state);
-
-        let endpoint_name_string = endpoint_name.to_string();
         // Thread to run the output pipeline.
-        spawn(move || {
-            Self::output_thread_func(
-                endpoint_id,
-                endpoint_name_string,
-                encoder,
-                parker,
+        let thread_handle = {
+            let endpoint_name = endpoint_name.to_string();
+            let queue = queue.clone();
+            let pushed_steps = pushed_steps.clone();
+            let disconnected = disconnected.clone();
+            let controller = self.clone();
+            let config = endpoint_config.clone();
+            spawn(move || {
+                Self::output_thread_func(
+                    endpoint_id,
+                    endpoint_name,
+                    config,
+                    encoder,
+                    parker,
+                    queue,
+                    pushed_steps,
+                    disconnected,
+                    controller,
+                )
+            })
+        };
+
+        outputs.insert(
+            endpoint_id,
+            OutputEndpointDescr::new(
+                endpoint_name,
+                &endpoint_config.stream,
+                collection_handle,
+                endpoint_config.consolidate || endpoint_config.drop_net_zero,
                 queue,
-                controller,
-            )
-        });
+                unparker,
+                pushed_steps,
+                disconnected,
+                thread_handle,
+            ),
+        );
 
         drop(outputs);
 
@@ -646,35 +1265,149 @@ impl ControllerInner {
         Ok(())
     }
 
+    fn disconnect_output(&self, endpoint_name: &str) -> AnyResult<()> {
+        let mut outputs = self.outputs.write().unwrap();
+
+        let endpoint_id = outputs
+            .iter()
+            .find(|(_, ep)| ep.endpoint_name == endpoint_name)
+            .map(|(endpoint_id, _)| *endpoint_id)
+            .ok_or_else(|| ControllerError::unknown_output_endpoint(endpoint_name))?;
+
+        // Once removed from `outputs`, the endpoint no longer receives
+        // batches from the circuit thread.
+        let ep = outputs.remove(&endpoint_id).unwrap();
+        drop(outputs);
+
+        // Wait for the endpoint thread to push remaining batches and exit.
+        ep.disconnected.store(true, Ordering::Release);
+        ep.unparker.unpark();
+        ep.thread_handle.join().map_err(|_| {
+            AnyError::msg(format!("output endpoint '{endpoint_name}' thread panicked"))
+        })?;
+
+        self.status.remove_output(&endpoint_id);
+
+        // Removing the endpoint may have unblocked the circuit thread waiting
+        // for room in output buffers or allowed more steps to be committed.
+        self.unpark_circuit();
+        self.commit_steps();
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn output_thread_func(
         endpoint_id: EndpointId,
         endpoint_name: String,
+        config: OutputEndpointConfig,
         mut encoder: Box<dyn Encoder>,
         parker: Parker,
         queue: Arc<BatchQueue>,
+        pushed_steps: Arc<AtomicU64>,
+        disconnected: Arc<AtomicBool>,
         controller: Arc<ControllerInner>,
     ) {
+        let max_buffering_delay = Duration::from_micros(config.max_buffering_delay_usecs);
+
+        // Outputs of steps merged before encoding.
+        let mut buffer = OutputBuffer::default();
+
+        // Set once the endpoint fails to deliver the output of a step.
+        let mut failed = false;
+
+        let mut push_buffer = |buffer: &mut OutputBuffer| {
+            let OutputBuffer {
+                batches,
+                step,
+                num_steps,
+                num_records,
+                ..
+            } = take(buffer);
+            let step = step.unwrap();
+            let mut num_transmitted_records = batches.iter().map(|b| b.len()).sum();
+
+            // Once the endpoint fails to deliver a step, it stops receiving
+            // outputs and none of the following steps gets committed, so that
+            // input endpoints don't acknowledge inputs whose outputs may have
+            // been lost.  Remaining batches are dropped, so that the circuit
+            // doesn't stall waiting for room in the output buffer.
+            //
+            // Errors while pushing the output to the endpoint are reported by
+            // `batch_end` (see `OutputProbe`).  We don't end the batch if the
+            // step cannot be encoded, so that transactional endpoints don't
+            // commit a partial output of the step.
+            if !failed {
+                if let Err(e) = encoder.consumer().batch_start(step) {
+                    controller.output_transport_error(endpoint_id, &endpoint_name, true, e);
+                    failed = true;
+                } else if let Err(e) = encoder.encode(batches.as_slice()) {
+                    controller.error(ControllerError::encoder_error(&endpoint_name, e));
+                    failed = true;
+                } else if let Err(e) = encoder.consumer().batch_end() {
+                    controller.output_transport_error(endpoint_id, &endpoint_name, true, e);
+                    failed = true;
+                }
+            }
+            if failed {
+                num_transmitted_records = 0;
+            }
+
+            // Output records have been transmitted -- update output stats,
+            // wake up the circuit thread if the number of queued records drops
+            // below high water mark.
+            controller.status.output_batch(
+                endpoint_id,
+                num_steps,
+                num_records,
+                num_transmitted_records,
+                &controller.circuit_thread_unparker,
+            );
+
+            if !failed {
+                pushed_steps.store(step + 1, Ordering::Release);
+                controller.commit_steps();
+            }
+        };
+
         loop {
             if controller.state() == PipelineState::Terminated {
                 return;
             }
 
-            // Dequeue the next output batch and push it to the encoder.
-            if let Some(data) = queue.pop() {
-                let num_records = data.iter().map(|b| b.len()).sum();
-
-                encoder.encode(data.as_slice()).unwrap_or_else(|e| {
-                    controller.error(ControllerError::encoder_error(&endpoint_name, e))
-                });
-
-                // `num_records` output records have been transmitted --
-                // update output stats, wake up the circuit thread if the
-                // number of queued records drops below high water mark.
-                controller.status.output_batch(
-                    endpoint_id,
-                    num_records,
-                    &controller.circuit_thread_unparker,
-                );
+            // Read the flag before dequeuing: the circuit thread doesn't queue
+            // any more batches once the flag is set, so an empty queue
+            // means there's nothing left to push.
+            let disconnected = disconnected.load(Ordering::Acquire);
+
+            // Dequeue the next output batch and add it to the buffer.  Push
+            // the buffer to the encoder once it has accumulated
+            // `min_batch_size_records` records or the buffering delay has
+            // expired.  Push empty outputs right away when the buffer is
+            // empty, so that steps that don't produce any outputs get
+            // committed without delay.
+            if let Some((step, data)) = queue.pop() {
+                buffer
+                    .push(step, data, config.drop_net_zero)
+                    .unwrap_or_else(|e| {
+                        controller.error(ControllerError::encoder_error(&endpoint_name, e))
+                    });
+
+                if buffer.num_records == 0
+                    || buffer.num_records as u64 >= config.min_batch_size_records
+                    || buffer.elapsed() >= max_buffering_delay
+                {
+                    push_buffer(&mut buffer);
+                }
+            } else if !buffer.is_empty()
+                && (disconnected || buffer.elapsed() >= max_buffering_delay)
+            {
+                push_buffer(&mut buffer);
+            } else if disconnected {
+                return;
+            } else if !buffer.is_empty() {
+                // Wait for more data until the buffering delay expires.
+                parker.park_timeout(max_buffering_delay - buffer.elapsed());
             } else {
                 // Queue is empty -- wait for the circuit thread to wake us up when
                 // more data is available.
@@ -706,18 +1439,263 @@ impl ControllerInner {
             ep.endpoint.disconnect();
         }
         inputs.clear();
+        self.disconnected_inputs.lock().unwrap().clear();
 
         self.state
             .store(PipelineState::Terminated as u32, Ordering::Release);
 
+        // Wake up threads waiting for steps to be committed.  Acquire the lock
+        // first, so that the notification isn't lost by a thread that has
+        // checked the state, but hasn't started waiting yet.
+        {
+            let _committed_steps = self.committed_steps.lock().unwrap();
+            self.steps_committed.notify_all();
+        }
+
         self.unpark_circuit();
         self.unpark_backpressure();
+
+        // Wake up output threads, so they can exit and drop their endpoints.
+        for ep in self.outputs.read().unwrap().values() {
+            ep.unparker.unpark();
+        }
     }
 
     fn error(&self, error: ControllerError) {
         (self.error_cb)(error);
     }
 
+    /// Notify input endpoints that the circuit is about to evaluate `step`.
+    ///
+    /// Updates the input positions reported in endpoint stats.
+    fn start_step(&self, step: Step) {
+        let inputs = self.inputs.lock().unwrap();
+        for (epid, ep) in inputs.iter() {
+            ep.endpoint.start_step(step);
+            self.status.input_position(*epid, ep.endpoint.position());
+        }
+
+        // Records pushed by disconnected endpoints before they were
+        // disconnected are consumed by the first step that starts afterwards.
+        for ep in self.disconnected_inputs.lock().unwrap().values_mut() {
+            if ep.last_step.is_none() {
+                ep.descr.endpoint.start_step(step);
+                ep.last_step = Some(step);
+            }
+        }
+    }
+
+    /// Notify input endpoints that the circuit has finished evaluating `step`.
+    fn end_step(&self, step: Step) {
+        let inputs = self.inputs.lock().unwrap();
+        for ep in inputs.values() {
+            ep.endpoint.end_step(step);
+        }
+
+        for ep in self.disconnected_inputs.lock().unwrap().values() {
+            if ep.last_step == Some(step) {
+                ep.descr.endpoint.end_step(step);
+            }
+        }
+    }
+
+    /// Commit all steps whose outputs have been pushed to all output
+    /// endpoints.
+    ///
+    /// Invoked by the circuit thread after each step and by output threads
+    /// after pushing a batch.
+    fn commit_steps(&self) {
+        let mut committed_steps = self.committed_steps.lock().unwrap();
+
+        let pushed_steps = self
+            .outputs
+            .read()
+            .unwrap()
+            .values()
+            .map(|output| output.pushed_steps.load(Ordering::Acquire))
+            .fold(self.num_steps.load(Ordering::Acquire), Step::min);
+
+        if pushed_steps > *committed_steps {
+            // Committing can block, e.g., Kafka endpoints commit offsets
+            // synchronously.  Don't hold the `inputs` lock, which the circuit
+            // thread acquires at every step, while committing.
+            let mut inputs = self
+                .inputs
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(epid, ep)| (*epid, ep.endpoint_name.clone(), ep.endpoint.clone()))
+                .collect::<Vec<_>>();
+
+            // Disconnected endpoints are dropped once their last step is
+            // committed.
+            let mut disconnected_inputs = self.disconnected_inputs.lock().unwrap();
+            for (epid, ep) in disconnected_inputs.iter() {
+                inputs.push((
+                    *epid,
+                    ep.descr.endpoint_name.clone(),
+                    ep.descr.endpoint.clone(),
+                ));
+            }
+            disconnected_inputs.retain(
+                |_, ep| !matches!(ep.last_step, Some(last_step) if last_step < pushed_steps),
+            );
+            drop(disconnected_inputs);
+
+            for (epid, endpoint_name, endpoint) in inputs {
+                endpoint
+                    .commit(pushed_steps - 1)
+                    .unwrap_or_else(|e| self.input_transport_error(epid, &endpoint_name, false, e));
+            }
+            *committed_steps = pushed_steps;
+            self.status.steps_committed(pushed_steps);
+
+            let mut uncommitted_inputs = self.uncommitted_inputs.lock().unwrap();
+            while let Some((_, first_input)) = uncommitted_inputs
+                .front()
+                .copied()
+                .filter(|(step, _)| *step < pushed_steps)
+            {
+                self.status.input_latency(first_input.elapsed());
+                uncommitted_inputs.pop_front();
+            }
+
+            self.steps_committed.notify_all();
+        }
+    }
+
+    /// Wait until the first `num_steps` steps have been committed, i.e.,
+    /// evaluated by the circuit and their outputs pushed to all output
+    /// endpoints.
+    ///
+    /// Asks the circuit to consume buffered inputs without delay if the
+    /// steps haven't been evaluated yet, but doesn't force it to evaluate
+    /// steps without inputs.  Returns early if step `num_steps - 1` is the
+    /// next step to be evaluated and all inputs received so far have been
+    /// consumed by committed steps (see [`Controller::wait_for_step`]).
+    ///
+    /// Returns `false` if `timeout` expires first.  Fails if the pipeline
+    /// terminates before the steps are committed.
+    fn wait_for_committed_steps(
+        self: &Arc<Self>,
+        num_steps: Step,
+        timeout: Option<Duration>,
+    ) -> AnyResult<bool> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut flush_requested = false;
+
+        let mut committed_steps = self.committed_steps.lock().unwrap();
+        while *committed_steps < num_steps {
+            if self.state() == PipelineState::Terminated {
+                Err(AnyError::msg("the pipeline has been terminated"))?;
+            }
+
+            // Read the number of buffered records before the step counter:
+            // the circuit thread increments the counter before consuming
+            // buffered inputs, so if the next step hasn't started by the time
+            // we read the counter, all inputs received before this point have
+            // been consumed by earlier steps.
+            if self.status.num_buffered_input_records() == 0 {
+                let next_step = self.status.global_metrics.step.load(Ordering::Acquire);
+                if next_step + 1 == num_steps && *committed_steps >= next_step {
+                    return Ok(true);
+                }
+            } else if !flush_requested && self.num_steps.load(Ordering::Acquire) < num_steps {
+                self.flush_requested.store(true, Ordering::Release);
+                self.unpark_circuit();
+                flush_requested = true;
+            }
+
+            committed_steps = match deadline {
+                None => self.steps_committed.wait(committed_steps).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(false);
+                    }
+                    self.steps_committed
+                        .wait_timeout(committed_steps, deadline - now)
+                        .unwrap()
+                        .0
+                }
+            };
+        }
+
+        Ok(true)
+    }
+
+    /// Request a checkpoint from the circuit thread.
+    ///
+    /// Waits for the circuit thread to serialize the state of the circuit and
+    /// for the outputs of all steps included in the checkpoint to be pushed to
+    /// output endpoints.
+    fn checkpoint(self: &Arc<Self>) -> AnyResult<Checkpoint> {
+        let (sender, receiver) = bounded(1);
+        *self.checkpoint_request.lock().unwrap() = Some(sender);
+        self.unpark_circuit();
+
+        let checkpoint = receiver
+            .recv()
+            .map_err(|_| AnyError::msg("checkpoint request was cancelled"))??;
+
+        self.wait_for_committed_steps(checkpoint.step, None)
+            .map_err(|_| {
+                AnyError::msg("the pipeline terminated before the checkpoint was complete")
+            })?;
+
+        Ok(checkpoint)
+    }
+
+    /// Request CPU profile from the circuit thread.
+    fn request_cpu_profile(self: &Arc<Self>) -> CpuProfileRequest {
+        let (sender, receiver) = bounded(1);
+        *self.cpu_profile_request.lock().unwrap() = Some(sender);
+        self.unpark_circuit();
+
+        CpuProfileRequest {
+            controller: self.clone(),
+            receiver,
+        }
+    }
+
+    /// Serialize the state of the circuit along with current input endpoint
+    /// positions.
+    ///
+    /// Invoked by the circuit thread between steps.
+    fn checkpoint_circuit(&self, circuit: &mut DBSPHandle) -> AnyResult<Checkpoint> {
+        let circuit = circuit.checkpoint().map_err(ControllerError::dbsp_error)?;
+
+        // Positions reported in endpoint stats were recorded at the start of
+        // the last step, i.e., they cover exactly the inputs consumed by the
+        // circuit.  Endpoints that don't track their position are recorded
+        // with a `null` position, which they will refuse to seek to, so that
+        // restoring from the checkpoint fails instead of re-ingesting their
+        // input from the start.
+        let input_positions = self
+            .status
+            .input_status()
+            .values()
+            .map(|status| {
+                (
+                    status.endpoint_name.clone(),
+                    status
+                        .metrics
+                        .position
+                        .lock()
+                        .unwrap()
+                        .clone()
+                        .unwrap_or(JsonValue::Null),
+                )
+            })
+            .collect();
+
+        Ok(Checkpoint {
+            step: self.num_steps.load(Ordering::Acquire),
+            input_positions,
+            circuit,
+        })
+    }
+
     /// Process an input transport error.
     ///
     /// Update endpoint stats and notify the error callback.
@@ -761,12 +1739,91 @@ impl ControllerInner {
     }
 }
 
+/// Pending CPU profile request (see [`Controller::request_cpu_profile`]).
+pub(crate) struct CpuProfileRequest {
+    controller: Arc<ControllerInner>,
+    receiver: Receiver<AnyResult<CpuProfile>>,
+}
+
+impl CpuProfileRequest {
+    /// Wait for the circuit thread to serve the request.
+    pub(crate) fn wait(self) -> AnyResult<CpuProfile> {
+        // The circuit thread may exit without serving the request.
+        loop {
+            match self.receiver.recv_timeout(Duration::from_millis(100)) {
+                Ok(profile) => return profile,
+                Err(RecvTimeoutError::Timeout)
+                    if self.controller.state() != PipelineState::Terminated => {}
+                Err(_) => return Err(AnyError::msg("the pipeline has been terminated")),
+            }
+        }
+    }
+}
+
+/// What to do with records rejected by the parser (see [`InputErrorPolicy`]).
+#[derive(Clone)]
+enum RejectAction {
+    Fail,
+    Skip,
+    DeadLetter(Arc<Mutex<Box<dyn OutputEndpoint>>>),
+}
+
+impl RejectAction {
+    /// Handle `record` rejected by the parser of input endpoint
+    /// `endpoint_name` with `error`.
+    fn reject(
+        &self,
+        controller: &Arc<ControllerInner>,
+        endpoint_id: EndpointId,
+        endpoint_name: &str,
+        record: &[u8],
+        error: AnyError,
+    ) {
+        match self {
+            Self::Fail => {
+                controller
+                    .status
+                    .input_transport_error(endpoint_id, true, &error);
+                controller.error(ControllerError::parse_error(endpoint_name, error));
+                controller.pause();
+            }
+            Self::Skip => {
+                controller.status.input_rejected(endpoint_id);
+                controller.error(ControllerError::parse_error(endpoint_name, error));
+            }
+            Self::DeadLetter(endpoint) => {
+                controller.status.input_rejected(endpoint_id);
+
+                let mut buffer = serde_json::to_vec(&serde_json::json!({
+                    "endpoint": endpoint_name,
+                    "error": error.to_string(),
+                    "record": String::from_utf8_lossy(record),
+                }))
+                .unwrap();
+                buffer.push(b'\n');
+
+                if let Err(e) = endpoint.lock().unwrap().push_buffer(&buffer) {
+                    controller.input_transport_error(
+                        endpoint_id,
+                        endpoint_name,
+                        false,
+                        AnyError::msg(format!("dead-letter endpoint error: {e}")),
+                    );
+                }
+            }
+        }
+    }
+}
+
 /// An input probe inserted between the transport endpoint and the parser to
 /// track stats and errors.
 struct InputProbe {
     endpoint_id: EndpointId,
     endpoint_name: String,
     parser: Box<dyn Parser>,
+    on_error: RejectAction,
+    /// Parse errors encountered since the last `input` or `eoi` call.
+    errors: Arc<Mutex<Vec<String>>>,
     controller: Arc<ControllerInner>,
     circuit_thread_unparker: Unparker,
     backpressure_thread_unparker: Unparker,
@@ -776,25 +1833,64 @@ impl InputProbe {
     fn new(
         endpoint_id: EndpointId,
         endpoint_name: &str,
-        parser: Box<dyn Parser>,
+        mut parser: Box<dyn Parser>,
+        on_error: RejectAction,
         controller: Arc<ControllerInner>,
         circuit_thread_unparker: Unparker,
         backpressure_thread_unparker: Unparker,
     ) -> Self {
+        let errors = Arc::new(Mutex::new(Vec::new()));
+
+        // Let the parser skip invalid records instead of failing the whole
+        // input buffer, if it supports it.
+        if !matches!(on_error, RejectAction::Fail) {
+            let on_error = on_error.clone();
+            let errors = errors.clone();
+            let controller = controller.clone();
+            let endpoint_name = endpoint_name.to_owned();
+            parser.on_invalid_record(Box::new(move |record, error| {
+                errors.lock().unwrap().push(error.to_string());
+                on_error.reject(&controller, endpoint_id, &endpoint_name, record, error)
+            }));
+        }
+
         Self {
             endpoint_id,
             endpoint_name: endpoint_name.to_owned(),
             parser,
+            on_error,
+            errors,
             controller,
             circuit_thread_unparker,
             backpressure_thread_unparker,
         }
     }
+
+    /// Take parse errors accumulated since the last call.
+    fn take_errors(&self) -> Vec<String> {
+        take(&mut *self.errors.lock().unwrap())
+    }
+
+    /// Handle a parser error.
+    ///
+    /// Errors returned by the parser apply to the entire input buffer
+    /// `data`, which gets rejected as a single record.
+    fn parse_error(&mut self, data: &[u8], error: AnyError) {
+        self.parser.clear();
+        self.errors.lock().unwrap().push(error.to_string());
+        self.on_error.reject(
+            &self.controller,
+            self.endpoint_id,
+            &self.endpoint_name,
+            data,
+            error,
+        );
+    }
 }
 
 /// `InputConsumer` interface exposed to the transport endpoint.
 impl InputConsumer for InputProbe {
-    fn input(&mut self, data: &[u8]) {
+    fn input(&mut self, data: &[u8]) -> Vec<String> {
         // println!("input consumer {} bytes", data.len());
         // Pass input buffer to the parser.
         match self.parser.input(data) {
@@ -810,15 +1906,13 @@ impl InputConsumer for InputProbe {
                     &self.backpressure_thread_unparker,
                 );
             }
-            Err(error) => {
-                self.parser.clear();
-                self.controller
-                    .error(ControllerError::parse_error(&self.endpoint_name, error));
-            }
+            Err(error) => self.parse_error(data, error),
         }
+
+        self.take_errors()
     }
 
-    fn eoi(&mut self) {
+    fn eoi(&mut self) -> Vec<String> {
         // The endpoint reached end-of-file.  Notify and flush the parser (even though
         // no new data has been received, the parser may contain some partially
         // parsed data and may be waiting for, e.g., and end-of-line or
@@ -833,12 +1927,10 @@ impl InputConsumer for InputProbe {
                     &self.circuit_thread_unparker,
                 );
             }
-            Err(error) => {
-                self.parser.clear();
-                self.controller
-                    .error(ControllerError::parse_error(&self.endpoint_name, error));
-            }
+            Err(error) => self.parse_error(&[], error),
         }
+
+        self.take_errors()
     }
 
     fn error(&mut self, fatal: bool, error: AnyError) {
@@ -851,6 +1943,7 @@ impl InputConsumer for InputProbe {
             self.endpoint_id,
             &self.endpoint_name,
             self.parser.fork(),
+            self.on_error.clone(),
             self.controller.clone(),
             self.circuit_thread_unparker.clone(),
             self.backpressure_thread_unparker.clone(),
@@ -860,31 +1953,73 @@ impl InputConsumer for InputProbe {
 
 /// An output probe inserted between the encoder and the output transport
 /// endpoint to track stats.
+///
+/// Once the endpoint fails to push a buffer, the probe drops the remaining
+/// buffers of the step and reports the error from `batch_end` instead of
+/// ending the batch, so the partial output of the step is never committed.
 struct OutputProbe {
     endpoint_id: EndpointId,
-    endpoint_name: String,
     endpoint: Box<dyn OutputEndpoint>,
     controller: Arc<ControllerInner>,
+    // The first error returned by the endpoint while pushing the output of
+    // the current step.
+    push_error: Option<AnyError>,
 }
 
 impl OutputProbe {
     pub fn new(
         endpoint_id: EndpointId,
-        endpoint_name: &str,
         endpoint: Box<dyn OutputEndpoint>,
         controller: Arc<ControllerInner>,
     ) -> Self {
         Self {
             endpoint_id,
-            endpoint_name: endpoint_name.to_owned(),
             endpoint,
             controller,
+            push_error: None,
         }
     }
 }
 
 impl OutputConsumer for OutputProbe {
+    fn batch_start(&mut self, step: Step) -> AnyResult<()> {
+        self.push_error = None;
+        self.endpoint.batch_start(step)
+    }
+
+    fn batch_end(&mut self) -> AnyResult<()> {
+        match self.push_error.take() {
+            Some(error) => Err(error),
+            None => self.endpoint.batch_end(),
+        }
+    }
+
+    fn is_keyed(&self) -> bool {
+        self.endpoint.is_keyed()
+    }
+
+    fn push_key(&mut self, key: &[u8], val: Option<&[u8]>) {
+        if self.push_error.is_some() {
+            return;
+        }
+
+        let num_bytes = key.len() + val.map_or(0, |val| val.len());
+
+        match self.endpoint.push_key(key, val) {
+            Ok(()) => {
+                self.controller
+                    .status
+                    .output_buffer(self.endpoint_id, num_bytes);
+            }
+            Err(error) => self.push_error = Some(error),
+        }
+    }
+
     fn push_buffer(&mut self, buffer: &[u8]) {
+        if self.push_error.is_some() {
+            return;
+        }
+
         let num_bytes = buffer.len();
 
         match self.endpoint.push_buffer(buffer) {
@@ -893,14 +2028,7 @@ impl OutputConsumer for OutputProbe {
                     .status
                     .output_buffer(self.endpoint_id, num_bytes);
             }
-            Err(error) => {
-                self.controller.output_transport_error(
-                    self.endpoint_id,
-                    &self.endpoint_name,
-                    false,
-                    error,
-                );
-            }
+            Err(error) => self.push_error = Some(error),
         }
     }
 }
@@ -908,12 +2036,23 @@ impl OutputConsumer for OutputProbe {
 #[cfg(test)]
 mod test {
     use crate::{
-        test::{generate_test_batch, test_circuit, wait, TestStruct},
-        Controller, ControllerConfig,
+        test::{generate_test_batch, test_circuit, test_distinct_circuit, wait, TestStruct},
+        Controller, ControllerConfig, ControllerError, InputEndpoint, OutputEndpoint,
+        PipelineState, Step,
     };
+    use anyhow::{Error as AnyError, Result as AnyResult};
     use csv::{ReaderBuilder as CsvReaderBuilder, WriterBuilder as CsvWriterBuilder};
-    use std::fs::remove_file;
-    use tempfile::NamedTempFile;
+    use std::{
+        fs::{remove_file, OpenOptions},
+        io::Write,
+        path::Path,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+    use tempfile::{NamedTempFile, TempDir};
 
     use proptest::prelude::*;
 
@@ -1019,4 +2158,949 @@ outputs:
             assert_eq!(actual, expected);
         }
     }
+
+    #[test]
+    fn test_checkpoint() {
+        let (circuit, catalog) = test_distinct_circuit(2);
+
+        let temp_input_file1 = NamedTempFile::new().unwrap();
+        let temp_input_file2 = NamedTempFile::new().unwrap();
+        let temp_output_file1 = NamedTempFile::new().unwrap();
+        let temp_output_file2 = NamedTempFile::new().unwrap();
+        let checkpoint_dir = TempDir::new().unwrap();
+        let checkpoint_path = checkpoint_dir.path().join("checkpoint");
+
+        let config_str = |restore_from: &str, input_name: &str, input: &Path, output: &Path| {
+            format!(
+                r#"
+{restore_from}
+inputs:
+    {input_name}:
+        transport:
+            name: file
+            config:
+                path: {:?}
+                follow: true
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+outputs:
+    test_output1:
+        stream: test_output1
+        transport:
+            name: file
+            config:
+                path: {:?}
+        format:
+            name: csv
+        "#,
+                input.to_str().unwrap(),
+                output.to_str().unwrap(),
+            )
+        };
+
+        let config: ControllerConfig = serde_yaml::from_str(&config_str(
+            "",
+            "test_input1",
+            temp_input_file1.path(),
+            temp_output_file1.path(),
+        ))
+        .unwrap();
+        let controller = Controller::with_config(
+            circuit,
+            catalog,
+            &config,
+            Box::new(|e| panic!("error: {e}")),
+        )
+        .unwrap();
+
+        let record = |id| TestStruct {
+            id,
+            b: true,
+            i: None,
+            s: "foo".to_string(),
+        };
+
+        let mut writer = CsvWriterBuilder::new()
+            .has_headers(false)
+            .from_writer(temp_input_file1.as_file());
+        writer.serialize(record(0)).unwrap();
+        writer.flush().unwrap();
+        controller.start();
+
+        wait(
+            || {
+                controller
+                    .status()
+                    .output_status()
+                    .get(&0)
+                    .unwrap()
+                    .transmitted_records()
+                    == 1
+            },
+            None,
+        );
+
+        // The checkpoint includes all evaluated steps; the controller keeps
+        // running afterwards.
+        let step = controller.checkpoint(&checkpoint_path).unwrap();
+        assert!(step > 0);
+        assert!(step <= controller.inner.num_steps.load(Ordering::Acquire));
+        assert!(checkpoint_path.join("metadata.json").is_file());
+        assert!(checkpoint_path.join("circuit.bin").is_file());
+        assert!(controller.inner.state() == PipelineState::Running);
+
+        // Overwrite the checkpoint.
+        assert!(controller.checkpoint(&checkpoint_path).unwrap() >= step);
+        let step = controller.inner.num_steps.load(Ordering::Acquire);
+        controller.pause();
+        assert_eq!(controller.checkpoint(&checkpoint_path).unwrap(), step);
+        assert!(controller.inner.state() == PipelineState::Paused);
+        controller.stop().unwrap();
+
+        let restore_from = format!("restore_from: {:?}", checkpoint_path.to_str().unwrap());
+
+        // The file endpoint can't resume from its position in the checkpoint.
+        let (circuit, catalog) = test_distinct_circuit(2);
+        let config: ControllerConfig = serde_yaml::from_str(&config_str(
+            &restore_from,
+            "test_input1",
+            temp_input_file1.path(),
+            temp_output_file2.path(),
+        ))
+        .unwrap();
+        assert!(Controller::with_config(
+            circuit,
+            catalog,
+            &config,
+            Box::new(|e| panic!("error: {e}"))
+        )
+        .is_err());
+
+        // Resume from the checkpoint with a new input endpoint.
+        let (circuit, catalog) = test_distinct_circuit(2);
+        let config: ControllerConfig = serde_yaml::from_str(&config_str(
+            &restore_from,
+            "test_input2",
+            temp_input_file2.path(),
+            temp_output_file2.path(),
+        ))
+        .unwrap();
+        let controller = Controller::with_config(
+            circuit,
+            catalog,
+            &config,
+            Box::new(|e| panic!("error: {e}")),
+        )
+        .unwrap();
+        assert_eq!(controller.inner.num_steps.load(Ordering::Acquire), step);
+        assert_eq!(*controller.inner.committed_steps.lock().unwrap(), step);
+
+        let mut writer = CsvWriterBuilder::new()
+            .has_headers(false)
+            .from_writer(temp_input_file2.as_file());
+        writer.serialize(record(0)).unwrap();
+        writer.serialize(record(1)).unwrap();
+        writer.flush().unwrap();
+        controller.start();
+
+        wait(
+            || {
+                controller
+                    .status()
+                    .output_status()
+                    .get(&0)
+                    .unwrap()
+                    .transmitted_records()
+                    == 1
+            },
+            None,
+        );
+        controller.stop().unwrap();
+
+        // The restored circuit has already seen record 0, so only record 1
+        // is new.
+        let actual: Vec<(TestStruct, i32)> = CsvReaderBuilder::new()
+            .has_headers(false)
+            .from_path(temp_output_file2.path())
+            .unwrap()
+            .deserialize()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(actual, vec![(record(1), 1)]);
+
+        // A checkpoint taken with a different number of workers can't be
+        // restored.
+        let (circuit, catalog) = test_distinct_circuit(1);
+        assert!(Controller::with_config(
+            circuit,
+            catalog,
+            &config,
+            Box::new(|e| panic!("error: {e}"))
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_connect_disconnect() {
+        let (circuit, catalog) = test_circuit(2);
+
+        let temp_input_file = NamedTempFile::new().unwrap();
+        let temp_output_file1 = NamedTempFile::new().unwrap();
+        let temp_output_file2 = NamedTempFile::new().unwrap();
+
+        let config: ControllerConfig = serde_yaml::from_str("inputs: {}").unwrap();
+        let controller = Controller::with_config(
+            circuit,
+            catalog,
+            &config,
+            Box::new(|e| panic!("error: {e}")),
+        )
+        .unwrap();
+        controller.start();
+
+        let input_config = serde_yaml::from_str(&format!(
+            r#"
+transport:
+    name: file
+    config:
+        path: {:?}
+        follow: true
+format:
+    name: csv
+    config:
+        input_stream: test_input1
+"#,
+            temp_input_file.path().to_str().unwrap()
+        ))
+        .unwrap();
+        let output_config = |path: &Path| {
+            serde_yaml::from_str(&format!(
+                r#"
+stream: test_output1
+transport:
+    name: file
+    config:
+        path: {:?}
+format:
+    name: csv
+"#,
+                path.to_str().unwrap()
+            ))
+            .unwrap()
+        };
+
+        controller
+            .connect_input("test_input1", &input_config)
+            .unwrap();
+        controller
+            .connect_output("test_output1", &output_config(temp_output_file1.path()))
+            .unwrap();
+        assert!(controller
+            .connect_output("test_output2", &output_config(temp_output_file2.path()))
+            .is_err());
+
+        let write_record = |id| {
+            let mut writer = CsvWriterBuilder::new()
+                .has_headers(false)
+                .from_writer(temp_input_file.as_file());
+            writer
+                .serialize(TestStruct {
+                    id,
+                    b: true,
+                    i: None,
+                    s: "foo".to_string(),
+                })
+                .unwrap();
+            writer.flush().unwrap();
+        };
+
+        write_record(0);
+        wait(
+            || {
+                controller
+                    .status()
+                    .output_status()
+                    .get(&0)
+                    .map(|status| status.transmitted_records())
+                    == Some(1)
+            },
+            None,
+        );
+
+        // Replace the output endpoint.
+        controller.disconnect_output("test_output1").unwrap();
+        assert!(controller.status().output_status().is_empty());
+        assert!(controller.disconnect_output("test_output1").is_err());
+        controller
+            .connect_output("test_output2", &output_config(temp_output_file2.path()))
+            .unwrap();
+
+        write_record(1);
+        wait(
+            || {
+                controller
+                    .status()
+                    .output_status()
+                    .values()
+                    .any(|status| status.transmitted_records() == 1)
+            },
+            None,
+        );
+
+        controller.disconnect_input("test_input1").unwrap();
+        assert!(controller.status().input_status().is_empty());
+        assert!(controller.disconnect_input("test_input1").is_err());
+
+        controller.stop().unwrap();
+
+        let read_ids = |path: &Path| {
+            CsvReaderBuilder::new()
+                .has_headers(false)
+                .from_path(path)
+                .unwrap()
+                .deserialize::<(TestStruct, i32)>()
+                .map(|res| res.unwrap().0.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(read_ids(temp_output_file1.path()), vec![0]);
+        assert_eq!(read_ids(temp_output_file2.path()), vec![1]);
+    }
+
+    /// Input endpoint that records the steps committed to it.
+    struct CommitTrackingInputEndpoint {
+        committed: Arc<Mutex<Vec<Step>>>,
+        dropped: Arc<AtomicBool>,
+    }
+
+    impl InputEndpoint for CommitTrackingInputEndpoint {
+        fn pause(&self) -> AnyResult<()> {
+            Ok(())
+        }
+
+        fn start(&self) -> AnyResult<()> {
+            Ok(())
+        }
+
+        fn disconnect(&self) {}
+
+        fn commit(&self, step: Step) -> AnyResult<()> {
+            self.committed.lock().unwrap().push(step);
+            Ok(())
+        }
+    }
+
+    impl Drop for CommitTrackingInputEndpoint {
+        fn drop(&mut self) {
+            self.dropped.store(true, Ordering::Release);
+        }
+    }
+
+    /// A disconnected input endpoint gets to commit the step that consumes
+    /// its last records before it is dropped.
+    #[test]
+    fn test_disconnect_input_commit() {
+        let (circuit, catalog) = test_circuit(2);
+
+        let config: ControllerConfig = serde_yaml::from_str("inputs: {}").unwrap();
+        let controller = Controller::with_config(
+            circuit,
+            catalog,
+            &config,
+            Box::new(|e| panic!("error: {e}")),
+        )
+        .unwrap();
+
+        let input_config = serde_yaml::from_str(
+            r#"
+transport:
+    name: test
+format:
+    name: csv
+    config:
+        input_stream: test_input1
+"#,
+        )
+        .unwrap();
+
+        let committed = Arc::new(Mutex::new(Vec::new()));
+        let dropped = Arc::new(AtomicBool::new(false));
+        let mut consumer = None;
+        controller
+            .connect_input_with("test_input1", &input_config, |probe| {
+                consumer = Some(probe);
+                Ok(Box::new(CommitTrackingInputEndpoint {
+                    committed: committed.clone(),
+                    dropped: dropped.clone(),
+                }))
+            })
+            .unwrap();
+
+        // Push a record and disconnect the endpoint before the paused
+        // circuit consumes it.
+        consumer.as_mut().unwrap().input(b"0,true,,foo\n");
+        controller.disconnect_input("test_input1").unwrap();
+        assert!(!dropped.load(Ordering::Acquire));
+
+        controller.start();
+        wait(|| dropped.load(Ordering::Acquire), None);
+        assert!(!committed.lock().unwrap().is_empty());
+
+        controller.stop().unwrap();
+    }
+
+    /// Output endpoint that fails to deliver the output of every step, either
+    /// when pushing the output or when committing it.
+    struct FailingOutputEndpoint {
+        fail_push: bool,
+        push_failed: bool,
+        // Number of steps committed after failing to push their output.
+        partial_commits: Arc<AtomicUsize>,
+    }
+
+    impl OutputEndpoint for FailingOutputEndpoint {
+        fn batch_start(&mut self, _step: Step) -> AnyResult<()> {
+            self.push_failed = false;
+            Ok(())
+        }
+
+        fn push_buffer(&mut self, _buffer: &[u8]) -> AnyResult<()> {
+            if self.fail_push {
+                self.push_failed = true;
+                Err(AnyError::msg("connection lost"))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn batch_end(&mut self) -> AnyResult<()> {
+            if self.push_failed {
+                self.partial_commits.fetch_add(1, Ordering::AcqRel);
+            }
+
+            if self.fail_push {
+                Ok(())
+            } else {
+                Err(AnyError::msg("transaction aborted"))
+            }
+        }
+    }
+
+    #[test]
+    fn test_output_failure() {
+        output_failure_test(false);
+    }
+
+    #[test]
+    fn test_output_push_failure() {
+        output_failure_test(true);
+    }
+
+    fn output_failure_test(fail_push: bool) {
+        let (circuit, catalog) = test_circuit(2);
+
+        let temp_input_file = NamedTempFile::new().unwrap();
+        std::fs::write(temp_input_file.path(), "0,true,,foo\n1,false,,bar\n").unwrap();
+
+        let config: ControllerConfig = serde_yaml::from_str(&format!(
+            r#"
+inputs:
+    test_input1:
+        transport:
+            name: file
+            config:
+                path: {:?}
+                follow: false
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+"#,
+            temp_input_file.path().to_str().unwrap(),
+        ))
+        .unwrap();
+
+        let fatal_errors = Arc::new(AtomicUsize::new(0));
+        let fatal_errors_clone = fatal_errors.clone();
+        let controller = Controller::with_config(
+            circuit,
+            catalog,
+            &config,
+            Box::new(move |e| match e {
+                ControllerError::OutputTransportError { fatal: true, .. } => {
+                    fatal_errors_clone.fetch_add(1, Ordering::AcqRel);
+                }
+                e => panic!("error: {e}"),
+            }),
+        )
+        .unwrap();
+
+        let output_config = serde_yaml::from_str(
+            r#"
+stream: test_output1
+transport:
+    name: file
+format:
+    name: csv
+"#,
+        )
+        .unwrap();
+        let partial_commits = Arc::new(AtomicUsize::new(0));
+        let partial_commits_clone = partial_commits.clone();
+        controller
+            .connect_output_with("test_output1", &output_config, move |_| {
+                Ok(Box::new(FailingOutputEndpoint {
+                    fail_push,
+                    push_failed: false,
+                    partial_commits: partial_commits_clone,
+                }))
+            })
+            .unwrap();
+        controller.start();
+
+        // The circuit keeps running, but none of its steps gets committed.
+        let global_metrics = &controller.status().global_metrics;
+        wait(
+            || {
+                controller
+                    .status()
+                    .input_status()
+                    .get(&0)
+                    .unwrap()
+                    .metrics
+                    .total_records
+                    .load(Ordering::Acquire)
+                    == 2
+                    && controller.status().num_buffered_input_records() == 0
+            },
+            None,
+        );
+        wait(|| fatal_errors.load(Ordering::Acquire) > 0, None);
+        assert!(global_metrics.step.load(Ordering::Acquire) > 0);
+        assert_eq!(global_metrics.committed_steps.load(Ordering::Acquire), 0);
+        assert_eq!(fatal_errors.load(Ordering::Acquire), 1);
+        assert_eq!(partial_commits.load(Ordering::Acquire), 0);
+
+        controller.stop().unwrap();
+    }
+
+    #[test]
+    fn test_input_error_policy() {
+        let (circuit, catalog) = test_circuit(2);
+
+        let temp_input_file = NamedTempFile::new().unwrap();
+        let temp_output_file = NamedTempFile::new().unwrap();
+        let temp_dead_letter_file = NamedTempFile::new().unwrap();
+
+        std::fs::write(
+            temp_input_file.path(),
+            "0,true,,foo\n1,maybe,,bar\n2,false,5,baz\nnonsense\n",
+        )
+        .unwrap();
+
+        let config: ControllerConfig = serde_yaml::from_str(&format!(
+            r#"
+inputs:
+    test_input1:
+        transport:
+            name: file
+            config:
+                path: {:?}
+                follow: false
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+        on_error: !dead_letter
+            name: file
+            config:
+                path: {:?}
+outputs:
+    test_output1:
+        stream: test_output1
+        transport:
+            name: file
+            config:
+                path: {:?}
+        format:
+            name: csv
+"#,
+            temp_input_file.path().to_str().unwrap(),
+            temp_dead_letter_file.path().to_str().unwrap(),
+            temp_output_file.path().to_str().unwrap(),
+        ))
+        .unwrap();
+
+        let controller = Controller::with_config(
+            circuit,
+            catalog,
+            &config,
+            Box::new(|e| panic!("error: {e}")),
+        )
+        .unwrap();
+        controller.start();
+
+        wait(
+            || {
+                controller
+                    .status()
+                    .output_status()
+                    .get(&0)
+                    .unwrap()
+                    .transmitted_records()
+                    == 2
+            },
+            None,
+        );
+        assert_eq!(
+            controller
+                .status()
+                .input_status()
+                .get(&0)
+                .unwrap()
+                .metrics
+                .num_rejected_records
+                .load(Ordering::Acquire),
+            2
+        );
+
+        controller.stop().unwrap();
+
+        let rejected = std::fs::read_to_string(temp_dead_letter_file.path())
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let line: serde_json::Value = serde_json::from_str(line).unwrap();
+                assert_eq!(line["endpoint"], "test_input1");
+                assert!(line["error"].is_string());
+                line["record"].as_str().unwrap().to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rejected, vec!["1,maybe,,bar", "nonsense"]);
+
+        // With the default policy, invalid records are reported and skipped.
+        let (circuit, catalog) = test_circuit(2);
+        let config: ControllerConfig = serde_yaml::from_str(&format!(
+            r#"
+inputs:
+    test_input1:
+        transport:
+            name: file
+            config:
+                path: {:?}
+                follow: false
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+"#,
+            temp_input_file.path().to_str().unwrap(),
+        ))
+        .unwrap();
+
+        let num_errors = Arc::new(AtomicUsize::new(0));
+        let num_errors_clone = num_errors.clone();
+        let controller = Controller::with_config(
+            circuit,
+            catalog,
+            &config,
+            Box::new(move |_| {
+                num_errors_clone.fetch_add(1, Ordering::AcqRel);
+            }),
+        )
+        .unwrap();
+        controller.start();
+
+        wait(|| num_errors.load(Ordering::Acquire) == 2, None);
+        wait(
+            || {
+                controller
+                    .status()
+                    .input_status()
+                    .get(&0)
+                    .unwrap()
+                    .metrics
+                    .num_rejected_records
+                    .load(Ordering::Acquire)
+                    == 2
+            },
+            None,
+        );
+        assert!(controller.inner.state() == PipelineState::Running);
+
+        controller.stop().unwrap();
+
+        // With the `fail` policy, parse errors pause the pipeline.
+        let (circuit, catalog) = test_circuit(2);
+        let config: ControllerConfig = serde_yaml::from_str(&format!(
+            r#"
+inputs:
+    test_input1:
+        transport:
+            name: file
+            config:
+                path: {:?}
+                follow: false
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+        on_error: fail
+"#,
+            temp_input_file.path().to_str().unwrap(),
+        ))
+        .unwrap();
+
+        let num_errors = Arc::new(AtomicUsize::new(0));
+        let num_errors_clone = num_errors.clone();
+        let controller = Controller::with_config(
+            circuit,
+            catalog,
+            &config,
+            Box::new(move |_| {
+                num_errors_clone.fetch_add(1, Ordering::AcqRel);
+            }),
+        )
+        .unwrap();
+        controller.start();
+
+        wait(|| num_errors.load(Ordering::Acquire) > 0, None);
+        wait(|| controller.inner.state() == PipelineState::Paused, None);
+        assert!(controller
+            .status()
+            .input_status()
+            .get(&0)
+            .unwrap()
+            .fatal_error
+            .lock()
+            .unwrap()
+            .is_some());
+
+        controller.stop().unwrap();
+    }
+
+    #[test]
+    fn test_drop_net_zero() {
+        let (circuit, catalog) = test_circuit(2);
+
+        let temp_input_file = NamedTempFile::new().unwrap();
+        let temp_output_file = NamedTempFile::new().unwrap();
+
+        // Buffer outputs until 8 updates have been queued: 5 inserts
+        // followed by 3 deletes, which cancel out 3 of the inserts.
+        let config: ControllerConfig = serde_yaml::from_str(&format!(
+            r#"
+inputs:
+    test_input1:
+        transport:
+            name: file
+            config:
+                path: {:?}
+                follow: true
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+                weight_column: 4
+outputs:
+    test_output1:
+        stream: test_output1
+        transport:
+            name: file
+            config:
+                path: {:?}
+        format:
+            name: csv
+        drop_net_zero: true
+        min_batch_size_records: 8
+        max_buffering_delay_usecs: 3600000000
+"#,
+            temp_input_file.path().to_str().unwrap(),
+            temp_output_file.path().to_str().unwrap(),
+        ))
+        .unwrap();
+
+        let controller = Controller::with_config(
+            circuit,
+            catalog,
+            &config,
+            Box::new(|e| panic!("error: {e}")),
+        )
+        .unwrap();
+        controller.start();
+
+        let buffered_records = || {
+            controller
+                .status()
+                .output_status()
+                .get(&0)
+                .unwrap()
+                .metrics
+                .buffered_records
+                .load(Ordering::Acquire)
+        };
+
+        let mut input = OpenOptions::new()
+            .append(true)
+            .open(temp_input_file.path())
+            .unwrap();
+        for id in 0..5 {
+            writeln!(input, "{id},true,,foo,1").unwrap();
+        }
+        input.flush().unwrap();
+        wait(|| buffered_records() == 5, None);
+
+        for id in 0..3 {
+            writeln!(input, "{id},true,,foo,-1").unwrap();
+        }
+        input.flush().unwrap();
+
+        wait(
+            || {
+                controller
+                    .status()
+                    .output_status()
+                    .get(&0)
+                    .unwrap()
+                    .transmitted_records()
+                    == 2
+            },
+            None,
+        );
+        assert_eq!(buffered_records(), 0);
+
+        controller.stop().unwrap();
+
+        let mut actual: Vec<_> = CsvReaderBuilder::new()
+            .has_headers(false)
+            .from_path(temp_output_file.path())
+            .unwrap()
+            .deserialize::<(TestStruct, i32)>()
+            .map(|res| res.unwrap())
+            .collect();
+        actual.sort();
+
+        let expected: Vec<_> = (3..5)
+            .map(|id| {
+                (
+                    TestStruct {
+                        id,
+                        b: true,
+                        i: None,
+                        s: "foo".to_string(),
+                    },
+                    1,
+                )
+            })
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_wait_for_step() {
+        let (circuit, catalog) = test_circuit(2);
+
+        let temp_input_file = NamedTempFile::new().unwrap();
+        let temp_output_file = NamedTempFile::new().unwrap();
+
+        std::fs::write(
+            temp_input_file.path(),
+            "0,true,,foo\n1,false,,bar\n2,false,5,baz\n",
+        )
+        .unwrap();
+
+        let config: ControllerConfig = serde_yaml::from_str(&format!(
+            r#"
+min_batch_size_records: 1000000
+max_buffering_delay_usecs: 1000000000
+inputs:
+    test_input1:
+        transport:
+            name: file
+            config:
+                path: {:?}
+                follow: false
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+outputs:
+    test_output1:
+        stream: test_output1
+        transport:
+            name: file
+            config:
+                path: {:?}
+        format:
+            name: csv
+"#,
+            temp_input_file.path().to_str().unwrap(),
+            temp_output_file.path().to_str().unwrap(),
+        ))
+        .unwrap();
+
+        let controller = Controller::with_config(
+            circuit,
+            catalog,
+            &config,
+            Box::new(|e| panic!("error: {e}")),
+        )
+        .unwrap();
+        controller.start();
+
+        // Wait for the controller to receive all inputs.
+        wait(
+            || {
+                controller
+                    .status()
+                    .input_status()
+                    .get(&0)
+                    .unwrap()
+                    .metrics
+                    .total_records
+                    .load(Ordering::Acquire)
+                    == 3
+            },
+            None,
+        );
+
+        // All inputs are reflected in the outputs once the next step
+        // completes.  The inputs don't reach `min_batch_size_records`, so
+        // the circuit only consumes them when asked to by a waiting client.
+        let global_metrics = &controller.status().global_metrics;
+        let step = global_metrics.step.load(Ordering::Acquire);
+        assert_eq!(step, 0);
+        assert!(controller.wait_for_step(step, None).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(temp_output_file.path())
+                .unwrap()
+                .lines()
+                .count(),
+            3
+        );
+        assert_eq!(global_metrics.committed_steps.load(Ordering::Acquire), 1);
+        assert!(
+            global_metrics
+                .input_latency_buckets
+                .iter()
+                .map(|bucket| bucket.load(Ordering::Acquire))
+                .sum::<u64>()
+                > 0
+        );
+
+        // The outputs already reflect all inputs received before the next
+        // step.
+        assert!(controller.wait_for_step(1, None).unwrap());
+
+        // Waiting for a future step doesn't force the circuit to evaluate
+        // empty steps.
+        assert!(!controller
+            .wait_for_step(2, Some(Duration::from_millis(100)))
+            .unwrap());
+        assert_eq!(global_metrics.step.load(Ordering::Acquire), 1);
+        assert!(controller.wait_for_step(Step::MAX, None).is_err());
+
+        controller.stop().unwrap();
+    }
 }
@@ -34,18 +34,69 @@ use super::{EndpointId, GlobalControllerConfig, InputEndpointConfig, OutputEndpo
 use anyhow::Error as AnyError;
 use crossbeam::sync::{ShardedLock, ShardedLockReadGuard, Unparker};
 use serde::{Serialize, Serializer};
+use serde_json::Value as JsonValue;
 use std::{
     collections::BTreeMap,
     sync::{
         atomic::{AtomicU64, Ordering},
         Mutex,
     },
+    time::{Duration, Instant},
 };
 
+/// Upper bounds of the buckets of the step latency and input latency
+/// histograms, in microseconds (see
+/// [`GlobalControllerMetrics::step_latency_buckets`] and
+/// [`GlobalControllerMetrics::input_latency_buckets`]).
+pub const STEP_LATENCY_BUCKETS_USECS: [u64; 12] = [
+    100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 1_000_000, 10_000_000,
+];
+
 #[derive(Default, Serialize)]
 pub struct GlobalControllerMetrics {
     /// Total number of records buffered by all endpoints.
     pub buffered_input_records: AtomicU64,
+
+    /// Number of steps evaluated by the circuit since the controller was
+    /// created.
+    pub total_steps: AtomicU64,
+
+    /// Total time spent evaluating steps, in microseconds.
+    pub total_step_usecs: AtomicU64,
+
+    /// Step latency histogram.
+    ///
+    /// The `i`th element counts steps that took longer than
+    /// `STEP_LATENCY_BUCKETS_USECS[i-1]` and at most
+    /// `STEP_LATENCY_BUCKETS_USECS[i]` microseconds.  The last element counts
+    /// steps that took longer than the last bound.
+    pub step_latency_buckets: [AtomicU64; STEP_LATENCY_BUCKETS_USECS.len() + 1],
+
+    /// The number of steps started by the circuit, including steps restored
+    /// from a checkpoint.
+    ///
+    /// Steps are numbered sequentially starting from 0, so this is also the
+    /// number of the next step.  Inputs received by the controller before
+    /// step `n` starts are reflected in the outputs of step `n` or earlier.
+    pub step: AtomicU64,
+
+    /// The number of steps whose outputs have been pushed to all output
+    /// endpoints.
+    ///
+    /// Step `n` is complete once this counter exceeds `n`.
+    pub committed_steps: AtomicU64,
+
+    /// Total input-to-output latency of all completed steps that consumed
+    /// inputs, in microseconds.
+    ///
+    /// The latency of a step is measured from the moment the first input
+    /// record consumed by the step was received to the moment the outputs of
+    /// the step have been pushed to all output endpoints.
+    pub total_input_latency_usecs: AtomicU64,
+
+    /// Input-to-output latency histogram, with the same buckets as
+    /// [`Self::step_latency_buckets`].
+    pub input_latency_buckets: [AtomicU64; STEP_LATENCY_BUCKETS_USECS.len() + 1],
 }
 
 type InputsStatus = ShardedLock<BTreeMap<EndpointId, InputEndpointStatus>>;
@@ -93,6 +144,11 @@ pub struct ControllerStatus {
     /// Output endpoint configs and metrics.
     #[serde(serialize_with = "serialize_outputs")]
     outputs: OutputsStatus,
+
+    /// The time when the first record not yet consumed by the circuit was
+    /// received.
+    #[serde(skip)]
+    first_buffered_input: Mutex<Option<Instant>>,
 }
 
 impl ControllerStatus {
@@ -102,6 +158,7 @@ impl ControllerStatus {
             global_metrics: Default::default(),
             inputs: ShardedLock::new(BTreeMap::new()),
             outputs: ShardedLock::new(BTreeMap::new()),
+            first_buffered_input: Mutex::new(None),
         }
     }
 
@@ -131,6 +188,20 @@ impl ControllerStatus {
         );
     }
 
+    /// Remove the stats of a disconnected input endpoint.
+    ///
+    /// Records buffered by the endpoint remain accounted for in
+    /// `GlobalControllerMetrics::buffered_input_records` until they are
+    /// consumed by the circuit.
+    pub fn remove_input(&self, endpoint_id: &EndpointId) {
+        self.inputs.write().unwrap().remove(endpoint_id);
+    }
+
+    /// Remove the stats of a disconnected output endpoint.
+    pub fn remove_output(&self, endpoint_id: &EndpointId) {
+        self.outputs.write().unwrap().remove(endpoint_id);
+    }
+
     /// Total number of records buffered by all input endpoints.
     pub fn num_buffered_input_records(&self) -> u64 {
         self.global_metrics
@@ -150,6 +221,16 @@ impl ControllerStatus {
         self.outputs.read().unwrap()
     }
 
+    /// Lookup input endpoint id by endpoint name.
+    pub fn input_endpoint_id_by_name(&self, endpoint_name: &str) -> Option<EndpointId> {
+        self.inputs
+            .read()
+            .unwrap()
+            .iter()
+            .find(|(_, endpoint_stats)| endpoint_stats.endpoint_name == endpoint_name)
+            .map(|(endpoint_id, _)| *endpoint_id)
+    }
+
     /// Number of records buffered by the endpoint or 0 if the endpoint
     /// doesn't exist (the latter is possible if the endpoint is being
     /// destroyed).
@@ -168,13 +249,67 @@ impl ControllerStatus {
     /// This method is invoked before `DBSPHandle::step` to indicate that all
     /// buffered data is about to be consumed.  See module-level documentation
     /// for details.
-    pub fn consume_buffered_inputs(&self) {
+    ///
+    /// Returns the time when the earliest of the consumed records was
+    /// received or `None` if there are no buffered records.
+    pub fn consume_buffered_inputs(&self) -> Option<Instant> {
         self.global_metrics
             .buffered_input_records
             .store(0, Ordering::Release);
         for endpoint_stats in self.inputs.read().unwrap().values() {
             endpoint_stats.consume_buffered();
         }
+        self.first_buffered_input.lock().unwrap().take()
+    }
+
+    /// Update step counters after the circuit has evaluated a step that
+    /// took `duration`.
+    pub fn step_completed(&self, duration: Duration) {
+        let usecs = duration.as_micros() as u64;
+        let bucket = STEP_LATENCY_BUCKETS_USECS.partition_point(|bound| *bound < usecs);
+
+        self.global_metrics
+            .total_steps
+            .fetch_add(1, Ordering::AcqRel);
+        self.global_metrics
+            .total_step_usecs
+            .fetch_add(usecs, Ordering::AcqRel);
+        self.global_metrics.step_latency_buckets[bucket].fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Update step counters when the circuit starts evaluating step
+    /// `num_steps - 1`.
+    pub fn steps_started(&self, num_steps: u64) {
+        self.global_metrics.step.store(num_steps, Ordering::Release);
+    }
+
+    /// Update step counters after the outputs of the first `num_steps`
+    /// steps have been pushed to all output endpoints.
+    pub fn steps_committed(&self, num_steps: u64) {
+        self.global_metrics
+            .committed_steps
+            .store(num_steps, Ordering::Release);
+    }
+
+    /// Update input latency counters after completing a step whose inputs
+    /// were received `latency` ago.
+    pub fn input_latency(&self, latency: Duration) {
+        let usecs = latency.as_micros() as u64;
+        let bucket = STEP_LATENCY_BUCKETS_USECS.partition_point(|bound| *bound < usecs);
+
+        self.global_metrics
+            .total_input_latency_usecs
+            .fetch_add(usecs, Ordering::AcqRel);
+        self.global_metrics.input_latency_buckets[bucket].fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Record the time when the controller received records while no records
+    /// were buffered.
+    fn first_input_buffered(&self) {
+        self.first_buffered_input
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Instant::now);
     }
 
     /// True if the number of records buffered by the endpoint exceeds
@@ -222,6 +357,10 @@ impl ControllerStatus {
             .buffered_input_records
             .fetch_add(num_records, Ordering::AcqRel);
 
+        if old == 0 && num_records > 0 {
+            self.first_input_buffered();
+        }
+
         if old == 0
             || (old <= global_config.min_batch_size_records
                 && old + num_records > global_config.min_batch_size_records)
@@ -273,6 +412,9 @@ impl ControllerStatus {
             .global_metrics
             .buffered_input_records
             .fetch_add(num_records, Ordering::AcqRel);
+        if old == 0 && num_records > 0 {
+            self.first_input_buffered();
+        }
         if old < global_config.min_batch_size_records
             && old + num_records >= global_config.min_batch_size_records
         {
@@ -293,14 +435,32 @@ impl ControllerStatus {
         }
     }
 
+    /// Update counters after pushing the outputs of `num_batches` steps to
+    /// the endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint_id` - id of the output endpoint.
+    /// * `num_batches` - number of steps whose outputs have been pushed.
+    /// * `num_records` - number of records queued for these steps (see
+    ///   [`Self::enqueue_batch`]).
+    /// * `num_transmitted_records` - number of records actually pushed to the
+    ///   endpoint, which can be smaller than `num_records` if the endpoint
+    ///   drops updates that cancel out.
+    /// * `circuit_thread_unparker` - unparker used to wake up the circuit
+    ///   thread if the number of buffered records drops below
+    ///   `max_buffered_records`.
     pub fn output_batch(
         &self,
         endpoint_id: EndpointId,
+        num_batches: usize,
         num_records: usize,
+        num_transmitted_records: usize,
         circuit_thread_unparker: &Unparker,
     ) {
         if let Some(endpoint_stats) = self.output_status().get(&endpoint_id) {
-            let old = endpoint_stats.output_batch(num_records);
+            let old =
+                endpoint_stats.output_batch(num_batches, num_records, num_transmitted_records);
             if old < endpoint_stats.config.max_buffered_records
                 && old + num_records as u64 >= endpoint_stats.config.max_buffered_records
             {
@@ -331,6 +491,23 @@ impl ControllerStatus {
         }
     }
 
+    /// Increment the number of records rejected by the endpoint's parser.
+    pub fn input_rejected(&self, endpoint_id: EndpointId) {
+        if let Some(endpoint_stats) = self.input_status().get(&endpoint_id) {
+            endpoint_stats
+                .metrics
+                .num_rejected_records
+                .fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    /// Update the input position reported by the endpoint.
+    pub fn input_position(&self, endpoint_id: EndpointId, position: Option<JsonValue>) {
+        if let Some(endpoint_stats) = self.input_status().get(&endpoint_id) {
+            *endpoint_stats.metrics.position.lock().unwrap() = position;
+        }
+    }
+
     pub fn output_transport_error(&self, endpoint_id: EndpointId, fatal: bool, error: &AnyError) {
         if let Some(endpoint_stats) = self.output_status().get(&endpoint_id) {
             endpoint_stats.error(fatal, error);
@@ -355,6 +532,17 @@ pub struct InputEndpointMetrics {
     pub buffered_records: AtomicU64,
 
     pub num_errors: AtomicU64,
+
+    /// Number of records that failed to parse and were dropped or sent to
+    /// the dead-letter endpoint according to the endpoint's `on_error`
+    /// policy.
+    pub num_rejected_records: AtomicU64,
+
+    /// Transport-specific input position at the start of the last step,
+    /// e.g., per-partition offsets for Kafka endpoints.
+    ///
+    /// `None` if the endpoint doesn't track its position.
+    pub position: Mutex<Option<JsonValue>>,
 }
 
 /// Input endpoint status information.
@@ -472,16 +660,23 @@ impl OutputEndpointStatus {
         self.metrics.buffered_batches.fetch_add(1, Ordering::AcqRel);
     }
 
-    fn output_batch(&self, num_records: usize) -> u64 {
+    fn output_batch(
+        &self,
+        num_batches: usize,
+        num_records: usize,
+        num_transmitted_records: usize,
+    ) -> u64 {
         self.metrics
             .transmitted_records
-            .fetch_add(num_records as u64, Ordering::Relaxed);
+            .fetch_add(num_transmitted_records as u64, Ordering::Relaxed);
 
         let old = self
             .metrics
             .buffered_records
             .fetch_sub(num_records as u64, Ordering::AcqRel);
-        self.metrics.buffered_batches.fetch_sub(1, Ordering::AcqRel);
+        self.metrics
+            .buffered_batches
+            .fetch_sub(num_batches as u64, Ordering::AcqRel);
         old
     }
 
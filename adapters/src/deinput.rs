@@ -163,6 +163,23 @@ pub trait DeCollectionHandle: Send {
     /// documentation for details.
     fn delete(&mut self, deserializer: &mut dyn ErasedDeserializer) -> Result<(), EError>;
 
+    /// Buffer a new delete update specified by the complete value of the
+    /// deleted record.
+    ///
+    /// Unlike [`delete`](`Self::delete`), this method always expects the
+    /// serialized record to match the value type of the stream, including
+    /// streams created with
+    /// [`Circuit::add_input_map`](`dbsp::Circuit::add_input_map`), which
+    /// extract the key to delete from the value.  This is useful for input
+    /// formats, such as change data capture streams, that carry the old value
+    /// of each deleted or modified record.
+    ///
+    /// The default implementation invokes [`delete`](`Self::delete`), which
+    /// is correct for streams that support deletion by value.
+    fn delete_val(&mut self, deserializer: &mut dyn ErasedDeserializer) -> Result<(), EError> {
+        self.delete(deserializer)
+    }
+
     /// Reserve space for at least `reservation` more updates in the
     /// internal input buffer.
     ///
@@ -336,6 +353,10 @@ where
 /// The [`delete`](`Self::delete`) method of this handle deserializes value
 /// `k` type `K` and buffers a `(k, None)` update for the underlying
 /// `UpsertHandle`.
+///
+/// The [`delete_val`](`Self::delete_val`) method of this handle deserializes
+/// value `v` type `V` and buffers a `(key_func(v), None)` update for the
+/// underlying `UpsertHandle`.
 pub struct DeMapHandle<K, V, F> {
     updates: Vec<(K, Option<V>)>,
     key_func: F,
@@ -373,6 +394,14 @@ where
         Ok(())
     }
 
+    fn delete_val(&mut self, deserializer: &mut dyn ErasedDeserializer) -> Result<(), EError> {
+        let val = deserialize::<V>(deserializer)?;
+        let key = (self.key_func)(&val);
+
+        self.updates.push((key, None));
+        Ok(())
+    }
+
     fn reserve(&mut self, reservation: usize) {
         self.updates.reserve(reservation);
     }
@@ -679,6 +708,47 @@ mod test {
         assert_eq!(map_output.consolidate(), map);
     }
 
+    // Delete `inputs` in JSON format by value.
+    fn delete_val_json(
+        dbsp: &mut DBSPHandle,
+        input_handles: &mut InputHandles,
+        output_handles: &OutputHandles,
+        inputs: &[TestStruct],
+    ) {
+        let zset = OrdZSet::from_tuples(
+            (),
+            inputs.iter().map(|v| (v.clone(), -1)).collect::<Vec<_>>(),
+        );
+        let map = <OrdIndexedZSet<i64, TestStruct, isize, usize>>::from_tuples(
+            (),
+            inputs
+                .iter()
+                .map(|v| ((v.id, v.clone()), -1isize))
+                .collect::<Vec<_>>(),
+        );
+
+        for input in inputs.iter() {
+            let input = to_json_string(input).unwrap();
+
+            for handle in [
+                &mut input_handles.0,
+                &mut input_handles.1,
+                &mut input_handles.2,
+            ] {
+                let mut deserializer = JsonDeserializer::new(StrRead::new(&input));
+                let mut deserializer = <dyn ErasedDeserializer>::erase(&mut deserializer);
+                handle.delete_val(&mut deserializer).unwrap();
+                handle.flush();
+            }
+        }
+
+        dbsp.step().unwrap();
+
+        assert_eq!(output_handles.0.consolidate(), zset);
+        assert_eq!(output_handles.1.consolidate(), zset);
+        assert_eq!(output_handles.2.consolidate(), map);
+    }
+
     #[test]
     fn test_collection() {
         let (mut dbsp, mut input_handles, output_handles) = decollection_test_circuit(NUM_WORKERS);
@@ -718,6 +788,9 @@ mod test {
             &inputs,
         );
 
+        insert_csv(&mut dbsp, &mut input_handles, &output_handles, &inputs);
+        delete_val_json(&mut dbsp, &mut input_handles, &output_handles, &inputs);
+
         dbsp.kill().unwrap();
     }
 }
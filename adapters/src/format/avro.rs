@@ -0,0 +1,990 @@
+use crate::{
+    format::{Encoder, InputFormat, OutputFormat, Parser},
+    Catalog, DeCollectionHandle, OutputConsumer, SerBatch,
+};
+use anyhow::{Error as AnyError, Result as AnyResult};
+use apache_avro::{types::Value as AvroValue, Schema as AvroSchema};
+use erased_serde::{Deserializer as ErasedDeserializer, Serialize as ErasedSerialize};
+use serde::{
+    de::{
+        value::{Error as DeError, MapDeserializer, SeqDeserializer},
+        Error as _, IntoDeserializer, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize, Deserializer,
+};
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use std::{
+    borrow::Cow,
+    io::{Read, Result as IoResult},
+    mem::take,
+    sync::{Arc, Mutex},
+};
+
+/// Magic bytes at the start of an Avro object container file.
+const AVRO_MAGIC: &[u8] = b"Obj\x01";
+
+/// Size of the sync marker that follows the header and each data block of
+/// an object container file.
+const SYNC_MARKER_SIZE: usize = 16;
+
+/// Largest byte sequence or data block accepted by the parser.
+///
+/// Guards against corrupted lengths, which would otherwise make the parser
+/// buffer input indefinitely waiting for the rest of the value.
+const MAX_BLOCK_SIZE: usize = 1 << 30;
+
+/// Avro format parser.
+pub struct AvroInputFormat;
+
+#[derive(Deserialize)]
+struct AvroParserConfig {
+    /// Input stream to feed parsed records to.
+    input_stream: String,
+
+    /// Writer schema in JSON format.
+    ///
+    /// When specified, the input stream must consist of Avro datums encoded
+    /// using this schema, without any additional framing.  This is the
+    /// typical representation for message-oriented transports like Kafka.
+    /// A datum can be split across multiple input buffers.
+    ///
+    /// When `None`, the input stream is expected to be an Avro object
+    /// container file, which starts with a header that contains the writer
+    /// schema.
+    #[serde(default)]
+    schema: Option<String>,
+}
+
+impl InputFormat for AvroInputFormat {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("avro")
+    }
+
+    fn new_parser(
+        &self,
+        config: &YamlValue,
+        catalog: &Arc<Mutex<Catalog>>,
+    ) -> AnyResult<Box<dyn Parser>> {
+        let config = AvroParserConfig::deserialize(config)?;
+        let schema = config
+            .schema
+            .as_ref()
+            .map(|schema| AvroSchema::parse_str(schema))
+            .transpose()
+            .map_err(|e| AnyError::msg(format!("invalid Avro schema: {e}")))?;
+
+        catalog
+            .lock()
+            .unwrap()
+            .input_collection_handle(&config.input_stream)
+            .map(|stream| Box::new(AvroParser::new(stream, schema)) as Box<dyn Parser>)
+            .ok_or_else(|| AnyError::msg(format!("unknown stream '{}'", config.input_stream)))
+    }
+}
+
+/// Object container file header.
+#[derive(Clone)]
+struct ContainerHeader {
+    schema: AvroSchema,
+    sync_marker: [u8; SYNC_MARKER_SIZE],
+}
+
+/// Parser state.
+#[derive(Clone)]
+enum AvroParserState {
+    /// Parsing a stream of datums with a schema specified in the config.
+    Datums(AvroSchema),
+
+    /// Waiting for the header of an object container file.
+    ContainerHeader,
+
+    /// Header parsed; parsing data blocks.
+    ContainerBlocks(ContainerHeader),
+}
+
+struct AvroParser {
+    /// Input handle to push parsed data to.
+    input_stream: Box<dyn DeCollectionHandle>,
+
+    /// Schema specified in the config.
+    config_schema: Option<AvroSchema>,
+
+    state: AvroParserState,
+
+    /// We cannot assume that the input buffer ends on a datum or block
+    /// boundary, so we save the "leftover" part of the buffer that contains
+    /// an incomplete datum, header, or block and prepend it to the next
+    /// input buffer.
+    leftover: Vec<u8>,
+}
+
+impl AvroParser {
+    fn new(input_stream: &dyn DeCollectionHandle, schema: Option<AvroSchema>) -> Self {
+        let state = match &schema {
+            Some(schema) => AvroParserState::Datums(schema.clone()),
+            None => AvroParserState::ContainerHeader,
+        };
+
+        Self {
+            input_stream: input_stream.fork(),
+            config_schema: schema,
+            state,
+            leftover: Vec::new(),
+        }
+    }
+
+    /// Decode `count` datums from container data block `data` and push them
+    /// to the input handle.
+    ///
+    /// Returns the number of records parsed.
+    fn parse_datums(
+        input_stream: &mut dyn DeCollectionHandle,
+        schema: &AvroSchema,
+        mut data: &[u8],
+        count: usize,
+    ) -> AnyResult<usize> {
+        let mut num_records = 0;
+
+        while !data.is_empty() && num_records < count {
+            let value = apache_avro::from_avro_datum(schema, &mut data, None)
+                .map_err(|e| AnyError::msg(format!("error decoding Avro datum: {e}")))?;
+            let mut deserializer = <dyn ErasedDeserializer>::erase(AvroDeserializer(&value));
+            input_stream.insert(&mut deserializer)?;
+            num_records += 1;
+        }
+
+        if num_records < count {
+            return Err(AnyError::msg(format!(
+                "Avro data block contains {num_records} records instead of {count}"
+            )));
+        }
+
+        Ok(num_records)
+    }
+
+    /// Parse as many complete datums as possible from `data`.
+    ///
+    /// Stops at the first incomplete datum or at the first datum that
+    /// fails to parse.  Returns the number of parsed records and the number
+    /// of bytes consumed from `data`.  Since datums are not delimited, the
+    /// parser cannot skip a datum that fails to decode; in this case the
+    /// entire buffer is consumed.
+    fn parse_datum_stream(
+        input_stream: &mut dyn DeCollectionHandle,
+        schema: &AvroSchema,
+        data: &[u8],
+    ) -> (AnyResult<usize>, usize) {
+        let mut consumed = 0;
+        let mut num_records = 0;
+
+        while consumed < data.len() {
+            let mut reader = EofTrackingReader::new(&data[consumed..]);
+            let value = match apache_avro::from_avro_datum(schema, &mut reader, None) {
+                Ok(value) => value,
+                // Incomplete datum: wait for more data.
+                Err(_) if reader.eof => break,
+                Err(e) => {
+                    return (
+                        Err(AnyError::msg(format!("error decoding Avro datum: {e}"))),
+                        data.len(),
+                    )
+                }
+            };
+            // A datum that occupies zero bytes, e.g., an empty record, can be
+            // decoded from any input, which would make us loop forever.
+            if reader.data.len() == data.len() - consumed {
+                return (
+                    Err(AnyError::msg(
+                        "Avro schema encodes datums as zero bytes, which cannot be parsed \
+                         from a stream without framing",
+                    )),
+                    data.len(),
+                );
+            }
+            consumed = data.len() - reader.data.len();
+
+            let mut deserializer = <dyn ErasedDeserializer>::erase(AvroDeserializer(&value));
+            if let Err(e) = input_stream.insert(&mut deserializer) {
+                return (Err(e.into()), consumed);
+            }
+            num_records += 1;
+        }
+
+        (Ok(num_records), consumed)
+    }
+
+    /// Parse as many complete datums or container blocks as possible from
+    /// `data`, depending on the parser mode.
+    ///
+    /// Returns the number of parsed records and the number of bytes consumed
+    /// from `data`.
+    fn parse(&mut self, data: &[u8]) -> (AnyResult<usize>, usize) {
+        match &self.state {
+            AvroParserState::Datums(schema) => {
+                Self::parse_datum_stream(self.input_stream.as_mut(), schema, data)
+            }
+            _ => self.parse_container(data),
+        }
+    }
+
+    /// Parse the object container file header and as many complete data
+    /// blocks as possible from `data`.
+    ///
+    /// Returns the number of parsed records and the number of bytes consumed
+    /// from `data`.
+    fn parse_container(&mut self, data: &[u8]) -> (AnyResult<usize>, usize) {
+        let mut consumed = 0;
+        let mut num_records = 0;
+
+        loop {
+            let mut reader = &data[consumed..];
+            match &self.state {
+                AvroParserState::Datums(_) => unreachable!(),
+                AvroParserState::ContainerHeader => match parse_header(&mut reader) {
+                    Ok(None) => return (Ok(num_records), consumed),
+                    Ok(Some(header)) => {
+                        self.state = AvroParserState::ContainerBlocks(header);
+                    }
+                    Err(e) => return (Err(e), data.len()),
+                },
+                AvroParserState::ContainerBlocks(header) => {
+                    let (count, block) = match read_block(&mut reader) {
+                        Ok(Some(block)) => block,
+                        Ok(None) => return (Ok(num_records), consumed),
+                        Err(e) => return (Err(e), data.len()),
+                    };
+                    if reader[..SYNC_MARKER_SIZE] != header.sync_marker {
+                        return (
+                            Err(AnyError::msg("invalid sync marker in Avro container file")),
+                            data.len(),
+                        );
+                    }
+                    reader = &reader[SYNC_MARKER_SIZE..];
+
+                    match Self::parse_datums(
+                        self.input_stream.as_mut(),
+                        &header.schema,
+                        block,
+                        count,
+                    ) {
+                        Ok(n) => num_records += n,
+                        Err(e) => {
+                            return (Err(e), data.len() - reader.len());
+                        }
+                    }
+                }
+            }
+            consumed = data.len() - reader.len();
+        }
+    }
+}
+
+/// Reader over a byte slice that records whether the consumer attempted to
+/// read past the end of the slice.
+///
+/// Used to tell an incomplete Avro datum from an invalid one.
+struct EofTrackingReader<'a> {
+    data: &'a [u8],
+    eof: bool,
+}
+
+impl<'a> EofTrackingReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, eof: false }
+    }
+}
+
+impl<'a> Read for EofTrackingReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        if buf.len() > self.data.len() {
+            self.eof = true;
+        }
+        self.data.read(buf)
+    }
+}
+
+/// Read a zigzag-encoded variable-length long.
+///
+/// Returns `None` if `reader` does not contain a complete value.
+fn read_long(reader: &mut &[u8]) -> Option<i64> {
+    let mut value = 0u64;
+
+    for (i, &byte) in reader.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            *reader = &reader[i + 1..];
+            return Some((value >> 1) as i64 ^ -((value & 1) as i64));
+        }
+    }
+
+    None
+}
+
+/// Read a non-negative length or count no greater than `MAX_BLOCK_SIZE`.
+///
+/// Returns `Ok(None)` if `reader` does not contain a complete value.
+fn read_size(reader: &mut &[u8], what: &str) -> AnyResult<Option<usize>> {
+    let size = match read_long(reader) {
+        Some(size) => size,
+        None => return Ok(None),
+    };
+    match usize::try_from(size) {
+        Ok(size) if size <= MAX_BLOCK_SIZE => Ok(Some(size)),
+        _ => Err(AnyError::msg(format!(
+            "invalid {what} {size} in Avro container file"
+        ))),
+    }
+}
+
+/// Read a length-prefixed byte sequence.
+///
+/// Returns `Ok(None)` if `reader` does not contain a complete value.
+fn read_bytes<'a>(reader: &mut &'a [u8]) -> AnyResult<Option<&'a [u8]>> {
+    let len = match read_size(reader, "length")? {
+        Some(len) => len,
+        None => return Ok(None),
+    };
+    if reader.len() < len {
+        return Ok(None);
+    }
+    let (bytes, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(Some(bytes))
+}
+
+/// Read a data block of an object container file, including the sync marker
+/// that follows it.
+///
+/// Returns the number of records in the block and the encoded records or
+/// `Ok(None)` if `reader` does not contain a complete block.  On success,
+/// the sync marker is left at the start of `reader`.
+fn read_block<'a>(reader: &mut &'a [u8]) -> AnyResult<Option<(usize, &'a [u8])>> {
+    let count = match read_size(reader, "record count")? {
+        Some(count) => count,
+        None => return Ok(None),
+    };
+    let block = match read_bytes(reader)? {
+        Some(block) => block,
+        None => return Ok(None),
+    };
+
+    if reader.len() < SYNC_MARKER_SIZE {
+        return Ok(None);
+    }
+
+    Ok(Some((count, block)))
+}
+
+/// Parse the header of an object container file.
+///
+/// Returns `Ok(None)` if `reader` does not contain the complete header.
+fn parse_header(reader: &mut &[u8]) -> AnyResult<Option<ContainerHeader>> {
+    if reader.len() < AVRO_MAGIC.len() {
+        return Ok(None);
+    }
+    if &reader[..AVRO_MAGIC.len()] != AVRO_MAGIC {
+        return Err(AnyError::msg(
+            "input is not an Avro object container file (no schema specified in the config and \
+             the input does not start with the container file magic bytes)",
+        ));
+    }
+    *reader = &reader[AVRO_MAGIC.len()..];
+
+    // File metadata is encoded as an Avro `map<bytes>`: a sequence of blocks
+    // terminated by an empty block.
+    let mut schema = None;
+    let mut codec = None;
+    loop {
+        let count = match read_long(reader) {
+            Some(count) => count,
+            None => return Ok(None),
+        };
+        if count == 0 {
+            break;
+        }
+        // A negative count is followed by the size of the block in bytes.
+        if count < 0 && read_long(reader).is_none() {
+            return Ok(None);
+        }
+        for _ in 0..count.unsigned_abs() {
+            let key = match read_bytes(reader)? {
+                Some(key) => key,
+                None => return Ok(None),
+            };
+            let value = match read_bytes(reader)? {
+                Some(value) => value,
+                None => return Ok(None),
+            };
+            match key {
+                b"avro.schema" => schema = Some(value),
+                b"avro.codec" => codec = Some(value),
+                _ => {}
+            }
+        }
+    }
+
+    if reader.len() < SYNC_MARKER_SIZE {
+        return Ok(None);
+    }
+    let mut sync_marker = [0; SYNC_MARKER_SIZE];
+    sync_marker.copy_from_slice(&reader[..SYNC_MARKER_SIZE]);
+    *reader = &reader[SYNC_MARKER_SIZE..];
+
+    if let Some(codec) = codec {
+        if codec != b"null" {
+            return Err(AnyError::msg(format!(
+                "unsupported Avro compression codec '{}'",
+                String::from_utf8_lossy(codec)
+            )));
+        }
+    }
+
+    let schema = schema
+        .ok_or_else(|| AnyError::msg("Avro container file header does not contain a schema"))?;
+    let schema = AvroSchema::parse_str(&String::from_utf8_lossy(schema))
+        .map_err(|e| AnyError::msg(format!("invalid Avro schema in container file: {e}")))?;
+
+    Ok(Some(ContainerHeader {
+        schema,
+        sync_marker,
+    }))
+}
+
+impl Parser for AvroParser {
+    fn input(&mut self, data: &[u8]) -> AnyResult<usize> {
+        let mut buffer = take(&mut self.leftover);
+        buffer.extend_from_slice(data);
+
+        let (res, consumed) = self.parse(&buffer);
+        buffer.drain(0..consumed);
+        self.leftover = buffer;
+
+        res
+    }
+
+    fn eoi(&mut self) -> AnyResult<usize> {
+        if self.leftover.is_empty() {
+            return Ok(0);
+        }
+
+        let leftover = take(&mut self.leftover);
+        let (res, consumed) = self.parse(&leftover);
+
+        if consumed < leftover.len() {
+            res?;
+            let what = match self.state {
+                AvroParserState::Datums(_) => "datum",
+                _ => "data block",
+            };
+            return Err(AnyError::msg(format!(
+                "incomplete Avro {what} at the end of input ({} bytes)",
+                leftover.len() - consumed
+            )));
+        }
+
+        res
+    }
+
+    fn flush(&mut self) {
+        self.input_stream.flush();
+    }
+
+    fn clear(&mut self) {
+        self.input_stream.clear_buffer();
+    }
+
+    fn fork(&self) -> Box<dyn Parser> {
+        Box::new(Self::new(&*self.input_stream, self.config_schema.clone()))
+    }
+}
+
+/// Serde deserializer that reads from a decoded Avro value.
+///
+/// Records and maps are deserialized as maps, arrays as sequences, and
+/// unions as their inner value.
+#[derive(Clone, Copy)]
+struct AvroDeserializer<'a>(&'a AvroValue);
+
+impl<'de, 'a> Deserializer<'de> for AvroDeserializer<'a> {
+    type Error = DeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            AvroValue::Null => visitor.visit_unit(),
+            AvroValue::Boolean(b) => visitor.visit_bool(*b),
+            AvroValue::Int(i) | AvroValue::Date(i) | AvroValue::TimeMillis(i) => {
+                visitor.visit_i32(*i)
+            }
+            AvroValue::Long(i)
+            | AvroValue::TimeMicros(i)
+            | AvroValue::TimestampMillis(i)
+            | AvroValue::TimestampMicros(i) => visitor.visit_i64(*i),
+            AvroValue::Float(f) => visitor.visit_f32(*f),
+            AvroValue::Double(f) => visitor.visit_f64(*f),
+            AvroValue::String(s) | AvroValue::Enum(_, s) => visitor.visit_str(s),
+            AvroValue::Bytes(bytes) | AvroValue::Fixed(_, bytes) => visitor.visit_bytes(bytes),
+            AvroValue::Uuid(uuid) => visitor.visit_string(uuid.to_string()),
+            AvroValue::Union(_, value) => AvroDeserializer(value).deserialize_any(visitor),
+            AvroValue::Array(values) => {
+                let mut seq = SeqDeserializer::new(values.iter().map(AvroDeserializer));
+                let result = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(result)
+            }
+            AvroValue::Map(map) => {
+                let mut map = MapDeserializer::new(
+                    map.iter()
+                        .map(|(key, value)| (key.as_str(), AvroDeserializer(value))),
+                );
+                let result = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(result)
+            }
+            AvroValue::Record(fields) => {
+                let mut map = MapDeserializer::new(
+                    fields
+                        .iter()
+                        .map(|(key, value)| (key.as_str(), AvroDeserializer(value))),
+                );
+                let result = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(result)
+            }
+            value => Err(DeError::custom(format!(
+                "unsupported Avro value: {value:?}"
+            ))),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            AvroValue::Null => visitor.visit_none(),
+            AvroValue::Union(_, value) if **value == AvroValue::Null => visitor.visit_none(),
+            AvroValue::Union(_, value) => visitor.visit_some(AvroDeserializer(value)),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            AvroValue::Enum(_, s) | AvroValue::String(s) => {
+                visitor.visit_enum(s.as_str().into_deserializer())
+            }
+            AvroValue::Union(_, value) => {
+                AvroDeserializer(value).deserialize_enum(name, variants, visitor)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, DeError> for AvroDeserializer<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// Avro format encoder.
+pub struct AvroOutputFormat;
+
+const fn default_buffer_size_records() -> usize {
+    10_000
+}
+
+#[derive(Deserialize)]
+struct AvroEncoderConfig {
+    /// Schema of output records in JSON format.
+    ///
+    /// Must be a record schema.  The encoder extends it with a `weight`
+    /// field of type `long` that stores the weight of each update.
+    schema: String,
+
+    #[serde(default = "default_buffer_size_records")]
+    buffer_size_records: usize,
+}
+
+impl OutputFormat for AvroOutputFormat {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("avro")
+    }
+
+    fn new_encoder(
+        &self,
+        config: &YamlValue,
+        consumer: Box<dyn OutputConsumer>,
+    ) -> AnyResult<Box<dyn Encoder>> {
+        let config = AvroEncoderConfig::deserialize(config)?;
+        let schema = weighted_schema(&config.schema)?;
+        let key_schema = AvroSchema::parse_str(&config.schema)
+            .map_err(|e| AnyError::msg(format!("invalid Avro schema: {e}")))?;
+
+        Ok(Box::new(AvroEncoder::new(
+            consumer,
+            schema,
+            key_schema,
+            config.buffer_size_records,
+        )))
+    }
+}
+
+/// Add a `weight` field to the record schema `schema`.
+fn weighted_schema(schema: &str) -> AnyResult<AvroSchema> {
+    let mut json = serde_json::from_str::<JsonValue>(schema)
+        .map_err(|e| AnyError::msg(format!("invalid Avro schema: {e}")))?;
+
+    let fields = json
+        .as_object_mut()
+        .filter(|schema| schema.get("type") == Some(&JsonValue::from("record")))
+        .and_then(|schema| schema.get_mut("fields"))
+        .and_then(JsonValue::as_array_mut)
+        .ok_or_else(|| AnyError::msg("Avro encoder requires a record schema"))?;
+
+    if fields
+        .iter()
+        .any(|field| field.get("name") == Some(&JsonValue::from("weight")))
+    {
+        return Err(AnyError::msg(
+            "Avro schema already contains a field named 'weight'",
+        ));
+    }
+    fields.push(serde_json::json!({"name": "weight", "type": "long"}));
+
+    AvroSchema::parse_str(&json.to_string())
+        .map_err(|e| AnyError::msg(format!("invalid Avro schema: {e}")))
+}
+
+/// Encoder that outputs each update as an Avro datum with an extra `weight`
+/// field, where positive and negative weights represent insertions and
+/// retractions respectively.
+struct AvroEncoder {
+    /// Input handle to push serialized data to.
+    output_consumer: Box<dyn OutputConsumer>,
+
+    /// Output schema, including the `weight` field.
+    schema: AvroSchema,
+
+    /// Schema used to encode message keys for keyed consumers (the
+    /// user-provided schema without the `weight` field).
+    key_schema: AvroSchema,
+
+    buffer_size_records: usize,
+
+    buffer: Vec<u8>,
+}
+
+impl AvroEncoder {
+    fn new(
+        output_consumer: Box<dyn OutputConsumer>,
+        schema: AvroSchema,
+        key_schema: AvroSchema,
+        buffer_size_records: usize,
+    ) -> Self {
+        Self {
+            output_consumer,
+            schema,
+            key_schema,
+            buffer_size_records,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Appends `record` with an additional `weight` field to the buffer.
+    fn encode_record(&mut self, record: &dyn ErasedSerialize, w: i64) -> AnyResult<()> {
+        let mut fields = record_fields(record)?;
+        fields.push(("weight".to_string(), AvroValue::Long(w)));
+        let value = AvroValue::Record(fields).resolve(&self.schema)?;
+        self.buffer
+            .extend_from_slice(&apache_avro::to_avro_datum(&self.schema, value)?);
+        Ok(())
+    }
+}
+
+/// Converts a struct to the list of its Avro fields.
+fn record_fields(record: &dyn ErasedSerialize) -> AnyResult<Vec<(String, AvroValue)>> {
+    match apache_avro::to_value(record)? {
+        AvroValue::Record(fields) => Ok(fields),
+        _ => Err(AnyError::msg(
+            "Avro encoder can only serialize struct types",
+        )),
+    }
+}
+
+impl Encoder for AvroEncoder {
+    fn consumer(&mut self) -> &mut dyn OutputConsumer {
+        self.output_consumer.as_mut()
+    }
+
+    fn encode(&mut self, batches: &[Box<dyn SerBatch>]) -> AnyResult<()> {
+        let mut num_records = 0;
+        let keyed = self.output_consumer.is_keyed();
+
+        for batch in batches.iter() {
+            let indexed = batch.is_indexed();
+            let mut cursor = batch.cursor();
+
+            while cursor.key_valid() {
+                if keyed {
+                    // Push the current record for each key as a separate
+                    // message keyed by the key without the weight field.
+                    // The record is the value of an indexed Z-set with
+                    // positive weight or the key itself for a Z-set.  Keys
+                    // without such a value are pushed as tombstones.
+                    let key = AvroValue::Record(record_fields(cursor.key())?)
+                        .resolve(&self.key_schema)?;
+                    let key = apache_avro::to_avro_datum(&self.key_schema, key)?;
+                    cursor.seek_inserted_val();
+
+                    if cursor.val_valid() {
+                        let w = cursor.weight();
+                        let record = if indexed { cursor.val() } else { cursor.key() };
+                        self.encode_record(record, w)?;
+                        self.output_consumer.push_key(&key, Some(&self.buffer));
+                        self.buffer.clear();
+                    } else {
+                        self.output_consumer.push_key(&key, None);
+                    }
+
+                    cursor.step_key();
+                    continue;
+                }
+
+                let w = cursor.weight();
+                self.encode_record(cursor.key(), w)?;
+                num_records += 1;
+
+                if num_records >= self.buffer_size_records {
+                    self.output_consumer.push_buffer(&self.buffer);
+                    self.buffer.clear();
+                    num_records = 0;
+                }
+
+                cursor.step_key();
+            }
+        }
+
+        if num_records > 0 {
+            self.output_consumer.push_buffer(&self.buffer);
+            self.buffer.clear();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        test::{MockDeZSet, MockOutputConsumer, TestStruct},
+        Catalog, InputFormat, OutputFormat, SerBatch,
+    };
+    use apache_avro::{types::Value as AvroValue, Schema as AvroSchema, Writer};
+    use dbsp::{trace::Batch, OrdZSet};
+    use std::sync::{Arc, Mutex};
+
+    const TEST_SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "TestStruct",
+        "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "b", "type": "boolean"},
+            {"name": "i", "type": ["null", "long"]},
+            {"name": "s", "type": "string"}
+        ]
+    }"#;
+
+    fn test_data() -> Vec<TestStruct> {
+        vec![
+            TestStruct {
+                id: 0,
+                b: true,
+                i: Some(10),
+                s: "foo".to_string(),
+            },
+            TestStruct {
+                id: 1,
+                b: false,
+                i: None,
+                s: "bar".to_string(),
+            },
+        ]
+    }
+
+    /// Create an Avro parser with the specified config string, feed `input`
+    /// to it in chunks of `chunk_size` bytes.
+    fn parse(config: &str, input: &[u8], chunk_size: usize) -> Vec<(TestStruct, bool)> {
+        let mut catalog = Catalog::new();
+        let zset = <MockDeZSet<TestStruct>>::new();
+        catalog.register_input_collection_handle("test_input", zset.clone());
+
+        let mut parser = <dyn InputFormat>::get_format("avro")
+            .unwrap()
+            .new_parser(
+                &serde_yaml::from_str(config).unwrap(),
+                &Arc::new(Mutex::new(catalog)),
+            )
+            .unwrap();
+
+        for chunk in input.chunks(chunk_size) {
+            parser.input(chunk).unwrap();
+            parser.flush();
+        }
+        parser.eoi().unwrap();
+        parser.flush();
+
+        let result = zset.state().flushed.clone();
+        result
+    }
+
+    #[test]
+    fn test_avro_inline_schema() {
+        let data = test_data();
+        let schema = AvroSchema::parse_str(TEST_SCHEMA).unwrap();
+
+        let mut input = Vec::new();
+        for val in data.iter() {
+            let value = apache_avro::to_value(val)
+                .unwrap()
+                .resolve(&schema)
+                .unwrap();
+            input.extend_from_slice(&apache_avro::to_avro_datum(&schema, value).unwrap());
+        }
+
+        let config = format!(
+            "input_stream: test_input\nschema: '{}'",
+            TEST_SCHEMA.replace('\n', " ")
+        );
+        // Datums split across input buffers must be reassembled.
+        for chunk_size in [1, 7, input.len()] {
+            assert_eq!(
+                parse(&config, &input, chunk_size),
+                data.iter()
+                    .map(|val| (val.clone(), true))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_avro_container() {
+        let data = test_data();
+        let schema = AvroSchema::parse_str(TEST_SCHEMA).unwrap();
+
+        let mut writer = Writer::new(&schema, Vec::new());
+        for val in data.iter() {
+            writer.append_ser(val).unwrap();
+            // Force a separate data block for each record.
+            writer.flush().unwrap();
+        }
+        let input = writer.into_inner().unwrap();
+
+        for chunk_size in [1, 7, 1000] {
+            assert_eq!(
+                parse("input_stream: test_input", &input, chunk_size),
+                data.iter()
+                    .map(|val| (val.clone(), true))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_avro_invalid_block() {
+        // Zigzag-encoded longs: 0x01 is -1, 0x02 is 1, 0x03 is -2.
+        let sync_marker = [0u8; super::SYNC_MARKER_SIZE];
+
+        // Negative record count.
+        let input = [&[0x01, 0x00][..], &sync_marker].concat();
+        assert!(super::read_block(&mut input.as_slice()).is_err());
+
+        // Negative block length.
+        let input = [&[0x02, 0x03][..], &sync_marker].concat();
+        assert!(super::read_block(&mut input.as_slice()).is_err());
+
+        // Block length too large.
+        let input = [0x02, 0xfe, 0xff, 0xff, 0xff, 0x0f];
+        assert!(super::read_block(&mut input.as_slice()).is_err());
+
+        // Incomplete block.
+        let input = [0x02, 0x04, 0x00];
+        assert!(super::read_block(&mut input.as_slice()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_avro_encoder() {
+        let data = test_data();
+        let batch = OrdZSet::from_tuples((), vec![(data[0].clone(), 1), (data[1].clone(), -1)]);
+        let batches =
+            vec![Box::new(crate::seroutput::SerBatchImpl::new(batch)) as Box<dyn SerBatch>];
+
+        let consumer = MockOutputConsumer::new();
+        let mut encoder = <dyn OutputFormat>::get_format("avro")
+            .unwrap()
+            .new_encoder(
+                &serde_yaml::from_str(&format!(
+                    "schema: '{}'\nbuffer_size_records: 1",
+                    TEST_SCHEMA.replace('\n', " ")
+                ))
+                .unwrap(),
+                Box::new(consumer.clone()),
+            )
+            .unwrap();
+        encoder.encode(&batches).unwrap();
+
+        let schema = super::weighted_schema(TEST_SCHEMA).unwrap();
+        let buffers = consumer.data.lock().unwrap();
+        assert_eq!(buffers.len(), 2);
+
+        let weights = buffers
+            .iter()
+            .map(|buffer| {
+                let fields =
+                    match apache_avro::from_avro_datum(&schema, &mut buffer.as_slice(), None)
+                        .unwrap()
+                    {
+                        AvroValue::Record(fields) => fields,
+                        _ => panic!("expected an Avro record"),
+                    };
+                fields
+                    .into_iter()
+                    .find(|(name, _)| name == "weight")
+                    .unwrap()
+                    .1
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(weights, vec![AvroValue::Long(1), AvroValue::Long(-1)]);
+    }
+}
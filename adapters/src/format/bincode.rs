@@ -0,0 +1,457 @@
+use crate::{
+    format::{Encoder, InputFormat, OutputFormat, Parser},
+    Catalog, DeCollectionHandle, OutputConsumer, SerBatch,
+};
+use anyhow::{Error as AnyError, Result as AnyResult};
+use erased_serde::{Deserializer as ErasedDeserializer, Serialize as ErasedSerialize};
+use serde::{
+    de::{DeserializeSeed, Error as _},
+    Deserialize, Deserializer,
+};
+use serde_yaml::Value as YamlValue;
+use std::{
+    borrow::Cow,
+    mem::{size_of, take},
+    sync::{Arc, Mutex},
+};
+
+/// Size of the length prefix that precedes each record.
+const LENGTH_PREFIX_SIZE: usize = size_of::<u32>();
+
+/// Bincode format parser.
+///
+/// The input stream is a sequence of length-prefixed records: each record is
+/// encoded as a 4-byte little-endian length followed by `length` bytes of
+/// payload serialized using bincode's standard configuration
+/// ([`bincode::config::standard`]).
+pub struct BincodeInputFormat;
+
+#[derive(Deserialize)]
+struct BincodeParserConfig {
+    /// Input stream to feed parsed records to.
+    input_stream: String,
+}
+
+impl InputFormat for BincodeInputFormat {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("bincode")
+    }
+
+    fn new_parser(
+        &self,
+        config: &YamlValue,
+        catalog: &Arc<Mutex<Catalog>>,
+    ) -> AnyResult<Box<dyn Parser>> {
+        let config = BincodeParserConfig::deserialize(config)?;
+        catalog
+            .lock()
+            .unwrap()
+            .input_collection_handle(&config.input_stream)
+            .map(|stream| Box::new(BincodeParser::new(stream)) as Box<dyn Parser>)
+            .ok_or_else(|| AnyError::msg(format!("unknown stream '{}'", config.input_stream)))
+    }
+}
+
+/// Deserialization seed that inserts the deserialized record into an input
+/// handle.
+///
+/// bincode does not expose its serde deserializer type, so we cannot erase it
+/// directly; instead we get access to the deserializer via this seed.
+struct InsertSeed<'a>(&'a mut dyn DeCollectionHandle);
+
+impl<'de, 'a> DeserializeSeed<'de> for InsertSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut deserializer = <dyn ErasedDeserializer>::erase(deserializer);
+        self.0.insert(&mut deserializer).map_err(D::Error::custom)
+    }
+}
+
+struct BincodeParser {
+    /// Input handle to push parsed data to.
+    input_stream: Box<dyn DeCollectionHandle>,
+
+    /// Since we cannot assume that the input buffer ends on a record
+    /// boundary, we save the "leftover" part of the buffer that contains an
+    /// incomplete record and prepend it to the next input buffer.
+    leftover: Vec<u8>,
+}
+
+impl BincodeParser {
+    fn new(input_stream: &dyn DeCollectionHandle) -> Self {
+        Self {
+            input_stream: input_stream.fork(),
+            leftover: Vec::new(),
+        }
+    }
+
+    /// Parse a sequence of length-prefixed records from `data`.
+    ///
+    /// Stops at the first incomplete record or at the first record that
+    /// fails to deserialize.  Returns the number of parsed records and the
+    /// number of bytes consumed from `data`, including the failed record.
+    fn parse_records(&mut self, data: &[u8]) -> (AnyResult<usize>, usize) {
+        let mut offset = 0;
+        let mut num_records = 0;
+
+        while data.len() - offset >= LENGTH_PREFIX_SIZE {
+            let len = u32::from_le_bytes(
+                data[offset..offset + LENGTH_PREFIX_SIZE]
+                    .try_into()
+                    .unwrap(),
+            ) as usize;
+            let start = offset + LENGTH_PREFIX_SIZE;
+            if data.len() - start < len {
+                break;
+            }
+            offset = start + len;
+
+            if let Err(e) = bincode::serde::decode_seed_from_slice(
+                InsertSeed(self.input_stream.as_mut()),
+                &data[start..offset],
+                bincode::config::standard(),
+            ) {
+                return (
+                    Err(AnyError::msg(format!(
+                        "failed to deserialize bincode record: {e}"
+                    ))),
+                    offset,
+                );
+            }
+            num_records += 1;
+        }
+
+        (Ok(num_records), offset)
+    }
+}
+
+impl Parser for BincodeParser {
+    fn input(&mut self, data: &[u8]) -> AnyResult<usize> {
+        if self.leftover.is_empty() {
+            // Fast path: parse directly from `data` and only copy the
+            // incomplete tail.
+            let (res, consumed) = self.parse_records(data);
+            self.leftover.extend_from_slice(&data[consumed..]);
+            res
+        } else {
+            let mut buffer = take(&mut self.leftover);
+            buffer.extend_from_slice(data);
+
+            let (res, consumed) = self.parse_records(&buffer);
+            buffer.drain(0..consumed);
+            self.leftover = buffer;
+
+            res
+        }
+    }
+
+    fn eoi(&mut self) -> AnyResult<usize> {
+        let leftover = take(&mut self.leftover);
+        let (res, consumed) = self.parse_records(&leftover);
+        let num_records = res?;
+
+        if consumed < leftover.len() {
+            // The remaining records will be reported when (if) `eoi` is
+            // invoked again.
+            self.leftover = leftover[consumed..].to_vec();
+            return Err(AnyError::msg(format!(
+                "incomplete bincode record at the end of input ({} bytes)",
+                leftover.len() - consumed
+            )));
+        }
+
+        Ok(num_records)
+    }
+
+    fn flush(&mut self) {
+        self.input_stream.flush();
+    }
+
+    fn clear(&mut self) {
+        self.input_stream.clear_buffer();
+    }
+
+    fn fork(&self) -> Box<dyn Parser> {
+        Box::new(Self::new(&*self.input_stream))
+    }
+}
+
+/// Bincode format encoder.
+pub struct BincodeOutputFormat;
+
+const fn default_buffer_size_records() -> usize {
+    10_000
+}
+
+#[derive(Deserialize)]
+struct BincodeEncoderConfig {
+    #[serde(default = "default_buffer_size_records")]
+    buffer_size_records: usize,
+}
+
+impl OutputFormat for BincodeOutputFormat {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("bincode")
+    }
+
+    fn new_encoder(
+        &self,
+        config: &YamlValue,
+        consumer: Box<dyn OutputConsumer>,
+    ) -> AnyResult<Box<dyn Encoder>> {
+        let config = BincodeEncoderConfig::deserialize(config)?;
+
+        Ok(Box::new(BincodeEncoder::new(consumer, config)))
+    }
+}
+
+/// Encoder that outputs each update as a length-prefixed
+/// `(key, value, weight)` tuple.
+struct BincodeEncoder {
+    /// Input handle to push serialized data to.
+    output_consumer: Box<dyn OutputConsumer>,
+
+    config: BincodeEncoderConfig,
+
+    buffer: Vec<u8>,
+}
+
+impl BincodeEncoder {
+    fn new(output_consumer: Box<dyn OutputConsumer>, config: BincodeEncoderConfig) -> Self {
+        Self {
+            output_consumer,
+            config,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Appends a length-prefixed `(key, val, w)` record to the buffer.
+    fn encode_record(
+        &mut self,
+        key: &dyn ErasedSerialize,
+        val: &dyn ErasedSerialize,
+        w: i64,
+    ) -> AnyResult<()> {
+        // Reserve space for the length prefix and fill it in once we know
+        // the size of the record.
+        let start = self.buffer.len();
+        self.buffer.extend_from_slice(&[0; LENGTH_PREFIX_SIZE]);
+        let len = bincode::serde::encode_into_std_write(
+            (key, val, w),
+            &mut self.buffer,
+            bincode::config::standard(),
+        )?;
+        let len = u32::try_from(len)
+            .map_err(|_| AnyError::msg(format!("bincode record too large ({len} bytes)")))?;
+        self.buffer[start..start + LENGTH_PREFIX_SIZE].copy_from_slice(&len.to_le_bytes());
+        Ok(())
+    }
+}
+
+impl Encoder for BincodeEncoder {
+    fn consumer(&mut self) -> &mut dyn OutputConsumer {
+        self.output_consumer.as_mut()
+    }
+
+    fn encode(&mut self, batches: &[Box<dyn SerBatch>]) -> AnyResult<()> {
+        let mut num_records = 0;
+        let keyed = self.output_consumer.is_keyed();
+
+        for batch in batches.iter() {
+            let mut cursor = batch.cursor();
+
+            while cursor.key_valid() {
+                if keyed {
+                    // Push the first value for each key with positive weight
+                    // as a separate message.  Keys without such a value
+                    // are pushed as tombstones.
+                    let key =
+                        bincode::serde::encode_to_vec(cursor.key(), bincode::config::standard())?;
+                    cursor.seek_inserted_val();
+
+                    if cursor.val_valid() {
+                        let w = cursor.weight();
+                        self.encode_record(cursor.key(), cursor.val(), w)?;
+                        self.output_consumer.push_key(&key, Some(&self.buffer));
+                        self.buffer.clear();
+                    } else {
+                        self.output_consumer.push_key(&key, None);
+                    }
+
+                    cursor.step_key();
+                    continue;
+                }
+
+                while cursor.val_valid() {
+                    let w = cursor.weight();
+                    self.encode_record(cursor.key(), cursor.val(), w)?;
+                    num_records += 1;
+
+                    if num_records >= self.config.buffer_size_records {
+                        self.output_consumer.push_buffer(&self.buffer);
+                        self.buffer.clear();
+                        num_records = 0;
+                    }
+
+                    cursor.step_val();
+                }
+
+                cursor.step_key();
+            }
+        }
+
+        if num_records > 0 {
+            self.output_consumer.push_buffer(&self.buffer);
+            self.buffer.clear();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LENGTH_PREFIX_SIZE;
+    use crate::{
+        test::{MockDeZSet, MockOutputConsumer, TestStruct},
+        Catalog, InputFormat, OutputFormat, SerBatch,
+    };
+    use dbsp::{trace::Batch, OrdZSet};
+    use std::sync::{Arc, Mutex};
+
+    fn test_data() -> Vec<TestStruct> {
+        vec![
+            TestStruct {
+                id: 0,
+                b: true,
+                i: Some(10),
+                s: "foo".to_string(),
+            },
+            TestStruct {
+                id: 1,
+                b: false,
+                i: None,
+                s: "bar".to_string(),
+            },
+        ]
+    }
+
+    fn encode_record<T: serde::Serialize>(val: &T, buffer: &mut Vec<u8>) {
+        let bytes = bincode::serde::encode_to_vec(val, bincode::config::standard()).unwrap();
+        buffer.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(&bytes);
+    }
+
+    /// Split `input` into chunks of `chunk_size` bytes and feed them to a
+    /// bincode parser.
+    fn parse(input: &[u8], chunk_size: usize) -> Vec<(TestStruct, bool)> {
+        let mut catalog = Catalog::new();
+        let zset = <MockDeZSet<TestStruct>>::new();
+        catalog.register_input_collection_handle("test_input", zset.clone());
+
+        let mut parser = <dyn InputFormat>::get_format("bincode")
+            .unwrap()
+            .new_parser(
+                &serde_yaml::from_str("input_stream: test_input").unwrap(),
+                &Arc::new(Mutex::new(catalog)),
+            )
+            .unwrap();
+
+        for chunk in input.chunks(chunk_size) {
+            parser.input(chunk).unwrap();
+            parser.flush();
+        }
+        parser.eoi().unwrap();
+        parser.flush();
+
+        let result = zset.state().flushed.clone();
+        result
+    }
+
+    #[test]
+    fn test_bincode_parser() {
+        let data = test_data();
+        let mut input = Vec::new();
+        for val in data.iter() {
+            encode_record(val, &mut input);
+        }
+
+        for chunk_size in [1, 3, 7, 1000] {
+            assert_eq!(
+                parse(&input, chunk_size),
+                data.iter()
+                    .map(|val| (val.clone(), true))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_bincode_parser_incomplete() {
+        let data = test_data();
+        let mut input = Vec::new();
+        encode_record(&data[0], &mut input);
+        encode_record(&data[1], &mut input);
+        input.truncate(input.len() - 1);
+
+        let mut catalog = Catalog::new();
+        let zset = <MockDeZSet<TestStruct>>::new();
+        catalog.register_input_collection_handle("test_input", zset.clone());
+
+        let mut parser = <dyn InputFormat>::get_format("bincode")
+            .unwrap()
+            .new_parser(
+                &serde_yaml::from_str("input_stream: test_input").unwrap(),
+                &Arc::new(Mutex::new(catalog)),
+            )
+            .unwrap();
+
+        assert_eq!(parser.input(&input).unwrap(), 1);
+        assert!(parser.eoi().is_err());
+        parser.flush();
+        assert_eq!(zset.state().flushed, vec![(data[0].clone(), true)]);
+    }
+
+    #[test]
+    fn test_bincode_encoder() {
+        let data = test_data();
+        let batch = OrdZSet::from_tuples((), vec![(data[0].clone(), 1), (data[1].clone(), -1)]);
+        let batches =
+            vec![Box::new(crate::seroutput::SerBatchImpl::new(batch)) as Box<dyn SerBatch>];
+
+        let consumer = MockOutputConsumer::new();
+        let mut encoder = <dyn OutputFormat>::get_format("bincode")
+            .unwrap()
+            .new_encoder(
+                &serde_yaml::from_str("buffer_size_records: 1").unwrap(),
+                Box::new(consumer.clone()),
+            )
+            .unwrap();
+        encoder.encode(&batches).unwrap();
+
+        let buffers = consumer.data.lock().unwrap();
+        assert_eq!(buffers.len(), 2);
+
+        let output = buffers
+            .iter()
+            .map(|buffer| {
+                let len = u32::from_le_bytes(buffer[0..LENGTH_PREFIX_SIZE].try_into().unwrap());
+                assert_eq!(len as usize, buffer.len() - LENGTH_PREFIX_SIZE);
+                bincode::serde::decode_from_slice::<(TestStruct, (), i64), _>(
+                    &buffer[LENGTH_PREFIX_SIZE..],
+                    bincode::config::standard(),
+                )
+                .unwrap()
+                .0
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            output,
+            vec![(data[0].clone(), (), 1), (data[1].clone(), (), -1)]
+        );
+    }
+}
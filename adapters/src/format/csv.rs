@@ -1,18 +1,17 @@
 use crate::{
-    format::{Encoder, InputFormat, OutputFormat, Parser},
+    format::{Encoder, InputFormat, InvalidRecordCallback, OutputFormat, Parser},
     Catalog, DeCollectionHandle, OutputConsumer, SerBatch,
 };
 use anyhow::{Error as AnyError, Result as AnyResult};
 use csv::{
-    byte_record_deserializer, Reader as CsvReader, ReaderBuilder as CsvReaderBuilder,
+    byte_record_deserializer, ByteRecord, ReaderBuilder as CsvReaderBuilder,
     WriterBuilder as CsvWriterBuilder,
 };
-use erased_serde::Deserializer as ErasedDeserializer;
+use erased_serde::{Deserializer as ErasedDeserializer, Serialize as ErasedSerialize};
 use serde::Deserialize;
 use serde_yaml::Value as YamlValue;
 use std::{
     borrow::Cow,
-    io::Read,
     mem::take,
     sync::{Arc, Mutex},
 };
@@ -20,10 +19,70 @@ use std::{
 /// CSV format parser.
 pub struct CsvInputFormat;
 
-#[derive(Deserialize)]
+const fn default_delimiter() -> char {
+    ','
+}
+
+const fn default_quote() -> char {
+    '"'
+}
+
+/// Convert a character specified in the config to a byte.
+fn ascii_char(c: char, option: &str) -> AnyResult<u8> {
+    if c.is_ascii() {
+        Ok(c as u8)
+    } else {
+        Err(AnyError::msg(format!(
+            "'{option}' must be an ASCII character, found '{c}'"
+        )))
+    }
+}
+
+#[derive(Deserialize, Clone)]
 struct CsvParserConfig {
     /// Input stream to feed parsed records to.
     input_stream: String,
+
+    /// Field delimiter.
+    #[serde(default = "default_delimiter")]
+    delimiter: char,
+
+    /// Quote character.
+    #[serde(default = "default_quote")]
+    quote: char,
+
+    /// Escape character used inside quoted fields.
+    ///
+    /// When `None`, quotes inside quoted fields are escaped by doubling
+    /// them.
+    #[serde(default)]
+    escape: Option<char>,
+
+    /// Set to `true` if the first row of the input stream is a header row.
+    ///
+    /// The header row is not pushed to the input stream.  If
+    /// [`columns`](`Self::columns`) is specified, the header row is validated
+    /// against it.
+    #[serde(default)]
+    header: bool,
+
+    /// Expected column names, including the weight column, if any.
+    ///
+    /// When specified, the parser fails if the header row does not match
+    /// this list.
+    #[serde(default)]
+    columns: Option<Vec<String>>,
+
+    /// Index of the column that stores the weight of the record.
+    ///
+    /// When specified, each row carries an explicit insert (positive weight)
+    /// or delete (negative weight) count.  A row with weight `w` is inserted
+    /// or deleted `|w|` times.  The weight column is removed from the row
+    /// before deserializing the record.
+    ///
+    /// When `None`, every row is inserted once.
+    #[serde(default)]
+    weight_column: Option<usize>,
 }
 
 impl InputFormat for CsvInputFormat {
@@ -37,12 +96,12 @@ impl InputFormat for CsvInputFormat {
         catalog: &Arc<Mutex<Catalog>>,
     ) -> AnyResult<Box<dyn Parser>> {
         let config = CsvParserConfig::deserialize(config)?;
-        catalog
-            .lock()
-            .unwrap()
+        let catalog = catalog.lock().unwrap();
+        let stream = catalog
             .input_collection_handle(&config.input_stream)
-            .map(|stream| Box::new(CsvParser::new(stream)) as Box<dyn Parser>)
-            .ok_or_else(|| AnyError::msg(format!("unknown stream '{}'", config.input_stream)))
+            .ok_or_else(|| AnyError::msg(format!("unknown stream '{}'", config.input_stream)))?;
+
+        Ok(Box::new(CsvParser::new(stream, config)?) as Box<dyn Parser>)
     }
 }
 
@@ -58,36 +117,139 @@ struct CsvParser {
     /// Builder used to create a new CSV reader for each received data
     /// buffer.
     builder: CsvReaderBuilder,
+
+    config: CsvParserConfig,
+
+    /// `true` if the header row hasn't been received yet.
+    header_pending: bool,
+
+    /// Callback to report invalid records to instead of failing (see
+    /// [`Parser::on_invalid_record`]).
+    invalid_record_callback: Option<InvalidRecordCallback>,
 }
 
 impl CsvParser {
-    fn new(input_stream: &dyn DeCollectionHandle) -> Self {
+    fn new(input_stream: &dyn DeCollectionHandle, config: CsvParserConfig) -> AnyResult<Self> {
         let mut builder = CsvReaderBuilder::new();
-        builder.has_headers(false);
-
-        Self {
+        builder
+            .has_headers(false)
+            .delimiter(ascii_char(config.delimiter, "delimiter")?)
+            .quote(ascii_char(config.quote, "quote")?)
+            .escape(
+                config
+                    .escape
+                    .map(|escape| ascii_char(escape, "escape"))
+                    .transpose()?,
+            );
+
+        Ok(Self {
             input_stream: input_stream.fork(),
             leftover: Vec::new(),
             builder,
-        }
+            header_pending: config.header,
+            config,
+            invalid_record_callback: None,
+        })
     }
 
-    fn parse_from_reader<R>(
-        input_stream: &mut dyn DeCollectionHandle,
-        mut reader: CsvReader<R>,
-    ) -> AnyResult<usize>
-    where
-        R: Read,
-    {
-        let mut num_records = 0;
+    /// Validate the header row against the expected column names.
+    fn validate_header(&self, record: &ByteRecord) -> AnyResult<()> {
+        if let Some(columns) = &self.config.columns {
+            if record.len() != columns.len()
+                || record
+                    .iter()
+                    .zip(columns.iter())
+                    .any(|(actual, expected)| actual != expected.as_bytes())
+            {
+                return Err(AnyError::msg(format!(
+                    "CSV header {:?} does not match expected columns {columns:?}",
+                    record
+                        .iter()
+                        .map(String::from_utf8_lossy)
+                        .collect::<Vec<_>>()
+                )));
+            }
+        }
 
-        for record in reader.byte_records() {
-            let record = record?;
+        Ok(())
+    }
 
-            let mut deserializer = byte_record_deserializer(&record, None);
+    /// Push a single record with weight `weight` to the input handle.
+    fn push_record(&mut self, record: &ByteRecord, weight: i64) -> AnyResult<()> {
+        for _ in 0..weight.unsigned_abs() {
+            let mut deserializer = byte_record_deserializer(record, None);
             let mut deserializer = <dyn ErasedDeserializer>::erase(&mut deserializer);
-            input_stream.insert(&mut deserializer)?;
-            num_records += 1;
+            if weight > 0 {
+                self.input_stream.insert(&mut deserializer)?;
+            } else {
+                self.input_stream.delete_val(&mut deserializer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Push a record, possibly containing a weight column, to the input
+    /// handle.
+    fn parse_record(&mut self, record: &ByteRecord) -> AnyResult<()> {
+        match self.config.weight_column {
+            None => self.push_record(record, 1),
+            Some(column) => {
+                let weight = record
+                    .get(column)
+                    .and_then(|weight| std::str::from_utf8(weight).ok())
+                    .and_then(|weight| weight.trim().parse::<i64>().ok())
+                    .ok_or_else(|| {
+                        AnyError::msg(format!(
+                            "invalid or missing weight in column {column} of CSV record {record:?}"
+                        ))
+                    })?;
+                let record = record
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != column)
+                    .map(|(_, field)| field)
+                    .collect::<ByteRecord>();
+                self.push_record(&record, weight)
+            }
+        }
+    }
+
+    /// Parse complete CSV records in `data`.
+    fn parse(&mut self, data: &[u8]) -> AnyResult<usize> {
+        let mut reader = self.builder.from_reader(data);
+        let mut record = ByteRecord::new();
+        let mut num_records = 0;
+
+        loop {
+            let start = reader.position().byte() as usize;
+            let res = reader.read_byte_record(&mut record);
+            let end = reader.position().byte() as usize;
+
+            let res = match res {
+                Ok(false) => break,
+                Ok(true) if self.header_pending => {
+                    self.header_pending = false;
+                    self.validate_header(&record)?;
+                    continue;
+                }
+                Ok(true) => self.parse_record(&record),
+                Err(e) => Err(e.into()),
+            };
+
+            match (res, &mut self.invalid_record_callback) {
+                (Ok(()), _) => num_records += 1,
+                (Err(e), Some(callback)) => {
+                    let raw = &data[start..end];
+                    callback(
+                        raw.strip_suffix(b"\n")
+                            .map(|raw| raw.strip_suffix(b"\r").unwrap_or(raw))
+                            .unwrap_or(raw),
+                        e,
+                    );
+                }
+                (Err(e), None) => return Err(e),
+            }
         }
 
         Ok(num_records)
@@ -123,16 +285,21 @@ impl Parser for CsvParser {
             // buffer.
             self.leftover.extend_from_slice(data);
             Ok(0)
+        } else if self.leftover.is_empty() {
+            // Fast path: parse directly from `data`.
+            let res = self.parse(&data[0..leftover]);
+            self.leftover.extend_from_slice(&data[leftover..]);
+            res
         } else {
-            let reader = self
-                .builder
-                .from_reader(Read::chain(&*self.leftover, &data[0..leftover]));
+            let mut buffer = take(&mut self.leftover);
+            buffer.extend_from_slice(&data[0..leftover]);
 
-            let res = Self::parse_from_reader(&mut *self.input_stream, reader);
+            let res = self.parse(&buffer);
             // println!("parse returned: {res:?}");
 
-            self.leftover.clear();
-            self.leftover.extend_from_slice(&data[leftover..]);
+            buffer.clear();
+            buffer.extend_from_slice(&data[leftover..]);
+            self.leftover = buffer;
 
             res
         }
@@ -144,9 +311,8 @@ impl Parser for CsvParser {
         }
 
         // Try to interpret the leftover chunk as a complete CSV line.
-        let reader = self.builder.from_reader(&*self.leftover);
-
-        Self::parse_from_reader(&mut *self.input_stream, reader)
+        let leftover = take(&mut self.leftover);
+        self.parse(&leftover)
     }
 
     fn flush(&mut self) {
@@ -158,7 +324,13 @@ impl Parser for CsvParser {
     }
 
     fn fork(&self) -> Box<dyn Parser> {
-        Box::new(Self::new(&*self.input_stream))
+        // The config has already been validated, so this cannot fail.
+        Box::new(Self::new(&*self.input_stream, self.config.clone()).unwrap())
+    }
+
+    fn on_invalid_record(&mut self, callback: InvalidRecordCallback) -> bool {
+        self.invalid_record_callback = Some(callback);
+        true
     }
 }
 
@@ -173,6 +345,18 @@ const fn default_buffer_size_records() -> usize {
 struct CsvEncoderConfig {
     #[serde(default = "default_buffer_size_records")]
     buffer_size_records: usize,
+
+    /// Field delimiter.
+    #[serde(default = "default_delimiter")]
+    delimiter: char,
+
+    /// Output a header row before the first record.
+    ///
+    /// Column names are derived from the field names of the output record
+    /// type, followed by a `weight` column.  Requires output records to be
+    /// structs.
+    #[serde(default)]
+    headers: bool,
 }
 
 impl OutputFormat for CsvOutputFormat {
@@ -187,7 +371,7 @@ impl OutputFormat for CsvOutputFormat {
     ) -> AnyResult<Box<dyn Encoder>> {
         let config = CsvEncoderConfig::deserialize(config)?;
 
-        Ok(Box::new(CsvEncoder::new(consumer, config)))
+        Ok(Box::new(CsvEncoder::new(consumer, config)?))
     }
 }
 
@@ -201,44 +385,122 @@ struct CsvEncoder {
 
     config: CsvEncoderConfig,
 
+    /// `true` if the header row hasn't been written yet.
+    header_pending: bool,
+
     buffer: Vec<u8>,
+
+    /// Buffer used to encode message keys for keyed consumers.
+    key_buffer: Vec<u8>,
 }
 
 impl CsvEncoder {
-    fn new(output_consumer: Box<dyn OutputConsumer>, config: CsvEncoderConfig) -> Self {
+    fn new(output_consumer: Box<dyn OutputConsumer>, config: CsvEncoderConfig) -> AnyResult<Self> {
         let mut builder = CsvWriterBuilder::new();
-        builder.has_headers(false);
+        builder
+            .has_headers(false)
+            .delimiter(ascii_char(config.delimiter, "delimiter")?);
 
-        Self {
+        Ok(Self {
             output_consumer,
             builder,
+            header_pending: config.headers,
             config,
             buffer: Vec::new(),
+            key_buffer: Vec::new(),
+        })
+    }
+
+    /// Compute the header row for records of the same type as `key`.
+    ///
+    /// The CSV writer only generates headers for top-level structs, but we
+    /// serialize `(key, weight)` tuples, so we let it generate the header for
+    /// `key` alone and parse the column names back from its output.
+    fn header(&self, key: &dyn ErasedSerialize) -> AnyResult<ByteRecord> {
+        let mut writer = CsvWriterBuilder::new().from_writer(Vec::new());
+        writer.serialize(key)?;
+        let output = writer.into_inner()?;
+
+        // The output contains two rows (header and record) iff the writer
+        // was able to generate column names.
+        let mut rows = CsvReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(output.as_slice())
+            .into_byte_records()
+            .collect::<Result<Vec<_>, _>>()?;
+        if rows.len() != 2 {
+            return Err(AnyError::msg(
+                "CSV headers require output records to be structs",
+            ));
         }
+        let mut header = rows.swap_remove(0);
+        header.push_field(b"weight");
+
+        Ok(header)
     }
 }
 
 impl Encoder for CsvEncoder {
+    fn consumer(&mut self) -> &mut dyn OutputConsumer {
+        self.output_consumer.as_mut()
+    }
+
     fn encode(&mut self, batches: &[Box<dyn SerBatch>]) -> AnyResult<()> {
         let buffer = take(&mut self.buffer);
         let mut writer = self.builder.from_writer(buffer);
         let mut num_records = 0;
+        let keyed = self.output_consumer.is_keyed();
 
         for batch in batches.iter() {
+            let indexed = batch.is_indexed();
             let mut cursor = batch.cursor();
 
             while cursor.key_valid() {
-                let w = cursor.weight();
-                writer.serialize((cursor.key(), w))?;
-                num_records += 1;
-
-                if num_records >= self.config.buffer_size_records {
-                    let mut buffer = writer.into_inner()?;
-                    // println!("push_buffer {}", std::str::from_utf8(&buffer).unwrap());
-                    self.output_consumer.push_buffer(&buffer);
-                    buffer.clear();
-                    num_records = 0;
-                    writer = self.builder.from_writer(buffer);
+                if keyed {
+                    // Push the current record for each key as a separate
+                    // message, keyed by the key without the weight column.
+                    // The record is the value of an indexed Z-set with
+                    // positive weight or the key itself for a Z-set.  Keys
+                    // without such a value are pushed as tombstones.
+                    let mut key_writer = self.builder.from_writer(take(&mut self.key_buffer));
+                    key_writer.serialize(cursor.key())?;
+                    let mut key_buffer = key_writer.into_inner()?;
+                    cursor.seek_inserted_val();
+
+                    if cursor.val_valid() {
+                        let w = cursor.weight();
+                        let record = if indexed { cursor.val() } else { cursor.key() };
+                        if self.header_pending {
+                            writer.write_byte_record(&self.header(record)?)?;
+                            self.header_pending = false;
+                        }
+                        writer.serialize((record, w))?;
+                        let mut buffer = writer.into_inner()?;
+                        self.output_consumer.push_key(&key_buffer, Some(&buffer));
+                        buffer.clear();
+                        writer = self.builder.from_writer(buffer);
+                    } else {
+                        self.output_consumer.push_key(&key_buffer, None);
+                    }
+                    key_buffer.clear();
+                    self.key_buffer = key_buffer;
+                } else {
+                    if self.header_pending {
+                        writer.write_byte_record(&self.header(cursor.key())?)?;
+                        self.header_pending = false;
+                    }
+                    let w = cursor.weight();
+                    writer.serialize((cursor.key(), w))?;
+                    num_records += 1;
+
+                    if num_records >= self.config.buffer_size_records {
+                        let mut buffer = writer.into_inner()?;
+                        // println!("push_buffer {}", std::str::from_utf8(&buffer).unwrap());
+                        self.output_consumer.push_buffer(&buffer);
+                        buffer.clear();
+                        num_records = 0;
+                        writer = self.builder.from_writer(buffer);
+                    }
                 }
 
                 cursor.step_key();
@@ -257,3 +519,209 @@ impl Encoder for CsvEncoder {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        test::{MockDeZSet, MockOutputConsumer, TestStruct},
+        Catalog, InputFormat, OutputFormat, SerBatch,
+    };
+    use dbsp::{trace::Batch, OrdZSet};
+    use std::sync::{Arc, Mutex};
+
+    fn test_data() -> Vec<TestStruct> {
+        vec![
+            TestStruct {
+                id: 0,
+                b: true,
+                i: Some(10),
+                s: "foo;bar".to_string(),
+            },
+            TestStruct {
+                id: 1,
+                b: false,
+                i: None,
+                s: "'baz'".to_string(),
+            },
+        ]
+    }
+
+    /// Create a CSV parser with the specified config string, feed `input`
+    /// to it in chunks of `chunk_size` bytes.
+    fn parse(
+        config: &str,
+        input: &str,
+        chunk_size: usize,
+    ) -> anyhow::Result<Vec<(TestStruct, bool)>> {
+        let mut catalog = Catalog::new();
+        let zset = <MockDeZSet<TestStruct>>::new();
+        catalog.register_input_collection_handle("test_input", zset.clone());
+
+        let mut parser = <dyn InputFormat>::get_format("csv").unwrap().new_parser(
+            &serde_yaml::from_str(config).unwrap(),
+            &Arc::new(Mutex::new(catalog)),
+        )?;
+
+        for chunk in input.as_bytes().chunks(chunk_size) {
+            parser.input(chunk)?;
+            parser.flush();
+        }
+        parser.eoi()?;
+        parser.flush();
+
+        let result = zset.state().flushed.clone();
+        Ok(result)
+    }
+
+    #[test]
+    fn test_csv_dialect() {
+        let data = test_data();
+        let input = "id;b;i;s\n0;true;10;'foo;bar'\n1;false;;'\\'baz\\''\n";
+        let config = r#"
+input_stream: test_input
+delimiter: ";"
+quote: "'"
+escape: "\\"
+header: true
+columns: [id, b, i, s]"#;
+
+        for chunk_size in [1, 7, 1000] {
+            assert_eq!(
+                parse(config, input, chunk_size).unwrap(),
+                data.iter()
+                    .map(|val| (val.clone(), true))
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        // Header mismatch.
+        let input = "id;b;i;x\n0;true;10;'foo;bar'\n";
+        assert!(parse(config, input, 1000).is_err());
+    }
+
+    #[test]
+    fn test_csv_weight_column() {
+        let data = test_data();
+        let input = "2,0,true,10,foo;bar\n-1,1,false,,'baz'\n0,1,false,,'baz'\n";
+
+        assert_eq!(
+            parse("input_stream: test_input\nweight_column: 0", input, 7).unwrap(),
+            vec![
+                (data[0].clone(), true),
+                (data[0].clone(), true),
+                (data[1].clone(), false)
+            ]
+        );
+
+        assert!(parse("input_stream: test_input\nweight_column: 5", input, 7).is_err());
+    }
+
+    #[test]
+    fn test_csv_invalid_records() {
+        let data = test_data();
+        let input = "0,true,10,foo;bar\n1,maybe,,'baz'\n1,false,,'baz'\n2,true\n";
+
+        let mut catalog = Catalog::new();
+        let zset = <MockDeZSet<TestStruct>>::new();
+        catalog.register_input_collection_handle("test_input", zset.clone());
+
+        let mut parser = <dyn InputFormat>::get_format("csv")
+            .unwrap()
+            .new_parser(
+                &serde_yaml::from_str("input_stream: test_input").unwrap(),
+                &Arc::new(Mutex::new(catalog)),
+            )
+            .unwrap();
+
+        let rejected = Arc::new(Mutex::new(Vec::new()));
+        let rejected_clone = rejected.clone();
+        assert!(parser.on_invalid_record(Box::new(move |record, _error| {
+            rejected_clone
+                .lock()
+                .unwrap()
+                .push(String::from_utf8(record.to_vec()).unwrap())
+        })));
+
+        for chunk in input.as_bytes().chunks(5) {
+            parser.input(chunk).unwrap();
+        }
+        parser.eoi().unwrap();
+        parser.flush();
+
+        assert_eq!(
+            zset.state().flushed,
+            vec![(data[0].clone(), true), (data[1].clone(), true)]
+        );
+        assert_eq!(
+            &*rejected.lock().unwrap(),
+            &["1,maybe,,'baz'".to_string(), "2,true".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_csv_encoder() {
+        let data = test_data();
+        let batch = OrdZSet::from_tuples((), vec![(data[0].clone(), 1), (data[1].clone(), -1)]);
+        let batches =
+            vec![Box::new(crate::seroutput::SerBatchImpl::new(batch)) as Box<dyn SerBatch>];
+
+        let consumer = MockOutputConsumer::new();
+        let mut encoder = <dyn OutputFormat>::get_format("csv")
+            .unwrap()
+            .new_encoder(
+                &serde_yaml::from_str("delimiter: \"|\"\nheaders: true").unwrap(),
+                Box::new(consumer.clone()),
+            )
+            .unwrap();
+        encoder.encode(&batches).unwrap();
+        encoder.encode(&batches).unwrap();
+
+        let buffers = consumer.data.lock().unwrap();
+        assert_eq!(buffers.len(), 2);
+        assert_eq!(
+            String::from_utf8(buffers[0].clone()).unwrap(),
+            "id|b|i|s|weight\n0|true|10|foo;bar|1\n1|false||'baz'|-1\n"
+        );
+        // Headers are only written once.
+        assert_eq!(
+            String::from_utf8(buffers[1].clone()).unwrap(),
+            "0|true|10|foo;bar|1\n1|false||'baz'|-1\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_encoder_keyed() {
+        let data = test_data();
+        let batch = OrdZSet::from_tuples((), vec![(data[0].clone(), 1), (data[1].clone(), -1)]);
+        let batches =
+            vec![Box::new(crate::seroutput::SerBatchImpl::new(batch)) as Box<dyn SerBatch>];
+
+        let consumer = MockOutputConsumer::keyed();
+        let mut encoder = <dyn OutputFormat>::get_format("csv")
+            .unwrap()
+            .new_encoder(
+                &serde_yaml::from_str("delimiter: \"|\"").unwrap(),
+                Box::new(consumer.clone()),
+            )
+            .unwrap();
+        encoder.encode(&batches).unwrap();
+
+        // One message per record, keyed by the record without the weight.
+        // The deleted record is pushed as a tombstone.
+        let buffers = consumer.data.lock().unwrap();
+        let keys = consumer.keys.as_ref().unwrap().lock().unwrap();
+        assert_eq!(
+            buffers
+                .iter()
+                .map(|buffer| String::from_utf8(buffer.clone()).unwrap())
+                .collect::<Vec<_>>(),
+            vec!["0|true|10|foo;bar|1\n", ""]
+        );
+        assert_eq!(
+            keys.iter()
+                .map(|key| String::from_utf8(key.clone()).unwrap())
+                .collect::<Vec<_>>(),
+            vec!["0|true|10|foo;bar\n", "1|false||'baz'\n"]
+        );
+    }
+}
@@ -0,0 +1,911 @@
+use crate::{
+    format::{Encoder, InputFormat, InvalidRecordCallback, OutputFormat, Parser},
+    Catalog, DeCollectionHandle, OutputConsumer, SerBatch,
+};
+use anyhow::{Error as AnyError, Result as AnyResult};
+use erased_serde::Deserializer as ErasedDeserializer;
+use serde::Deserialize;
+use serde_json::{value::RawValue, Deserializer as JsonDeserializer, Serializer as JsonSerializer};
+use serde_yaml::Value as YamlValue;
+use std::{
+    borrow::Cow,
+    mem::take,
+    sync::{Arc, Mutex},
+};
+
+/// JSON format parser.
+pub struct JsonInputFormat;
+
+/// Supported JSON data change event formats.
+///
+/// Each element in a JSON-formatted input stream specifies
+/// an update to one or more records in an input table.  We support
+/// several different ways to represent such updates.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonUpdateFormat {
+    /// Each element in the input stream is a record to be inserted into the
+    /// input stream.
+    #[default]
+    Raw,
+
+    /// Each element in the input stream is an update envelope of the form
+    /// `{"insert": record}` or `{"delete": record}` (or both, in which case
+    /// the delete is applied first).  Insertions are pushed to the circuit via
+    /// [`DeCollectionHandle::insert`], deletions via
+    /// [`DeCollectionHandle::delete`].
+    InsertDelete,
+
+    /// Debezium-style change data capture (CDC) events.
+    ///
+    /// Each element in the input stream is an event envelope of the form
+    /// `{"before": record, "after": record, "op": op}`, optionally wrapped in
+    /// a `{"payload": ...}` object, as produced by the Debezium JSON converter
+    /// with schemas enabled.  `op` is one of:
+    ///
+    /// * `"c"` (create) and `"r"` (snapshot read): insert `after`.
+    /// * `"d"` (delete): delete `before`.
+    /// * `"u"` (update): delete `before` and insert `after`.
+    ///
+    /// Deleted records are pushed to the circuit via
+    /// [`DeCollectionHandle::delete_val`], which works for both ZSet and map
+    /// input streams.  `null` values (tombstones that Debezium emits after
+    /// each delete event) are ignored.
+    Debezium,
+}
+
+#[derive(Deserialize)]
+struct JsonParserConfig {
+    /// Input stream to feed parsed records to.
+    input_stream: String,
+
+    /// Format of individual updates in the stream.
+    #[serde(default)]
+    update_format: JsonUpdateFormat,
+
+    /// Set to `true` if updates in this stream are packaged into JSON arrays.
+    ///
+    /// When `false`, the input stream is expected to contain a sequence of
+    /// JSON values (typically, one value per line), each representing a
+    /// single update.  When `true`, each top-level value in the stream is an
+    /// array of updates, e.g., `[{"insert": ...}, {"delete": ...}]`.
+    #[serde(default)]
+    array: bool,
+}
+
+/// An update envelope used by [`JsonUpdateFormat::InsertDelete`].
+#[derive(Deserialize)]
+struct InsertDeleteUpdate<'a> {
+    #[serde(borrow, default)]
+    insert: Option<&'a RawValue>,
+    #[serde(borrow, default)]
+    delete: Option<&'a RawValue>,
+}
+
+/// Debezium event payload used by [`JsonUpdateFormat::Debezium`].
+#[derive(Deserialize)]
+struct DebeziumPayload<'a> {
+    #[serde(borrow, default)]
+    before: Option<&'a RawValue>,
+    #[serde(borrow, default)]
+    after: Option<&'a RawValue>,
+    #[serde(borrow)]
+    op: Cow<'a, str>,
+}
+
+/// Debezium event envelope.
+///
+/// Events produced with schemas enabled wrap the payload in a `payload`
+/// field; otherwise payload fields are stored at the top level.
+#[derive(Deserialize)]
+struct DebeziumEnvelope<'a> {
+    #[serde(borrow, default)]
+    payload: Option<&'a RawValue>,
+}
+
+impl InputFormat for JsonInputFormat {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("json")
+    }
+
+    fn new_parser(
+        &self,
+        config: &YamlValue,
+        catalog: &Arc<Mutex<Catalog>>,
+    ) -> AnyResult<Box<dyn Parser>> {
+        let config = JsonParserConfig::deserialize(config)?;
+        catalog
+            .lock()
+            .unwrap()
+            .input_collection_handle(&config.input_stream)
+            .map(|stream| {
+                Box::new(JsonParser::new(stream, config.update_format, config.array))
+                    as Box<dyn Parser>
+            })
+            .ok_or_else(|| AnyError::msg(format!("unknown stream '{}'", config.input_stream)))
+    }
+}
+
+struct JsonParser {
+    /// Input handle to push parsed data to.
+    input_stream: Box<dyn DeCollectionHandle>,
+
+    /// Handle connected to the same input stream, used to check that both
+    /// halves of an update that deletes and inserts a record deserialize
+    /// before pushing either of them to `input_stream`.  Its buffer is
+    /// cleared after every use.
+    scratch_stream: Box<dyn DeCollectionHandle>,
+
+    update_format: JsonUpdateFormat,
+
+    array: bool,
+
+    /// Since we cannot assume that the input buffer ends on a value
+    /// boundary, we save the "leftover" part of the buffer that contains an
+    /// incomplete JSON value and prepend it to the next input buffer.
+    leftover: Vec<u8>,
+
+    /// Callback to report invalid records to instead of failing (see
+    /// [`Parser::on_invalid_record`]).
+    invalid_record_callback: Option<InvalidRecordCallback>,
+}
+
+impl JsonParser {
+    fn new(
+        input_stream: &dyn DeCollectionHandle,
+        update_format: JsonUpdateFormat,
+        array: bool,
+    ) -> Self {
+        Self {
+            input_stream: input_stream.fork(),
+            scratch_stream: input_stream.fork(),
+            update_format,
+            array,
+            leftover: Vec::new(),
+            invalid_record_callback: None,
+        }
+    }
+
+    /// Report an invalid record to the invalid record callback, if any;
+    /// otherwise return `error`.
+    fn reject(&mut self, record: &[u8], error: AnyError) -> AnyResult<()> {
+        match &mut self.invalid_record_callback {
+            Some(callback) => {
+                callback(record, error);
+                Ok(())
+            }
+            None => Err(error),
+        }
+    }
+
+    /// Push a single update to the input handle, rejecting it if it is
+    /// invalid.
+    fn apply_or_reject(&mut self, update: &RawValue) -> AnyResult<usize> {
+        self.apply_update(update).or_else(|e| {
+            self.reject(update.get().as_bytes(), e)?;
+            Ok(0)
+        })
+    }
+
+    /// Push a single update to the input handle.
+    ///
+    /// Returns the number of records inserted or deleted by the update.
+    fn apply_update(&mut self, update: &RawValue) -> AnyResult<usize> {
+        match self.update_format {
+            JsonUpdateFormat::Raw => {
+                let mut deserializer = JsonDeserializer::from_str(update.get());
+                let mut deserializer = <dyn ErasedDeserializer>::erase(&mut deserializer);
+                self.input_stream.insert(&mut deserializer)?;
+                Ok(1)
+            }
+            JsonUpdateFormat::InsertDelete => {
+                let envelope = serde_json::from_str::<InsertDeleteUpdate>(update.get())?;
+
+                if envelope.delete.is_none() && envelope.insert.is_none() {
+                    return Err(AnyError::msg(format!(
+                        "update must contain an 'insert' or 'delete' field: '{}'",
+                        update.get()
+                    )));
+                }
+
+                self.apply_delete_insert(envelope.delete, false, envelope.insert)
+            }
+            JsonUpdateFormat::Debezium => {
+                if update.get() == "null" {
+                    return Ok(0);
+                }
+
+                let envelope = serde_json::from_str::<DebeziumEnvelope>(update.get())?;
+                let payload = serde_json::from_str::<DebeziumPayload>(
+                    envelope.payload.unwrap_or(update).get(),
+                )?;
+
+                let (before, after) = match &*payload.op {
+                    "c" | "r" => (None, Some(payload.after)),
+                    "d" => (Some(payload.before), None),
+                    "u" => (Some(payload.before), Some(payload.after)),
+                    op => {
+                        return Err(AnyError::msg(format!(
+                            "unsupported Debezium operation '{op}': '{}'",
+                            update.get()
+                        )))
+                    }
+                };
+
+                let before = before
+                    .map(|before| {
+                        before.ok_or_else(|| {
+                            AnyError::msg(format!(
+                                "Debezium event is missing the 'before' value: '{}'",
+                                update.get()
+                            ))
+                        })
+                    })
+                    .transpose()?;
+                let after = after
+                    .map(|after| {
+                        after.ok_or_else(|| {
+                            AnyError::msg(format!(
+                                "Debezium event is missing the 'after' value: '{}'",
+                                update.get()
+                            ))
+                        })
+                    })
+                    .transpose()?;
+
+                self.apply_delete_insert(before, true, after)
+            }
+        }
+    }
+
+    /// Push an optional delete followed by an optional insert to the input
+    /// handle.  The deleted record is specified by value if `delete_by_val`
+    /// is `true` (see [`DeCollectionHandle::delete_val`]).
+    ///
+    /// An update that contains both a delete and an insert is first pushed
+    /// to the scratch handle, so that it is not half-applied if one of the
+    /// records fails to deserialize.
+    ///
+    /// Returns the number of records inserted or deleted by the update.
+    fn apply_delete_insert(
+        &mut self,
+        delete: Option<&RawValue>,
+        delete_by_val: bool,
+        insert: Option<&RawValue>,
+    ) -> AnyResult<usize> {
+        if delete.is_some() && insert.is_some() {
+            let result =
+                push_delete_insert(self.scratch_stream.as_mut(), delete, delete_by_val, insert);
+            self.scratch_stream.clear_buffer();
+            result?;
+        }
+
+        push_delete_insert(self.input_stream.as_mut(), delete, delete_by_val, insert)
+    }
+
+    /// Parse a sequence of JSON values from `data`.
+    ///
+    /// Stops at the first incomplete value, which can only happen at the
+    /// end of `data`.  Returns the number of parsed records and the number of
+    /// bytes consumed from `data`.
+    ///
+    /// If an invalid record callback is installed, invalid updates are
+    /// passed to the callback and skipped.  On a syntax error, the rest of
+    /// the current line is skipped and parsing resumes from the next line.
+    fn parse_values(&mut self, data: &[u8]) -> (AnyResult<usize>, usize) {
+        let mut base = 0;
+        let mut stream = JsonDeserializer::from_slice(data).into_iter::<&RawValue>();
+        let mut num_records = 0;
+
+        loop {
+            let offset = base + stream.byte_offset();
+            match stream.next() {
+                None => return (Ok(num_records), data.len()),
+                Some(Err(e)) if e.is_eof() => return (Ok(num_records), offset),
+                Some(Err(e)) => {
+                    let start = offset
+                        + data[offset..]
+                            .iter()
+                            .position(|c| !c.is_ascii_whitespace())
+                            .unwrap_or(0);
+                    let end = data[start..]
+                        .iter()
+                        .position(|&c| c == b'\n')
+                        .map_or(data.len(), |pos| start + pos);
+                    if let Err(e) = self.reject(&data[start..end], e.into()) {
+                        return (Err(e), data.len());
+                    }
+                    base = (end + 1).min(data.len());
+                    stream = JsonDeserializer::from_slice(&data[base..]).into_iter::<&RawValue>();
+                }
+                Some(Ok(value)) => {
+                    let res = if self.array {
+                        match serde_json::from_str::<Vec<&RawValue>>(value.get()) {
+                            Ok(updates) => updates
+                                .into_iter()
+                                .try_fold(0, |n, update| Ok(n + self.apply_or_reject(update)?)),
+                            Err(e) => self.reject(value.get().as_bytes(), e.into()).map(|()| 0),
+                        }
+                    } else {
+                        self.apply_or_reject(value)
+                    };
+                    match res {
+                        Ok(n) => num_records += n,
+                        Err(e) => return (Err(e), data.len()),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the index of the first character following the last newline
+    /// in `data`.
+    fn split_on_newline(data: &[u8]) -> usize {
+        let data_len = data.len();
+        let index = data
+            .iter()
+            .rev()
+            .position(|&x| x == b'\n')
+            .unwrap_or(data_len);
+
+        data_len - index
+    }
+}
+
+/// Push an optional delete followed by an optional insert to `input_stream`
+/// (see [`JsonParser::apply_delete_insert`]).
+fn push_delete_insert(
+    input_stream: &mut dyn DeCollectionHandle,
+    delete: Option<&RawValue>,
+    delete_by_val: bool,
+    insert: Option<&RawValue>,
+) -> AnyResult<usize> {
+    let mut num_records = 0;
+
+    if let Some(val) = delete {
+        let mut deserializer = JsonDeserializer::from_str(val.get());
+        let mut deserializer = <dyn ErasedDeserializer>::erase(&mut deserializer);
+        if delete_by_val {
+            input_stream.delete_val(&mut deserializer)?;
+        } else {
+            input_stream.delete(&mut deserializer)?;
+        }
+        num_records += 1;
+    }
+
+    if let Some(val) = insert {
+        let mut deserializer = JsonDeserializer::from_str(val.get());
+        let mut deserializer = <dyn ErasedDeserializer>::erase(&mut deserializer);
+        input_stream.insert(&mut deserializer)?;
+        num_records += 1;
+    }
+
+    Ok(num_records)
+}
+
+impl Parser for JsonParser {
+    fn input(&mut self, data: &[u8]) -> AnyResult<usize> {
+        let leftover = Self::split_on_newline(data);
+
+        if leftover == 0 {
+            // `data` doesn't contain a new-line character; append it to
+            // the `leftover` buffer so it gets processed with the next input
+            // buffer.
+            self.leftover.extend_from_slice(data);
+            Ok(0)
+        } else {
+            let mut buffer = take(&mut self.leftover);
+            buffer.extend_from_slice(&data[0..leftover]);
+
+            let (res, consumed) = self.parse_values(&buffer);
+
+            // Values that span multiple lines may still be incomplete;
+            // prepend them to the next buffer along with the tail of `data`.
+            buffer.drain(0..consumed);
+            buffer.extend_from_slice(&data[leftover..]);
+            self.leftover = buffer;
+
+            res
+        }
+    }
+
+    fn eoi(&mut self) -> AnyResult<usize> {
+        if self.leftover.is_empty() {
+            return Ok(0);
+        }
+
+        // Try to interpret the leftover chunk as a sequence of complete JSON
+        // values.
+        let leftover = take(&mut self.leftover);
+        let (res, consumed) = self.parse_values(&leftover);
+
+        if consumed < leftover.len() {
+            let num_records = res?;
+            self.reject(
+                &leftover[consumed..],
+                AnyError::msg(format!(
+                    "incomplete JSON value at the end of input: '{}'",
+                    String::from_utf8_lossy(&leftover[consumed..])
+                )),
+            )?;
+            return Ok(num_records);
+        }
+
+        res
+    }
+
+    fn flush(&mut self) {
+        self.input_stream.flush();
+    }
+
+    fn clear(&mut self) {
+        self.input_stream.clear_buffer();
+    }
+
+    fn fork(&self) -> Box<dyn Parser> {
+        Box::new(Self::new(
+            &*self.input_stream,
+            self.update_format,
+            self.array,
+        ))
+    }
+
+    fn on_invalid_record(&mut self, callback: InvalidRecordCallback) -> bool {
+        self.invalid_record_callback = Some(callback);
+        true
+    }
+}
+
+/// JSON format encoder.
+pub struct JsonOutputFormat;
+
+const fn default_buffer_size_records() -> usize {
+    10_000
+}
+
+#[derive(Deserialize)]
+struct JsonEncoderConfig {
+    #[serde(default = "default_buffer_size_records")]
+    buffer_size_records: usize,
+
+    /// Package records in each output buffer into a JSON array.
+    ///
+    /// When `false`, the encoder outputs newline-delimited JSON.
+    #[serde(default)]
+    array: bool,
+}
+
+impl OutputFormat for JsonOutputFormat {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("json")
+    }
+
+    fn new_encoder(
+        &self,
+        config: &YamlValue,
+        consumer: Box<dyn OutputConsumer>,
+    ) -> AnyResult<Box<dyn Encoder>> {
+        let config = JsonEncoderConfig::deserialize(config)?;
+
+        Ok(Box::new(JsonEncoder::new(consumer, config)))
+    }
+}
+
+/// Encoder that outputs each record as a `[record, weight]` pair, where
+/// positive and negative weights represent insertions and retractions
+/// respectively.
+struct JsonEncoder {
+    /// Input handle to push serialized data to.
+    output_consumer: Box<dyn OutputConsumer>,
+
+    config: JsonEncoderConfig,
+
+    buffer: Vec<u8>,
+}
+
+impl JsonEncoder {
+    fn new(output_consumer: Box<dyn OutputConsumer>, config: JsonEncoderConfig) -> Self {
+        Self {
+            output_consumer,
+            config,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Close the current JSON array (if any) and push the contents of the
+    /// buffer to the consumer.
+    fn push_buffer(&mut self) {
+        if self.config.array {
+            self.buffer.extend_from_slice(b"]\n");
+        }
+        self.output_consumer.push_buffer(&self.buffer);
+        self.buffer.clear();
+    }
+}
+
+impl Encoder for JsonEncoder {
+    fn consumer(&mut self) -> &mut dyn OutputConsumer {
+        self.output_consumer.as_mut()
+    }
+
+    fn encode(&mut self, batches: &[Box<dyn SerBatch>]) -> AnyResult<()> {
+        let mut num_records = 0;
+        let keyed = self.output_consumer.is_keyed();
+
+        for batch in batches.iter() {
+            let indexed = batch.is_indexed();
+            let mut cursor = batch.cursor();
+
+            while cursor.key_valid() {
+                if keyed {
+                    // Push the current record for each key as a separate
+                    // message keyed by the JSON encoding of the key.  The
+                    // record is the value of an indexed Z-set with positive
+                    // weight or the key itself for a Z-set.  Keys without
+                    // such a value have been deleted and are pushed as
+                    // tombstones.
+                    let key = serde_json::to_vec(cursor.key())?;
+                    cursor.seek_inserted_val();
+
+                    if cursor.val_valid() {
+                        let w = cursor.weight();
+                        let record = if indexed { cursor.val() } else { cursor.key() };
+                        if self.config.array {
+                            self.buffer.push(b'[');
+                        }
+                        let mut serializer = JsonSerializer::new(&mut self.buffer);
+                        serde::Serialize::serialize(&(record, w), &mut serializer)?;
+                        self.buffer.extend_from_slice(if self.config.array {
+                            b"]\n"
+                        } else {
+                            b"\n"
+                        });
+                        self.output_consumer.push_key(&key, Some(&self.buffer));
+                        self.buffer.clear();
+                    } else {
+                        self.output_consumer.push_key(&key, None);
+                    }
+
+                    cursor.step_key();
+                    continue;
+                }
+
+                let w = cursor.weight();
+
+                if self.config.array {
+                    self.buffer.push(if num_records == 0 { b'[' } else { b',' });
+                }
+
+                let mut serializer = JsonSerializer::new(&mut self.buffer);
+                serde::Serialize::serialize(&(cursor.key(), w), &mut serializer)?;
+
+                if !self.config.array {
+                    self.buffer.push(b'\n');
+                }
+                num_records += 1;
+
+                if num_records >= self.config.buffer_size_records {
+                    self.push_buffer();
+                    num_records = 0;
+                }
+
+                cursor.step_key();
+            }
+        }
+
+        if num_records > 0 {
+            self.push_buffer();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        test::{MockDeZSet, MockOutputConsumer, TestStruct},
+        Catalog, InputFormat, OutputFormat, SerBatch,
+    };
+    use dbsp::{trace::Batch, OrdIndexedZSet, OrdZSet};
+    use serde_yaml::Value as YamlValue;
+    use std::sync::{Arc, Mutex};
+
+    fn test_data() -> Vec<TestStruct> {
+        vec![
+            TestStruct {
+                id: 0,
+                b: true,
+                i: Some(10),
+                s: "foo".to_string(),
+            },
+            TestStruct {
+                id: 1,
+                b: false,
+                i: None,
+                s: "bar\nbaz".to_string(),
+            },
+        ]
+    }
+
+    /// Create a JSON parser with the specified config string, feed `input`
+    /// to it in chunks of `chunk_size` bytes.
+    fn parse(config: &str, input: &str, chunk_size: usize) -> Vec<(TestStruct, bool)> {
+        let mut catalog = Catalog::new();
+        let zset = <MockDeZSet<TestStruct>>::new();
+        catalog.register_input_collection_handle("test_input", zset.clone());
+
+        let mut parser = <dyn InputFormat>::get_format("json")
+            .unwrap()
+            .new_parser(
+                &serde_yaml::from_str(config).unwrap(),
+                &Arc::new(Mutex::new(catalog)),
+            )
+            .unwrap();
+
+        for chunk in input.as_bytes().chunks(chunk_size) {
+            parser.input(chunk).unwrap();
+            parser.flush();
+        }
+        parser.eoi().unwrap();
+        parser.flush();
+
+        let result = zset.state().flushed.clone();
+        result
+    }
+
+    #[test]
+    fn test_json_ndjson() {
+        let data = test_data();
+        let input = data
+            .iter()
+            .map(|val| serde_json::to_string(val).unwrap() + "\n")
+            .collect::<String>();
+
+        for chunk_size in [1, 7, 1000] {
+            let result = parse("input_stream: test_input", &input, chunk_size);
+            assert_eq!(
+                result,
+                data.iter()
+                    .map(|val| (val.clone(), true))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_array_insert_delete() {
+        let data = test_data();
+        let input = format!(
+            "[{{\"insert\": {}}},\n {{\"delete\": {}}}]\n[{{\"insert\": {}}}]",
+            serde_json::to_string(&data[0]).unwrap(),
+            serde_json::to_string(&data[1]).unwrap(),
+            serde_json::to_string(&data[1]).unwrap(),
+        );
+
+        for chunk_size in [1, 7, 1000] {
+            let result = parse(
+                "input_stream: test_input\nupdate_format: insert_delete\narray: true",
+                &input,
+                chunk_size,
+            );
+            assert_eq!(
+                result,
+                vec![
+                    (data[0].clone(), true),
+                    (data[1].clone(), false),
+                    (data[1].clone(), true)
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_invalid_records() {
+        let data = test_data();
+        let input = format!(
+            "{}\n{{\"id\": \"zero\"}}\n{{not json}}\n{}\n",
+            serde_json::to_string(&data[0]).unwrap(),
+            serde_json::to_string(&data[1]).unwrap(),
+        );
+
+        let mut catalog = Catalog::new();
+        let zset = <MockDeZSet<TestStruct>>::new();
+        catalog.register_input_collection_handle("test_input", zset.clone());
+
+        let mut parser = <dyn InputFormat>::get_format("json")
+            .unwrap()
+            .new_parser(
+                &serde_yaml::from_str("input_stream: test_input").unwrap(),
+                &Arc::new(Mutex::new(catalog)),
+            )
+            .unwrap();
+
+        let rejected = Arc::new(Mutex::new(Vec::new()));
+        let rejected_clone = rejected.clone();
+        assert!(parser.on_invalid_record(Box::new(move |record, _error| {
+            rejected_clone
+                .lock()
+                .unwrap()
+                .push(String::from_utf8(record.to_vec()).unwrap())
+        })));
+
+        for chunk in input.as_bytes().chunks(7) {
+            parser.input(chunk).unwrap();
+        }
+        parser.eoi().unwrap();
+        parser.flush();
+
+        assert_eq!(
+            zset.state().flushed,
+            vec![(data[0].clone(), true), (data[1].clone(), true)]
+        );
+        assert_eq!(
+            &*rejected.lock().unwrap(),
+            &["{\"id\": \"zero\"}".to_string(), "{not json}".to_string()]
+        );
+    }
+
+    /// An update whose insert fails to deserialize is rejected as a whole,
+    /// including its valid delete.
+    #[test]
+    fn test_json_insert_delete_atomic() {
+        let data = test_data();
+        let val0 = serde_json::to_string(&data[0]).unwrap();
+        let val1 = serde_json::to_string(&data[1]).unwrap();
+        let input = format!(
+            "{{\"delete\": {val0}, \"insert\": {{\"id\": \"zero\"}}}}\n\
+             {{\"delete\": {val0}, \"insert\": {val1}}}\n"
+        );
+
+        let mut catalog = Catalog::new();
+        let zset = <MockDeZSet<TestStruct>>::new();
+        catalog.register_input_collection_handle("test_input", zset.clone());
+
+        let mut parser = <dyn InputFormat>::get_format("json")
+            .unwrap()
+            .new_parser(
+                &serde_yaml::from_str("input_stream: test_input\nupdate_format: insert_delete")
+                    .unwrap(),
+                &Arc::new(Mutex::new(catalog)),
+            )
+            .unwrap();
+
+        let rejected = Arc::new(Mutex::new(0));
+        let rejected_clone = rejected.clone();
+        assert!(parser.on_invalid_record(Box::new(move |_record, _error| {
+            *rejected_clone.lock().unwrap() += 1
+        })));
+
+        parser.input(input.as_bytes()).unwrap();
+        parser.eoi().unwrap();
+        parser.flush();
+
+        assert_eq!(
+            zset.state().flushed,
+            vec![(data[0].clone(), false), (data[1].clone(), true)]
+        );
+        assert_eq!(*rejected.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_json_debezium() {
+        let data = test_data();
+        let val0 = serde_json::to_string(&data[0]).unwrap();
+        let val1 = serde_json::to_string(&data[1]).unwrap();
+        let input = format!(
+            "{{\"before\": null, \"after\": {val0}, \"op\": \"r\"}}\n\
+             {{\"schema\": {{}}, \"payload\": {{\"before\": {val0}, \"after\": {val1}, \"op\": \"u\", \"ts_ms\": 1}}}}\n\
+             {{\"before\": {val1}, \"after\": null, \"op\": \"d\"}}\n\
+             null\n"
+        );
+
+        for chunk_size in [1, 7, 1000] {
+            let result = parse(
+                "input_stream: test_input\nupdate_format: debezium",
+                &input,
+                chunk_size,
+            );
+            assert_eq!(
+                result,
+                vec![
+                    (data[0].clone(), true),
+                    (data[0].clone(), false),
+                    (data[1].clone(), true),
+                    (data[1].clone(), false),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_encoder() {
+        let data = test_data();
+        let batch = OrdZSet::from_tuples((), vec![(data[0].clone(), 1), (data[1].clone(), -1)]);
+        let batches =
+            vec![Box::new(crate::seroutput::SerBatchImpl::new(batch)) as Box<dyn SerBatch>];
+
+        let consumer = MockOutputConsumer::new();
+        let mut encoder = <dyn OutputFormat>::get_format("json")
+            .unwrap()
+            .new_encoder(
+                &serde_yaml::from_str("array: true").unwrap(),
+                Box::new(consumer.clone()),
+            )
+            .unwrap();
+        encoder.encode(&batches).unwrap();
+
+        let buffers = consumer.data.lock().unwrap();
+        assert_eq!(buffers.len(), 1);
+        let output: Vec<(TestStruct, i64)> = serde_json::from_slice(&buffers[0]).unwrap();
+        assert_eq!(output, vec![(data[0].clone(), 1), (data[1].clone(), -1)]);
+    }
+
+    #[test]
+    fn test_json_encoder_keyed() {
+        let data = test_data();
+        let batch = OrdZSet::from_tuples((), vec![(data[0].clone(), 1), (data[1].clone(), -1)]);
+        let batches =
+            vec![Box::new(crate::seroutput::SerBatchImpl::new(batch)) as Box<dyn SerBatch>];
+
+        let consumer = MockOutputConsumer::keyed();
+        let mut encoder = <dyn OutputFormat>::get_format("json")
+            .unwrap()
+            .new_encoder(&YamlValue::Null, Box::new(consumer.clone()))
+            .unwrap();
+        encoder.encode(&batches).unwrap();
+
+        // One message per record, keyed by the record.  The deleted record
+        // is pushed as a tombstone.
+        let buffers = consumer.data.lock().unwrap();
+        let keys = consumer.keys.as_ref().unwrap().lock().unwrap();
+        assert_eq!(buffers.len(), 2);
+        let output: (TestStruct, i64) = serde_json::from_slice(&buffers[0]).unwrap();
+        assert_eq!(output, (data[0].clone(), 1));
+        assert!(buffers[1].is_empty());
+        assert_eq!(
+            keys.iter()
+                .map(|key| serde_json::from_slice(key).unwrap())
+                .collect::<Vec<TestStruct>>(),
+            vec![data[0].clone(), data[1].clone()]
+        );
+    }
+
+    #[test]
+    fn test_json_encoder_keyed_indexed() {
+        let data = test_data();
+        let updated = TestStruct {
+            s: "qux".to_string(),
+            ..data[0].clone()
+        };
+
+        // Key 0 is updated, key 1 is deleted.
+        let batch = <OrdIndexedZSet<u32, TestStruct, i64>>::from_tuples(
+            (),
+            vec![
+                ((0u32, data[0].clone()), -1),
+                ((0u32, updated.clone()), 1),
+                ((1u32, data[1].clone()), -1),
+            ],
+        );
+        let batches =
+            vec![Box::new(crate::seroutput::SerBatchImpl::new(batch)) as Box<dyn SerBatch>];
+
+        let consumer = MockOutputConsumer::keyed();
+        let mut encoder = <dyn OutputFormat>::get_format("json")
+            .unwrap()
+            .new_encoder(&YamlValue::Null, Box::new(consumer.clone()))
+            .unwrap();
+        encoder.encode(&batches).unwrap();
+
+        // The updated key carries the new value, the deleted key is pushed
+        // as a tombstone.
+        let buffers = consumer.data.lock().unwrap();
+        let keys = consumer.keys.as_ref().unwrap().lock().unwrap();
+        assert_eq!(buffers.len(), 2);
+        let output: (TestStruct, i64) = serde_json::from_slice(&buffers[0]).unwrap();
+        assert_eq!(output, (updated, 1));
+        assert!(buffers[1].is_empty());
+        assert_eq!(&*keys, &[b"0".to_vec(), b"1".to_vec()]);
+    }
+}
@@ -1,5 +1,5 @@
-use crate::{Catalog, SerBatch};
-use anyhow::Result as AnyResult;
+use crate::{Catalog, SerBatch, Step};
+use anyhow::{Error as AnyError, Result as AnyResult};
 use once_cell::sync::Lazy;
 use serde_yaml::Value as YamlValue;
 use std::{
@@ -8,19 +8,45 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+mod avro;
+mod bincode;
 mod csv;
+mod json;
 
-use self::csv::{CsvInputFormat, CsvOutputFormat};
+use self::{
+    avro::{AvroInputFormat, AvroOutputFormat},
+    bincode::{BincodeInputFormat, BincodeOutputFormat},
+    csv::{CsvInputFormat, CsvOutputFormat},
+    json::{JsonInputFormat, JsonOutputFormat},
+};
 
 /// Static map of supported input formats.
 // TODO: support for registering new formats at runtime in order to allow
 // external crates to implement new formats.
-static INPUT_FORMATS: Lazy<BTreeMap<&'static str, Box<dyn InputFormat>>> =
-    Lazy::new(|| BTreeMap::from([("csv", Box::new(CsvInputFormat) as Box<dyn InputFormat>)]));
+static INPUT_FORMATS: Lazy<BTreeMap<&'static str, Box<dyn InputFormat>>> = Lazy::new(|| {
+    BTreeMap::from([
+        ("avro", Box::new(AvroInputFormat) as Box<dyn InputFormat>),
+        (
+            "bincode",
+            Box::new(BincodeInputFormat) as Box<dyn InputFormat>,
+        ),
+        ("csv", Box::new(CsvInputFormat) as Box<dyn InputFormat>),
+        ("json", Box::new(JsonInputFormat) as Box<dyn InputFormat>),
+    ])
+});
 
 /// Static map of supported output formats.
-static OUTPUT_FORMATS: Lazy<BTreeMap<&'static str, Box<dyn OutputFormat>>> =
-    Lazy::new(|| BTreeMap::from([("csv", Box::new(CsvOutputFormat) as Box<dyn OutputFormat>)]));
+static OUTPUT_FORMATS: Lazy<BTreeMap<&'static str, Box<dyn OutputFormat>>> = Lazy::new(|| {
+    BTreeMap::from([
+        ("avro", Box::new(AvroOutputFormat) as Box<dyn OutputFormat>),
+        (
+            "bincode",
+            Box::new(BincodeOutputFormat) as Box<dyn OutputFormat>,
+        ),
+        ("csv", Box::new(CsvOutputFormat) as Box<dyn OutputFormat>),
+        ("json", Box::new(JsonOutputFormat) as Box<dyn OutputFormat>),
+    ])
+});
 
 /// Trait that represents a specific data format.
 ///
@@ -60,6 +86,12 @@ impl dyn InputFormat {
     }
 }
 
+/// Callback invoked by a parser for each input record that fails to parse
+/// (see [`Parser::on_invalid_record`]).
+///
+/// Receives the raw record and the parse error.
+pub type InvalidRecordCallback = Box<dyn FnMut(&[u8], AnyError) + Send>;
+
 /// Parser that converts a raw byte stream into a stream of database records.
 pub trait Parser: Send {
     /// Push a chunk of data to the parser.
@@ -102,8 +134,25 @@ pub trait Parser: Send {
     /// Create a new parser with the same configuration as `self`.
     ///
     /// Used by multithreaded transport endpoints to create multiple parallel
-    /// input pipelines.
+    /// input pipelines.  The callback installed via
+    /// [`on_invalid_record`](`Self::on_invalid_record`) is not inherited by
+    /// the new parser.
     fn fork(&self) -> Box<dyn Parser>;
+
+    /// Skip invalid records instead of failing.
+    ///
+    /// By default, [`input`](`Self::input`) and [`eoi`](`Self::eoi`) fail on
+    /// the first record that cannot be parsed.  Once `callback` is installed,
+    /// the parser instead passes each invalid record along with the error to
+    /// `callback` and continues parsing the rest of the input.
+    ///
+    /// Returns `false` if the parser does not support skipping individual
+    /// records, e.g., because it cannot locate the start of the next record
+    /// after an error.  Such parsers keep failing on invalid input.  The
+    /// default implementation returns `false`.
+    fn on_invalid_record(&mut self, _callback: InvalidRecordCallback) -> bool {
+        false
+    }
 }
 
 pub trait OutputFormat: Send + Sync {
@@ -132,9 +181,52 @@ impl dyn OutputFormat {
 }
 
 pub trait Encoder: Send {
+    /// Returns a reference to the consumer that the encoder is connected to.
+    fn consumer(&mut self) -> &mut dyn OutputConsumer;
+
     fn encode(&mut self, batches: &[Box<dyn SerBatch>]) -> AnyResult<()>;
 }
 
 pub trait OutputConsumer: Send {
+    /// Notifies the consumer that the following buffers contain the output
+    /// of circuit step `step`.
+    ///
+    /// An error means that the consumer cannot deliver the output of the
+    /// step.  The default implementation does nothing.
+    fn batch_start(&mut self, _step: Step) -> AnyResult<()> {
+        Ok(())
+    }
+
     fn push_buffer(&mut self, buffer: &[u8]);
+
+    /// Returns `true` if the consumer expects the encoder to push the updates
+    /// for each key as a separate message via [`push_key`](`Self::push_key`).
+    ///
+    /// The default implementation returns `false`.
+    fn is_keyed(&self) -> bool {
+        false
+    }
+
+    /// Push a message containing all updates for a single key.
+    ///
+    /// `key` is the encoding of the key alone, in the same format as `val`.
+    /// `val` is `None` for a tombstone, i.e., a message that deletes the key,
+    /// which allows log-compacted transports to discard earlier messages
+    /// with the same key.  The default implementation discards the key and
+    /// ignores tombstones.
+    fn push_key(&mut self, _key: &[u8], val: Option<&[u8]>) {
+        if let Some(val) = val {
+            self.push_buffer(val)
+        }
+    }
+
+    /// Notifies the consumer that all outputs of the step passed to the
+    /// last [`batch_start`](`Self::batch_start`) call have been pushed.
+    ///
+    /// An error means that the output of the step has not been delivered,
+    /// e.g., because the transaction that contained it has been aborted.
+    /// The default implementation does nothing.
+    fn batch_end(&mut self) -> AnyResult<()> {
+        Ok(())
+    }
 }
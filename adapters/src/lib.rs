@@ -136,11 +136,16 @@ pub use catalog::Catalog;
 pub use deinput::{
     DeCollectionHandle, DeMapHandle, DeScalarHandle, DeScalarHandleImpl, DeSetHandle, DeZSetHandle,
 };
-pub use format::{Encoder, InputFormat, OutputConsumer, OutputFormat, Parser};
-pub use seroutput::{SerBatch, SerCursor, SerOutputBatchHandle};
+pub use format::{
+    Encoder, InputFormat, InvalidRecordCallback, OutputConsumer, OutputFormat, Parser,
+};
+pub use seroutput::{KeyFilter, SerBatch, SerCursor, SerOutputBatchHandle};
 
 pub use controller::{Controller, ControllerConfig, ControllerError};
 pub use transport::{
     FileInputTransport, InputConsumer, InputEndpoint, InputTransport, OutputEndpoint,
-    OutputTransport,
+    OutputTransport, Step,
 };
+
+#[cfg(feature = "server")]
+pub use transport::{HttpInputEndpoint, HttpOutputEndpoint};
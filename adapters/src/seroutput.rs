@@ -1,10 +1,16 @@
+use anyhow::{Error as AnyError, Result as AnyResult};
 use dbsp::{
-    trace::{Batch, BatchReader, Cursor},
+    algebra::HasZero,
+    trace::{cursor::CursorList, Batch, BatchReader, Builder, Cursor},
     OutputHandle,
 };
 use erased_serde::Serialize as ErasedSerialize;
-use serde::Serialize;
-use std::sync::Arc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::{
+    any::{Any, TypeId},
+    sync::{Arc, Mutex},
+};
 
 /// A type-erased batch whose contents can be serialized.
 ///
@@ -22,9 +28,29 @@ pub trait SerBatch: Send {
         self.len() == 0
     }
 
+    /// Returns `true` if the batch is an indexed Z-set, i.e., if it maps
+    /// keys to records, and `false` if it is a Z-set, i.e., if its keys are
+    /// records and its values are `()`.
+    fn is_indexed(&self) -> bool;
+
     /// Cursor over the batch.
     fn cursor<'a>(&'a self) -> Box<dyn SerCursor + 'a>;
 
+    /// Returns `self` as `Any`, so it can be downcast to the concrete batch
+    /// type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Merge `self` with `other`.
+    ///
+    /// Weights of identical records are added up, so updates that cancel
+    /// each other out are dropped from the result.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `other` is not of the same concrete type as `self`, e.g., if
+    /// the two batches were produced by different output streams.
+    fn merge(&self, other: &dyn SerBatch) -> AnyResult<Box<dyn SerBatch>>;
+
     // fn fork(&self) -> Box<dyn SerBatch>;
 }
 
@@ -82,6 +108,15 @@ pub trait SerCursor {
 
     /// Rewinds the cursor to the first value for current key.
     fn rewind_vals(&mut self);
+
+    /// Advances the cursor to the next value for the current key with a
+    /// positive weight, or past the last value if there is no such value,
+    /// e.g., because the batch deletes the key.
+    fn seek_inserted_val(&mut self) {
+        while self.val_valid() && self.weight() <= 0 {
+            self.step_val();
+        }
+    }
 }
 
 /// [`SerBatch`] implementation that wraps a `BatchReader`.
@@ -102,11 +137,15 @@ where
             batch: Arc::new(batch),
         }
     }
+
+    pub fn from_arc(batch: Arc<B>) -> Self {
+        Self { batch }
+    }
 }
 
 impl<B> SerBatch for SerBatchImpl<B>
 where
-    B: BatchReader<Time = ()> + Clone + Send + Sync,
+    B: Batch<Time = ()> + Send + Sync,
     B::Key: Serialize,
     B::Val: Serialize,
     B::R: Into<i64>,
@@ -119,10 +158,26 @@ where
         self.batch.len()
     }
 
+    fn is_indexed(&self) -> bool {
+        TypeId::of::<B::Val>() != TypeId::of::<()>()
+    }
+
     fn cursor<'a>(&'a self) -> Box<dyn SerCursor + 'a> {
         Box::new(SerBatchCursor::new(&*self.batch))
     }
 
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn merge(&self, other: &dyn SerBatch) -> AnyResult<Box<dyn SerBatch>> {
+        let other = other
+            .as_any()
+            .downcast_ref::<Self>()
+            .ok_or_else(|| AnyError::msg("cannot merge batches of different types"))?;
+        Ok(Box::new(Self::new(self.batch.merge(&other.batch))))
+    }
+
     /*fn fork(&self) -> Box<dyn SerBatch> {
         Box::new(Self {
             batch: self.batch.clone(),
@@ -216,6 +271,67 @@ pub trait SerOutputBatchHandle: Send + Sync {
 
     /// Returns an alias to `self`.
     fn fork(&self) -> Box<dyn SerOutputBatchHandle>;
+
+    /// `true` if the handle maintains the integral of the output stream
+    /// (see [`Catalog::register_materialized_output_batch_handle`]).
+    ///
+    /// [`Catalog::register_materialized_output_batch_handle`]: crate::Catalog::register_materialized_output_batch_handle
+    fn is_materialized(&self) -> bool;
+
+    /// Returns the current contents of the integrated output stream,
+    /// restricted to keys that match `filter`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the handle does not maintain the integral of the stream or
+    /// if the bounds in `filter` cannot be deserialized into the key type of
+    /// the stream.
+    fn snapshot(&self, filter: &KeyFilter) -> AnyResult<Box<dyn SerBatch>>;
+}
+
+/// Restricts the set of keys returned by
+/// [`SerOutputBatchHandle::snapshot`].
+///
+/// Keys are specified as JSON values, which are deserialized into the key
+/// type of the stream.
+#[derive(Clone, Debug, Default)]
+pub struct KeyFilter {
+    /// Only return keys greater than or equal to `lower`.
+    pub lower: Option<JsonValue>,
+
+    /// Only return keys strictly less than `upper`.
+    pub upper: Option<JsonValue>,
+
+    /// Only return keys that start with `prefix`.
+    ///
+    /// A key matches the prefix if its JSON encoding does.  A string prefix
+    /// matches strings that start with it, an array prefix matches arrays
+    /// (e.g., tuples) whose leading elements are equal to the elements of the
+    /// prefix, and an object prefix matches objects (e.g., structs) that
+    /// contain all fields of the prefix with equal values.  Any other value
+    /// only matches keys equal to it.
+    pub prefix: Option<JsonValue>,
+}
+
+impl KeyFilter {
+    /// `true` if the filter accepts all keys.
+    pub fn is_empty(&self) -> bool {
+        self.lower.is_none() && self.upper.is_none() && self.prefix.is_none()
+    }
+}
+
+/// Returns `true` if `key` starts with `prefix` (see [`KeyFilter::prefix`]).
+fn json_starts_with(key: &JsonValue, prefix: &JsonValue) -> bool {
+    match (key, prefix) {
+        (JsonValue::String(key), JsonValue::String(prefix)) => key.starts_with(prefix.as_str()),
+        (JsonValue::Array(key), JsonValue::Array(prefix)) => {
+            prefix.len() <= key.len() && key.iter().zip(prefix.iter()).all(|(k, p)| k == p)
+        }
+        (JsonValue::Object(key), JsonValue::Object(prefix)) => prefix
+            .iter()
+            .all(|(field, p)| key.get(field).map(|k| k == p).unwrap_or(false)),
+        (key, prefix) => key == prefix,
+    }
 }
 
 impl<B> SerOutputBatchHandle for OutputHandle<B>
@@ -245,4 +361,238 @@ where
     fn fork(&self) -> Box<dyn SerOutputBatchHandle> {
         Box::new(self.clone())
     }
+
+    fn is_materialized(&self) -> bool {
+        false
+    }
+
+    fn snapshot(&self, _filter: &KeyFilter) -> AnyResult<Box<dyn SerBatch>> {
+        Err(AnyError::msg(
+            "output stream is not materialized; use 'Catalog::register_materialized_output_batch_handle' to register the stream",
+        ))
+    }
+}
+
+/// An output handle that maintains the integral of the output stream, i.e.,
+/// the current contents of the view computed by the stream.
+///
+/// Every batch taken from the handle is added to the integral, so the
+/// integral is only up to date if the handle is read after each step of the
+/// circuit.  The [`Controller`](`crate::Controller`) takes care of this.
+///
+/// Like a [`Spine`](`dbsp::trace::Spine`), the integral is stored as a list
+/// of batches of geometrically decreasing sizes.  A new batch is only merged
+/// with the smallest batches in the list, so that each update gets merged a
+/// logarithmic number of times rather than at every step.
+#[derive(Clone)]
+pub struct MaterializedOutputHandle<B> {
+    handle: OutputHandle<B>,
+    integral: Arc<Mutex<Vec<Arc<B>>>>,
+}
+
+impl<B> MaterializedOutputHandle<B>
+where
+    B: Batch<Time = ()> + Send + Sync,
+{
+    pub fn new(handle: OutputHandle<B>) -> Self {
+        Self {
+            handle,
+            integral: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Add `batches` to the integral.
+    fn update_integral(&self, batches: &[B]) {
+        let mut integral = self.integral.lock().unwrap();
+
+        for batch in batches.iter().filter(|batch| !batch.is_empty()) {
+            // Merge the new batch with the last batch in the integral while
+            // the latter is at most twice as large.
+            let mut batch = batch.clone();
+            while let Some(last) = integral.last() {
+                if last.len() > 2 * batch.len() {
+                    break;
+                }
+                batch = last.merge(&batch);
+                integral.pop();
+            }
+
+            if !batch.is_empty() {
+                integral.push(Arc::new(batch));
+            }
+        }
+    }
+}
+
+impl<B> SerOutputBatchHandle for MaterializedOutputHandle<B>
+where
+    B: Batch<Time = ()> + Send + Sync,
+    B::Key: Serialize + DeserializeOwned,
+    B::Val: Serialize,
+    B::R: Into<i64>,
+{
+    fn take_from_worker(&self, worker: usize) -> Option<Box<dyn SerBatch>> {
+        self.handle.take_from_worker(worker).map(|batch| {
+            self.update_integral(std::slice::from_ref(&batch));
+            Box::new(SerBatchImpl::new(batch)) as Box<dyn SerBatch>
+        })
+    }
+
+    fn take_from_all(&self) -> Vec<Box<dyn SerBatch>> {
+        let batches = self.handle.take_from_all();
+        self.update_integral(&batches);
+
+        batches
+            .into_iter()
+            .map(|batch| Box::new(SerBatchImpl::new(batch)) as Box<dyn SerBatch>)
+            .collect()
+    }
+
+    fn consolidate(&self) -> Box<dyn SerBatch> {
+        let batch = self.handle.consolidate();
+        self.update_integral(std::slice::from_ref(&batch));
+        Box::new(SerBatchImpl::new(batch))
+    }
+
+    fn fork(&self) -> Box<dyn SerOutputBatchHandle> {
+        Box::new(self.clone())
+    }
+
+    fn is_materialized(&self) -> bool {
+        true
+    }
+
+    fn snapshot(&self, filter: &KeyFilter) -> AnyResult<Box<dyn SerBatch>> {
+        // Release the lock before reading the batches, so the snapshot
+        // doesn't block the controller.
+        let integral = self.integral.lock().unwrap().clone();
+        if filter.is_empty() && integral.len() <= 1 {
+            let batch = integral
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| Arc::new(B::empty(())));
+            return Ok(Box::new(SerBatchImpl::from_arc(batch)));
+        }
+
+        let lower = filter
+            .lower
+            .as_ref()
+            .map(B::Key::deserialize)
+            .transpose()
+            .map_err(|e| AnyError::msg(format!("invalid lower bound: {e}")))?;
+        let upper = filter
+            .upper
+            .as_ref()
+            .map(B::Key::deserialize)
+            .transpose()
+            .map_err(|e| AnyError::msg(format!("invalid upper bound: {e}")))?;
+
+        let mut builder = B::Builder::new_builder(());
+        let mut cursor = CursorList::new(integral.iter().map(|batch| batch.cursor()).collect());
+
+        if let Some(lower) = &lower {
+            cursor.seek_key(lower);
+        }
+
+        while cursor.key_valid() {
+            if let Some(upper) = &upper {
+                if cursor.key() >= upper {
+                    break;
+                }
+            }
+
+            let matches = match &filter.prefix {
+                None => true,
+                Some(prefix) => json_starts_with(&serde_json::to_value(cursor.key())?, prefix),
+            };
+
+            if matches {
+                while cursor.val_valid() {
+                    // Updates in different batches can cancel out.
+                    let weight = cursor.weight();
+                    if !weight.is_zero() {
+                        builder.push((
+                            B::item_from(cursor.key().clone(), cursor.val().clone()),
+                            weight,
+                        ));
+                    }
+                    cursor.step_val();
+                }
+            }
+            cursor.step_key();
+        }
+
+        Ok(Box::new(SerBatchImpl::new(builder.done())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KeyFilter, MaterializedOutputHandle, SerBatch, SerOutputBatchHandle};
+    use dbsp::Runtime;
+    use serde_json::{json, Value as JsonValue};
+
+    fn contents(batch: &dyn SerBatch) -> Vec<(JsonValue, i64)> {
+        let mut result = Vec::new();
+        let mut cursor = batch.cursor();
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                result.push((serde_json::to_value(cursor.key()).unwrap(), cursor.weight()));
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+        result
+    }
+
+    #[test]
+    fn test_materialized_output() {
+        let (mut circuit, (input, output)) = Runtime::init_circuit(2, |circuit| {
+            let (input, hinput) = circuit.add_input_zset::<u64, i32>();
+            (hinput, input.output())
+        })
+        .unwrap();
+        let handle = MaterializedOutputHandle::new(output);
+
+        // Insert one key per step: the integral consists of a logarithmic
+        // number of batches.
+        for key in 0..100 {
+            input.push(key, 1);
+            circuit.step().unwrap();
+            handle.take_from_all();
+        }
+        assert!(handle.integral.lock().unwrap().len() <= 7);
+
+        // Deletions cancel out insertions stored in different batches.
+        for key in (0..100).step_by(2) {
+            input.push(key, -1);
+            circuit.step().unwrap();
+            handle.take_from_all();
+        }
+
+        let snapshot = handle.snapshot(&KeyFilter::default()).unwrap();
+        assert_eq!(
+            contents(&*snapshot),
+            (1..100)
+                .step_by(2)
+                .map(|key| (json!(key), 1))
+                .collect::<Vec<_>>()
+        );
+
+        let filter = KeyFilter {
+            lower: Some(json!(10)),
+            upper: Some(json!(20)),
+            prefix: None,
+        };
+        let snapshot = handle.snapshot(&filter).unwrap();
+        assert_eq!(
+            contents(&*snapshot),
+            (11..20)
+                .step_by(2)
+                .map(|key| (json!(key), 1))
+                .collect::<Vec<_>>()
+        );
+
+        circuit.kill().unwrap();
+    }
 }
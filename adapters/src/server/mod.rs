@@ -1,18 +1,37 @@
-use crate::{Catalog, Controller, ControllerConfig, ControllerError};
+use crate::{
+    controller::{ConfigError, InputEndpointConfig, OutputEndpointConfig},
+    transport::HTTP_TRANSPORT_NAME,
+    Catalog, Controller, ControllerConfig, ControllerError, HttpInputEndpoint, HttpOutputEndpoint,
+    InputEndpoint, KeyFilter, OutputConsumer, OutputEndpoint, OutputFormat, Step,
+};
 use actix_files as fs;
 use actix_files::NamedFile;
 use actix_web::{
+    delete,
     dev::{ServiceFactory, ServiceRequest},
     get,
+    http::header,
     middleware::Logger,
-    rt, web,
-    web::Data as WebData,
-    App, Error as ActixError, HttpResponse, HttpServer, Responder, Result as ActixResult,
+    post, rt, web,
+    web::{Bytes, Data as WebData},
+    App, Error as ActixError, HttpRequest, HttpResponse, HttpServer, Responder,
+    Result as ActixResult,
 };
 use anyhow::Result as AnyResult;
 use dbsp::DBSPHandle;
+use futures::StreamExt;
 use log::error;
-use std::sync::Mutex;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use std::{
+    collections::BTreeMap,
+    mem::take,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+mod openmetrics;
 
 // TODO:
 //
@@ -20,26 +39,146 @@ use std::sync::Mutex;
 
 struct ServerState {
     controller: Mutex<Option<Controller>>,
+
+    /// HTTP input endpoints indexed by endpoint name.
+    http_inputs: Mutex<BTreeMap<String, HttpInputEndpoint>>,
+
+    /// HTTP output endpoints indexed by endpoint name.
+    http_outputs: Mutex<BTreeMap<String, HttpOutputEndpoint>>,
 }
 
 impl ServerState {
-    fn new(controller: Controller) -> Self {
+    fn new(
+        controller: Controller,
+        http_inputs: BTreeMap<String, HttpInputEndpoint>,
+        http_outputs: BTreeMap<String, HttpOutputEndpoint>,
+    ) -> Self {
         Self {
             controller: Mutex::new(Some(controller)),
+            http_inputs: Mutex::new(http_inputs),
+            http_outputs: Mutex::new(http_outputs),
+        }
+    }
+
+    /// Create a controller with the specified config.
+    ///
+    /// HTTP endpoints are not managed by the controller: we create them here
+    /// and connect them to the controller via
+    /// [`Controller::connect_input_with`] and
+    /// [`Controller::connect_output_with`], keeping a reference to each
+    /// endpoint to forward client requests to it.
+    fn with_config(
+        circuit: DBSPHandle,
+        catalog: Catalog,
+        config: &ControllerConfig,
+        error_cb: Box<dyn Fn(ControllerError) + Send + Sync>,
+    ) -> AnyResult<Self> {
+        let mut config = config.clone();
+        let (http_input_configs, inputs) = config
+            .inputs
+            .into_iter()
+            .partition(|(_, input_config)| input_config.transport.name == HTTP_TRANSPORT_NAME);
+        config.inputs = inputs;
+        let (http_output_configs, outputs) = config
+            .outputs
+            .into_iter()
+            .partition(|(_, output_config)| output_config.transport.name == HTTP_TRANSPORT_NAME);
+        config.outputs = outputs;
+
+        let controller = Controller::with_config(circuit, catalog, &config, error_cb)?;
+        let state = Self::new(controller, BTreeMap::new(), BTreeMap::new());
+
+        {
+            let controller = state.controller.lock().unwrap();
+            let controller = controller.as_ref().unwrap();
+
+            for (endpoint_name, input_config) in http_input_configs.iter() {
+                state.connect_input(controller, endpoint_name, input_config)?;
+            }
+
+            for (endpoint_name, output_config) in http_output_configs.iter() {
+                state.connect_output(controller, endpoint_name, output_config)?;
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Connect an input endpoint to `controller`.
+    ///
+    /// HTTP endpoints are created here and registered in `http_inputs`; other
+    /// endpoints are created by the controller.
+    fn connect_input(
+        &self,
+        controller: &Controller,
+        endpoint_name: &str,
+        config: &InputEndpointConfig,
+    ) -> AnyResult<()> {
+        if config.transport.name != HTTP_TRANSPORT_NAME {
+            return controller.connect_input(endpoint_name, config);
+        }
+
+        let mut http_inputs = self.http_inputs.lock().unwrap();
+        controller.connect_input_with(endpoint_name, config, |consumer| {
+            let endpoint = HttpInputEndpoint::new(consumer);
+            http_inputs.insert(endpoint_name.to_string(), endpoint.clone());
+            Ok(Box::new(endpoint) as Box<dyn InputEndpoint>)
+        })
+    }
+
+    /// Disconnect an input endpoint from `controller`.
+    fn disconnect_input(&self, controller: &Controller, endpoint_name: &str) -> AnyResult<()> {
+        controller.disconnect_input(endpoint_name)?;
+        self.http_inputs.lock().unwrap().remove(endpoint_name);
+        Ok(())
+    }
+
+    /// Connect an output endpoint to `controller`.
+    ///
+    /// HTTP endpoints are created here and registered in `http_outputs`;
+    /// other endpoints are created by the controller.
+    fn connect_output(
+        &self,
+        controller: &Controller,
+        endpoint_name: &str,
+        config: &OutputEndpointConfig,
+    ) -> AnyResult<()> {
+        if config.transport.name != HTTP_TRANSPORT_NAME {
+            return controller.connect_output(endpoint_name, config);
+        }
+
+        let endpoint = HttpOutputEndpoint::with_config(&config.transport.config)?;
+        controller.connect_output_with(endpoint_name, config, |_| {
+            Ok(Box::new(endpoint.clone()) as Box<dyn OutputEndpoint>)
+        })?;
+        self.http_outputs
+            .lock()
+            .unwrap()
+            .insert(endpoint_name.to_string(), endpoint);
+        Ok(())
+    }
+
+    /// Disconnect an output endpoint from `controller`.
+    ///
+    /// Terminates the streams of HTTP clients subscribed to the endpoint
+    /// once the controller has pushed all remaining outputs to it.
+    fn disconnect_output(&self, controller: &Controller, endpoint_name: &str) -> AnyResult<()> {
+        controller.disconnect_output(endpoint_name)?;
+        if let Some(endpoint) = self.http_outputs.lock().unwrap().remove(endpoint_name) {
+            endpoint.disconnect();
         }
+        Ok(())
     }
 }
 
 pub fn run(circuit: DBSPHandle, catalog: Catalog, yaml_config: &str, port: u16) -> AnyResult<()> {
     let config: ControllerConfig = serde_yaml::from_str(yaml_config)?;
-    let controller = Controller::with_config(
+    let state = WebData::new(ServerState::with_config(
         circuit,
         catalog,
         &config,
         Box::new(|e| error!("{e}")) as Box<dyn Fn(ControllerError) + Send + Sync>,
-    )?;
-
-    let state = WebData::new(ServerState::new(controller));
+    )?);
 
     rt::System::new().block_on(
         HttpServer::new(move || build_app(App::new().wrap(Logger::default()), state.clone()))
@@ -62,6 +201,15 @@ where
         .service(pause)
         .service(shutdown)
         .service(status)
+        .service(metrics)
+        .service(wait_for_step)
+        .service(input_endpoint)
+        .service(output_endpoint)
+        .service(connect_input)
+        .service(disconnect_input)
+        .service(connect_output)
+        .service(disconnect_output)
+        .service(view)
 }
 
 async fn index() -> ActixResult<NamedFile> {
@@ -103,10 +251,459 @@ async fn status(state: WebData<ServerState>) -> impl Responder {
     }
 }
 
+/// Export controller and circuit metrics in the OpenMetrics text format.
+#[get("/metrics")]
+async fn metrics(state: WebData<ServerState>) -> impl Responder {
+    let (controller_status, cpu_profile_request) = match &*state.controller.lock().unwrap() {
+        Some(controller) => (controller.shared_status(), controller.request_cpu_profile()),
+        None => return HttpResponse::Conflict().body("The pipeline has been terminated"),
+    };
+
+    // The circuit thread serves the profile request between steps.  Wait for
+    // it on the blocking thread pool without holding the controller lock.
+    let metrics = web::block(move || {
+        cpu_profile_request
+            .wait()
+            .map(|cpu_profile| openmetrics::render_metrics(&controller_status, &cpu_profile))
+    })
+    .await;
+
+    match metrics {
+        Ok(Ok(metrics)) => HttpResponse::Ok()
+            .content_type(openmetrics::OPENMETRICS_CONTENT_TYPE)
+            .body(metrics),
+        Ok(Err(e)) => {
+            HttpResponse::InternalServerError().body(format!("Failed to collect metrics: {e}"))
+        }
+        Err(e) => {
+            HttpResponse::InternalServerError().body(format!("Failed to collect metrics: {e}"))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct WaitForStepQuery {
+    /// Step number to wait for.
+    step: Step,
+
+    /// Maximal time to wait, in milliseconds.
+    #[serde(default = "default_wait_timeout_ms")]
+    timeout_ms: u64,
+}
+
+fn default_wait_timeout_ms() -> u64 {
+    30_000
+}
+
+/// Interval between checks for step completion in [`wait_for_step`].
+const WAIT_FOR_STEP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Long-poll request that completes once the circuit has evaluated step
+/// `step` and pushed its outputs to all output endpoints (see
+/// [`Controller::wait_for_step`]).
+///
+/// Responds with `408 Request Timeout` if the step is not completed within
+/// `timeout_ms` milliseconds.
+#[get("/wait_for_step")]
+async fn wait_for_step(
+    state: WebData<ServerState>,
+    query: web::Query<WaitForStepQuery>,
+) -> impl Responder {
+    let deadline = Instant::now() + Duration::from_millis(query.timeout_ms);
+
+    // Don't block the executor or the controller lock while waiting: poll
+    // the controller without blocking instead.
+    loop {
+        let complete = match &*state.controller.lock().unwrap() {
+            Some(controller) => controller.wait_for_step(query.step, Some(Duration::ZERO)),
+            None => return HttpResponse::Conflict().body("The pipeline has been terminated"),
+        };
+
+        match complete {
+            Ok(true) => {
+                return HttpResponse::Ok()
+                    .content_type(mime::APPLICATION_JSON)
+                    .body(serde_json::json!({ "step": query.step }).to_string())
+            }
+            Ok(false) if Instant::now() < deadline => {
+                rt::time::sleep(WAIT_FOR_STEP_POLL_INTERVAL).await
+            }
+            Ok(false) => {
+                return HttpResponse::RequestTimeout()
+                    .body(format!("Timeout waiting for step {}", query.step))
+            }
+            Err(e) => return HttpResponse::Conflict().body(e.to_string()),
+        }
+    }
+}
+
+/// Push data to an HTTP input endpoint.
+///
+/// The request body must contain a complete sequence of records in the
+/// format configured for the endpoint.  Responds with `400 Bad Request`,
+/// listing parse errors one per line, if any records fail to parse.  Such
+/// records are handled according to the endpoint's error policy, e.g.,
+/// skipped, while valid records in the request are still accepted.
+#[post("/input/{endpoint_name}")]
+async fn input_endpoint(
+    state: WebData<ServerState>,
+    endpoint_name: web::Path<String>,
+    body: web::Bytes,
+) -> impl Responder {
+    let endpoint_name = endpoint_name.into_inner();
+    let endpoint = match state
+        .http_inputs
+        .lock()
+        .unwrap()
+        .get(&endpoint_name)
+        .cloned()
+    {
+        Some(endpoint) => endpoint,
+        None => {
+            return HttpResponse::NotFound()
+                .body(format!("Unknown HTTP input endpoint '{endpoint_name}'"))
+        }
+    };
+
+    // Check for backpressure.  Release the lock before pushing data to the
+    // endpoint, so that parsing doesn't block other requests.
+    match &*state.controller.lock().unwrap() {
+        Some(controller) => {
+            let controller_status = controller.status();
+            if let Some(endpoint_id) = controller_status.input_endpoint_id_by_name(&endpoint_name) {
+                if controller_status.input_endpoint_full(&endpoint_id) {
+                    return HttpResponse::TooManyRequests()
+                        .body(format!("Input endpoint '{endpoint_name}' is full"));
+                }
+            }
+        }
+        None => return HttpResponse::Conflict().body("The pipeline has been terminated"),
+    }
+
+    // Parsing a large request can take a while; don't block the executor.
+    let result = match web::block(move || endpoint.push(&body)).await {
+        Ok(result) => result,
+        Err(e) => {
+            return HttpResponse::InternalServerError()
+                .body(format!("Failed to process input: {e}"))
+        }
+    };
+
+    match result {
+        Ok(errors) if errors.is_empty() => HttpResponse::Ok().body("Data received"),
+        Ok(errors) => HttpResponse::BadRequest().body(format!(
+            "Input endpoint '{endpoint_name}' failed to parse {} record(s):\n{}",
+            errors.len(),
+            errors.join("\n")
+        )),
+        Err(e) => HttpResponse::Conflict().body(format!(
+            "Input endpoint '{endpoint_name}' cannot accept data: {e}"
+        )),
+    }
+}
+
+/// Subscribe to an HTTP output endpoint.
+///
+/// Streams buffers produced by the endpoint's encoder as they become
+/// available, using chunked transfer encoding.  Clients that send
+/// `Accept: text/event-stream` receive each buffer as a server-sent event
+/// instead, with one `data` line per line of output.
+#[get("/output/{endpoint_name}")]
+async fn output_endpoint(
+    state: WebData<ServerState>,
+    endpoint_name: web::Path<String>,
+    req: HttpRequest,
+) -> impl Responder {
+    let endpoint_name = endpoint_name.into_inner();
+    let endpoint = match state
+        .http_outputs
+        .lock()
+        .unwrap()
+        .get(&endpoint_name)
+        .cloned()
+    {
+        Some(endpoint) => endpoint,
+        None => {
+            return HttpResponse::NotFound()
+                .body(format!("Unknown HTTP output endpoint '{endpoint_name}'"))
+        }
+    };
+
+    if state.controller.lock().unwrap().is_none() {
+        return HttpResponse::Conflict().body("The pipeline has been terminated");
+    }
+
+    let sse = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .map(|accept| accept.contains(mime::TEXT_EVENT_STREAM.essence_str()))
+        .unwrap_or(false);
+
+    let receiver = endpoint.subscribe();
+    if sse {
+        HttpResponse::Ok()
+            .content_type(mime::TEXT_EVENT_STREAM)
+            .streaming(receiver.map(|buffer| Ok::<_, ActixError>(sse_event(&buffer))))
+    } else {
+        HttpResponse::Ok()
+            .content_type(mime::APPLICATION_OCTET_STREAM)
+            .streaming(receiver.map(Ok::<_, ActixError>))
+    }
+}
+
+/// Format an output buffer as a server-sent event.
+fn sse_event(buffer: &[u8]) -> Bytes {
+    let mut event = String::with_capacity(buffer.len() + 16);
+    for line in String::from_utf8_lossy(buffer).lines() {
+        event.push_str("data: ");
+        event.push_str(line);
+        event.push('\n');
+    }
+    event.push('\n');
+    Bytes::from(event)
+}
+
+/// Connect a new input endpoint.
+///
+/// The request body contains the endpoint configuration in YAML or JSON
+/// format (see [`InputEndpointConfig`]).
+#[post("/input_endpoints/{endpoint_name}")]
+async fn connect_input(
+    state: WebData<ServerState>,
+    endpoint_name: web::Path<String>,
+    body: web::Bytes,
+) -> impl Responder {
+    let endpoint_name = endpoint_name.into_inner();
+    let config: InputEndpointConfig = match serde_yaml::from_slice(&body) {
+        Ok(config) => config,
+        Err(e) => {
+            return HttpResponse::BadRequest()
+                .body(format!("Invalid input endpoint configuration: {e}"))
+        }
+    };
+
+    match &*state.controller.lock().unwrap() {
+        Some(controller) => endpoint_response(
+            state.connect_input(controller, &endpoint_name, &config),
+            format!("Input endpoint '{endpoint_name}' connected"),
+        ),
+        None => HttpResponse::Conflict().body("The pipeline has been terminated"),
+    }
+}
+
+/// Disconnect an input endpoint.
+#[delete("/input_endpoints/{endpoint_name}")]
+async fn disconnect_input(
+    state: WebData<ServerState>,
+    endpoint_name: web::Path<String>,
+) -> impl Responder {
+    let endpoint_name = endpoint_name.into_inner();
+
+    match &*state.controller.lock().unwrap() {
+        Some(controller) => endpoint_response(
+            state.disconnect_input(controller, &endpoint_name),
+            format!("Input endpoint '{endpoint_name}' disconnected"),
+        ),
+        None => HttpResponse::Conflict().body("The pipeline has been terminated"),
+    }
+}
+
+/// Connect a new output endpoint.
+///
+/// The request body contains the endpoint configuration in YAML or JSON
+/// format (see [`OutputEndpointConfig`]).
+#[post("/output_endpoints/{endpoint_name}")]
+async fn connect_output(
+    state: WebData<ServerState>,
+    endpoint_name: web::Path<String>,
+    body: web::Bytes,
+) -> impl Responder {
+    let endpoint_name = endpoint_name.into_inner();
+    let config: OutputEndpointConfig = match serde_yaml::from_slice(&body) {
+        Ok(config) => config,
+        Err(e) => {
+            return HttpResponse::BadRequest()
+                .body(format!("Invalid output endpoint configuration: {e}"))
+        }
+    };
+
+    match &*state.controller.lock().unwrap() {
+        Some(controller) => endpoint_response(
+            state.connect_output(controller, &endpoint_name, &config),
+            format!("Output endpoint '{endpoint_name}' connected"),
+        ),
+        None => HttpResponse::Conflict().body("The pipeline has been terminated"),
+    }
+}
+
+/// Disconnect an output endpoint.
+///
+/// Responds after all outputs queued for the endpoint have been pushed to
+/// it.
+#[delete("/output_endpoints/{endpoint_name}")]
+async fn disconnect_output(
+    state: WebData<ServerState>,
+    endpoint_name: web::Path<String>,
+) -> impl Responder {
+    let endpoint_name = endpoint_name.into_inner();
+
+    match &*state.controller.lock().unwrap() {
+        Some(controller) => endpoint_response(
+            state.disconnect_output(controller, &endpoint_name),
+            format!("Output endpoint '{endpoint_name}' disconnected"),
+        ),
+        None => HttpResponse::Conflict().body("The pipeline has been terminated"),
+    }
+}
+
+/// Convert the result of connecting or disconnecting an endpoint to an HTTP
+/// response.
+fn endpoint_response(result: AnyResult<()>, success_msg: String) -> HttpResponse {
+    let e = match result {
+        Ok(()) => return HttpResponse::Ok().body(success_msg),
+        Err(e) => e,
+    };
+
+    match e.downcast_ref::<ControllerError>() {
+        Some(ControllerError::Config {
+            config_error:
+                ConfigError::UnknownInputEndpoint { .. } | ConfigError::UnknownOutputEndpoint { .. },
+        }) => HttpResponse::NotFound().body(e.to_string()),
+        Some(ControllerError::Config {
+            config_error:
+                ConfigError::DuplicateInputEndpoint { .. }
+                | ConfigError::DuplicateOutputEndpoint { .. }
+                | ConfigError::DuplicateOutputStreamConsumer { .. },
+        }) => HttpResponse::Conflict().body(e.to_string()),
+        _ => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct ViewQuery {
+    /// Output format name.
+    #[serde(default = "default_view_format")]
+    format: String,
+
+    /// JSON-encoded lower bound on keys (see [`KeyFilter::lower`]).
+    lower: Option<String>,
+
+    /// JSON-encoded upper bound on keys (see [`KeyFilter::upper`]).
+    upper: Option<String>,
+
+    /// JSON-encoded key prefix (see [`KeyFilter::prefix`]).
+    prefix: Option<String>,
+}
+
+fn default_view_format() -> String {
+    "csv".to_string()
+}
+
+/// Parse a key specified in a query string.
+///
+/// Values that are not valid JSON are interpreted as strings, so that
+/// string keys don't need to be quoted.
+fn parse_query_key(key: &Option<String>) -> Option<JsonValue> {
+    key.as_ref()
+        .map(|key| serde_json::from_str(key).unwrap_or_else(|_| JsonValue::String(key.clone())))
+}
+
+/// `OutputConsumer` that accumulates encoded output in memory.
+struct VecOutputConsumer(Arc<Mutex<Vec<u8>>>);
+
+impl OutputConsumer for VecOutputConsumer {
+    fn push_buffer(&mut self, buffer: &[u8]) {
+        self.0.lock().unwrap().extend_from_slice(buffer);
+    }
+}
+
+/// Query the current contents of a materialized output stream.
+///
+/// Encodes the snapshot using the output format specified by the `format`
+/// query parameter (CSV by default), optionally restricted to keys in the
+/// range `[lower, upper)` and/or matching `prefix`.
+#[get("/view/{stream_name}")]
+async fn view(
+    state: WebData<ServerState>,
+    stream_name: web::Path<String>,
+    query: web::Query<ViewQuery>,
+) -> impl Responder {
+    let stream_name = stream_name.into_inner();
+
+    let filter = KeyFilter {
+        lower: parse_query_key(&query.lower),
+        upper: parse_query_key(&query.upper),
+        prefix: parse_query_key(&query.prefix),
+    };
+
+    let snapshot = match &*state.controller.lock().unwrap() {
+        Some(controller) => controller.snapshot(&stream_name, &filter),
+        None => return HttpResponse::Conflict().body("The pipeline has been terminated"),
+    };
+
+    let snapshot = match snapshot {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            return match e.downcast_ref::<ControllerError>() {
+                Some(ControllerError::Config {
+                    config_error: ConfigError::UnknownOutputStream { .. },
+                }) => HttpResponse::NotFound().body(e.to_string()),
+                _ => HttpResponse::BadRequest()
+                    .body(format!("Failed to query stream '{stream_name}': {e}")),
+            }
+        }
+    };
+
+    let format = match <dyn OutputFormat>::get_format(&query.format) {
+        Some(format) => format,
+        None => {
+            return HttpResponse::BadRequest()
+                .body(format!("Unknown output format '{}'", query.format))
+        }
+    };
+
+    // The encoder is created with the default configuration.  Formats that
+    // cannot be used without configuration, e.g., Avro, which requires a
+    // schema, are rejected.
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let mut encoder = match format.new_encoder(
+        &YamlValue::Null,
+        Box::new(VecOutputConsumer(buffer.clone())),
+    ) {
+        Ok(encoder) => encoder,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!(
+                "Output format '{}' is not supported by this endpoint: {e}",
+                query.format
+            ))
+        }
+    };
+
+    if let Err(e) = encoder.encode(&[snapshot]) {
+        return HttpResponse::InternalServerError()
+            .body(format!("Failed to encode stream '{stream_name}': {e}"));
+    }
+
+    let body = take(&mut *buffer.lock().unwrap());
+    let content_type = match query.format.as_str() {
+        "csv" => mime::TEXT_CSV,
+        "json" => mime::APPLICATION_JSON,
+        _ => mime::APPLICATION_OCTET_STREAM,
+    };
+
+    HttpResponse::Ok().content_type(content_type).body(body)
+}
+
 #[get("/shutdown")]
 async fn shutdown(state: WebData<ServerState>) -> impl Responder {
     let controller = state.controller.lock().unwrap().take();
     if let Some(controller) = controller {
+        // Terminate output streams.
+        for endpoint in state.http_outputs.lock().unwrap().values() {
+            endpoint.disconnect();
+        }
+
         match controller.stop() {
             Ok(()) => HttpResponse::Ok().body("Pipeline terminated"),
             Err(e) => HttpResponse::InternalServerError()
@@ -117,6 +714,641 @@ async fn shutdown(state: WebData<ServerState>) -> impl Responder {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::{build_app, ServerState};
+    use crate::{
+        test::{test_circuit, wait, TestStruct},
+        Catalog, ControllerConfig, ControllerError, PipelineState,
+    };
+    use actix_web::{
+        body::MessageBody,
+        http::{header, StatusCode},
+        test,
+        web::Data as WebData,
+        App,
+    };
+    use dbsp::Runtime;
+    use futures::future::poll_fn;
+    use log::error;
+    use std::{fs::read_to_string, sync::atomic::Ordering, thread::sleep, time::Duration};
+    use tempfile::NamedTempFile;
+
+    fn create_state(config_str: &str) -> WebData<ServerState> {
+        let (circuit, catalog) = test_circuit(4);
+        let config: ControllerConfig = serde_yaml::from_str(config_str).unwrap();
+        WebData::new(
+            ServerState::with_config(
+                circuit,
+                catalog,
+                &config,
+                Box::new(|e| error!("{e}")) as Box<dyn Fn(ControllerError) + Send + Sync>,
+            )
+            .unwrap(),
+        )
+    }
+
+    #[actix_web::test]
+    async fn test_http_input() {
+        let output_file = NamedTempFile::new().unwrap();
+
+        let config_str = format!(
+            r#"
+inputs:
+    test_input1:
+        transport:
+            name: http
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+outputs:
+    test_output1:
+        stream: test_output1
+        transport:
+            name: file
+            config:
+                path: {:?}
+        format:
+            name: csv
+"#,
+            output_file.path().to_str().unwrap()
+        );
+
+        let state = create_state(&config_str);
+        let app = test::init_service(build_app(App::new(), state.clone())).await;
+
+        // Unknown endpoint.
+        let req = test::TestRequest::post()
+            .uri("/input/no_such_endpoint")
+            .set_payload("1,true,,foo\n")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // The pipeline is paused.
+        let req = test::TestRequest::post()
+            .uri("/input/test_input1")
+            .set_payload("1,true,,foo\n")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let req = test::TestRequest::get().uri("/start").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        // Wait for the controller to unpause the endpoint.
+        wait(
+            || state.http_inputs.lock().unwrap()["test_input1"].state() == PipelineState::Running,
+            None,
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/input/test_input1")
+            .set_payload("1,true,,foo\n2,false,5,bar\n")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        wait(
+            || read_to_string(output_file.path()).unwrap().lines().count() == 2,
+            None,
+        );
+
+        let req = test::TestRequest::get().uri("/shutdown").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        // Input after shutdown must fail.
+        let req = test::TestRequest::post()
+            .uri("/input/test_input1")
+            .set_payload("3,true,,baz\n")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+    }
+
+    #[actix_web::test]
+    async fn test_metrics() {
+        let output_file = NamedTempFile::new().unwrap();
+
+        let config_str = format!(
+            r#"
+cpu_profiler: true
+inputs:
+    test_input1:
+        transport:
+            name: http
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+        on_error: skip
+outputs:
+    test_output1:
+        stream: test_output1
+        transport:
+            name: file
+            config:
+                path: {:?}
+        format:
+            name: csv
+"#,
+            output_file.path().to_str().unwrap()
+        );
+
+        let state = create_state(&config_str);
+        let app = test::init_service(build_app(App::new(), state.clone())).await;
+
+        let req = test::TestRequest::get().uri("/start").to_request();
+        test::call_service(&app, req).await;
+        wait(
+            || state.http_inputs.lock().unwrap()["test_input1"].state() == PipelineState::Running,
+            None,
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/input/test_input1")
+            .set_payload("1,true,,foo\n2,maybe,5,bar\n3,false,5,baz\n")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.starts_with("Input endpoint 'test_input1' failed to parse 1 record(s):\n"));
+        wait(
+            || read_to_string(output_file.path()).unwrap().lines().count() == 2,
+            None,
+        );
+
+        let req = test::TestRequest::get().uri("/metrics").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert!(resp
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("application/openmetrics-text"));
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        let lines = body.lines().collect::<Vec<_>>();
+        assert_eq!(lines.last(), Some(&"# EOF"));
+        assert!(lines.contains(&"# TYPE dbsp_steps counter"));
+        assert!(lines.contains(&"dbsp_input_records_total{endpoint=\"test_input1\"} 2"));
+        assert!(lines.contains(&"dbsp_input_rejected_records_total{endpoint=\"test_input1\"} 1"));
+        assert!(
+            lines.contains(&"dbsp_output_transmitted_records_total{endpoint=\"test_output1\"} 2")
+        );
+        let sample = |name: &str| {
+            lines
+                .iter()
+                .find_map(|line| line.strip_prefix(name))
+                .unwrap()
+                .trim()
+                .parse::<u64>()
+                .unwrap()
+        };
+        assert!(sample("dbsp_steps_total ") > 0);
+        assert_eq!(
+            sample("dbsp_steps_total "),
+            sample("dbsp_step_latency_seconds_count ")
+        );
+        assert_eq!(
+            sample("dbsp_steps_total "),
+            sample("dbsp_step_latency_seconds_bucket{le=\"+Inf\"} ")
+        );
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("dbsp_operator_cpu_seconds_total{worker=\"3\"")));
+
+        let req = test::TestRequest::get().uri("/shutdown").to_request();
+        test::call_service(&app, req).await;
+
+        let req = test::TestRequest::get().uri("/metrics").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+    }
+
+    #[actix_web::test]
+    async fn test_wait_for_step() {
+        let output_file = NamedTempFile::new().unwrap();
+
+        let config_str = format!(
+            r#"
+inputs:
+    test_input1:
+        transport:
+            name: http
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+outputs:
+    test_output1:
+        stream: test_output1
+        transport:
+            name: file
+            config:
+                path: {:?}
+        format:
+            name: csv
+"#,
+            output_file.path().to_str().unwrap()
+        );
+
+        let state = create_state(&config_str);
+        let app = test::init_service(build_app(App::new(), state.clone())).await;
+
+        let req = test::TestRequest::get().uri("/start").to_request();
+        test::call_service(&app, req).await;
+        wait(
+            || state.http_inputs.lock().unwrap()["test_input1"].state() == PipelineState::Running,
+            None,
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/input/test_input1")
+            .set_payload("1,true,,foo\n2,false,5,bar\n")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        // Once the current step completes, outputs reflect all inputs pushed
+        // before.
+        let step = state
+            .controller
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .status()
+            .global_metrics
+            .step
+            .load(Ordering::Acquire);
+        let req = test::TestRequest::get()
+            .uri(&format!("/wait_for_step?step={step}"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(
+            read_to_string(output_file.path()).unwrap().lines().count(),
+            2
+        );
+
+        let req = test::TestRequest::get().uri("/shutdown").to_request();
+        test::call_service(&app, req).await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/wait_for_step?step={}", step + 1))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+    }
+
+    #[actix_web::test]
+    async fn test_view() {
+        let (circuit, catalog) = {
+            let (circuit, (input, output)) = Runtime::init_circuit(4, |circuit| {
+                let (input, hinput) = circuit.add_input_zset::<TestStruct, i32>();
+                (hinput, input.output())
+            })
+            .unwrap();
+
+            let mut catalog = Catalog::new();
+            catalog.register_input_zset_handle("test_input1", input);
+            catalog.register_materialized_output_batch_handle("test_output1", output);
+            (circuit, catalog)
+        };
+
+        let config_str = r#"
+inputs:
+    test_input1:
+        transport:
+            name: http
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+"#;
+        let config: ControllerConfig = serde_yaml::from_str(config_str).unwrap();
+        let state = WebData::new(
+            ServerState::with_config(
+                circuit,
+                catalog,
+                &config,
+                Box::new(|e| error!("{e}")) as Box<dyn Fn(ControllerError) + Send + Sync>,
+            )
+            .unwrap(),
+        );
+        let app = test::init_service(build_app(App::new(), state.clone())).await;
+
+        let req = test::TestRequest::get().uri("/start").to_request();
+        test::call_service(&app, req).await;
+        wait(
+            || state.http_inputs.lock().unwrap()["test_input1"].state() == PipelineState::Running,
+            None,
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/input/test_input1")
+            .set_payload("1,true,,foo\n2,false,5,bar\n2,true,,baz\n3,true,,foo\n")
+            .to_request();
+        test::call_service(&app, req).await;
+
+        // Wait for the view to be updated.
+        let mut body = String::new();
+        for _ in 0..1000 {
+            let req = test::TestRequest::get()
+                .uri("/view/test_output1")
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(resp.status().is_success());
+            body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+            if body.lines().count() == 4 {
+                break;
+            }
+            sleep(Duration::from_millis(10));
+        }
+        assert_eq!(body.lines().count(), 4);
+
+        // Retract a record.
+        let req = test::TestRequest::post()
+            .uri("/input/test_input1")
+            .set_payload("1,true,,foo\n")
+            .to_request();
+        test::call_service(&app, req).await;
+        let req = test::TestRequest::post()
+            .uri("/input/test_input1")
+            .set_payload("1,true,,foo\n")
+            .to_request();
+        test::call_service(&app, req).await;
+
+        // Key prefix.
+        let req = test::TestRequest::get()
+            .uri("/view/test_output1?format=json&prefix=%7B%22id%22%3A2%7D")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert_eq!(body.lines().count(), 2);
+        assert!(body.lines().all(|l| l.contains("\"id\":2")));
+
+        // Key range.
+        let req = test::TestRequest::get()
+            .uri("/view/test_output1?upper=%7B%22id%22%3A2%2C%22b%22%3Atrue%2C%22i%22%3Anull%2C%22s%22%3A%22%22%7D")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.lines().any(|l| l.starts_with("2,false,5,bar")));
+        assert!(!body.lines().any(|l| l.starts_with("2,true")));
+        assert!(!body.lines().any(|l| l.starts_with("3,")));
+
+        // Errors.
+        let req = test::TestRequest::get()
+            .uri("/view/no_such_stream")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get()
+            .uri("/view/test_output1?lower=%5B1%5D")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::get()
+            .uri("/view/test_output1?format=no_such_format")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // Avro requires a schema, which cannot be specified in the query.
+        let req = test::TestRequest::get()
+            .uri("/view/test_output1?format=avro")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::get().uri("/shutdown").to_request();
+        test::call_service(&app, req).await;
+    }
+
+    #[actix_web::test]
+    async fn test_http_output() {
+        let config_str = r#"
+inputs:
+    test_input1:
+        transport:
+            name: http
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+outputs:
+    test_output1:
+        stream: test_output1
+        transport:
+            name: http
+        format:
+            name: csv
+"#;
+
+        let state = create_state(config_str);
+        let app = test::init_service(build_app(App::new(), state.clone())).await;
+
+        // Unknown endpoint.
+        let req = test::TestRequest::get()
+            .uri("/output/no_such_endpoint")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // Subscribe to output as a chunked stream and as server-sent events.
+        let req = test::TestRequest::get()
+            .uri("/output/test_output1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let mut chunked_body = Box::pin(resp.into_body());
+
+        let req = test::TestRequest::get()
+            .uri("/output/test_output1")
+            .insert_header((header::ACCEPT, "text/event-stream"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let mut sse_body = Box::pin(resp.into_body());
+
+        assert_eq!(
+            state.http_outputs.lock().unwrap()["test_output1"].num_subscribers(),
+            2
+        );
+
+        let req = test::TestRequest::get().uri("/start").to_request();
+        test::call_service(&app, req).await;
+        wait(
+            || state.http_inputs.lock().unwrap()["test_input1"].state() == PipelineState::Running,
+            None,
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/input/test_input1")
+            .set_payload("1,true,,foo\n2,false,5,bar\n")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        // Read until both records have been received.
+        let mut output = String::new();
+        while output.lines().count() < 2 {
+            let chunk = poll_fn(|cx| chunked_body.as_mut().poll_next(cx))
+                .await
+                .unwrap()
+                .unwrap();
+            output.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        assert_eq!(output.lines().count(), 2);
+
+        let mut events = String::new();
+        while events.lines().filter(|l| l.starts_with("data: ")).count() < 2 {
+            let chunk = poll_fn(|cx| sse_body.as_mut().poll_next(cx))
+                .await
+                .unwrap()
+                .unwrap();
+            events.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        assert!(events.ends_with("\n\n"));
+
+        // Shutdown terminates the streams.
+        let req = test::TestRequest::get().uri("/shutdown").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        assert!(poll_fn(|cx| chunked_body.as_mut().poll_next(cx))
+            .await
+            .is_none());
+    }
+    #[actix_web::test]
+    async fn test_connect_disconnect() {
+        let output_file = NamedTempFile::new().unwrap();
+
+        let config_str = r#"
+inputs:
+    test_input1:
+        transport:
+            name: http
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+"#;
+
+        let state = create_state(config_str);
+        let app = test::init_service(build_app(App::new(), state.clone())).await;
+
+        let req = test::TestRequest::get().uri("/start").to_request();
+        test::call_service(&app, req).await;
+
+        // Connect an output endpoint to the running pipeline.
+        let req = test::TestRequest::post()
+            .uri("/output_endpoints/test_output1")
+            .set_payload(format!(
+                r#"
+stream: test_output1
+transport:
+    name: file
+    config:
+        path: {:?}
+format:
+    name: csv
+"#,
+                output_file.path().to_str().unwrap()
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        // Duplicate endpoint.
+        let req = test::TestRequest::post()
+            .uri("/output_endpoints/test_output1")
+            .set_payload("{stream: test_output1, transport: {name: http}, format: {name: csv}}")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        // Invalid config.
+        let req = test::TestRequest::post()
+            .uri("/output_endpoints/test_output2")
+            .set_payload("stream: test_output1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        wait(
+            || state.http_inputs.lock().unwrap()["test_input1"].state() == PipelineState::Running,
+            None,
+        );
+        let req = test::TestRequest::post()
+            .uri("/input/test_input1")
+            .set_payload("1,true,,foo\n2,false,5,bar\n")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        wait(
+            || read_to_string(output_file.path()).unwrap().lines().count() == 2,
+            None,
+        );
+
+        // Disconnect both endpoints.
+        let req = test::TestRequest::delete()
+            .uri("/output_endpoints/test_output1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let req = test::TestRequest::delete()
+            .uri("/input_endpoints/test_input1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert!(state.http_inputs.lock().unwrap().is_empty());
+
+        let req = test::TestRequest::post()
+            .uri("/input/test_input1")
+            .set_payload("3,true,,baz\n")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::delete()
+            .uri("/output_endpoints/test_output1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // Reconnect the input endpoint.
+        let req = test::TestRequest::post()
+            .uri("/input_endpoints/test_input1")
+            .set_payload(
+                "{transport: {name: http}, format: {name: csv, config: {input_stream: test_input1}}}",
+            )
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert!(state
+            .http_inputs
+            .lock()
+            .unwrap()
+            .contains_key("test_input1"));
+
+        let req = test::TestRequest::get().uri("/shutdown").to_request();
+        test::call_service(&app, req).await;
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "with-kafka")]
 mod test_with_kafka {
@@ -136,7 +1368,7 @@ mod test_with_kafka {
         strategy::{Strategy, ValueTree},
         test_runner::TestRunner,
     };
-    use std::{sync::Arc, thread::sleep, time::Duration};
+    use std::{collections::BTreeMap, sync::Arc, thread::sleep, time::Duration};
 
     #[actix_web::test]
     async fn test_server() {
@@ -211,7 +1443,11 @@ outputs:
 
         // Create service
         println!("Creating HTTP server");
-        let state = WebData::new(ServerState::new(controller));
+        let state = WebData::new(ServerState::new(
+            controller,
+            BTreeMap::new(),
+            BTreeMap::new(),
+        ));
         let app =
             test::init_service(build_app(App::new().wrap(Logger::default()), state.clone())).await;
 
@@ -0,0 +1,367 @@
+//! Export controller and circuit metrics in the OpenMetrics text format.
+//!
+//! See <https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md>.
+//!
+//! Endpoint metrics are labeled with the endpoint name (`endpoint`);
+//! per-operator CPU metrics are labeled with the worker index (`worker`),
+//! global node id (`node_id`) and name (`operator`) of the operator.
+
+use crate::controller::{
+    ControllerStatus, InputEndpointMetrics, OutputEndpointMetrics, STEP_LATENCY_BUCKETS_USECS,
+};
+use dbsp::profile::OperatorProfile;
+use std::{
+    fmt::{Display, Write},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Content type of the OpenMetrics text format.
+pub(super) const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Metric type.
+#[derive(Clone, Copy)]
+enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl MetricType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Counter => "counter",
+            Self::Gauge => "gauge",
+            Self::Histogram => "histogram",
+        }
+    }
+}
+
+/// Writes metric families and samples to a string buffer.
+#[derive(Default)]
+struct MetricsWriter {
+    buffer: String,
+}
+
+impl MetricsWriter {
+    /// Start a new metric family.
+    fn family(&mut self, name: &str, metric_type: MetricType, help: &str) {
+        writeln!(self.buffer, "# TYPE {name} {}", metric_type.as_str()).unwrap();
+        writeln!(self.buffer, "# HELP {name} {help}").unwrap();
+    }
+
+    /// Write a sample of the current metric family.
+    fn sample<V>(&mut self, name: &str, labels: &[(&str, &str)], value: V)
+    where
+        V: Display,
+    {
+        self.buffer.push_str(name);
+        if !labels.is_empty() {
+            self.buffer.push('{');
+            for (i, (label, label_value)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.buffer.push(',');
+                }
+                write!(self.buffer, "{label}=\"").unwrap();
+                for c in label_value.chars() {
+                    match c {
+                        '\\' => self.buffer.push_str("\\\\"),
+                        '"' => self.buffer.push_str("\\\""),
+                        '\n' => self.buffer.push_str("\\n"),
+                        c => self.buffer.push(c),
+                    }
+                }
+                self.buffer.push('"');
+            }
+            self.buffer.push('}');
+        }
+        writeln!(self.buffer, " {value}").unwrap();
+    }
+
+    /// Write a metric family with one sample per endpoint.
+    fn endpoint_family<'a, I>(&mut self, name: &str, metric_type: MetricType, help: &str, values: I)
+    where
+        I: IntoIterator<Item = (&'a str, &'a AtomicU64)>,
+    {
+        self.family(name, metric_type, help);
+
+        let sample_name = match metric_type {
+            MetricType::Counter => format!("{name}_total"),
+            _ => name.to_string(),
+        };
+        for (endpoint_name, value) in values {
+            self.sample(
+                &sample_name,
+                &[("endpoint", endpoint_name)],
+                value.load(Ordering::Acquire),
+            );
+        }
+    }
+
+    /// Write a latency histogram with buckets `STEP_LATENCY_BUCKETS_USECS`.
+    fn latency_histogram(
+        &mut self,
+        name: &str,
+        help: &str,
+        buckets: &[AtomicU64; STEP_LATENCY_BUCKETS_USECS.len() + 1],
+        total_usecs: &AtomicU64,
+    ) {
+        self.family(name, MetricType::Histogram, help);
+
+        let bucket_name = format!("{name}_bucket");
+        let mut count = 0;
+        for (bucket, bound) in STEP_LATENCY_BUCKETS_USECS.iter().enumerate() {
+            count += buckets[bucket].load(Ordering::Acquire);
+            self.sample(
+                &bucket_name,
+                &[("le", &format!("{:?}", *bound as f64 / 1_000_000.0))],
+                count,
+            );
+        }
+        count += buckets[STEP_LATENCY_BUCKETS_USECS.len()].load(Ordering::Acquire);
+        self.sample(&bucket_name, &[("le", "+Inf")], count);
+        self.sample(
+            &format!("{name}_sum"),
+            &[],
+            total_usecs.load(Ordering::Acquire) as f64 / 1_000_000.0,
+        );
+        self.sample(&format!("{name}_count"), &[], count);
+    }
+
+    fn finish(mut self) -> String {
+        self.buffer.push_str("# EOF\n");
+        self.buffer
+    }
+}
+
+/// Render controller status and the CPU profile of the circuit in the
+/// OpenMetrics text format.
+pub(super) fn render_metrics(
+    status: &ControllerStatus,
+    cpu_profile: &[Vec<OperatorProfile>],
+) -> String {
+    let global_metrics = &status.global_metrics;
+    let mut writer = MetricsWriter::default();
+
+    // Global metrics.
+    writer.family(
+        "dbsp_buffered_input_records",
+        MetricType::Gauge,
+        "Number of records buffered by all input endpoints.",
+    );
+    writer.sample(
+        "dbsp_buffered_input_records",
+        &[],
+        global_metrics
+            .buffered_input_records
+            .load(Ordering::Acquire),
+    );
+
+    writer.family(
+        "dbsp_steps",
+        MetricType::Counter,
+        "Number of steps evaluated by the circuit.",
+    );
+    writer.sample(
+        "dbsp_steps_total",
+        &[],
+        global_metrics.total_steps.load(Ordering::Acquire),
+    );
+
+    writer.latency_histogram(
+        "dbsp_step_latency_seconds",
+        "Time it took the circuit to evaluate a step.",
+        &global_metrics.step_latency_buckets,
+        &global_metrics.total_step_usecs,
+    );
+
+    writer.family(
+        "dbsp_step",
+        MetricType::Gauge,
+        "Number of steps started by the circuit, i.e., the number of the next step.",
+    );
+    writer.sample(
+        "dbsp_step",
+        &[],
+        global_metrics.step.load(Ordering::Acquire),
+    );
+
+    writer.family(
+        "dbsp_committed_steps",
+        MetricType::Gauge,
+        "Number of steps whose outputs have been pushed to all output endpoints.",
+    );
+    writer.sample(
+        "dbsp_committed_steps",
+        &[],
+        global_metrics.committed_steps.load(Ordering::Acquire),
+    );
+
+    writer.latency_histogram(
+        "dbsp_input_latency_seconds",
+        "Time from receiving the first input record consumed by a step to pushing the outputs of the step to all output endpoints.",
+        &global_metrics.input_latency_buckets,
+        &global_metrics.total_input_latency_usecs,
+    );
+
+    // Input endpoint metrics.
+    {
+        let inputs = status.input_status();
+        let input_metric = |writer: &mut MetricsWriter,
+                            name: &str,
+                            metric_type: MetricType,
+                            help: &str,
+                            f: fn(&InputEndpointMetrics) -> &AtomicU64| {
+            writer.endpoint_family(
+                name,
+                metric_type,
+                help,
+                inputs
+                    .values()
+                    .map(|input| (input.endpoint_name.as_str(), f(&input.metrics))),
+            )
+        };
+
+        input_metric(
+            &mut writer,
+            "dbsp_input_bytes",
+            MetricType::Counter,
+            "Bytes received by the input endpoint.",
+            |m| &m.total_bytes,
+        );
+        input_metric(
+            &mut writer,
+            "dbsp_input_records",
+            MetricType::Counter,
+            "Records received by the input endpoint.",
+            |m| &m.total_records,
+        );
+        input_metric(
+            &mut writer,
+            "dbsp_input_buffered_bytes",
+            MetricType::Gauge,
+            "Bytes buffered by the input endpoint, not yet consumed by the circuit.",
+            |m| &m.buffered_bytes,
+        );
+        input_metric(
+            &mut writer,
+            "dbsp_input_buffered_records",
+            MetricType::Gauge,
+            "Records buffered by the input endpoint, not yet consumed by the circuit.",
+            |m| &m.buffered_records,
+        );
+        input_metric(
+            &mut writer,
+            "dbsp_input_errors",
+            MetricType::Counter,
+            "Errors reported by the input endpoint.",
+            |m| &m.num_errors,
+        );
+        input_metric(
+            &mut writer,
+            "dbsp_input_rejected_records",
+            MetricType::Counter,
+            "Records rejected by the parser of the input endpoint.",
+            |m| &m.num_rejected_records,
+        );
+    }
+
+    // Output endpoint metrics.
+    {
+        let outputs = status.output_status();
+        let output_metric =
+            |writer: &mut MetricsWriter,
+             name: &str,
+             metric_type: MetricType,
+             help: &str,
+             f: fn(&OutputEndpointMetrics) -> &AtomicU64| {
+                writer.endpoint_family(
+                    name,
+                    metric_type,
+                    help,
+                    outputs
+                        .values()
+                        .map(|output| (output.endpoint_name.as_str(), f(&output.metrics))),
+                )
+            };
+
+        output_metric(
+            &mut writer,
+            "dbsp_output_transmitted_records",
+            MetricType::Counter,
+            "Records sent by the output endpoint.",
+            |m| &m.transmitted_records,
+        );
+        output_metric(
+            &mut writer,
+            "dbsp_output_transmitted_bytes",
+            MetricType::Counter,
+            "Bytes sent by the output endpoint.",
+            |m| &m.transmitted_bytes,
+        );
+        output_metric(
+            &mut writer,
+            "dbsp_output_buffered_records",
+            MetricType::Gauge,
+            "Records queued for the output endpoint.",
+            |m| &m.buffered_records,
+        );
+        output_metric(
+            &mut writer,
+            "dbsp_output_buffered_batches",
+            MetricType::Gauge,
+            "Batches queued for the output endpoint.",
+            |m| &m.buffered_batches,
+        );
+        output_metric(
+            &mut writer,
+            "dbsp_output_errors",
+            MetricType::Counter,
+            "Errors reported by the output endpoint.",
+            |m| &m.num_errors,
+        );
+    }
+
+    // Per-operator CPU usage.
+    writer.family(
+        "dbsp_operator_cpu_seconds",
+        MetricType::Counter,
+        "Time spent evaluating the operator.",
+    );
+    for (worker, profile) in cpu_profile.iter().enumerate() {
+        let worker = worker.to_string();
+        for operator in profile.iter() {
+            writer.sample(
+                "dbsp_operator_cpu_seconds_total",
+                &[
+                    ("worker", &worker),
+                    ("node_id", &operator.node_id.to_string()),
+                    ("operator", &operator.name),
+                ],
+                operator.cpu.total_time().as_secs_f64(),
+            );
+        }
+    }
+
+    writer.family(
+        "dbsp_operator_invocations",
+        MetricType::Counter,
+        "Number of times the operator has been evaluated.",
+    );
+    for (worker, profile) in cpu_profile.iter().enumerate() {
+        let worker = worker.to_string();
+        for operator in profile.iter() {
+            writer.sample(
+                "dbsp_operator_invocations_total",
+                &[
+                    ("worker", &worker),
+                    ("node_id", &operator.node_id.to_string()),
+                    ("operator", &operator.name),
+                ],
+                operator.cpu.invocations(),
+            );
+        }
+    }
+
+    writer.finish()
+}
@@ -109,6 +109,16 @@ impl TestProducer {
     }
 
     pub fn send_to_topic(&self, data: &[Vec<TestStruct>], topic: &str) {
+        self.send(data, topic, None)
+    }
+
+    /// Send each batch in `data` as a separate message to the specified
+    /// partition.
+    pub fn send_to_partition(&self, data: &[Vec<TestStruct>], topic: &str, partition: i32) {
+        self.send(data, topic, Some(partition))
+    }
+
+    fn send(&self, data: &[Vec<TestStruct>], topic: &str, partition: Option<i32>) {
         for batch in data {
             let mut writer = CsvWriterBuilder::new()
                 .has_headers(false)
@@ -120,7 +130,10 @@ impl TestProducer {
             writer.flush().unwrap();
             let bytes = writer.into_inner().unwrap();
 
-            let record = <BaseRecord<(), [u8], ()>>::to(topic).payload(&bytes);
+            let mut record = <BaseRecord<(), [u8], ()>>::to(topic).payload(&bytes);
+            if let Some(partition) = partition {
+                record = record.partition(partition);
+            }
             self.producer.send(record).unwrap();
         }
         // producer.flush(Timeout::Never).unwrap();
@@ -13,6 +13,13 @@ pub struct MockDeZSetState<T> {
 
     /// Records flushed since the last `reset`.
     pub flushed: Vec<(T, bool)>,
+
+    /// Id of the handle that buffered each record in `buffered`.  Every
+    /// forked handle has its own logical input buffer.
+    owners: Vec<usize>,
+
+    /// Id to assign to the next forked handle.
+    next_handle_id: usize,
 }
 
 impl<T> Default for MockDeZSetState<T> {
@@ -26,6 +33,8 @@ impl<T> MockDeZSetState<T> {
         Self {
             buffered: Vec::new(),
             flushed: Vec::new(),
+            owners: Vec::new(),
+            next_handle_id: 1,
         }
     }
 
@@ -33,10 +42,38 @@ impl<T> MockDeZSetState<T> {
     pub fn reset(&mut self) {
         self.buffered.clear();
         self.flushed.clear();
+        self.owners.clear();
+    }
+
+    fn push(&mut self, handle_id: usize, val: T, polarity: bool) {
+        self.buffered.push((val, polarity));
+        self.owners.push(handle_id);
+    }
+
+    /// Remove records buffered by handle `handle_id` from `buffered`.
+    fn take_buffered(&mut self, handle_id: usize) -> Vec<(T, bool)> {
+        let mut taken = Vec::new();
+        let mut kept = Vec::new();
+        for (record, owner) in take(&mut self.buffered)
+            .into_iter()
+            .zip(take(&mut self.owners))
+        {
+            if owner == handle_id {
+                taken.push(record);
+            } else {
+                kept.push(record);
+                self.owners.push(owner);
+            }
+        }
+        self.buffered = kept;
+        taken
     }
 }
 
-pub struct MockDeZSet<T>(Arc<Mutex<MockDeZSetState<T>>>);
+pub struct MockDeZSet<T> {
+    state: Arc<Mutex<MockDeZSetState<T>>>,
+    handle_id: usize,
+}
 
 impl<T> Default for MockDeZSet<T> {
     fn default() -> Self {
@@ -47,21 +84,27 @@ impl<T> Default for MockDeZSet<T> {
 /// Mock implementation of `DeCollectionHandle`.
 impl<T> Clone for MockDeZSet<T> {
     fn clone(&self) -> Self {
-        Self(self.0.clone())
+        Self {
+            state: self.state.clone(),
+            handle_id: self.handle_id,
+        }
     }
 }
 
 impl<T> MockDeZSet<T> {
     pub fn new() -> Self {
-        Self(Arc::new(Mutex::new(MockDeZSetState::new())))
+        Self {
+            state: Arc::new(Mutex::new(MockDeZSetState::new())),
+            handle_id: 0,
+        }
     }
 
     pub fn reset(&self) {
-        self.0.lock().unwrap().reset();
+        self.state().reset();
     }
 
     pub fn state(&self) -> MutexGuard<MockDeZSetState<T>> {
-        self.0.lock().unwrap()
+        self.state.lock().unwrap()
     }
 }
 
@@ -71,30 +114,37 @@ where
 {
     fn insert(&mut self, deserializer: &mut dyn ErasedDeserializer) -> Result<(), EError> {
         let val = deserialize::<T>(deserializer)?;
-        self.0.lock().unwrap().buffered.push((val, true));
+        self.state().push(self.handle_id, val, true);
         Ok(())
     }
 
     fn delete(&mut self, deserializer: &mut dyn ErasedDeserializer) -> Result<(), EError> {
         let val = deserialize::<T>(deserializer)?;
-        self.0.lock().unwrap().buffered.push((val, false));
+        self.state().push(self.handle_id, val, false);
         Ok(())
     }
 
     fn reserve(&mut self, _reservation: usize) {}
 
     fn flush(&mut self) {
-        let mut state = self.0.lock().unwrap();
+        let mut state = self.state();
 
-        let mut buffered = take(&mut state.buffered);
+        let mut buffered = state.take_buffered(self.handle_id);
         state.flushed.append(&mut buffered);
     }
 
     fn clear_buffer(&mut self) {
-        self.0.lock().unwrap().buffered.clear();
+        self.state().take_buffered(self.handle_id);
     }
 
     fn fork(&self) -> Box<dyn DeCollectionHandle> {
-        Box::new(self.clone())
+        let mut state = self.state();
+        let handle_id = state.next_handle_id;
+        state.next_handle_id += 1;
+
+        Box::new(Self {
+            state: self.state.clone(),
+            handle_id,
+        })
     }
 }
@@ -80,22 +80,27 @@ impl MockInputConsumer {
 }
 
 impl InputConsumer for MockInputConsumer {
-    fn input(&mut self, data: &[u8]) {
+    fn input(&mut self, data: &[u8]) -> Vec<String> {
         // println!("input");
         let mut state = self.state();
 
         state.data.extend_from_slice(data);
         let parser_result = state.parser.input(data);
         // println!("parser returned '{:?}'", state.parser_result);
-        if let Err(e) = &parser_result {
-            if let Some(error_cb) = &mut state.error_cb {
-                error_cb(e);
-            } else {
-                panic!("mock_input_consumer: parse error '{e}'");
+        let errors = match &parser_result {
+            Err(e) => {
+                if let Some(error_cb) = &mut state.error_cb {
+                    error_cb(e);
+                } else {
+                    panic!("mock_input_consumer: parse error '{e}'");
+                }
+                vec![e.to_string()]
             }
-        }
+            Ok(_) => Vec::new(),
+        };
         state.parser_result = Some(parser_result);
         state.parser.flush();
+        errors
     }
 
     fn error(&mut self, _fatal: bool, error: AnyError) {
@@ -109,8 +114,9 @@ impl InputConsumer for MockInputConsumer {
         state.endpoint_error = Some(error);
     }
 
-    fn eoi(&mut self) {
+    fn eoi(&mut self) -> Vec<String> {
         self.state().eoi = true;
+        Vec::new()
     }
 
     fn fork(&self) -> Box<dyn InputConsumer> {
@@ -0,0 +1,48 @@
+use crate::OutputConsumer;
+use std::sync::{Arc, Mutex};
+
+/// Output consumer that records all buffers pushed to it.
+#[derive(Clone, Default)]
+pub struct MockOutputConsumer {
+    pub data: Arc<Mutex<Vec<Vec<u8>>>>,
+    /// Keys of messages pushed via `push_key`, if the consumer is keyed.
+    /// Tombstones are recorded in `data` as empty buffers.
+    pub keys: Option<Arc<Mutex<Vec<Vec<u8>>>>>,
+}
+
+impl MockOutputConsumer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a consumer that expects a separate message per key.
+    pub fn keyed() -> Self {
+        Self {
+            data: Default::default(),
+            keys: Some(Default::default()),
+        }
+    }
+}
+
+impl OutputConsumer for MockOutputConsumer {
+    fn push_buffer(&mut self, buffer: &[u8]) {
+        self.data.lock().unwrap().push(buffer.to_vec());
+    }
+
+    fn is_keyed(&self) -> bool {
+        self.keys.is_some()
+    }
+
+    fn push_key(&mut self, key: &[u8], val: Option<&[u8]>) {
+        self.keys
+            .as_ref()
+            .expect("push_key called on an unkeyed consumer")
+            .lock()
+            .unwrap()
+            .push(key.to_vec());
+        self.data
+            .lock()
+            .unwrap()
+            .push(val.unwrap_or_default().to_vec());
+    }
+}
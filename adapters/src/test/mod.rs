@@ -16,10 +16,12 @@ mod data;
 pub mod kafka;
 mod mock_dezset;
 mod mock_input_consumer;
+mod mock_output_consumer;
 
 pub use data::{generate_test_batch, generate_test_batches, TestStruct};
 pub use mock_dezset::MockDeZSet;
 pub use mock_input_consumer::MockInputConsumer;
+pub use mock_output_consumer::MockOutputConsumer;
 
 pub struct TestLogger;
 pub static TEST_LOGGER: TestLogger = TestLogger;
@@ -108,3 +110,21 @@ pub fn test_circuit(workers: usize) -> (DBSPHandle, Catalog) {
 
     (circuit, catalog)
 }
+
+/// Create a stateful test circuit that outputs changes to the set of distinct
+/// records received from the input stream.
+pub fn test_distinct_circuit(workers: usize) -> (DBSPHandle, Catalog) {
+    let (circuit, (input, output)) = Runtime::init_circuit(workers, |circuit| {
+        let (input, hinput) = circuit.add_input_zset::<TestStruct, i32>();
+
+        let houtput = input.distinct_incremental().output();
+        (hinput, houtput)
+    })
+    .unwrap();
+
+    let mut catalog = Catalog::new();
+    catalog.register_input_zset_handle("test_input1", input);
+    catalog.register_output_batch_handle("test_output1", output);
+
+    (circuit, catalog)
+}
@@ -1,17 +1,22 @@
-use super::{InputConsumer, InputEndpoint, InputTransport, OutputEndpoint, OutputTransport};
+use super::{InputConsumer, InputEndpoint, InputTransport, OutputEndpoint, OutputTransport, Step};
 use crate::PipelineState;
 use anyhow::{Error as AnyError, Result as AnyResult};
 use crossbeam::sync::{Parker, Unparker};
+use flate2::{read::MultiGzDecoder, write::GzEncoder};
+use lz4_flex::frame::{FrameDecoder, FrameEncoder};
 use num_traits::FromPrimitive;
 use serde::Deserialize;
 use serde_yaml::Value as YamlValue;
 use std::{
     borrow::Cow,
-    fs::File,
-    io::{BufRead, BufReader, Result as IoResult, Write},
+    collections::{BTreeMap, HashSet, VecDeque},
+    fs::{metadata, File, OpenOptions},
+    io::{BufRead, BufReader, Read, Result as IoResult, Write},
+    mem::swap,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicU32, Ordering},
-        Arc,
+        Arc, Mutex,
     },
     thread::{sleep, spawn},
     time::Duration,
@@ -19,7 +24,108 @@ use std::{
 
 const SLEEP_MS: u64 = 200;
 
+/// Compression codec used by the file transport.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Compression {
+    /// Uncompressed file.
+    None,
+
+    /// gzip.  Concatenated gzip members are decompressed as a single stream.
+    Gzip,
+
+    /// Zstandard.  Concatenated frames are decompressed as a single stream.
+    Zstd,
+
+    /// LZ4 frame format.  Concatenated frames are decompressed as a single
+    /// stream.
+    Lz4,
+}
+
+impl Compression {
+    /// Detect compression codec from the file extension: `.gz`, `.zst`
+    /// or `.zstd`, and `.lz4`.  Files with other extensions are assumed to be
+    /// uncompressed.
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Self::Gzip,
+            Some("zst") | Some("zstd") => Self::Zstd,
+            Some("lz4") => Self::Lz4,
+            _ => Self::None,
+        }
+    }
+
+    /// Returns `compression` if specified; otherwise detects compression
+    /// from the file extension.
+    fn resolve(compression: Option<Self>, path: &Path) -> Self {
+        compression.unwrap_or_else(|| Self::from_path(path))
+    }
+}
+
+/// Open file for reading, decompressing its contents if necessary.
+fn open_reader(
+    path: &Path,
+    compression: Option<Compression>,
+    buffer_size: Option<usize>,
+) -> AnyResult<Box<dyn BufRead + Send>> {
+    let file = File::open(path)?;
+
+    let reader: Box<dyn Read + Send> = match Compression::resolve(compression, path) {
+        Compression::None => Box::new(file),
+        Compression::Gzip => Box::new(MultiGzDecoder::new(file)),
+        Compression::Zstd => Box::new(zstd::Decoder::new(file)?),
+        Compression::Lz4 => Box::new(Lz4MultiFrameDecoder::new(file)),
+    };
+
+    Ok(match buffer_size {
+        Some(buffer_size) if buffer_size > 0 => {
+            Box::new(BufReader::with_capacity(buffer_size, reader))
+        }
+        _ => Box::new(BufReader::new(reader)),
+    })
+}
+
+/// LZ4 decoder that decodes concatenated frames as a single stream, like
+/// [`MultiGzDecoder`] does for gzip members.
+///
+/// `FrameDecoder` stops at the end of the first frame.
+struct Lz4MultiFrameDecoder<R: Read> {
+    /// `None` only while switching to the next frame.
+    decoder: Option<FrameDecoder<BufReader<R>>>,
+}
+
+impl<R: Read> Lz4MultiFrameDecoder<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            decoder: Some(FrameDecoder::new(BufReader::new(reader))),
+        }
+    }
+}
+
+impl<R: Read> Read for Lz4MultiFrameDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        loop {
+            let decoder = self.decoder.as_mut().unwrap();
+            let len = decoder.read(buf)?;
+            if len > 0 || buf.is_empty() {
+                return Ok(len);
+            }
+
+            // End of frame: start decoding the next frame, if any.
+            if decoder.get_mut().fill_buf()?.is_empty() {
+                return Ok(0);
+            }
+            let reader = self.decoder.take().unwrap().into_inner();
+            self.decoder = Some(FrameDecoder::new(reader));
+        }
+    }
+}
+
 /// `InputTransport` implementation that reads data from file.
+///
+/// File endpoints don't track their input position, so a pipeline that
+/// includes them cannot be restored from a checkpoint (see
+/// [`InputEndpoint::seek`]).
 pub struct FileInputTransport;
 
 impl InputTransport for FileInputTransport {
@@ -42,7 +148,38 @@ impl InputTransport for FileInputTransport {
 #[derive(Deserialize)]
 struct FileInputConfig {
     /// File path.
-    path: String,
+    ///
+    /// Exactly one of `path` and `pattern` must be specified.
+    path: Option<String>,
+
+    /// Glob pattern, e.g., `/data/landing/*.csv`.
+    ///
+    /// When specified, the endpoint ingests all files matching the pattern,
+    /// one file at a time, in the order specified by `order`.  A pattern
+    /// that names a directory matches all files in the directory.  Each file
+    /// is parsed independently, i.e., the parser receives an
+    /// [`eoi`](`InputConsumer::eoi`) notification at the end of each file.
+    ///
+    /// Files must not be modified once they match the pattern.  Producers
+    /// should create files under a different name and atomically rename
+    /// them once complete.
+    pattern: Option<String>,
+
+    /// The order in which files matching `pattern` are ingested.
+    #[serde(default)]
+    order: FileOrder,
+
+    /// Path to a file that records files matching `pattern` that have
+    /// been fully consumed, one path per line.
+    ///
+    /// A file is recorded once the step that ingested it has been
+    /// committed, i.e., its outputs have been pushed to all output
+    /// endpoints.  When specified, files listed in this file are skipped, so
+    /// that the pipeline can be restarted without re-ingesting them; files
+    /// ingested by steps that haven't been committed are ingested again.
+    /// The file is created if it doesn't exist.  When not specified,
+    /// consumed files are only tracked in memory.
+    consumed_files_log: Option<String>,
 
     /// Read buffer size.
     ///
@@ -50,20 +187,140 @@ struct FileInputConfig {
     /// default is used.
     buffer_size_bytes: Option<usize>,
 
+    /// Compression codec: `none`, `gzip`, `zstd`, or `lz4`.
+    ///
+    /// Default: when this parameter is not specified, compression is
+    /// detected from the file extension (see [`Compression::from_path`]).
+    /// When `pattern` is specified, detection is performed separately for
+    /// each file.
+    compression: Option<Compression>,
+
     /// Enable file following.
     ///
     /// When `false`, the endpoint outputs an [`eoi`](`InputConsumer::eoi`)
     /// message and stops upon reaching the end of file.  When `true`, the
     /// endpoint will keep watching the file and outputting any new content
     /// appended to it.
+    ///
+    /// When `pattern` is specified, `false` means that the endpoint stops
+    /// after consuming all files that match the pattern, and `true` means
+    /// that it keeps watching for new matching files.
     #[serde(default)]
     follow: bool,
 }
 
+/// The order in which the file transport ingests files that match a glob
+/// pattern.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FileOrder {
+    /// Ingest files in lexicographic order of their paths.
+    #[default]
+    Lexicographic,
+
+    /// Ingest files in the order of their modification times, oldest first.
+    /// Files with identical modification times are ingested in
+    /// lexicographic order.
+    Mtime,
+}
+
+/// Files matching a glob pattern that have been consumed by the endpoint.
+///
+/// Shared by the endpoint worker thread, which records files it has read,
+/// and the endpoint, which writes them to the consumed files log as the
+/// steps that ingested them get committed.
+struct ConsumedFiles {
+    /// Files read by the endpoint or recorded in the log.
+    files: HashSet<PathBuf>,
+
+    /// Files read since the last `start_step`.
+    unassigned: Vec<PathBuf>,
+
+    /// Files read before the start of each step that hasn't been
+    /// committed yet, indexed by step.
+    uncommitted: BTreeMap<Step, Vec<PathBuf>>,
+
+    log: Option<File>,
+}
+
+impl ConsumedFiles {
+    fn new(log_path: Option<&str>) -> AnyResult<Self> {
+        let mut files = HashSet::new();
+
+        let log = match log_path {
+            None => None,
+            Some(log_path) => {
+                let log = OpenOptions::new()
+                    .read(true)
+                    .append(true)
+                    .create(true)
+                    .open(log_path)
+                    .map_err(|e| {
+                        AnyError::msg(format!(
+                            "error opening consumed files log '{log_path}': {e}"
+                        ))
+                    })?;
+                for line in BufReader::new(&log).lines() {
+                    let line = line?;
+                    if !line.is_empty() {
+                        files.insert(PathBuf::from(line));
+                    }
+                }
+                Some(log)
+            }
+        };
+
+        Ok(Self {
+            files,
+            unassigned: Vec::new(),
+            uncommitted: BTreeMap::new(),
+            log,
+        })
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        self.files.contains(path)
+    }
+
+    /// Record that the endpoint has pushed the entire contents of `path`
+    /// downstream.
+    fn read(&mut self, path: PathBuf) {
+        self.files.insert(path.clone());
+        self.unassigned.push(path);
+    }
+
+    /// Files read so far are ingested by step `step`.
+    fn start_step(&mut self, step: Step) {
+        if !self.unassigned.is_empty() {
+            self.uncommitted
+                .entry(step)
+                .or_default()
+                .append(&mut self.unassigned);
+        }
+    }
+
+    /// Write files ingested by steps up to `step` to the log.
+    fn commit(&mut self, step: Step) -> AnyResult<()> {
+        let mut committed = self.uncommitted.split_off(&(step + 1));
+        swap(&mut committed, &mut self.uncommitted);
+
+        if let Some(log) = &mut self.log {
+            for path in committed.values().flatten() {
+                writeln!(log, "{}", path.display())?;
+            }
+            log.flush()?;
+        }
+        Ok(())
+    }
+}
+
 struct FileInputEndpoint {
     config: FileInputConfig,
     status: Arc<AtomicU32>,
     unparker: Option<Unparker>,
+    /// Consumed files; only used when the endpoint is configured with a
+    /// `pattern`.
+    consumed: Option<Arc<Mutex<ConsumedFiles>>>,
 }
 
 impl FileInputEndpoint {
@@ -72,16 +329,35 @@ impl FileInputEndpoint {
             config,
             status: Arc::new(AtomicU32::new(PipelineState::Paused as u32)),
             unparker: None,
+            consumed: None,
         }
     }
 
     fn connect(&mut self, consumer: Box<dyn InputConsumer>) -> AnyResult<()> {
-        let file = File::open(&self.config.path)?;
-        let reader = match self.config.buffer_size_bytes {
-            Some(buffer_size) if buffer_size > 0 => BufReader::with_capacity(buffer_size, file),
-            _ => BufReader::new(file),
+        let path = match (&self.config.path, &self.config.pattern) {
+            (Some(path), None) => path,
+            (None, Some(pattern)) => return self.connect_pattern(&pattern.clone(), consumer),
+            _ => {
+                return Err(AnyError::msg(
+                    "file transport config must specify exactly one of 'path' and 'pattern'",
+                ))
+            }
         };
 
+        if self.config.follow
+            && Compression::resolve(self.config.compression, Path::new(path)) != Compression::None
+        {
+            return Err(AnyError::msg(
+                "file following is not supported for compressed files",
+            ));
+        }
+
+        let reader = open_reader(
+            Path::new(path),
+            self.config.compression,
+            self.config.buffer_size_bytes,
+        )?;
+
         let parker = Parker::new();
         self.unparker = Some(parker.unparker().clone());
         let status = self.status.clone();
@@ -90,6 +366,175 @@ impl FileInputEndpoint {
         Ok(())
     }
 
+    fn connect_pattern(
+        &mut self,
+        pattern: &str,
+        consumer: Box<dyn InputConsumer>,
+    ) -> AnyResult<()> {
+        // A directory matches all files in it.
+        let pattern = if Path::new(pattern).is_dir() {
+            Path::new(pattern).join("*").to_string_lossy().into_owned()
+        } else {
+            pattern.to_string()
+        };
+        // Validate the pattern.
+        glob::Pattern::new(&pattern)?;
+
+        let consumed = Arc::new(Mutex::new(ConsumedFiles::new(
+            self.config.consumed_files_log.as_deref(),
+        )?));
+        self.consumed = Some(consumed.clone());
+
+        let parker = Parker::new();
+        self.unparker = Some(parker.unparker().clone());
+        let status = self.status.clone();
+        let order = self.config.order;
+        let compression = self.config.compression;
+        let buffer_size = self.config.buffer_size_bytes;
+        let follow = self.config.follow;
+        let _worker = spawn(move || {
+            Self::pattern_worker_thread(
+                pattern,
+                order,
+                compression,
+                buffer_size,
+                consumed,
+                consumer,
+                parker,
+                status,
+                follow,
+            )
+        });
+        Ok(())
+    }
+
+    /// Returns files matching `pattern` that haven't been consumed yet, in
+    /// the order in which they should be ingested.
+    fn list_files(
+        pattern: &str,
+        order: FileOrder,
+        consumed: &ConsumedFiles,
+    ) -> AnyResult<VecDeque<PathBuf>> {
+        let mut files = Vec::new();
+        for path in glob::glob(pattern)? {
+            let path = path?;
+            if !path.is_file() || consumed.contains(&path) {
+                continue;
+            }
+            match order {
+                FileOrder::Lexicographic => files.push((None, path)),
+                FileOrder::Mtime => files.push((Some(metadata(&path)?.modified()?), path)),
+            }
+        }
+
+        files.sort();
+        Ok(files.into_iter().map(|(_, path)| path).collect())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn pattern_worker_thread(
+        pattern: String,
+        order: FileOrder,
+        compression: Option<Compression>,
+        buffer_size: Option<usize>,
+        consumed: Arc<Mutex<ConsumedFiles>>,
+        mut consumer: Box<dyn InputConsumer>,
+        parker: Parker,
+        status: Arc<AtomicU32>,
+        follow: bool,
+    ) {
+        // Files found by the last scan that haven't been read yet.
+        let mut files = VecDeque::new();
+
+        loop {
+            match PipelineState::from_u32(status.load(Ordering::Acquire)) {
+                Some(PipelineState::Paused) => parker.park(),
+                Some(PipelineState::Running) => {
+                    if files.is_empty() {
+                        files = match Self::list_files(&pattern, order, &consumed.lock().unwrap()) {
+                            Ok(files) => files,
+                            Err(e) => {
+                                consumer.error(true, e);
+                                return;
+                            }
+                        };
+                    }
+                    let path = match files.pop_front() {
+                        None if !follow => {
+                            consumer.eoi();
+                            return;
+                        }
+                        None => {
+                            sleep(Duration::from_millis(SLEEP_MS));
+                            continue;
+                        }
+                        Some(path) => path,
+                    };
+
+                    // Parse each file with a separate parser instance, so that
+                    // an incomplete record at the end of one file doesn't get
+                    // merged with the start of the next one.
+                    match Self::read_file(
+                        &path,
+                        compression,
+                        buffer_size,
+                        consumer.fork(),
+                        &parker,
+                        &status,
+                    ) {
+                        Ok(false) => return,
+                        Ok(true) => {}
+                        Err(e) => consumer.error(
+                            false,
+                            AnyError::msg(format!("error reading '{}': {e}", path.display())),
+                        ),
+                    }
+
+                    // Don't retry files that failed to read.  The file gets
+                    // recorded in the log once the step that ingested it is
+                    // committed.
+                    consumed.lock().unwrap().read(path);
+                }
+                Some(PipelineState::Terminated) => return,
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    /// Push the contents of the file to `consumer`, pausing when the
+    /// endpoint is paused.
+    ///
+    /// Returns `false` if the endpoint was disconnected before reaching the
+    /// end of file.
+    fn read_file(
+        path: &Path,
+        compression: Option<Compression>,
+        buffer_size: Option<usize>,
+        mut consumer: Box<dyn InputConsumer>,
+        parker: &Parker,
+        status: &AtomicU32,
+    ) -> AnyResult<bool> {
+        let mut reader = open_reader(path, compression, buffer_size)?;
+
+        loop {
+            match PipelineState::from_u32(status.load(Ordering::Acquire)) {
+                Some(PipelineState::Paused) => parker.park(),
+                Some(PipelineState::Running) => {
+                    let data = reader.fill_buf()?;
+                    if data.is_empty() {
+                        consumer.eoi();
+                        return Ok(true);
+                    }
+                    consumer.input(data);
+                    let len = data.len();
+                    reader.consume(len);
+                }
+                Some(PipelineState::Terminated) => return Ok(false),
+                _ => unreachable!(),
+            }
+        }
+    }
+
     fn unpark(&self) {
         if let Some(unparker) = &self.unparker {
             unparker.unpark();
@@ -97,7 +542,7 @@ impl FileInputEndpoint {
     }
 
     fn worker_thread(
-        mut reader: BufReader<File>,
+        mut reader: Box<dyn BufRead + Send>,
         mut consumer: Box<dyn InputConsumer>,
         parker: Parker,
         status: Arc<AtomicU32>,
@@ -161,6 +606,19 @@ impl InputEndpoint for FileInputEndpoint {
         // Wake up the worker if it's paused.
         self.unpark();
     }
+
+    fn start_step(&self, step: Step) {
+        if let Some(consumed) = &self.consumed {
+            consumed.lock().unwrap().start_step(step);
+        }
+    }
+
+    fn commit(&self, step: Step) -> AnyResult<()> {
+        match &self.consumed {
+            Some(consumed) => consumed.lock().unwrap().commit(step),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Drop for FileInputEndpoint {
@@ -193,33 +651,97 @@ impl OutputTransport for FileOutputTransport {
 struct FileOutputConfig {
     /// File path.
     path: String,
+
+    /// Compression codec: `none`, `gzip`, `zstd`, or `lz4`.
+    ///
+    /// Each buffer received from the encoder is compressed into a separate
+    /// gzip member, zstd frame, or LZ4 frame and written to the file right
+    /// away, so the file is a valid compressed stream after every buffer,
+    /// not only once the endpoint is destroyed.
+    ///
+    /// Default: when this parameter is not specified, compression is
+    /// detected from the file extension (see [`Compression::from_path`]).
+    compression: Option<Compression>,
+
+    /// Compression level.
+    ///
+    /// Default: when this parameter is not specified, the default level
+    /// of the codec is used.  Ignored for LZ4.
+    compression_level: Option<i32>,
 }
 
 struct FileOutputEndpoint {
     file: File,
+    compression: Compression,
+    compression_level: Option<i32>,
 }
 
 impl FileOutputEndpoint {
-    fn new(config: FileOutputConfig) -> IoResult<Self> {
-        let file = File::create(config.path)?;
-        Ok(Self { file })
+    fn new(config: FileOutputConfig) -> AnyResult<Self> {
+        let path = Path::new(&config.path);
+        let file = File::create(path)?;
+
+        Ok(Self {
+            file,
+            compression: Compression::resolve(config.compression, path),
+            compression_level: config.compression_level,
+        })
     }
 }
 
 impl OutputEndpoint for FileOutputEndpoint {
     fn push_buffer(&mut self, buffer: &[u8]) -> AnyResult<()> {
-        self.file.write_all(buffer)?;
+        // Write a complete gzip member or zstd/LZ4 frame, including the
+        // trailer, for each buffer.  The readers in `open_reader` decode
+        // concatenated members and frames as a single stream.
+        match self.compression {
+            Compression::None => self.file.write_all(buffer)?,
+            Compression::Gzip => {
+                let level = self
+                    .compression_level
+                    .map(|level| flate2::Compression::new(level.clamp(0, 9) as u32))
+                    .unwrap_or_default();
+                let mut encoder = GzEncoder::new(&mut self.file, level);
+                encoder.write_all(buffer)?;
+                encoder.finish()?;
+            }
+            Compression::Zstd => {
+                let level = self
+                    .compression_level
+                    .unwrap_or(zstd::DEFAULT_COMPRESSION_LEVEL);
+                let mut encoder = zstd::Encoder::new(&mut self.file, level)?;
+                encoder.write_all(buffer)?;
+                encoder.finish()?;
+            }
+            Compression::Lz4 => {
+                let mut encoder = FrameEncoder::new(&mut self.file);
+                encoder.write_all(buffer)?;
+                encoder.finish()?;
+            }
+        }
+
+        self.file.flush()?;
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::test::{mock_input_pipeline, wait};
+    use super::FileOutputTransport;
+    use crate::{
+        test::{mock_input_pipeline, wait},
+        OutputTransport,
+    };
     use csv::WriterBuilder as CsvWriterBuilder;
     use serde::{Deserialize, Serialize};
-    use std::{io::Write, thread::sleep, time::Duration};
-    use tempfile::NamedTempFile;
+    use std::{
+        fs::{read_to_string, File},
+        io::Write,
+        path::Path,
+        thread::sleep,
+        time::Duration,
+    };
+    use tempfile::{NamedTempFile, TempDir};
 
     #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
     struct TestStruct {
@@ -372,4 +894,167 @@ format:
 
         endpoint.disconnect();
     }
+
+    fn write_csv_file(path: &Path, data: &[TestStruct]) {
+        let mut writer = CsvWriterBuilder::new()
+            .has_headers(false)
+            .from_writer(File::create(path).unwrap());
+        for val in data.iter().cloned() {
+            writer.serialize(val).unwrap();
+        }
+        writer.flush().unwrap();
+    }
+
+    #[test]
+    fn test_csv_file_pattern() {
+        let test_data = vec![
+            TestStruct::new("foo".to_string(), true, 10),
+            TestStruct::new("bar".to_string(), false, -10),
+            TestStruct::new("baz".to_string(), true, 0),
+        ];
+        let dir = TempDir::new().unwrap();
+        let log_file = NamedTempFile::new().unwrap();
+
+        // Files are created in reverse order, but must be consumed in
+        // lexicographic order.
+        write_csv_file(&dir.path().join("part2.csv"), &test_data[2..3]);
+        write_csv_file(&dir.path().join("part1.csv"), &test_data[1..2]);
+        write_csv_file(&dir.path().join("part0.csv"), &test_data[0..1]);
+        write_csv_file(&dir.path().join("ignored.txt"), &test_data);
+
+        let config_str = format!(
+            r#"
+transport:
+    name: file
+    config:
+        pattern: {:?}
+        consumed_files_log: {:?}
+        buffer_size_bytes: 5
+        follow: true
+format:
+    name: csv
+    config:
+        input_stream: test_input
+"#,
+            dir.path().join("*.csv").to_str().unwrap(),
+            log_file.path().to_str().unwrap(),
+        );
+
+        let (endpoint, consumer, zset) = mock_input_pipeline::<TestStruct>(
+            "test_input",
+            serde_yaml::from_str(&config_str).unwrap(),
+        );
+
+        endpoint.start().unwrap();
+        wait(|| zset.state().flushed.len() == test_data.len(), None);
+        for (i, (val, polarity)) in zset.state().flushed.iter().enumerate() {
+            assert!(polarity);
+            assert_eq!(val, &test_data[i]);
+        }
+        zset.reset();
+
+        // New files are picked up.
+        write_csv_file(&dir.path().join("part3.csv"), &test_data[0..1]);
+        wait(|| zset.state().flushed.len() == 1, None);
+        zset.reset();
+
+        // Files are only recorded in the log once the step that ingested
+        // them is committed.
+        sleep(Duration::from_millis(100));
+        assert_eq!(read_to_string(log_file.path()).unwrap(), "");
+        endpoint.start_step(0);
+        endpoint.commit(0).unwrap();
+        assert_eq!(read_to_string(log_file.path()).unwrap().lines().count(), 4);
+        endpoint.disconnect();
+        drop(endpoint);
+        drop(consumer);
+
+        // Files in the log are skipped after restart.
+        write_csv_file(&dir.path().join("part4.csv"), &test_data[1..2]);
+        let (endpoint, _consumer, zset) = mock_input_pipeline::<TestStruct>(
+            "test_input",
+            serde_yaml::from_str(&config_str).unwrap(),
+        );
+        endpoint.start().unwrap();
+        wait(|| zset.state().flushed.len() == 1, None);
+        sleep(Duration::from_millis(500));
+        assert_eq!(zset.state().flushed.len(), 1);
+        assert_eq!(zset.state().flushed[0].0, test_data[1]);
+
+        endpoint.disconnect();
+    }
+
+    #[test]
+    fn test_csv_file_compression() {
+        let test_data = [
+            TestStruct::new("foo".to_string(), true, 10),
+            TestStruct::new("bar".to_string(), false, -10),
+        ];
+        let mut csv = CsvWriterBuilder::new()
+            .has_headers(false)
+            .from_writer(Vec::new());
+        for val in test_data.iter().cloned() {
+            csv.serialize(val).unwrap();
+        }
+        let csv = csv.into_inner().unwrap();
+
+        let dir = TempDir::new().unwrap();
+
+        // Compression detected from the extension or specified explicitly.
+        for (file_name, compression) in [
+            ("test.csv.gz", ""),
+            ("test.csv.zst", ""),
+            ("test.csv.lz4", ""),
+            ("test_gzip.bin", "compression: gzip"),
+            ("test_zstd.bin", "compression: zstd"),
+            ("test_lz4.bin", "compression: lz4"),
+        ] {
+            let path = dir.path().join(file_name);
+
+            let output_config = serde_yaml::from_str(&format!(
+                "path: {:?}\n{compression}",
+                path.to_str().unwrap()
+            ))
+            .unwrap();
+            let mut endpoint = FileOutputTransport
+                .new_endpoint(&output_config, Box::new(|_, _| {}))
+                .unwrap();
+            // Push data in multiple buffers.  The file is complete as soon
+            // as the buffers have been pushed, without destroying the
+            // endpoint.
+            endpoint.push_buffer(&csv[0..3]).unwrap();
+            endpoint.push_buffer(&csv[3..]).unwrap();
+
+            // The file must not contain uncompressed data.
+            assert_ne!(std::fs::read(&path).unwrap(), csv);
+
+            let config_str = format!(
+                r#"
+transport:
+    name: file
+    config:
+        path: {:?}
+        buffer_size_bytes: 5
+        {compression}
+format:
+    name: csv
+    config:
+        input_stream: test_input
+"#,
+                path.to_str().unwrap()
+            );
+
+            let (endpoint, _consumer, zset) = mock_input_pipeline::<TestStruct>(
+                "test_input",
+                serde_yaml::from_str(&config_str).unwrap(),
+            );
+
+            endpoint.start().unwrap();
+            wait(|| zset.state().flushed.len() == test_data.len(), None);
+            for (i, (val, polarity)) in zset.state().flushed.iter().enumerate() {
+                assert!(polarity);
+                assert_eq!(val, &test_data[i]);
+            }
+        }
+    }
 }
@@ -0,0 +1,333 @@
+//! HTTP transport.
+//!
+//! Unlike other transports, HTTP endpoints don't actively pull data from
+//! (or push data to) an external service.  Instead, they are driven by the
+//! HTTP [server](`crate::server`), which creates the endpoints when
+//! instantiating the pipeline and forwards client requests to them.
+
+use super::{InputConsumer, InputEndpoint, OutputEndpoint};
+use crate::PipelineState;
+use actix_web::web::Bytes;
+use anyhow::{Error as AnyError, Result as AnyResult};
+use futures::channel::mpsc::{channel, Receiver, Sender};
+use num_traits::FromPrimitive;
+use serde::Deserialize;
+use serde_yaml::Value as YamlValue;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    Arc, Mutex,
+};
+
+/// Name of the HTTP transport in endpoint configs.
+pub const HTTP_TRANSPORT_NAME: &str = "http";
+
+struct HttpInputEndpointInner {
+    state: AtomicU32,
+    consumer: Mutex<Box<dyn InputConsumer>>,
+}
+
+/// Input endpoint that receives data pushed by HTTP clients.
+///
+/// The endpoint is shared between the controller, which pauses and resumes
+/// it, and the HTTP server, which calls [`Self::push`] for each incoming
+/// request.  Cloning the endpoint yields a new reference to the same
+/// endpoint.
+#[derive(Clone)]
+pub struct HttpInputEndpoint {
+    inner: Arc<HttpInputEndpointInner>,
+}
+
+impl HttpInputEndpoint {
+    /// Create a new endpoint that pushes received data to `consumer`.
+    ///
+    /// The endpoint is created in a paused state.
+    pub fn new(consumer: Box<dyn InputConsumer>) -> Self {
+        Self {
+            inner: Arc::new(HttpInputEndpointInner {
+                state: AtomicU32::new(PipelineState::Paused as u32),
+                consumer: Mutex::new(consumer),
+            }),
+        }
+    }
+
+    /// Current state of the endpoint.
+    pub fn state(&self) -> PipelineState {
+        PipelineState::from_u32(self.inner.state.load(Ordering::Acquire)).unwrap()
+    }
+
+    /// Push a chunk of data received from a client.
+    ///
+    /// `data` must contain a complete sequence of records: each call uses
+    /// a fresh instance of the parser, which receives an
+    /// [`eoi`](`InputConsumer::eoi`) notification at the end of the chunk.
+    /// This allows multiple clients to push data concurrently without
+    /// interleaving partial records.
+    ///
+    /// Returns the errors encountered while parsing `data`.  Invalid records
+    /// are handled according to the endpoint's error policy; valid records
+    /// are accepted even if some records in the chunk fail to parse.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the endpoint is paused or disconnected.
+    pub fn push(&self, data: &[u8]) -> AnyResult<Vec<String>> {
+        match self.state() {
+            PipelineState::Paused => Err(AnyError::msg("endpoint is paused")),
+            PipelineState::Terminated => Err(AnyError::msg("endpoint has been disconnected")),
+            PipelineState::Running => {
+                let mut consumer = self.inner.consumer.lock().unwrap().fork();
+                let mut errors = consumer.input(data);
+                errors.append(&mut consumer.eoi());
+                Ok(errors)
+            }
+        }
+    }
+}
+
+impl InputEndpoint for HttpInputEndpoint {
+    fn pause(&self) -> AnyResult<()> {
+        self.inner
+            .state
+            .store(PipelineState::Paused as u32, Ordering::Release);
+        Ok(())
+    }
+
+    fn start(&self) -> AnyResult<()> {
+        self.inner
+            .state
+            .store(PipelineState::Running as u32, Ordering::Release);
+        Ok(())
+    }
+
+    fn disconnect(&self) {
+        self.inner
+            .state
+            .store(PipelineState::Terminated as u32, Ordering::Release);
+    }
+}
+
+#[derive(Deserialize)]
+struct HttpOutputConfig {
+    /// Maximal number of buffers queued for each subscriber.
+    ///
+    /// When a subscriber falls behind by this many buffers, the endpoint
+    /// disconnects it: the subscriber's stream terminates after delivering
+    /// the buffers already queued.  This way, a slow client cannot stall
+    /// the pipeline or other subscribers.
+    #[serde(default = "default_max_buffered_chunks")]
+    max_buffered_chunks: usize,
+}
+
+const fn default_max_buffered_chunks() -> usize {
+    16
+}
+
+struct HttpOutputEndpointInner {
+    config: HttpOutputConfig,
+    disconnected: AtomicBool,
+    subscribers: Mutex<Vec<Sender<Bytes>>>,
+}
+
+/// Output endpoint that streams data to HTTP clients.
+///
+/// Every buffer pushed to the endpoint is broadcast to all current
+/// subscribers.  Buffers pushed while there are no subscribers are
+/// discarded.  Like [`HttpInputEndpoint`], the endpoint is shared between
+/// the controller and the HTTP server.
+#[derive(Clone)]
+pub struct HttpOutputEndpoint {
+    inner: Arc<HttpOutputEndpointInner>,
+}
+
+impl HttpOutputEndpoint {
+    /// Create a new endpoint with the specified transport-specific
+    /// configuration.
+    pub fn with_config(config: &YamlValue) -> AnyResult<Self> {
+        let config = HttpOutputConfig::deserialize(config)?;
+        if config.max_buffered_chunks == 0 {
+            return Err(AnyError::msg(
+                "'max_buffered_chunks' must be greater than 0",
+            ));
+        }
+
+        Ok(Self {
+            inner: Arc::new(HttpOutputEndpointInner {
+                config,
+                disconnected: AtomicBool::new(false),
+                subscribers: Mutex::new(Vec::new()),
+            }),
+        })
+    }
+
+    /// Subscribe to the output of the endpoint.
+    ///
+    /// Returns a stream of buffers pushed to the endpoint after this
+    /// method was called.  The stream terminates when the endpoint gets
+    /// disconnected.  Dropping the receiver unsubscribes the client.
+    pub fn subscribe(&self) -> Receiver<Bytes> {
+        let (sender, receiver) = channel(self.inner.config.max_buffered_chunks);
+
+        let mut subscribers = self.inner.subscribers.lock().unwrap();
+        // Check the flag while holding the lock to avoid racing with
+        // `disconnect`.  If the endpoint is disconnected, drop the sender
+        // to terminate the stream.
+        if !self.inner.disconnected.load(Ordering::Acquire) {
+            subscribers.push(sender);
+        }
+
+        receiver
+    }
+
+    /// Number of active subscribers.
+    pub fn num_subscribers(&self) -> usize {
+        self.inner.subscribers.lock().unwrap().len()
+    }
+
+    /// Disconnect all subscribers and reject new subscriptions.
+    pub fn disconnect(&self) {
+        let mut subscribers = self.inner.subscribers.lock().unwrap();
+        self.inner.disconnected.store(true, Ordering::Release);
+        subscribers.clear();
+    }
+}
+
+impl OutputEndpoint for HttpOutputEndpoint {
+    fn push_buffer(&mut self, buffer: &[u8]) -> AnyResult<()> {
+        let mut subscribers = self.inner.subscribers.lock().unwrap();
+        if subscribers.is_empty() {
+            return Ok(());
+        }
+
+        let buffer = Bytes::copy_from_slice(buffer);
+
+        // Never block on a subscriber.  Drop subscribers whose queue is full
+        // or who have dropped the receiver; dropping the sender terminates
+        // the stream once the client has received the queued buffers.
+        subscribers.retain_mut(|subscriber| subscriber.try_send(buffer.clone()).is_ok());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HttpInputEndpoint, HttpOutputEndpoint};
+    use crate::{
+        controller::FormatConfig,
+        test::{MockDeZSet, MockInputConsumer, TestStruct},
+        Catalog, InputEndpoint, OutputEndpoint,
+    };
+    use futures::{executor::block_on, StreamExt};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_http_input() {
+        let mut catalog = Catalog::new();
+        let input_handle = <MockDeZSet<TestStruct>>::new();
+        catalog.register_input_collection_handle("test_input", input_handle.clone());
+
+        let format_config: FormatConfig = serde_yaml::from_str(
+            r#"
+name: csv
+config:
+    input_stream: test_input
+"#,
+        )
+        .unwrap();
+        let consumer =
+            MockInputConsumer::from_config(&format_config, &Arc::new(Mutex::new(catalog)));
+        let endpoint = HttpInputEndpoint::new(Box::new(consumer));
+
+        // The endpoint is created paused.
+        assert!(endpoint.push(b"1,true,,foo\n").is_err());
+
+        endpoint.start().unwrap();
+        endpoint.push(b"1,true,,foo\n2,false,5,bar\n").unwrap();
+        endpoint.clone().push(b"3,true,,baz\n").unwrap();
+        assert_eq!(
+            input_handle.state().flushed,
+            vec![
+                (
+                    TestStruct {
+                        id: 1,
+                        b: true,
+                        i: None,
+                        s: "foo".to_string()
+                    },
+                    true
+                ),
+                (
+                    TestStruct {
+                        id: 2,
+                        b: false,
+                        i: Some(5),
+                        s: "bar".to_string()
+                    },
+                    true
+                ),
+                (
+                    TestStruct {
+                        id: 3,
+                        b: true,
+                        i: None,
+                        s: "baz".to_string()
+                    },
+                    true
+                ),
+            ]
+        );
+
+        endpoint.pause().unwrap();
+        assert!(endpoint.push(b"4,true,,foo\n").is_err());
+
+        endpoint.disconnect();
+        assert!(endpoint.push(b"4,true,,foo\n").is_err());
+    }
+
+    #[test]
+    fn test_http_output() {
+        let config = serde_yaml::from_str("max_buffered_chunks: 2").unwrap();
+        let endpoint = HttpOutputEndpoint::with_config(&config).unwrap();
+
+        // No subscribers: data is discarded.
+        endpoint.clone().push_buffer(b"foo").unwrap();
+
+        let mut receiver1 = endpoint.subscribe();
+        let receiver2 = endpoint.subscribe();
+        assert_eq!(endpoint.num_subscribers(), 2);
+
+        endpoint.clone().push_buffer(b"bar").unwrap();
+        assert_eq!(&block_on(receiver1.next()).unwrap()[..], b"bar");
+
+        // Unsubscribe.
+        drop(receiver2);
+        endpoint.clone().push_buffer(b"baz").unwrap();
+        assert_eq!(&block_on(receiver1.next()).unwrap()[..], b"baz");
+        assert_eq!(endpoint.num_subscribers(), 1);
+
+        // A subscriber that falls behind gets disconnected after receiving
+        // the buffers queued for it, without blocking the endpoint or other
+        // subscribers.
+        let mut receiver3 = endpoint.subscribe();
+        for i in 0..10 {
+            endpoint
+                .clone()
+                .push_buffer(format!("{i}").as_bytes())
+                .unwrap();
+            assert_eq!(
+                &block_on(receiver1.next()).unwrap()[..],
+                format!("{i}").as_bytes()
+            );
+        }
+        assert_eq!(endpoint.num_subscribers(), 1);
+        let received: Vec<_> = block_on(receiver3.by_ref().collect::<Vec<_>>());
+        assert!(!received.is_empty());
+        assert!(received.len() < 10);
+        assert_eq!(&received[0][..], b"0");
+
+        // Disconnect terminates the stream.
+        endpoint.disconnect();
+        assert!(block_on(receiver1.next()).is_none());
+        assert!(block_on(endpoint.subscribe().next()).is_none());
+    }
+}
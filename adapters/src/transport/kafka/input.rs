@@ -1,21 +1,22 @@
 use super::{refine_kafka_error, KafkaLogLevel};
-use crate::{InputConsumer, InputEndpoint, InputTransport, PipelineState};
+use crate::{InputConsumer, InputEndpoint, InputTransport, PipelineState, Step};
 use anyhow::{Error as AnyError, Result as AnyResult};
 use num_traits::FromPrimitive;
 use rdkafka::{
     config::{FromClientConfigAndContext, RDKafkaLogLevel},
-    consumer::{BaseConsumer, Consumer, ConsumerContext, Rebalance, RebalanceProtocol},
+    consumer::{BaseConsumer, CommitMode, Consumer, ConsumerContext, Rebalance, RebalanceProtocol},
     error::{KafkaError, KafkaResult},
-    ClientConfig, ClientContext, Message,
+    ClientConfig, ClientContext, Message, Offset, TopicPartitionList,
 };
 use serde::Deserialize;
+use serde_json::Value as JsonValue;
 use serde_yaml::Value as YamlValue;
 use std::{
     borrow::Cow,
-    collections::BTreeMap,
+    collections::{BTreeMap, VecDeque},
     sync::{
         atomic::{AtomicU32, Ordering},
-        Arc, Mutex, Weak,
+        Arc, Condvar, Mutex, Weak,
     },
     thread::spawn,
     time::{Duration, SystemTime, UNIX_EPOCH},
@@ -23,6 +24,12 @@ use std::{
 
 const POLL_TIMEOUT: Duration = Duration::from_millis(100);
 
+/// Timeout for looking up partition offsets by timestamp.
+const OFFSETS_FOR_TIMES_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Timeout for repositioning a partition when resuming from a checkpoint.
+const SEEK_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// On startup, the endpoint waits to join the consumer group.
 /// This constant defines the default wait timeout.
 const fn default_group_join_timeout_secs() -> u32 {
@@ -49,6 +56,56 @@ impl InputTransport for KafkaInputTransport {
     }
 }
 
+/// Position in a partition to start reading from.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KafkaStartOffset {
+    /// Resume from the offset committed by the consumer group.  If there is
+    /// no committed offset, start from the position determined by the
+    /// `auto.offset.reset` option.
+    #[default]
+    Committed,
+
+    /// Start from the first message in the partition.
+    Beginning,
+
+    /// Only read messages that arrive after the endpoint was created.
+    End,
+
+    /// Start from the specified offset.
+    Offset(i64),
+
+    /// Start from the first message whose timestamp, in milliseconds since
+    /// the Unix epoch, is greater than or equal to the specified value.
+    Timestamp(i64),
+}
+
+impl KafkaStartOffset {
+    /// Convert to `rdkafka` offset.  Timestamps must be resolved to offsets
+    /// separately.
+    fn to_offset(self) -> Offset {
+        match self {
+            Self::Committed => Offset::Stored,
+            Self::Beginning => Offset::Beginning,
+            Self::End => Offset::End,
+            Self::Offset(offset) => Offset::Offset(offset),
+            Self::Timestamp(_) => unreachable!(),
+        }
+    }
+}
+
+/// A topic partition explicitly assigned to the endpoint.
+#[derive(Deserialize)]
+pub struct KafkaPartitionConfig {
+    topic: String,
+
+    partition: i32,
+
+    /// Position to start reading the partition from.  Overrides the
+    /// endpoint-wide `start_offset` setting.
+    start_offset: Option<KafkaStartOffset>,
+}
+
 /// Input endpoint configuration.
 #[derive(Deserialize)]
 pub struct KafkaInputConfig {
@@ -64,8 +121,26 @@ pub struct KafkaInputConfig {
     kafka_options: BTreeMap<String, String>,
 
     /// List of topics to subscribe to.
+    ///
+    /// The endpoint joins the consumer group specified by the `group.id`
+    /// option and reads from partitions assigned to it by the group.  Must be
+    /// empty if `assignment` is specified.
+    #[serde(default)]
     topics: Vec<String>,
 
+    /// List of partitions to read from.
+    ///
+    /// When specified, the endpoint reads from these partitions directly,
+    /// bypassing consumer group partition assignment, which is useful for
+    /// replaying a specific range of messages.
+    #[serde(default)]
+    assignment: Vec<KafkaPartitionConfig>,
+
+    /// Position to start reading partitions listed in `assignment` from,
+    /// unless overridden for individual partitions.
+    #[serde(default)]
+    start_offset: KafkaStartOffset,
+
     /// The log level of the client.
     ///
     /// If not specified, the log level will be calculated based on the global
@@ -102,10 +177,16 @@ impl KafkaInputConfig {
     /// Validate configuration, set default option values required by this
     /// adapter.
     fn validate(&mut self) -> AnyResult<()> {
-        // Commit automatically.
+        if self.topics.is_empty() == self.assignment.is_empty() {
+            Err(AnyError::msg(
+                "exactly one of 'topics' and 'assignment' must be specified",
+            ))?;
+        }
+        // Offsets are committed explicitly once the circuit has processed the
+        // corresponding input records (see `KafkaInputEndpoint::commit`).
         // See https://docs.confluent.io/platform/current/clients/consumer.html#offset-management
-        self.enforce_option("enable.auto.commit", "true")?;
-        self.enforce_option("enable.auto.offset.store", "true")?;
+        self.enforce_option("enable.auto.commit", "false")?;
+        self.enforce_option("enable.auto.offset.store", "false")?;
 
         let group_id = format!(
             "{}",
@@ -174,9 +255,37 @@ impl ConsumerContext for KafkaInputContext {
     }
 }
 
+/// Offsets of the next message to read from each partition, indexed by
+/// topic and partition.
+type Offsets = BTreeMap<(String, i32), i64>;
+
+/// Input position tracked by the endpoint.
+#[derive(Default)]
+struct Position {
+    /// Set between `start_step` and `end_step`, while the worker thread must
+    /// not push data to the consumer.
+    in_step: bool,
+
+    /// Offsets following the last message pushed to the consumer.
+    offsets: Offsets,
+
+    /// Offsets recorded at the start of each step that hasn't been committed
+    /// yet.
+    uncommitted: VecDeque<(Step, Offsets)>,
+
+    /// Offsets committed to Kafka.
+    committed: Offsets,
+}
+
 struct KafkaInputEndpointInner {
     state: AtomicU32,
     kafka_consumer: BaseConsumer<KafkaInputContext>,
+    /// `true` if partitions are assigned by the consumer group, `false` if
+    /// they are assigned explicitly via `KafkaInputConfig::assignment`.
+    subscribed: bool,
+    position: Mutex<Position>,
+    /// Signals the worker thread when `position.in_step` is cleared.
+    step_completed: Condvar,
 }
 
 impl KafkaInputEndpointInner {
@@ -203,35 +312,42 @@ impl KafkaInputEndpointInner {
         let endpoint = Arc::new(Self {
             state: AtomicU32::new(PipelineState::Paused as u32),
             kafka_consumer,
+            subscribed: config.assignment.is_empty(),
+            position: Mutex::new(Position::default()),
+            step_completed: Condvar::new(),
         });
 
         *endpoint.kafka_consumer.context().endpoint.lock().unwrap() = Arc::downgrade(&endpoint);
 
-        // Subscibe consumer to `topics`.
-        endpoint
-            .kafka_consumer
-            .subscribe(&config.topics.iter().map(String::as_str).collect::<Vec<_>>())?;
-
-        // Wait for the consumer to join the group by waiting for the group
-        // rebalance protocol to be set.
-        for attempt in 0..=config.group_join_timeout_secs {
-            if matches!(
-                endpoint.kafka_consumer.rebalance_protocol(),
-                RebalanceProtocol::None
-            ) {
-                if attempt == config.group_join_timeout_secs {
-                    return Err(AnyError::msg(format!(
-                        "failed to join consumer group '{}', giving up after {}s",
-                        config.kafka_options.get("group.id").unwrap(),
-                        config.group_join_timeout_secs
-                    )));
+        if endpoint.subscribed {
+            // Subscibe consumer to `topics`.
+            endpoint
+                .kafka_consumer
+                .subscribe(&config.topics.iter().map(String::as_str).collect::<Vec<_>>())?;
+
+            // Wait for the consumer to join the group by waiting for the group
+            // rebalance protocol to be set.
+            for attempt in 0..=config.group_join_timeout_secs {
+                if matches!(
+                    endpoint.kafka_consumer.rebalance_protocol(),
+                    RebalanceProtocol::None
+                ) {
+                    if attempt == config.group_join_timeout_secs {
+                        return Err(AnyError::msg(format!(
+                            "failed to join consumer group '{}', giving up after {}s",
+                            config.kafka_options.get("group.id").unwrap(),
+                            config.group_join_timeout_secs
+                        )));
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(1000));
+                    // kafka_consumer.poll(POLL_TIMEOUT);
+                    // println!("waiting to join the group");
+                } else {
+                    break;
                 }
-                std::thread::sleep(std::time::Duration::from_millis(1000));
-                // kafka_consumer.poll(POLL_TIMEOUT);
-                // println!("waiting to join the group");
-            } else {
-                break;
             }
+        } else {
+            endpoint.assign_partitions(&config)?;
         }
 
         let endpoint_clone = endpoint.clone();
@@ -261,6 +377,46 @@ impl KafkaInputEndpointInner {
         self.state.store(state as u32, Ordering::Release);
     }
 
+    /// Assign partitions listed in `config.assignment` to the consumer.
+    ///
+    /// Partitions are assigned in a paused state.
+    fn assign_partitions(&self, config: &KafkaInputConfig) -> AnyResult<()> {
+        let mut partitions = TopicPartitionList::new();
+        let mut timestamps = TopicPartitionList::new();
+
+        for partition in config.assignment.iter() {
+            match partition.start_offset.unwrap_or(config.start_offset) {
+                KafkaStartOffset::Timestamp(timestamp) => timestamps.add_partition_offset(
+                    &partition.topic,
+                    partition.partition,
+                    Offset::Offset(timestamp),
+                )?,
+                start_offset => partitions.add_partition_offset(
+                    &partition.topic,
+                    partition.partition,
+                    start_offset.to_offset(),
+                )?,
+            }
+        }
+
+        // Resolve timestamps to offsets.  Partitions without messages newer
+        // than the timestamp resolve to the end of the partition.
+        if timestamps.count() > 0 {
+            let offsets = self
+                .kafka_consumer
+                .offsets_for_times(timestamps, OFFSETS_FOR_TIMES_TIMEOUT)
+                .map_err(|e| self.refine_error(e).1)?;
+            for elem in offsets.elements() {
+                partitions.add_partition_offset(elem.topic(), elem.partition(), elem.offset())?;
+            }
+        }
+
+        self.kafka_consumer.assign(&partitions)?;
+        self.pause_partitions()?;
+
+        Ok(())
+    }
+
     /// Pause all partitions assigned to the consumer.
     fn pause_partitions(&self) -> KafkaResult<()> {
         // println!("pause");
@@ -282,6 +438,81 @@ impl KafkaInputEndpointInner {
         refine_kafka_error(self.kafka_consumer.client(), e)
     }
 
+    /// Record the current position as the start of `step`, and block the
+    /// worker thread until `end_step`.
+    fn start_step(&self, step: Step) {
+        let mut position = self.position.lock().unwrap();
+        position.in_step = true;
+
+        let offsets = position.offsets.clone();
+        position.uncommitted.push_back((step, offsets));
+    }
+
+    fn end_step(&self) {
+        self.position.lock().unwrap().in_step = false;
+        self.step_completed.notify_all();
+    }
+
+    /// Commit offsets recorded at the start of the latest step not exceeding
+    /// `step`.
+    fn commit(&self, step: Step) -> AnyResult<()> {
+        let mut position = self.position.lock().unwrap();
+
+        let mut offsets = None;
+        while matches!(position.uncommitted.front(), Some((s, _)) if *s <= step) {
+            offsets = position.uncommitted.pop_front().map(|(_, offsets)| offsets);
+        }
+
+        let offsets = match offsets {
+            Some(offsets) if !offsets.is_empty() && offsets != position.committed => offsets,
+            _ => return Ok(()),
+        };
+
+        let mut partitions = TopicPartitionList::new();
+        for ((topic, partition), offset) in offsets.iter() {
+            partitions.add_partition_offset(topic, *partition, Offset::Offset(*offset))?;
+        }
+
+        self.kafka_consumer
+            .commit(&partitions, CommitMode::Sync)
+            .map_err(|e| self.refine_error(e).1)?;
+        position.committed = offsets;
+
+        Ok(())
+    }
+
+    /// Resume reading each partition from the offset specified in `offsets`.
+    ///
+    /// Partitions currently assigned to the consumer are repositioned
+    /// directly.  When partitions are assigned by the consumer group, the
+    /// offsets are also committed to the group, so that partitions assigned
+    /// by future rebalances start from the same offsets.
+    fn seek(&self, offsets: Offsets) -> AnyResult<()> {
+        let assignment = self.kafka_consumer.assignment()?;
+        let mut partitions = TopicPartitionList::new();
+
+        for ((topic, partition), offset) in offsets.iter() {
+            if assignment.find_partition(topic, *partition).is_some() {
+                self.kafka_consumer
+                    .seek(topic, *partition, Offset::Offset(*offset), SEEK_TIMEOUT)
+                    .map_err(|e| self.refine_error(e).1)?;
+            }
+            partitions.add_partition_offset(topic, *partition, Offset::Offset(*offset))?;
+        }
+
+        let mut position = self.position.lock().unwrap();
+
+        if self.subscribed && partitions.count() > 0 {
+            self.kafka_consumer
+                .commit(&partitions, CommitMode::Sync)
+                .map_err(|e| self.refine_error(e).1)?;
+            position.committed = offsets.clone();
+        }
+        position.offsets = offsets;
+
+        Ok(())
+    }
+
     fn worker_thread(endpoint: Arc<KafkaInputEndpointInner>, mut consumer: Box<dyn InputConsumer>) {
         let mut actual_state = PipelineState::Paused;
         loop {
@@ -329,9 +560,23 @@ impl KafkaInputEndpointInner {
                     // println!("received {} bytes", message.payload().unwrap().len());
                     // message.payload().map(|payload| consumer.input(payload));
 
+                    // Wait for the current step to complete, so that the
+                    // message and its offset get attributed to the same step.
+                    let mut position = endpoint
+                        .step_completed
+                        .wait_while(endpoint.position.lock().unwrap(), |position| {
+                            position.in_step
+                        })
+                        .unwrap();
+
                     if let Some(payload) = message.payload() {
                         consumer.input(payload);
                     }
+
+                    position.offsets.insert(
+                        (message.topic().to_string(), message.partition()),
+                        message.offset() + 1,
+                    );
                 }
             }
         }
@@ -353,6 +598,59 @@ impl InputEndpoint for KafkaInputEndpoint {
 
     fn disconnect(&self) {
         self.0.set_state(PipelineState::Terminated);
+
+        // The endpoint may be disconnected in the middle of a step, in which
+        // case it won't receive the `end_step` notification.  Unblock the
+        // worker thread, so it can observe the new state and exit.
+        self.0.end_step();
+    }
+
+    fn start_step(&self, step: Step) {
+        self.0.start_step(step);
+    }
+
+    fn end_step(&self, _step: Step) {
+        self.0.end_step();
+    }
+
+    fn commit(&self, step: Step) -> AnyResult<()> {
+        self.0.commit(step)
+    }
+
+    fn position(&self) -> Option<JsonValue> {
+        // Report offsets as `{topic: {partition: offset}}`.
+        let mut topics = serde_json::Map::new();
+        for ((topic, partition), offset) in self.0.position.lock().unwrap().offsets.iter() {
+            topics
+                .entry(topic.clone())
+                .or_insert_with(|| JsonValue::Object(Default::default()))
+                .as_object_mut()
+                .unwrap()
+                .insert(partition.to_string(), JsonValue::from(*offset));
+        }
+
+        Some(JsonValue::Object(topics))
+    }
+
+    fn seek(&self, position: &JsonValue) -> AnyResult<()> {
+        // Parse offsets in the format produced by `position`.
+        let invalid_position =
+            || AnyError::msg(format!("invalid Kafka input position: {position}"));
+
+        let mut offsets = Offsets::new();
+        for (topic, partitions) in position.as_object().ok_or_else(invalid_position)? {
+            for (partition, offset) in partitions.as_object().ok_or_else(invalid_position)? {
+                offsets.insert(
+                    (
+                        topic.clone(),
+                        partition.parse().map_err(|_| invalid_position())?,
+                    ),
+                    offset.as_i64().ok_or_else(invalid_position)?,
+                );
+            }
+        }
+
+        self.0.seek(offsets)
     }
 }
 
@@ -1,9 +1,10 @@
-use super::KafkaLogLevel;
-use crate::{OutputEndpoint, OutputTransport};
+use super::{refine_kafka_error, KafkaLogLevel};
+use crate::{OutputEndpoint, OutputTransport, Step};
 use anyhow::{Error as AnyError, Result as AnyResult};
 use crossbeam::sync::{Parker, Unparker};
 use rdkafka::{
     config::{FromClientConfigAndContext, RDKafkaLogLevel},
+    error::KafkaError,
     producer::{BaseRecord, DeliveryResult, Producer, ProducerContext, ThreadedProducer},
     ClientConfig, ClientContext,
 };
@@ -37,6 +38,10 @@ const fn default_max_inflight_messages() -> u32 {
     1000
 }
 
+const fn default_transaction_timeout_secs() -> u32 {
+    10
+}
+
 /// Output endpoint configuration.
 #[derive(Deserialize)]
 pub struct KafkaOutputConfig {
@@ -68,6 +73,67 @@ pub struct KafkaOutputConfig {
     /// Defaults to 1000.
     #[serde(default = "default_max_inflight_messages")]
     max_inflight_messages: u32,
+
+    /// Write the output of each circuit step in a separate Kafka transaction.
+    ///
+    /// Consumers configured with `isolation.level=read_committed` observe
+    /// the output of each step atomically.  Requires the `transactional.id`
+    /// option.  When a new producer with the same `transactional.id` starts,
+    /// it fences off the previous instance and aborts its pending
+    /// transaction.  If a transaction fails to commit, the endpoint fails and
+    /// the pipeline stops committing input offsets, so that the inputs of
+    /// the failed step are replayed after a restart.
+    #[serde(default)]
+    transactional: bool,
+
+    /// Timeout in seconds for transaction initialization and commit.
+    ///
+    /// Defaults to 10.
+    #[serde(default = "default_transaction_timeout_secs")]
+    transaction_timeout_secs: u32,
+
+    /// Write a separate message for each key in the output batch, keyed by
+    /// the encoding of the key in the output format.
+    ///
+    /// For an indexed view, the payload is the current value for the key;
+    /// otherwise it is the record itself.  Keys whose records were all
+    /// deleted are written as tombstones, i.e., messages with a null
+    /// payload, so that the topic can be compacted.
+    ///
+    /// Messages are assigned to partitions by hashing the key, so all
+    /// updates to the same record land in the same partition, as required,
+    /// e.g., by compacted topics.  Unless the `partitioner` option is set
+    /// explicitly, the endpoint uses the `murmur2_random` partitioner, which
+    /// is compatible with the Java client.
+    #[serde(default)]
+    keyed: bool,
+}
+
+impl KafkaOutputConfig {
+    /// Validate configuration, set default option values required by this
+    /// adapter.
+    fn validate(&mut self) -> AnyResult<()> {
+        if self.keyed {
+            self.kafka_options
+                .entry("partitioner".to_string())
+                .or_insert_with(|| "murmur2_random".to_string());
+        }
+
+        let transactional_id = self.kafka_options.contains_key("transactional.id");
+
+        if self.transactional && !transactional_id {
+            Err(AnyError::msg(
+                "transactional mode requires the 'transactional.id' option",
+            ))?;
+        }
+        if !self.transactional && transactional_id {
+            Err(AnyError::msg(
+                "'transactional.id' option is only valid with 'transactional: true'",
+            ))?;
+        }
+
+        Ok(())
+    }
 }
 
 /// Producer context object used to handle async delivery notifications from
@@ -118,14 +184,19 @@ struct KafkaOutputEndpoint {
     topic: String,
     max_inflight_messages: u32,
     parker: Parker,
+    /// `Some(timeout)` in transactional mode.
+    transaction_timeout: Option<Duration>,
+    keyed: bool,
 }
 
 impl KafkaOutputEndpoint {
     fn new(
-        config: KafkaOutputConfig,
+        mut config: KafkaOutputConfig,
         async_error_callback: Box<dyn Fn(bool, AnyError) + Send + Sync>,
     ) -> AnyResult<Self> {
-        // Create Kafka consumer configuration.
+        config.validate()?;
+
+        // Create Kafka producer configuration.
         let mut client_config = ClientConfig::new();
 
         for (key, value) in config.kafka_options.iter() {
@@ -144,19 +215,31 @@ impl KafkaOutputEndpoint {
         // Create Kafka producer.
         let kafka_producer = ThreadedProducer::from_config_and_context(&client_config, context)?;
 
+        let transaction_timeout = config
+            .transactional
+            .then(|| Duration::from_secs(config.transaction_timeout_secs as u64));
+
+        // Register `transactional.id` with the broker, fencing off previous
+        // producers with the same id.
+        if let Some(timeout) = transaction_timeout {
+            kafka_producer
+                .init_transactions(timeout)
+                .map_err(|e| refine_kafka_error(kafka_producer.client(), e).1)?;
+        }
+
         Ok(Self {
             kafka_producer,
             topic: config.topic,
             max_inflight_messages: config.max_inflight_messages,
             parker,
+            transaction_timeout,
+            keyed: config.keyed,
         })
     }
-}
 
-impl OutputEndpoint for KafkaOutputEndpoint {
-    fn push_buffer(&mut self, buffer: &[u8]) -> AnyResult<()> {
-        // Wait for the number of unacknowledged messages to drop
-        // below `max_inflight_messages`.
+    /// Wait for the number of unacknowledged messages to drop below
+    /// `max_inflight_messages`.
+    fn wait_for_inflight_messages(&self) {
         while self.kafka_producer.in_flight_count() as i64 > self.max_inflight_messages as i64 {
             // FIXME: It appears that the delivery callback can be invoked before the
             // in-flight counter is decremented, in which case we may never get
@@ -166,6 +249,50 @@ impl OutputEndpoint for KafkaOutputEndpoint {
             // thread _after_ the in-flight counter has been decremented.
             self.parker.park_timeout(OUTPUT_POLLING_INTERVAL);
         }
+    }
+
+    /// Handle an error returned by a transactional operation.
+    ///
+    /// Aborts the current transaction if required.  Always returns an error,
+    /// since the output of the step has not been delivered, including when
+    /// the producer has been fenced off by another producer with the same
+    /// `transactional.id`.
+    fn transaction_error(&self, timeout: Duration, e: KafkaError) -> AnyError {
+        if let KafkaError::Transaction(error) = &e {
+            if !error.is_fatal() && error.txn_requires_abort() {
+                return match self.kafka_producer.abort_transaction(timeout) {
+                    Ok(()) => AnyError::msg(format!("Kafka transaction aborted: {error}")),
+                    Err(e) => refine_kafka_error(self.kafka_producer.client(), e).1,
+                };
+            }
+        }
+
+        refine_kafka_error(self.kafka_producer.client(), e).1
+    }
+}
+
+impl OutputEndpoint for KafkaOutputEndpoint {
+    fn batch_start(&mut self, _step: Step) -> AnyResult<()> {
+        if let Some(timeout) = self.transaction_timeout {
+            self.kafka_producer
+                .begin_transaction()
+                .map_err(|e| self.transaction_error(timeout, e))?;
+        }
+        Ok(())
+    }
+
+    fn batch_end(&mut self) -> AnyResult<()> {
+        if let Some(timeout) = self.transaction_timeout {
+            // Flushes all outstanding messages before committing.
+            self.kafka_producer
+                .commit_transaction(timeout)
+                .map_err(|e| self.transaction_error(timeout, e))?;
+        }
+        Ok(())
+    }
+
+    fn push_buffer(&mut self, buffer: &[u8]) -> AnyResult<()> {
+        self.wait_for_inflight_messages();
 
         let record = <BaseRecord<(), [u8], ()>>::to(&self.topic).payload(buffer);
         self.kafka_producer
@@ -173,4 +300,22 @@ impl OutputEndpoint for KafkaOutputEndpoint {
             .map_err(|(err, _record)| err)?;
         Ok(())
     }
+
+    fn is_keyed(&self) -> bool {
+        self.keyed
+    }
+
+    fn push_key(&mut self, key: &[u8], val: Option<&[u8]>) -> AnyResult<()> {
+        self.wait_for_inflight_messages();
+
+        // A message without a payload is a tombstone.
+        let mut record = <BaseRecord<[u8], [u8], ()>>::to(&self.topic).key(key);
+        if let Some(val) = val {
+            record = record.payload(val);
+        }
+        self.kafka_producer
+            .send(record)
+            .map_err(|(err, _record)| err)?;
+        Ok(())
+    }
 }
@@ -47,6 +47,237 @@ fn wait_for_output_unordered(zset: &MockDeZSet<TestStruct>, data: &[Vec<TestStru
     assert_eq!(zset_sorted, data_sorted);
 }
 
+#[test]
+fn test_kafka_input_commit() {
+    let _ = log::set_logger(&TEST_LOGGER);
+    log::set_max_level(LevelFilter::Debug);
+
+    let kafka_resources = KafkaResources::create_topics(&[("input_commit_test_topic", 1)]);
+
+    let config_str = r#"
+transport:
+    name: kafka
+    config:
+        bootstrap.servers: "localhost"
+        auto.offset.reset: "earliest"
+        group.id: "input_commit_test_group"
+        topics: [input_commit_test_topic]
+        log_level: debug
+format:
+    name: csv
+    config:
+        input_stream: test_input
+"#;
+
+    let data1 = vec![(0..10)
+        .map(|id| TestStruct {
+            id,
+            b: true,
+            i: None,
+            s: format!("foo{id}"),
+        })
+        .collect::<Vec<_>>()];
+    let data2 = vec![(10..20)
+        .map(|id| TestStruct {
+            id,
+            b: false,
+            i: Some(id as i64),
+            s: format!("bar{id}"),
+        })
+        .collect::<Vec<_>>()];
+
+    let producer = TestProducer::new();
+
+    println!("Test: commit offsets after a step");
+
+    let (endpoint, _consumer, zset) =
+        mock_input_pipeline::<TestStruct>("test_input", serde_yaml::from_str(config_str).unwrap());
+    endpoint.start().unwrap();
+
+    producer.send_to_topic(&data1, "input_commit_test_topic");
+    wait_for_output_ordered(&zset, &data1);
+
+    // Simulate a step that processes `data1`.
+    endpoint.start_step(0);
+    endpoint.end_step(0);
+    endpoint.commit(0).unwrap();
+
+    // Records received after the step are not committed.
+    producer.send_to_topic(&data2, "input_commit_test_topic");
+    wait_for_output_ordered(&zset, &[data1[0].clone(), data2[0].clone()]);
+
+    endpoint.disconnect();
+    drop(endpoint);
+
+    println!("Test: restart from committed offsets");
+
+    let (endpoint, _consumer, zset) =
+        mock_input_pipeline::<TestStruct>("test_input", serde_yaml::from_str(config_str).unwrap());
+    endpoint.start().unwrap();
+
+    wait_for_output_ordered(&zset, &data2);
+    sleep(Duration::from_millis(1000));
+    assert_eq!(zset.state().flushed.len(), data2[0].len());
+
+    endpoint.disconnect();
+    drop(endpoint);
+
+    println!("Delete Kafka resources");
+    drop(kafka_resources);
+}
+
+#[test]
+fn test_kafka_input_assignment() {
+    let _ = log::set_logger(&TEST_LOGGER);
+    log::set_max_level(LevelFilter::Debug);
+
+    let kafka_resources = KafkaResources::create_topics(&[("input_assignment_test_topic", 2)]);
+
+    // Ten single-record messages.
+    let data = (0..10)
+        .map(|id| {
+            vec![TestStruct {
+                id,
+                b: true,
+                i: None,
+                s: format!("foo{id}"),
+            }]
+        })
+        .collect::<Vec<_>>();
+
+    // Write the same data to both partitions.
+    let producer = TestProducer::new();
+    producer.send_to_partition(&data, "input_assignment_test_topic", 0);
+    producer.send_to_partition(&data, "input_assignment_test_topic", 1);
+
+    // Read partition 0 from offset 5; partition 1 from the beginning.
+    let config_str = r#"
+transport:
+    name: kafka
+    config:
+        bootstrap.servers: "localhost"
+        assignment:
+            - topic: input_assignment_test_topic
+              partition: 0
+              start_offset: !offset 5
+            - topic: input_assignment_test_topic
+              partition: 1
+        start_offset: beginning
+        log_level: debug
+format:
+    name: csv
+    config:
+        input_stream: test_input
+"#;
+
+    let (endpoint, _consumer, zset) =
+        mock_input_pipeline::<TestStruct>("test_input", serde_yaml::from_str(config_str).unwrap());
+
+    // Partitions are assigned in a paused state.
+    sleep(Duration::from_millis(1000));
+    assert_eq!(zset.state().flushed.len(), 0);
+
+    endpoint.start().unwrap();
+
+    let expected = [&data[5..], &data[..]].concat();
+    wait_for_output_unordered(&zset, &expected);
+
+    endpoint.start_step(0);
+    let position = endpoint.position().unwrap();
+    endpoint.end_step(0);
+
+    assert_eq!(
+        position,
+        serde_json::json!({
+            "input_assignment_test_topic": {
+                "0": 10,
+                "1": 10,
+            }
+        })
+    );
+
+    endpoint.disconnect();
+    drop(endpoint);
+
+    println!("Delete Kafka resources");
+    drop(kafka_resources);
+}
+
+#[test]
+fn test_kafka_output_transactional() {
+    let _ = log::set_logger(&TEST_LOGGER);
+    log::set_max_level(LevelFilter::Debug);
+
+    let kafka_resources = KafkaResources::create_topics(&[
+        ("transactional_test_input_topic", 1),
+        ("transactional_test_output_topic", 1),
+    ]);
+
+    let config_str = r#"
+inputs:
+    test_input1:
+        transport:
+            name: kafka
+            config:
+                bootstrap.servers: "localhost"
+                auto.offset.reset: "earliest"
+                topics: [transactional_test_input_topic]
+                log_level: debug
+        format:
+            name: csv
+            config:
+                input_stream: test_input1
+outputs:
+    test_output2:
+        stream: test_output1
+        transport:
+            name: kafka
+            config:
+                bootstrap.servers: "localhost"
+                topic: transactional_test_output_topic
+                transactional: true
+                transactional.id: "transactional_test_producer"
+        format:
+            name: csv
+"#;
+
+    let data = vec![(0..100)
+        .map(|id| TestStruct {
+            id,
+            b: id % 2 == 0,
+            i: None,
+            s: format!("foo{id}"),
+        })
+        .collect::<Vec<_>>()];
+
+    let (circuit, catalog) = test_circuit(4);
+    let config: ControllerConfig = serde_yaml::from_str(config_str).unwrap();
+    let controller = Controller::with_config(
+        circuit,
+        catalog,
+        &config,
+        Box::new(|e| panic!("error: {e}")),
+    )
+    .unwrap();
+
+    // `BufferConsumer` uses the default `read_committed` isolation level, so
+    // it only observes committed transactions.
+    let buffer_consumer = BufferConsumer::new("transactional_test_output_topic");
+
+    let producer = TestProducer::new();
+    producer.send_to_topic(&data, "transactional_test_input_topic");
+
+    controller.start();
+    buffer_consumer.wait_for_output_unordered(&data);
+
+    drop(buffer_consumer);
+
+    controller.stop().unwrap();
+
+    println!("Delete Kafka resources");
+    drop(kafka_resources);
+}
+
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(2))]
 
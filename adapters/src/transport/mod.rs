@@ -1,19 +1,31 @@
 use anyhow::{Error as AnyError, Result as AnyResult};
 use once_cell::sync::Lazy;
+use serde_json::Value as JsonValue;
 use serde_yaml::Value as YamlValue;
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 
 mod file;
 
+#[cfg(feature = "server")]
+mod http;
+
 #[cfg(feature = "with-kafka")]
 mod kafka;
 
 pub use file::{FileInputTransport, FileOutputTransport};
 
+#[cfg(feature = "server")]
+pub use http::{HttpInputEndpoint, HttpOutputEndpoint, HTTP_TRANSPORT_NAME};
+
 #[cfg(feature = "with-kafka")]
 pub use kafka::{KafkaInputTransport, KafkaOutputTransport};
 
+/// Sequence number of a circuit step.
+///
+/// The controller numbers steps consecutively starting from 0.
+pub type Step = u64;
+
 /// Static map of supported input transports.
 // TODO: support for registering new transports at runtime in order to allow
 // external crates to implement new transports.
@@ -87,7 +99,7 @@ impl dyn InputTransport {
 
 /// Input transport endpoint receives a stream of bytes via the underlying
 /// data transport protocol and pushes it to the associated [`InputConsumer`].
-pub trait InputEndpoint: Send {
+pub trait InputEndpoint: Send + Sync {
     /// Pause the endpoint.
     ///
     /// The endpoint must stop pushing data downstream.  This method may
@@ -103,11 +115,72 @@ pub trait InputEndpoint: Send {
     /// Disconnect the endpoint.
     ///
     /// Disconnect the endpoint and stop receiving data.  This is the last
-    /// method invoked before the endpoint object is dropped.  It may return
+    /// method invoked before the endpoint object is dropped, except for
+    /// [`commit`](`Self::commit`), which can still be invoked for steps
+    /// evaluated before the endpoint was disconnected.  It may return
     /// before the dataflow has been fully terminated, i.e., few additional
     /// data buffers may be pushed downstream before the endpoint gets
     /// disconnected.
     fn disconnect(&self);
+
+    /// Notifies the endpoint that the controller is about to evaluate circuit
+    /// step `step`.
+    ///
+    /// Endpoints that can replay their input from a given position (see
+    /// [`commit`](`Self::commit`)) should record their current position and
+    /// stop pushing data downstream until [`end_step`](`Self::end_step`) is
+    /// invoked.  This guarantees that step `step` processes all data pushed
+    /// before this call and none of the data pushed after it.
+    ///
+    /// The default implementation does nothing.
+    fn start_step(&self, _step: Step) {}
+
+    /// Notifies the endpoint that the controller has finished evaluating
+    /// circuit step `step`.
+    ///
+    /// The endpoint can resume pushing data downstream.
+    ///
+    /// The default implementation does nothing.
+    fn end_step(&self, _step: Step) {}
+
+    /// Notifies the endpoint that step `step` has been evaluated and all
+    /// outputs it produced have been pushed to output endpoints.
+    ///
+    /// Endpoints that support replay can durably record the input position
+    /// at the start of the step (see [`start_step`](`Self::start_step`)), so
+    /// that, after a restart, they resume from the first input record not yet
+    /// processed by the circuit.
+    ///
+    /// The default implementation does nothing.
+    fn commit(&self, _step: Step) -> AnyResult<()> {
+        Ok(())
+    }
+
+    /// Current input position of the endpoint in a transport-specific
+    /// format, reported in endpoint metrics.
+    ///
+    /// The controller invokes this method after
+    /// [`start_step`](`Self::start_step`).  The default implementation
+    /// returns `None`.
+    fn position(&self) -> Option<JsonValue> {
+        None
+    }
+
+    /// Move the endpoint to `position`, previously reported by
+    /// [`position`](`Self::position`).
+    ///
+    /// The controller invokes this method when restoring the pipeline from a
+    /// checkpoint, before starting the endpoint, so that it resumes reading
+    /// input right after the data processed before the checkpoint.  Endpoints
+    /// that report their position must support this method.  Endpoints that
+    /// don't are checkpointed with a `null` position and cannot be restored:
+    /// the default implementation fails, so that the pipeline does not
+    /// re-ingest input that is already reflected in the restored state.
+    fn seek(&self, _position: &JsonValue) -> AnyResult<()> {
+        Err(AnyError::msg(
+            "input endpoint does not support resuming from a checkpoint",
+        ))
+    }
 }
 
 /// Input stream consumer.
@@ -117,7 +190,11 @@ pub trait InputEndpoint: Send {
 // TODO: `input_owned`.
 pub trait InputConsumer: Send {
     /// Push a chunk of data to the consumer.
-    fn input(&mut self, data: &[u8]);
+    ///
+    /// Returns the errors encountered while parsing the data.  The consumer
+    /// handles parse errors according to the endpoint's error policy;
+    /// endpoints can additionally report them to the source of the data.
+    fn input(&mut self, data: &[u8]) -> Vec<String>;
 
     /// Endpoint failed.
     ///
@@ -126,8 +203,10 @@ pub trait InputConsumer: Send {
 
     /// End-of-input-stream notification.
     ///
-    /// No more data will be received from the endpoint.
-    fn eoi(&mut self);
+    /// No more data will be received from the endpoint.  Returns the errors
+    /// encountered while parsing the remaining buffered data (see
+    /// [`input`](`Self::input`)).
+    fn eoi(&mut self) -> Vec<String>;
 
     /// Create a new consumer instance.
     ///
@@ -182,5 +261,56 @@ impl dyn OutputTransport {
 }
 
 pub trait OutputEndpoint: Send {
+    /// Notifies the endpoint that the following buffers contain the output
+    /// of circuit step `step`.
+    ///
+    /// Endpoints that support transactions can use this method together
+    /// with [`batch_end`](`Self::batch_end`) to deliver the output of each
+    /// step atomically.
+    ///
+    /// The default implementation does nothing.
+    fn batch_start(&mut self, _step: Step) -> AnyResult<()> {
+        Ok(())
+    }
+
     fn push_buffer(&mut self, buffer: &[u8]) -> AnyResult<()>;
+
+    /// Returns `true` if the endpoint expects a separate message for each
+    /// output key (see [`OutputConsumer::push_key`]).
+    ///
+    /// The default implementation returns `false`.
+    fn is_keyed(&self) -> bool {
+        false
+    }
+
+    /// Push a message with the specified key.
+    ///
+    /// Only invoked for endpoints that return `true` from
+    /// [`is_keyed`](`Self::is_keyed`).  `val` is `None` for a tombstone
+    /// message that deletes the key.  The default implementation discards
+    /// the key and ignores tombstones.
+    fn push_key(&mut self, _key: &[u8], val: Option<&[u8]>) -> AnyResult<()> {
+        match val {
+            Some(val) => self.push_buffer(val),
+            None => Ok(()),
+        }
+    }
+
+    /// Notifies the endpoint that all outputs of the current step have been
+    /// pushed.
+    ///
+    /// Transactional endpoints commit the transaction started by
+    /// [`batch_start`](`Self::batch_start`) and fail if the commit fails.
+    /// Failing either method, or failing to push any output of the step,
+    /// tells the controller that the output of the step has not been
+    /// delivered: the controller treats this as a fatal endpoint error and
+    /// stops committing steps, so that input endpoints replay the inputs of
+    /// the step after a restart.  This method is not invoked for steps whose
+    /// output could not be pushed or encoded, so the transaction is never
+    /// committed.
+    ///
+    /// The default implementation does nothing.
+    fn batch_end(&mut self) -> AnyResult<()> {
+        Ok(())
+    }
 }
@@ -694,6 +694,10 @@ impl<'a, K, V, R, O> Cursor<'a, K, V, (), R> for HashedKVCursor<'a, K, V, R, O>
         todo!()
     }
 
+    fn step_val_reverse(&mut self) {
+        todo!()
+    }
+
     fn seek_val(&mut self, _value: &V) {
         todo!()
     }
@@ -712,6 +716,10 @@ impl<'a, K, V, R, O> Cursor<'a, K, V, (), R> for HashedKVCursor<'a, K, V, R, O>
     fn rewind_vals(&mut self) {
         todo!()
     }
+
+    fn fast_forward_vals(&mut self) {
+        todo!()
+    }
 }
 
 struct HashedKVConsumer<K, V, R, O> {
@@ -908,6 +916,7 @@ where
         &mut self,
         left: &HashedKVBatch<K, V, R, O>,
         right: &HashedKVBatch<K, V, R, O>,
+        lower_key_bound: &Option<K>,
         fuel: &mut isize,
     ) {
         if *fuel <= 0 {
@@ -918,6 +927,11 @@ where
         *fuel -= consumed as isize;
 
         for (key, offset, side) in self.keys.drain(..consumed) {
+            // Skip keys below `lower_key_bound`
+            if matches!(lower_key_bound, Some(bound) if &key < bound) {
+                continue;
+            }
+
             let batch = match side {
                 Side::Left => left,
                 Side::Right => right,
@@ -3,12 +3,18 @@ mod zset_macro;
 
 use crate::{
     algebra::{GroupValue, HasOne, HasZero, ZRingValue},
+    circuit::checkpoint::Checkpointable,
     trace::{cursor::Cursor, Batch, Builder},
     NumEntries,
 };
 
 /// An indexed Z-set maps arbitrary keys to Z-set values.
-pub trait IndexedZSet: Batch<Time = ()> + GroupValue + NumEntries {
+///
+/// With the `checkpoint` feature, indexed Z-sets must be serializable with
+/// `bincode`, which allows operators that store them, e.g.,
+/// [`Z1`](crate::operator::Z1), to checkpoint their state (see
+/// [`Checkpointable`]).
+pub trait IndexedZSet: Batch<Time = ()> + GroupValue + NumEntries + Checkpointable {
     /// Returns an indexed Z-set that contains all elements with positive
     /// weights from `self` with weights set to 1.
     fn distinct(&self) -> Self
@@ -49,7 +55,7 @@ pub trait IndexedZSet: Batch<Time = ()> + GroupValue + NumEntries {
     }
 }
 
-impl<Z> IndexedZSet for Z where Z: Batch<Time = ()> + GroupValue + NumEntries {}
+impl<Z> IndexedZSet for Z where Z: Batch<Time = ()> + GroupValue + NumEntries + Checkpointable {}
 
 /// The Z-set trait.
 ///
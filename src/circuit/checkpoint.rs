@@ -0,0 +1,205 @@
+//! Circuit checkpoints.
+//!
+//! A checkpoint captures the state of all stateful operators in a circuit
+//! between clock cycles, so that an identical circuit can later resume
+//! computation from the same state (see [`DBSPHandle::checkpoint`] and
+//! [`DBSPHandle::restore`]).
+//!
+//! Operators serialize their state using `bincode`.  Since most operators are
+//! generic over the type of data they store, serializing their state requires
+//! the data type to implement `bincode::Encode` and `bincode::Decode`, which
+//! is only required when the crate is compiled with the `checkpoint` feature
+//! (see [`Checkpointable`]).  With this feature, batch types are encoded as
+//! lists of `(key, value, weight)` tuples.  Without it, checkpointing a
+//! circuit succeeds as long as all operators are in their initial state and
+//! fails otherwise.
+
+#[cfg(feature = "checkpoint")]
+use crate::trace::{cursor::Cursor, Batch, BatchReader};
+use crate::{
+    circuit::{GlobalNodeId, NodeId},
+    Error,
+};
+use bincode::{config::standard, decode_from_slice, encode_to_vec, Decode, Encode};
+#[cfg(feature = "checkpoint")]
+use bincode::{
+    de::Decoder,
+    enc::Encoder,
+    error::{DecodeError, EncodeError},
+};
+
+/// Trait for values stored in the state of stateful operators, e.g.,
+/// [`Z1`](crate::operator::Z1).
+///
+/// When the crate is compiled with the `checkpoint` feature, this trait
+/// requires `bincode::Encode` and `bincode::Decode`, which are used to
+/// checkpoint operator state.  Otherwise it is implemented for all types.
+#[cfg(feature = "checkpoint")]
+pub trait Checkpointable: Encode + Decode {}
+
+#[cfg(feature = "checkpoint")]
+impl<T> Checkpointable for T where T: Encode + Decode {}
+
+#[cfg(not(feature = "checkpoint"))]
+pub trait Checkpointable {}
+
+#[cfg(not(feature = "checkpoint"))]
+impl<T> Checkpointable for T {}
+
+/// Serialized state of a circuit instantiated in a multithreaded runtime.
+///
+/// Contains the state of stateful operators in each worker, indexed by
+/// operator id.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Encode, Decode)]
+pub struct CircuitCheckpoint {
+    // `workers[i]` is a list of `(node path, state)` pairs for worker `i`.
+    workers: Vec<Vec<(Vec<usize>, Vec<u8>)>>,
+}
+
+impl CircuitCheckpoint {
+    pub(crate) fn new(workers: Vec<Vec<(GlobalNodeId, Vec<u8>)>>) -> Self {
+        Self {
+            workers: workers
+                .into_iter()
+                .map(|states| {
+                    states
+                        .into_iter()
+                        .map(|(node_id, state)| {
+                            (node_id.path().iter().map(NodeId::id).collect(), state)
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Number of worker threads in the runtime the checkpoint was taken from.
+    pub fn num_workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Operator states of the `worker`th worker.
+    pub(crate) fn worker_state(&self, worker: usize) -> Vec<(GlobalNodeId, Vec<u8>)> {
+        self.workers[worker]
+            .iter()
+            .map(|(path, state)| {
+                (
+                    GlobalNodeId::from_path_vec(path.iter().copied().map(NodeId::new).collect()),
+                    state.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Serialize the checkpoint into a byte array.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        encode(self)
+    }
+
+    /// Deserialize a checkpoint produced by [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        decode(bytes)
+    }
+}
+
+fn encode<T>(value: &T) -> Result<Vec<u8>, Error>
+where
+    T: Encode,
+{
+    encode_to_vec(value, standard())
+        .map_err(|e| Error::Custom(format!("failed to encode checkpoint: {e}")))
+}
+
+fn decode<T>(bytes: &[u8]) -> Result<T, Error>
+where
+    T: Decode,
+{
+    decode_from_slice(bytes, standard())
+        .map(|(value, _)| value)
+        .map_err(|e| Error::Custom(format!("failed to decode checkpoint: {e}")))
+}
+
+/// Serialize a value stored in a trace or another stateful operator.
+#[cfg(feature = "checkpoint")]
+pub(crate) fn encode_data<T>(value: &T) -> Result<Vec<u8>, Error>
+where
+    T: Checkpointable,
+{
+    encode(value)
+}
+
+#[cfg(not(feature = "checkpoint"))]
+pub(crate) fn encode_data<T>(_value: &T) -> Result<Vec<u8>, Error>
+where
+    T: Checkpointable,
+{
+    Err(unsupported())
+}
+
+/// Deserialize a value serialized with [`encode_data`].
+#[cfg(feature = "checkpoint")]
+pub(crate) fn decode_data<T>(bytes: &[u8]) -> Result<T, Error>
+where
+    T: Checkpointable,
+{
+    decode(bytes)
+}
+
+#[cfg(not(feature = "checkpoint"))]
+pub(crate) fn decode_data<T>(_bytes: &[u8]) -> Result<T, Error>
+where
+    T: Checkpointable,
+{
+    Err(unsupported())
+}
+
+/// Error returned by operators whose state cannot be serialized.
+pub(crate) fn unsupported() -> Error {
+    Error::Custom(if cfg!(feature = "checkpoint") {
+        "checkpointing the state of this operator is not supported".to_string()
+    } else {
+        "checkpointing the state of this operator requires the 'checkpoint' feature".to_string()
+    })
+}
+
+/// Encode the contents of an untimed batch as a list of
+/// `(key, value, weight)` tuples.
+///
+/// Used to implement `bincode::Encode` for batch types.
+#[cfg(feature = "checkpoint")]
+pub(crate) fn encode_batch<B, E>(batch: &B, encoder: &mut E) -> Result<(), EncodeError>
+where
+    B: BatchReader<Time = ()>,
+    E: Encoder,
+{
+    let mut tuples = Vec::with_capacity(batch.len());
+
+    let mut cursor = batch.cursor();
+    while cursor.key_valid() {
+        while cursor.val_valid() {
+            tuples.push((cursor.key().clone(), cursor.val().clone(), cursor.weight()));
+            cursor.step_val();
+        }
+        cursor.step_key();
+    }
+
+    tuples.encode(encoder)
+}
+
+/// Decode a batch encoded with [`encode_batch`].
+#[cfg(feature = "checkpoint")]
+pub(crate) fn decode_batch<B, D>(decoder: &mut D) -> Result<B, DecodeError>
+where
+    B: Batch<Time = ()>,
+    D: Decoder,
+{
+    let tuples: Vec<(B::Key, B::Val, B::R)> = Decode::decode(decoder)?;
+
+    Ok(B::from_tuples(
+        (),
+        tuples
+            .into_iter()
+            .map(|(key, val, weight)| (B::item_from(key, val), weight))
+            .collect(),
+    ))
+}
@@ -42,7 +42,7 @@ use crate::{
     },
     circuit_cache_key,
     operator::communication::Exchange,
-    Runtime,
+    Error, Runtime,
 };
 use std::{
     borrow::Cow,
@@ -324,9 +324,23 @@ pub trait Node {
 
     fn metadata(&self, output: &mut OperatorMeta);
 
+    /// Serialize the state of the operator (see
+    /// [`Operator::checkpoint`](super::operator_traits::Operator::checkpoint)).
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        Ok(None)
+    }
+
+    /// Restore the state of the operator (see
+    /// [`Operator::restore`](super::operator_traits::Operator::restore)).
+    fn restore(&mut self, _state: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool;
 
     fn map_nodes_recursive(&self, _f: &mut dyn FnMut(&dyn Node)) {}
+
+    fn map_nodes_recursive_mut(&self, _f: &mut dyn FnMut(&mut dyn Node)) {}
 }
 
 /// Id of an operator, guaranteed to be unique within a circuit.
@@ -967,6 +981,15 @@ impl<P> Circuit<P> {
         }
     }
 
+    /// Like [`Self::map_nodes_recursive`], but passes a mutable reference to
+    /// each node to `f`.
+    pub(crate) fn map_nodes_recursive_mut(&self, f: &mut dyn FnMut(&mut dyn Node)) {
+        for node in self.inner_mut().nodes.iter_mut() {
+            f(node.as_mut());
+            node.map_nodes_recursive_mut(f);
+        }
+    }
+
     /// Deliver `clock_start` notification to all nodes in the circuit.
     pub(super) fn clock_start(&self, scope: Scope) {
         for node in self.inner_mut().nodes.iter_mut() {
@@ -2046,6 +2069,14 @@ where
         self.operator.metadata(output);
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.operator.checkpoint()
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.operator.restore(state)
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -2121,6 +2152,14 @@ where
         self.operator.metadata(output);
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.operator.checkpoint()
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.operator.restore(state)
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -2207,6 +2246,14 @@ where
         self.operator.metadata(output);
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.operator.checkpoint()
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.operator.restore(state)
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -2285,6 +2332,14 @@ where
         self.operator.metadata(output);
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.operator.checkpoint()
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.operator.restore(state)
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -2399,6 +2454,14 @@ where
         self.operator.metadata(output);
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.operator.checkpoint()
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.operator.restore(state)
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -2517,6 +2580,14 @@ where
         self.operator.metadata(output);
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.operator.checkpoint()
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.operator.restore(state)
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -2653,6 +2724,14 @@ where
         self.operator.metadata(output);
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.operator.checkpoint()
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.operator.restore(state)
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -2774,6 +2853,14 @@ where
         self.operator.metadata(output);
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.operator.checkpoint()
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.operator.restore(state)
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -2867,6 +2954,14 @@ where
         unsafe { (*self.operator.get()).metadata(output) }
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        unsafe { &*self.operator.get() }.checkpoint()
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        unsafe { &mut *self.operator.get() }.restore(state)
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         unsafe { (*self.operator.get()).fixedpoint(scope) }
     }
@@ -2933,7 +3028,8 @@ where
     }
 
     // Don't call `clock_start`/`clock_end` on the operator.  `FeedbackOutputNode`
-    // will do that.
+    // will do that.  Likewise, `FeedbackOutputNode` takes care of checkpointing
+    // the shared operator.
     fn clock_start(&mut self, _scope: Scope) {}
 
     unsafe fn clock_end(&mut self, _scope: Scope) {}
@@ -3079,6 +3175,10 @@ where
     fn map_nodes_recursive(&self, f: &mut dyn FnMut(&dyn Node)) {
         self.circuit.map_nodes_recursive(f);
     }
+
+    fn map_nodes_recursive_mut(&self, f: &mut dyn FnMut(&mut dyn Node)) {
+        self.circuit.map_nodes_recursive_mut(f);
+    }
 }
 
 /// Top-level circuit with executor.
@@ -3120,6 +3220,84 @@ impl CircuitHandle {
         self.executor.run(&self.circuit)
     }
 
+    /// Serialize the state of all operators in the circuit.
+    ///
+    /// Must be invoked between clock cycles.  Returns a list of
+    /// `(node id, state)` pairs for all operators that carry state across
+    /// clock cycles (see
+    /// [`Operator::checkpoint`](super::operator_traits::Operator::checkpoint)).
+    /// Fails if the state of one of the operators cannot be serialized.
+    pub fn checkpoint(&self) -> Result<Vec<(GlobalNodeId, Vec<u8>)>, Error> {
+        let mut result = Vec::new();
+        let mut error = None;
+
+        self.circuit.map_nodes_recursive(&mut |node: &dyn Node| {
+            if error.is_some() {
+                return;
+            }
+
+            match node.checkpoint() {
+                Ok(Some(state)) => result.push((node.global_id().clone(), state)),
+                Ok(None) => {}
+                Err(e) => {
+                    error = Some(Error::Custom(format!(
+                        "failed to checkpoint operator '{}' ({}): {e}",
+                        node.name(),
+                        node.global_id()
+                    )))
+                }
+            }
+        });
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(result),
+        }
+    }
+
+    /// Restore the state of the circuit from a checkpoint created by
+    /// [`Self::checkpoint`].
+    ///
+    /// Must be invoked before the first clock cycle of a circuit identical to
+    /// the one the checkpoint was taken from.  Fails if the checkpoint
+    /// contains state for an operator that does not exist in this circuit.
+    pub fn restore(&self, checkpoint: &[(GlobalNodeId, Vec<u8>)]) -> Result<(), Error> {
+        let mut states: HashMap<&GlobalNodeId, &[u8]> = checkpoint
+            .iter()
+            .map(|(node_id, state)| (node_id, state.as_slice()))
+            .collect();
+        let mut error = None;
+
+        self.circuit
+            .map_nodes_recursive_mut(&mut |node: &mut dyn Node| {
+                if error.is_some() {
+                    return;
+                }
+
+                if let Some(state) = states.remove(node.global_id()) {
+                    if let Err(e) = node.restore(state) {
+                        error = Some(Error::Custom(format!(
+                            "failed to restore operator '{}' ({}): {e}",
+                            node.name(),
+                            node.global_id()
+                        )));
+                    }
+                }
+            });
+
+        if let Some(error) = error {
+            return Err(error);
+        }
+
+        if let Some(node_id) = states.keys().next() {
+            return Err(Error::Custom(format!(
+                "checkpoint contains the state of unknown operator {node_id}"
+            )));
+        }
+
+        Ok(())
+    }
+
     /// Attach a scheduler event handler to the circuit.
     ///
     /// This method is identical to
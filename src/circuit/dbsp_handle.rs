@@ -1,9 +1,12 @@
 use crate::{
-    circuit::runtime::RuntimeHandle, profile::Profiler, Circuit, Error as DBSPError, Runtime,
-    RuntimeError, SchedulerError,
+    circuit::{runtime::RuntimeHandle, CircuitCheckpoint, GlobalNodeId},
+    profile::{OperatorProfile, Profiler},
+    Circuit, Error as DBSPError, Runtime, RuntimeError, SchedulerError,
 };
 use crossbeam::channel::{bounded, Receiver, Sender, TryRecvError};
-use std::{fs, fs::create_dir_all, path::Path, thread::Result as ThreadResult, time::Instant};
+use std::{
+    fs, fs::create_dir_all, path::Path, sync::Arc, thread::Result as ThreadResult, time::Instant,
+};
 
 impl Runtime {
     /// Instantiate a circuit in a multithreaded runtime.
@@ -101,6 +104,28 @@ impl Runtime {
                             return;
                         }
                     }
+                    Ok(Command::CpuProfile) => {
+                        if status_sender
+                            .send(Ok(Response::CpuProfile(profiler.cpu_profile())))
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                    Ok(Command::Checkpoint) => {
+                        if status_sender
+                            .send(Ok(Response::Checkpoint(circuit.checkpoint())))
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                    Ok(Command::Restore(checkpoint)) => {
+                        let status = circuit.restore(&checkpoint.worker_state(worker_index));
+                        if status_sender.send(Ok(Response::Restore(status))).is_err() {
+                            return;
+                        }
+                    }
                     // Nothing to do: do some housekeeping and relinquish the CPU if there's none
                     // left.
                     Err(TryRecvError::Empty) => {
@@ -157,11 +182,17 @@ enum Command {
     Step,
     EnableProfiler,
     DumpProfile,
+    CpuProfile,
+    Checkpoint,
+    Restore(Arc<CircuitCheckpoint>),
 }
 
 enum Response {
     Unit,
     Profile(String),
+    CpuProfile(Vec<OperatorProfile>),
+    Checkpoint(Result<Vec<(GlobalNodeId, Vec<u8>)>, DBSPError>),
+    Restore(Result<(), DBSPError>),
 }
 
 /// A handle to control the execution of a circuit in a multithreaded runtime.
@@ -279,6 +310,85 @@ impl DBSPHandle {
         Ok(())
     }
 
+    /// Returns the CPU usage of circuit nodes in each worker thread.
+    ///
+    /// The `i`th element of the returned vector contains the profile of
+    /// worker `i`.  Profiles are empty unless CPU profiling was enabled with
+    /// [`Self::enable_cpu_profiler`].
+    pub fn cpu_profile(&mut self) -> Result<Vec<Vec<OperatorProfile>>, DBSPError> {
+        let mut profiles = Vec::with_capacity(self.num_workers());
+
+        self.broadcast_command(Command::CpuProfile, |resp| {
+            if let Response::CpuProfile(profile) = resp {
+                profiles.push(profile);
+            }
+        })?;
+
+        Ok(profiles)
+    }
+
+    /// Serialize the state of the circuit.
+    ///
+    /// Captures the state of all stateful operators in all workers (see
+    /// [`Operator::checkpoint`](`crate::circuit::operator_traits::Operator::checkpoint`)).
+    /// The checkpoint can later be used to initialize an identical circuit
+    /// instantiated with the same number of workers (see [`Self::restore`]).
+    /// Must not be called concurrently with [`Self::step`].
+    ///
+    /// Fails if the state of at least one operator cannot be serialized,
+    /// e.g., because the crate was compiled without the `checkpoint` feature
+    /// and the operator is not in its initial state.  Unlike errors during [`Self::step`], this does not terminate the
+    /// circuit.
+    pub fn checkpoint(&mut self) -> Result<CircuitCheckpoint, DBSPError> {
+        let mut workers = Vec::with_capacity(self.num_workers());
+        let mut error = None;
+
+        self.broadcast_command(Command::Checkpoint, |resp| {
+            if let Response::Checkpoint(state) = resp {
+                match state {
+                    Ok(state) => workers.push(state),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                }
+            }
+        })?;
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(CircuitCheckpoint::new(workers)),
+        }
+    }
+
+    /// Restore the state of the circuit from a checkpoint created by
+    /// [`Self::checkpoint`].
+    ///
+    /// Must be invoked before the first call to [`Self::step`] on a circuit
+    /// identical to the one the checkpoint was taken from, running with the
+    /// same number of workers.
+    pub fn restore(&mut self, checkpoint: &CircuitCheckpoint) -> Result<(), DBSPError> {
+        if checkpoint.num_workers() != self.num_workers() {
+            return Err(DBSPError::Custom(format!(
+                "checkpoint was created with {} workers, but the circuit is running with {} workers",
+                checkpoint.num_workers(),
+                self.num_workers()
+            )));
+        }
+
+        let mut error = None;
+
+        self.broadcast_command(Command::Restore(Arc::new(checkpoint.clone())), |resp| {
+            if let Response::Restore(Err(e)) = resp {
+                error.get_or_insert(e);
+            }
+        })?;
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
     /// Terminate the execution of the circuit, exiting all worker threads.
     ///
     /// If one or more of the worker threads panics, returns the argument the
@@ -306,7 +416,10 @@ impl Drop for DBSPHandle {
 
 #[cfg(test)]
 mod tests {
-    use crate::{operator::Generator, Error as DBSPError, Runtime, RuntimeError};
+    use crate::{
+        operator::Generator, Circuit, CircuitCheckpoint, CollectionHandle, Error as DBSPError,
+        OrdZSet, OutputHandle, Runtime, RuntimeError,
+    };
 
     // Panic during initialization in worker thread.
     #[test]
@@ -413,4 +526,154 @@ mod tests {
 
         handle.step().unwrap();
     }
+
+    fn distinct_circuit(
+        circuit: &mut Circuit<()>,
+    ) -> (
+        CollectionHandle<u64, isize>,
+        OutputHandle<OrdZSet<u64, isize>>,
+    ) {
+        let (zset, zset_handle) = circuit.add_input_zset::<u64, isize>();
+        (zset_handle, zset.distinct_incremental().output())
+    }
+
+    #[test]
+    fn test_cpu_profile() {
+        let (mut handle, (mut input, _output)) =
+            Runtime::init_circuit(2, distinct_circuit).unwrap();
+
+        // Profiling is disabled by default.
+        handle.step().unwrap();
+        assert!(handle.cpu_profile().unwrap().iter().all(Vec::is_empty));
+
+        handle.enable_cpu_profiler().unwrap();
+        input.append(&mut vec![(1, 1), (2, 1)]);
+        handle.step().unwrap();
+        handle.step().unwrap();
+
+        let profiles = handle.cpu_profile().unwrap();
+        assert_eq!(profiles.len(), 2);
+        for profile in profiles {
+            assert!(!profile.is_empty());
+            assert!(profile.iter().all(|op| op.cpu.invocations() == 2));
+            assert!(profile.iter().any(|op| op.name.starts_with("Distinct")));
+        }
+        handle.kill().unwrap();
+    }
+
+    // Checkpoint a circuit that hasn't received any inputs yet.
+    #[test]
+    fn test_checkpoint_initial() {
+        let (mut handle, _) = Runtime::init_circuit(2, distinct_circuit).unwrap();
+        let checkpoint = handle.checkpoint().unwrap();
+        assert_eq!(checkpoint.num_workers(), 2);
+        handle.kill().unwrap();
+
+        let bytes = checkpoint.to_bytes().unwrap();
+        let checkpoint = CircuitCheckpoint::from_bytes(&bytes).unwrap();
+
+        let (mut handle, _) = Runtime::init_circuit(2, distinct_circuit).unwrap();
+        handle.restore(&checkpoint).unwrap();
+        handle.step().unwrap();
+        handle.kill().unwrap();
+
+        // Number of workers must match.
+        let (mut handle, _) = Runtime::init_circuit(4, distinct_circuit).unwrap();
+        assert!(handle.restore(&checkpoint).is_err());
+        handle.kill().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "checkpoint")]
+    fn test_checkpoint_restore() {
+        use crate::trace::Batch;
+
+        let (mut handle, (mut input, output)) = Runtime::init_circuit(2, distinct_circuit).unwrap();
+
+        input.append(&mut vec![(1, 1), (2, 1), (3, 1)]);
+        handle.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            OrdZSet::from_keys((), vec![(1, 1), (2, 1), (3, 1)])
+        );
+
+        let checkpoint = handle.checkpoint().unwrap();
+        handle.kill().unwrap();
+
+        let (mut handle, (mut input, output)) = Runtime::init_circuit(2, distinct_circuit).unwrap();
+        handle.restore(&checkpoint).unwrap();
+
+        // The restored circuit remembers that `1` is already in the set.
+        input.append(&mut vec![(1, -1), (4, 1)]);
+        handle.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            OrdZSet::from_keys((), vec![(1, -1), (4, 1)])
+        );
+        handle.kill().unwrap();
+    }
+
+    // Without the `checkpoint` feature, only circuits in their initial state
+    // can be checkpointed.
+    #[test]
+    #[cfg(not(feature = "checkpoint"))]
+    fn test_checkpoint_unsupported() {
+        let (mut handle, (mut input, _output)) =
+            Runtime::init_circuit(2, distinct_circuit).unwrap();
+
+        input.append(&mut vec![(1, 1), (2, 1), (3, 1)]);
+        handle.step().unwrap();
+
+        assert!(handle.checkpoint().is_err());
+        handle.kill().unwrap();
+    }
+
+    // Checkpoint the state of `Z1` operators used by `integrate` and
+    // `differentiate`.
+    #[test]
+    #[cfg(feature = "checkpoint")]
+    fn test_checkpoint_integral() {
+        use crate::{operator::FilterMap, trace::Batch};
+
+        type ZSetOutput = OutputHandle<OrdZSet<u64, isize>>;
+
+        fn integral_circuit(
+            circuit: &mut Circuit<()>,
+        ) -> (CollectionHandle<u64, isize>, ZSetOutput, ZSetOutput) {
+            let (zset, zset_handle) = circuit.add_input_zset::<u64, isize>();
+            let integral = zset.integrate();
+            (
+                zset_handle,
+                integral.output(),
+                integral.map(|x| x + 1).differentiate().output(),
+            )
+        }
+
+        let (mut handle, (mut input, _integral, _differential)) =
+            Runtime::init_circuit(2, integral_circuit).unwrap();
+
+        input.append(&mut vec![(1, 1), (2, 1)]);
+        handle.step().unwrap();
+
+        let checkpoint =
+            CircuitCheckpoint::from_bytes(&handle.checkpoint().unwrap().to_bytes().unwrap())
+                .unwrap();
+        handle.kill().unwrap();
+
+        let (mut handle, (mut input, integral, differential)) =
+            Runtime::init_circuit(2, integral_circuit).unwrap();
+        handle.restore(&checkpoint).unwrap();
+
+        input.append(&mut vec![(1, -1), (3, 1)]);
+        handle.step().unwrap();
+        assert_eq!(
+            integral.consolidate(),
+            OrdZSet::from_keys((), vec![(2, 1), (3, 1)])
+        );
+        assert_eq!(
+            differential.consolidate(),
+            OrdZSet::from_keys((), vec![(2, -1), (4, 1)])
+        );
+        handle.kill().unwrap();
+    }
 }
@@ -8,6 +8,7 @@
 mod activations;
 mod dbsp_handle;
 
+pub(crate) mod checkpoint;
 pub(crate) mod runtime;
 
 #[macro_use]
@@ -19,6 +20,7 @@ pub mod schedule;
 pub mod trace;
 
 pub use activations::{Activations, Activator};
+pub use checkpoint::{Checkpointable, CircuitCheckpoint};
 pub use circuit_builder::{
     Circuit, CircuitHandle, ExportId, ExportStream, FeedbackConnector, GlobalNodeId, NodeId,
     OwnershipPreference, Scope, Stream,
@@ -3,9 +3,12 @@
 //! Operators are the building blocks of DBSP circuits.  An operator
 //! consumes one or more input streams and produces an output stream.
 
-use crate::circuit::{
-    metadata::{OperatorLocation, OperatorMeta},
-    OwnershipPreference, Scope,
+use crate::{
+    circuit::{
+        metadata::{OperatorLocation, OperatorMeta},
+        OwnershipPreference, Scope,
+    },
+    Error,
 };
 use std::borrow::Cow;
 
@@ -145,6 +148,28 @@ pub trait Operator: 'static {
     {
     }
 
+    /// Serialize the state of the operator.
+    ///
+    /// This method is invoked between clock cycles of the top-level circuit
+    /// in order to checkpoint the state of the circuit (see
+    /// [`DBSPHandle::checkpoint`](`crate::DBSPHandle::checkpoint`)).
+    /// It returns `None` if the operator does not carry any state across
+    /// clock cycles or if its state is equal to the state of a freshly
+    /// created operator.  Operators whose state cannot be serialized must
+    /// return an error rather than silently dropping the state.
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        Ok(None)
+    }
+
+    /// Restore operator state from a checkpoint.
+    ///
+    /// `state` is the output of [`Self::checkpoint`] produced by an identical
+    /// operator in an identical circuit.  The method is invoked on a freshly
+    /// created circuit, after `clock_start` and before the first clock cycle.
+    fn restore(&mut self, _state: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+
     /// Check if the operator is in a stable state.
     ///
     /// This method is invoked as part of checking if the circuit has reached a
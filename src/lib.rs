@@ -26,7 +26,8 @@ pub use crate::ref_pair::RefPair;
 pub use crate::time::Timestamp;
 
 pub use circuit::{
-    Circuit, CircuitHandle, DBSPHandle, Runtime, RuntimeError, SchedulerError, Stream,
+    Checkpointable, Circuit, CircuitCheckpoint, CircuitHandle, DBSPHandle, Runtime, RuntimeError,
+    SchedulerError, Stream,
 };
 pub use operator::{CollectionHandle, InputHandle, OutputHandle, UpsertHandle};
 pub use trace::ord::{OrdIndexedZSet, OrdZSet};
@@ -81,43 +81,8 @@ where
     K: DBData + Default,
     V: DBData + Default,
 {
-    /// Outer join:
-    /// - returns the output of `join_func` for common keys.
-    /// - returns the output of `left_func` for keys only found in `self`, but
-    ///   not `other`.
-    /// - returns the output of `right_func` for keys only found in `other`, but
-    ///   not `self`.
-    pub(super) fn outer_join<V2, F, FL, FR, O>(
-        &self,
-        other: &Stream<Circuit<P>, OrdIndexedZSet<K, V2, isize>>,
-        join_func: F,
-        left_func: FL,
-        right_func: FR,
-    ) -> Stream<Circuit<P>, OrdZSet<O, isize>>
-    where
-        Self: FilterMap<Circuit<P>, R = isize>,
-        V2: DBData + Default,
-        O: DBData + Default,
-        F: Fn(&K, &V, &V2) -> O + Clone + 'static,
-        for<'a> FL: Fn(<Self as FilterMap<Circuit<P>>>::ItemRef<'a>) -> O + Clone + 'static,
-        for<'a> FR: Fn(
-                <Stream<Circuit<P>, OrdIndexedZSet<K, V2, isize>> as FilterMap<Circuit<P>>>::ItemRef<
-                    'a,
-                >,
-            ) -> O
-            + Clone
-            + 'static,
-    {
-        let center = self.join_generic::<(), _, _, _, _>(other, move |k, v1, v2| {
-            std::iter::once((join_func(k, v1, v2), ()))
-        });
-        let left = self.antijoin::<(), _>(other).map_generic(left_func);
-        let right = other.antijoin::<(), _>(self).map_generic(right_func);
-        center.sum(&[left, right])
-    }
-
-    /// Like `outer_join`, but uses default value for the missing side of the
-    /// join.
+    /// Like [`Stream::outer_join`], but uses default value for the missing
+    /// side of the join.
     pub(super) fn outer_join_default<V2, F, O>(
         &self,
         other: &Stream<Circuit<P>, OrdIndexedZSet<K, V2, isize>>,
@@ -128,14 +93,13 @@ where
         O: DBData + Default,
         F: Fn(&K, &V, &V2) -> O + Clone + 'static,
     {
-        let join_func_left = join_func.clone();
-        let join_func_right = join_func.clone();
-        self.outer_join(
-            other,
-            join_func,
-            move |(k, v1)| join_func_left(k, v1, &V2::default()),
-            move |(k, v2)| join_func_right(k, &V::default(), v2),
-        )
+        self.outer_join::<(), _, _, _>(other, move |k, v1, v2| {
+            join_func(
+                k,
+                v1.unwrap_or(&V::default()),
+                v2.unwrap_or(&V2::default()),
+            )
+        })
     }
 }
 
@@ -1,11 +1,9 @@
 use super::NexmarkStream;
 use crate::{
-    algebra::UnimplementedSemigroup,
     nexmark::model::{Bid, Event},
-    operator::{FilterMap, Fold},
+    operator::FilterMap,
     Circuit, OrdZSet, Stream,
 };
-use std::collections::VecDeque;
 
 ///
 /// Query 19: Auction TOP-10 Price (Not in original suite)
@@ -44,16 +42,8 @@ pub fn q19(input: NexmarkStream) -> Q19Stream {
     });
 
     bids_by_auction
-        .aggregate::<(), _>(<Fold<_, UnimplementedSemigroup<_>, _, _>>::new(
-            VecDeque::with_capacity(TOP_BIDS),
-            |top: &mut VecDeque<Bid>, (_price, bid): &(usize, Bid), _w| {
-                if top.len() >= TOP_BIDS {
-                    top.pop_front();
-                }
-                top.push_back(bid.clone());
-            },
-        ))
-        .flat_map(|(_, vec)| -> VecDeque<Bid> { (*vec).clone() })
+        .topk_desc(TOP_BIDS)
+        .map(|(_auction, (_price, bid))| bid.clone())
 }
 
 #[cfg(test)]
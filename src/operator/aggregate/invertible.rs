@@ -0,0 +1,348 @@
+//! Standard [invertible aggregators](`crate::operator::InvertibleAggregator`).
+
+use crate::{
+    algebra::{AddAssignByRef, AddByRef, HasZero, MulByRef, NegByRef},
+    operator::{aggregate::InvertibleAggregator, Avg},
+    DBWeight,
+};
+use size_of::SizeOf;
+use std::{
+    hash::Hash,
+    ops::{Add, AddAssign, Div, Neg, Sub},
+};
+
+/// An [invertible aggregator](`crate::operator::InvertibleAggregator`) that
+/// computes the total weight of values in a group.
+#[derive(Clone)]
+pub struct Count;
+
+impl<V, R> InvertibleAggregator<V, R> for Count
+where
+    R: DBWeight + Neg<Output = R> + NegByRef,
+{
+    type Accumulator = R;
+    type Output = R;
+
+    fn contribution(&self, _value: &V, weight: &R) -> R {
+        weight.clone()
+    }
+
+    fn finalize(&self, accumulator: &R) -> R {
+        accumulator.clone()
+    }
+}
+
+/// An [invertible aggregator](`crate::operator::InvertibleAggregator`) that
+/// computes the weighted sum of `f(v) * w` over all values `v` with weight `w`
+/// in a group.
+///
+/// The accumulator tracks the count of values in the group in addition to
+/// their sum in order to distinguish a group whose sum is zero from an empty
+/// group.
+#[derive(Clone)]
+pub struct WeightedSum<F> {
+    f: F,
+}
+
+impl<F> WeightedSum<F> {
+    /// Create a `WeightedSum` aggregator that sums up `f(v) * w` for all
+    /// values `v` with weight `w` in each group.
+    pub const fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<V, R, T, F> InvertibleAggregator<V, R> for WeightedSum<F>
+where
+    T: DBWeight + MulByRef<R, Output = T> + Neg<Output = T> + NegByRef,
+    R: DBWeight + Neg<Output = R> + NegByRef,
+    F: Fn(&V) -> T + Clone + 'static,
+{
+    type Accumulator = Avg<T, R>;
+    type Output = T;
+
+    fn contribution(&self, value: &V, weight: &R) -> Avg<T, R> {
+        Avg::new((self.f)(value).mul_by_ref(weight), weight.clone())
+    }
+
+    fn finalize(&self, accumulator: &Avg<T, R>) -> T {
+        accumulator.sum()
+    }
+}
+
+/// An [invertible aggregator](`crate::operator::InvertibleAggregator`) that
+/// computes the weighted average of `f(v)` over all values `v` in a group.
+///
+/// This aggregator computes the same result as
+/// [`Stream::average`](`crate::Stream::average`) when used with
+/// [`Stream::aggregate_invertible`](`crate::Stream::aggregate_invertible`).
+#[derive(Clone)]
+pub struct Average<F> {
+    f: F,
+}
+
+impl<F> Average<F> {
+    /// Create an `Average` aggregator that computes the average of `f(v)`
+    /// for all values `v` in each group.
+    pub const fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<V, R, T, F> InvertibleAggregator<V, R> for Average<F>
+where
+    T: DBWeight + MulByRef<R, Output = T> + Neg<Output = T> + NegByRef,
+    T: From<R> + Div<Output = T>,
+    R: DBWeight + Neg<Output = R> + NegByRef,
+    F: Fn(&V) -> T + Clone + 'static,
+{
+    type Accumulator = Avg<T, R>;
+    type Output = T;
+
+    fn contribution(&self, value: &V, weight: &R) -> Avg<T, R> {
+        Avg::new((self.f)(value).mul_by_ref(weight), weight.clone())
+    }
+
+    fn finalize(&self, accumulator: &Avg<T, R>) -> T {
+        // The accumulator of a non-empty group has a non-zero count.
+        accumulator.compute_avg().unwrap()
+    }
+}
+
+/// Representation of a partially computed variance aggregate as a
+/// `(sum, sum_squares, count)` tuple.
+///
+/// `Var` forms a commutative group with point-wise plus operation.  The
+/// actual variance value is computed from its components by the [`Variance`]
+/// aggregator.
+#[derive(Debug, Default, Clone, Eq, Hash, PartialEq, Ord, PartialOrd, SizeOf)]
+pub struct Var<T, R> {
+    sum: T,
+    sum_squares: T,
+    count: R,
+}
+
+impl<T, R> Var<T, R> {
+    /// Create a new `Var` object with the given `sum`, `sum_squares`, and
+    /// `count`.
+    pub const fn new(sum: T, sum_squares: T, count: R) -> Self {
+        Self {
+            sum,
+            sum_squares,
+            count,
+        }
+    }
+
+    /// Returns the `sum` component of the tuple.
+    pub fn sum(&self) -> T
+    where
+        T: Clone,
+    {
+        self.sum.clone()
+    }
+
+    /// Returns the `sum_squares` component of the tuple.
+    pub fn sum_squares(&self) -> T
+    where
+        T: Clone,
+    {
+        self.sum_squares.clone()
+    }
+
+    /// Returns the `count` component of the tuple.
+    pub fn count(&self) -> R
+    where
+        R: Clone,
+    {
+        self.count.clone()
+    }
+
+    /// Returns the population variance `sum_squares / count - (sum /
+    /// count)^2` or `None` if `count` is zero.
+    pub fn compute_variance(&self) -> Option<T>
+    where
+        R: Clone + HasZero,
+        T: From<R> + Div<Output = T> + Sub<Output = T> + MulByRef<Output = T> + Clone,
+    {
+        if self.count.is_zero() {
+            None
+        } else {
+            let count = T::from(self.count.clone());
+            let mean = self.sum.clone() / count.clone();
+
+            Some(self.sum_squares.clone() / count - mean.mul_by_ref(&mean))
+        }
+    }
+}
+
+impl<T, R> bincode::Encode for Var<T, R>
+where
+    T: bincode::Encode + bincode::Decode,
+    R: bincode::Encode + bincode::Decode,
+{
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> core::result::Result<(), bincode::error::EncodeError> {
+        bincode::Encode::encode(&self.sum, encoder)?;
+        bincode::Encode::encode(&self.sum_squares, encoder)?;
+        bincode::Encode::encode(&self.count, encoder)?;
+        Ok(())
+    }
+}
+
+impl<T, R> bincode::Decode for Var<T, R>
+where
+    T: bincode::Encode + bincode::Decode,
+    R: bincode::Encode + bincode::Decode,
+{
+    fn decode<D: bincode::de::Decoder>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        let sum: T = bincode::Decode::decode(decoder)?;
+        let sum_squares: T = bincode::Decode::decode(decoder)?;
+        let count: R = bincode::Decode::decode(decoder)?;
+        Ok(Self::new(sum, sum_squares, count))
+    }
+}
+
+impl<T, R> HasZero for Var<T, R>
+where
+    T: HasZero,
+    R: HasZero,
+{
+    fn is_zero(&self) -> bool {
+        self.sum.is_zero() && self.sum_squares.is_zero() && self.count.is_zero()
+    }
+
+    fn zero() -> Self {
+        Self::new(T::zero(), T::zero(), R::zero())
+    }
+}
+
+impl<T, R> Add for Var<T, R>
+where
+    T: Add<Output = T>,
+    R: Add<Output = R>,
+{
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.sum + rhs.sum,
+            self.sum_squares + rhs.sum_squares,
+            self.count + rhs.count,
+        )
+    }
+}
+
+impl<T, R> AddByRef for Var<T, R>
+where
+    T: AddByRef,
+    R: AddByRef,
+{
+    fn add_by_ref(&self, other: &Self) -> Self {
+        Self::new(
+            self.sum.add_by_ref(&other.sum),
+            self.sum_squares.add_by_ref(&other.sum_squares),
+            self.count.add_by_ref(&other.count),
+        )
+    }
+}
+
+impl<T, R> AddAssign for Var<T, R>
+where
+    T: AddAssign,
+    R: AddAssign,
+{
+    fn add_assign(&mut self, rhs: Self) {
+        self.sum += rhs.sum;
+        self.sum_squares += rhs.sum_squares;
+        self.count += rhs.count;
+    }
+}
+
+impl<T, R> AddAssignByRef for Var<T, R>
+where
+    T: AddAssignByRef,
+    R: AddAssignByRef,
+{
+    fn add_assign_by_ref(&mut self, rhs: &Self) {
+        self.sum.add_assign_by_ref(&rhs.sum);
+        self.sum_squares.add_assign_by_ref(&rhs.sum_squares);
+        self.count.add_assign_by_ref(&rhs.count);
+    }
+}
+
+impl<T, R> Neg for Var<T, R>
+where
+    T: Neg<Output = T>,
+    R: Neg<Output = R>,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(self.sum.neg(), self.sum_squares.neg(), self.count.neg())
+    }
+}
+
+impl<T, R> NegByRef for Var<T, R>
+where
+    T: NegByRef,
+    R: NegByRef,
+{
+    fn neg_by_ref(&self) -> Self {
+        Self::new(
+            self.sum.neg_by_ref(),
+            self.sum_squares.neg_by_ref(),
+            self.count.neg_by_ref(),
+        )
+    }
+}
+
+/// An [invertible aggregator](`crate::operator::InvertibleAggregator`) that
+/// computes the weighted population variance of `f(v)` over all values `v`
+/// in a group.
+///
+/// The variance is computed as `E[f(v)^2] - E[f(v)]^2` from the sum,
+/// the sum of squares, and the count of values in the group.  With integer
+/// types both expected values are rounded down.
+#[derive(Clone)]
+pub struct Variance<F> {
+    f: F,
+}
+
+impl<F> Variance<F> {
+    /// Create a `Variance` aggregator that computes the population variance
+    /// of `f(v)` for all values `v` in each group.
+    pub const fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<V, R, T, F> InvertibleAggregator<V, R> for Variance<F>
+where
+    T: DBWeight + MulByRef<R, Output = T> + Neg<Output = T> + NegByRef,
+    T: From<R> + Div<Output = T> + Sub<Output = T> + MulByRef<Output = T>,
+    R: DBWeight + Neg<Output = R> + NegByRef,
+    F: Fn(&V) -> T + Clone + 'static,
+{
+    type Accumulator = Var<T, R>;
+    type Output = T;
+
+    fn contribution(&self, value: &V, weight: &R) -> Var<T, R> {
+        let x = (self.f)(value);
+        let square = MulByRef::<T>::mul_by_ref(&x, &x);
+
+        Var::new(
+            MulByRef::<R>::mul_by_ref(&x, weight),
+            MulByRef::<R>::mul_by_ref(&square, weight),
+            weight.clone(),
+        )
+    }
+
+    fn finalize(&self, accumulator: &Var<T, R>) -> T {
+        // The accumulator of a non-empty group has a non-zero count.
+        accumulator.compute_variance().unwrap()
+    }
+}
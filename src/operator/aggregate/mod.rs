@@ -14,6 +14,7 @@ use crate::{
         PartialOrder, Semigroup, ZRingValue,
     },
     circuit::{
+        checkpoint::{decode_data, encode_data},
         operator_traits::{BinaryOperator, Operator, UnaryOperator},
         Circuit, Scope, Stream,
     },
@@ -22,17 +23,19 @@ use crate::{
         cursor::{Cursor, CursorGroup},
         Batch, BatchReader, Builder, Spine,
     },
-    DBData, DBTimestamp, DBWeight, OrdIndexedZSet, OrdZSet,
+    DBData, DBTimestamp, DBWeight, Error, OrdIndexedZSet, OrdZSet,
 };
 
 // Some standard aggregators.
 mod average;
 mod fold;
+mod invertible;
 mod max;
 mod min;
 
 pub use average::Avg;
 pub use fold::Fold;
+pub use invertible::{Average, Count, Var, Variance, WeightedSum};
 pub use max::{Max, MaxSemigroup};
 pub use min::{Min, MinSemigroup};
 
@@ -121,6 +124,49 @@ pub trait Aggregator<K, T, R>: Clone + 'static {
     }
 }
 
+/// A trait for aggregators with an inverse.
+///
+/// An invertible aggregator maps each `(value, weight)` pair in a group to
+/// its contribution to the aggregate.  Contributions are elements of
+/// a commutative group ([`Accumulator`](`Self::Accumulator`)).  The
+/// accumulator of a group is the sum of the contributions of its elements.
+/// Since accumulators can be negated, deleting values from a group is
+/// as cheap as inserting them: the contribution of a deleted value is simply
+/// subtracted from the accumulator.  This allows
+/// [`Stream::aggregate_invertible`] to update the aggregate using only
+/// the changes to each group, without rescanning the entire group like
+/// [`Stream::aggregate`] does.
+///
+/// The final [`Output`](`Self::Output`) of the aggregator is computed by
+/// applying [`finalize`](`Self::finalize`) to the accumulator.
+///
+/// # Invariants
+///
+/// The accumulator must be zero if and only if the group is empty, i.e.,
+/// the total weight of all values in the group is zero.  Keys whose
+/// accumulator is zero are removed from the output of the aggregation
+/// operator.  For example, the [`WeightedSum`] aggregator keeps track of
+/// the count of values in addition to their sum, so that a non-empty group
+/// whose sum is zero is not confused with an empty group.
+///
+/// See [`Count`], [`WeightedSum`], [`Average`], and [`Variance`] for
+/// ready-made implementations of this trait.
+pub trait InvertibleAggregator<V, R>: Clone + 'static {
+    /// Accumulator type.
+    type Accumulator: DBWeight + GroupValue;
+
+    /// Aggregate type produced by this aggregator.
+    type Output: DBData;
+
+    /// Computes the contribution of `value` with weight `weight` to the
+    /// accumulator.
+    fn contribution(&self, value: &V, weight: &R) -> Self::Accumulator;
+
+    /// Compute the final value of the aggregate from a non-zero
+    /// accumulator.
+    fn finalize(&self, accumulator: &Self::Accumulator) -> Self::Output;
+}
+
 /// Aggregator used internally by [`Stream::aggregate_linear`].  Computes
 /// the total sum of weights.
 #[derive(Clone)]
@@ -155,6 +201,47 @@ where
     }
 }
 
+/// Aggregator used internally by [`Stream::aggregate_invertible`].  Computes
+/// the total sum of weights, which store accumulator values of an
+/// [`InvertibleAggregator`], and finalizes the result.
+#[derive(Clone)]
+struct InvertibleFinalize<A, V, R> {
+    aggregator: A,
+    phantom: PhantomData<(V, R)>,
+}
+
+impl<A, V, R> InvertibleFinalize<A, V, R> {
+    fn new(aggregator: A) -> Self {
+        Self {
+            aggregator,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A, V, R, T> Aggregator<(), T, A::Accumulator> for InvertibleFinalize<A, V, R>
+where
+    A: InvertibleAggregator<V, R>,
+    V: Clone + 'static,
+    R: Clone + 'static,
+    T: Timestamp,
+{
+    type Accumulator = A::Accumulator;
+    type Output = A::Output;
+    type Semigroup = DefaultSemigroup<A::Accumulator>;
+
+    fn aggregate<'s, C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<'s, (), (), T, A::Accumulator>,
+    {
+        WeightedCount.aggregate(cursor)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        self.aggregator.finalize(&accumulator)
+    }
+}
+
 impl<P, Z> Stream<Circuit<P>, Z>
 where
     P: Clone + 'static,
@@ -281,6 +368,69 @@ where
         self.weigh(f).aggregate_generic::<TS, _, _>(WeightedCount)
     }
 
+    /// Incremental aggregation operator for [invertible
+    /// aggregators](`InvertibleAggregator`).
+    ///
+    /// This operator computes the same result as [`Self::aggregate`], but
+    /// updates the aggregate of each key by adding the contributions of the
+    /// new values and subtracting the contributions of deleted values from
+    /// the accumulator.  Its cost is therefore proportional to the size
+    /// of the change rather than the size of the affected groups.
+    #[allow(clippy::type_complexity)]
+    pub fn aggregate_invertible<TS, A>(
+        &self,
+        aggregator: A,
+    ) -> Stream<Circuit<P>, OrdIndexedZSet<Z::Key, A::Output, Z::R>>
+    where
+        TS: DBTimestamp,
+        Z: IndexedZSet,
+        A: InvertibleAggregator<Z::Val, Z::R>,
+        Z::R: ZRingValue,
+    {
+        self.aggregate_invertible_generic::<TS, A, _>(aggregator)
+    }
+
+    /// Like [`Self::aggregate_invertible`], but can return any batch type.
+    pub fn aggregate_invertible_generic<TS, A, O>(&self, aggregator: A) -> Stream<Circuit<P>, O>
+    where
+        TS: DBTimestamp,
+        Z: IndexedZSet,
+        A: InvertibleAggregator<Z::Val, Z::R>,
+        O: Batch<Key = Z::Key, Val = A::Output, Time = ()>,
+        O::R: ZRingValue,
+    {
+        // Compute the contribution of each input batch to the accumulator of
+        // each key.  The result is a Z-set that stores accumulators as weights,
+        // which we aggregate and finalize in the same way as `aggregate_linear`.
+        let contribution = aggregator.clone();
+        let contributions =
+            self.try_sharded_version()
+                .apply_named("InvertibleContribution", move |batch: &Z| {
+                    let mut delta =
+                        <<OrdZSet<Z::Key, A::Accumulator> as Batch>::Builder>::with_capacity(
+                            (),
+                            batch.key_count(),
+                        );
+                    let mut cursor = batch.cursor();
+                    while cursor.key_valid() {
+                        let mut acc = A::Accumulator::zero();
+                        while cursor.val_valid() {
+                            let weight = cursor.weight();
+                            acc += contribution.contribution(cursor.val(), &weight);
+                            cursor.step_val();
+                        }
+                        if !acc.is_zero() {
+                            delta.push((cursor.key().clone(), acc));
+                        }
+                        cursor.step_key();
+                    }
+                    delta.done()
+                });
+        contributions.mark_sharded_if(self);
+
+        contributions.aggregate_generic::<TS, _, O>(InvertibleFinalize::new(aggregator))
+    }
+
     /// Convert indexed Z-set `Z` into a Z-set where the weight of each key
     /// is computed as:
     ///
@@ -584,6 +734,19 @@ where
         self.time = self.time.advance(scope + 1);
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        if self.time == <IT::Time as Timestamp>::clock_start() && self.keys_of_interest.is_empty() {
+            Ok(None)
+        } else {
+            encode_data(&(self.time.clone(), self.keys_of_interest.clone())).map(Some)
+        }
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        (self.time, self.keys_of_interest) = decode_data(state)?;
+        Ok(())
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         let epoch_end = self.time.epoch_end(scope);
 
@@ -683,7 +846,7 @@ mod test {
         algebra::DefaultSemigroup,
         indexed_zset,
         operator::GeneratorNested,
-        operator::{Fold, Min},
+        operator::{Average, Count, Fold, Min, Variance, WeightedSum},
         time::NestedTimestamp32,
         trace::{cursor::Cursor, Batch, BatchReader},
         zset, Circuit, OrdIndexedZSet, OrdZSet, Runtime, Stream,
@@ -786,6 +949,26 @@ mod test {
                     },
                 );
 
+                // Invertible count aggregate must match its linear version.
+                let count_invertible = input
+                    .aggregate_invertible::<NestedTimestamp32, _>(Count)
+                    .gather(0);
+                let count_linear: Stream<_, OrdIndexedZSet<usize, isize, isize>> = input
+                    .aggregate_linear::<NestedTimestamp32, _, _>(|_key: &usize, _val: &isize| 1)
+                    .gather(0);
+
+                count_invertible
+                    .apply2(
+                        &count_linear,
+                        |d1: &OrdIndexedZSet<usize, isize, isize>,
+                         d2: &OrdIndexedZSet<usize, isize, isize>| {
+                            (d1.clone(), d2.clone())
+                        },
+                    )
+                    .inspect(|(d1, d2)| {
+                        assert_eq!(d1, d2);
+                    });
+
                 let min_inc = input.aggregate::<NestedTimestamp32, _>(Min).gather(0);
                 let min_noninc = input
                     .integrate_nested()
@@ -997,4 +1180,87 @@ mod test {
     fn count_test4() {
         count_test(4);
     }
+
+    fn invertible_aggregate_test(workers: usize) {
+        let (mut dbsp, (mut input_handle, count, sum, average, variance)) =
+            Runtime::init_circuit(workers, |circuit| {
+                let (input_stream, input_handle) =
+                    circuit.add_input_indexed_zset::<usize, isize, isize>();
+
+                let count = input_stream.aggregate_invertible::<(), _>(Count).output();
+                let sum = input_stream
+                    .aggregate_invertible::<(), _>(WeightedSum::new(|v: &isize| *v))
+                    .output();
+                let average = input_stream
+                    .aggregate_invertible::<(), _>(Average::new(|v: &isize| *v))
+                    .output();
+                let variance = input_stream
+                    .aggregate_invertible::<(), _>(Variance::new(|v: &isize| *v))
+                    .output();
+
+                (input_handle, count, sum, average, variance)
+            })
+            .unwrap();
+
+        input_handle.append(&mut vec![
+            (1, (2, 1)),
+            (1, (4, 1)),
+            (1, (6, 1)),
+            (1, (8, 1)),
+            (2, (5, 2)),
+        ]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            count.consolidate(),
+            indexed_zset! {1 => {4 => 1}, 2 => {2 => 1}}
+        );
+        assert_eq!(
+            sum.consolidate(),
+            indexed_zset! {1 => {20 => 1}, 2 => {10 => 1}}
+        );
+        assert_eq!(
+            average.consolidate(),
+            indexed_zset! {1 => {5 => 1}, 2 => {5 => 1}}
+        );
+        assert_eq!(
+            variance.consolidate(),
+            indexed_zset! {1 => {5 => 1}, 2 => {0 => 1}}
+        );
+
+        // A non-empty group whose sum is zero is not removed from the output.
+        input_handle.append(&mut vec![(1, (2, -1)), (1, (8, -1)), (3, (0, 1))]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            count.consolidate(),
+            indexed_zset! {1 => {4 => -1, 2 => 1}, 3 => {1 => 1}}
+        );
+        assert_eq!(
+            sum.consolidate(),
+            indexed_zset! {1 => {20 => -1, 10 => 1}, 3 => {0 => 1}}
+        );
+        assert_eq!(average.consolidate(), indexed_zset! {3 => {0 => 1}});
+        assert_eq!(
+            variance.consolidate(),
+            indexed_zset! {1 => {5 => -1, 1 => 1}, 3 => {0 => 1}}
+        );
+
+        input_handle.append(&mut vec![(2, (5, -2))]);
+        dbsp.step().unwrap();
+        assert_eq!(count.consolidate(), indexed_zset! {2 => {2 => -1}});
+        assert_eq!(sum.consolidate(), indexed_zset! {2 => {10 => -1}});
+        assert_eq!(average.consolidate(), indexed_zset! {2 => {5 => -1}});
+        assert_eq!(variance.consolidate(), indexed_zset! {2 => {0 => -1}});
+
+        dbsp.kill().unwrap();
+    }
+
+    #[test]
+    fn invertible_aggregate_test1() {
+        invertible_aggregate_test(1);
+    }
+
+    #[test]
+    fn invertible_aggregate_test4() {
+        invertible_aggregate_test(4);
+    }
 }
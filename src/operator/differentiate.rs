@@ -2,7 +2,7 @@
 
 use crate::{
     algebra::GroupValue,
-    circuit::{Circuit, GlobalNodeId, Stream},
+    circuit::{checkpoint::Checkpointable, Circuit, GlobalNodeId, Stream},
     circuit_cache_key,
     operator::Minus,
     NumEntries,
@@ -15,7 +15,7 @@ circuit_cache_key!(NestedDifferentiateId<C, D>(GlobalNodeId => Stream<C, D>));
 impl<P, D> Stream<Circuit<P>, D>
 where
     P: Clone + 'static,
-    D: SizeOf + NumEntries + GroupValue,
+    D: SizeOf + NumEntries + GroupValue + Checkpointable,
 {
     /// Stream differentiation.
     ///
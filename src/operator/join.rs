@@ -1,22 +1,28 @@
 //! Relational join operator.
 
 use crate::{
-    algebra::{IndexedZSet, Lattice, MulByRef, PartialOrder, ZRingValue, ZSet},
+    algebra::{
+        AddAssignByRef, HasZero, IndexedZSet, Lattice, MulByRef, NegByRef, PartialOrder,
+        ZRingValue, ZSet,
+    },
     circuit::{
+        checkpoint::{decode_data, encode_data, unsupported},
         metadata::{MetaItem, OperatorLocation, OperatorMeta},
-        operator_traits::{BinaryOperator, Operator},
+        operator_traits::{BinaryOperator, Operator, QuaternaryOperator},
         Circuit, GlobalNodeId, Scope, Stream,
     },
     circuit_cache_key,
+    operator::TraceBound,
     time::Timestamp,
     trace::{cursor::Cursor as TraceCursor, Batch, BatchReader, Batcher, Builder, Spine, Trace},
-    DBData, DBTimestamp, OrdIndexedZSet, OrdZSet,
+    DBData, DBTimestamp, Error, OrdIndexedZSet, OrdZSet,
 };
 use size_of::{Context, SizeOf};
 use std::{
+    any::TypeId,
     borrow::Cow,
     cmp::{min, Ordering},
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap},
     iter::once,
     marker::PhantomData,
     mem::{needs_drop, MaybeUninit},
@@ -229,6 +235,40 @@ where
         other: &Stream<Circuit<P>, I2>,
         join_func: F,
     ) -> Stream<Circuit<P>, Z>
+    where
+        TS: DBTimestamp,
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        Z: IndexedZSet<R = I1::R>,
+        Z::R: MulByRef<Output = Z::R>,
+        F: Fn(&I1::Key, &I1::Val, &I2::Val) -> It + Clone + 'static,
+        It: IntoIterator<Item = (Z::Key, Z::Val)> + 'static,
+    {
+        self.join_generic_with_bounds::<TS, _, _, _, _>(
+            other,
+            join_func,
+            TraceBound::new(),
+            TraceBound::new(),
+        )
+    }
+
+    /// Like [`Self::join_generic`], but allows the traces of `self` and
+    /// `other` to discard keys below `self_bound` and `other_bound`
+    /// respectively (see [`Stream::trace_with_bound`]).
+    ///
+    /// This is useful when joining streams indexed by time, e.g., with bounds
+    /// derived from a watermark: keys below the bound are no longer updated
+    /// and do not need to be stored.  Since traces discard keys lazily,
+    /// updates to one of the inputs with keys below the current bound of the
+    /// other input are never joined, whether or not the trace of the other
+    /// input still contains these keys.
+    #[track_caller]
+    pub fn join_generic_with_bounds<TS, I2, F, Z, It>(
+        &self,
+        other: &Stream<Circuit<P>, I2>,
+        join_func: F,
+        self_bound: TraceBound<I1::Key>,
+        other_bound: TraceBound<I1::Key>,
+    ) -> Stream<Circuit<P>, Z>
     where
         TS: DBTimestamp,
         I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
@@ -280,11 +320,16 @@ where
         let left = self.shard();
         let right = other.shard();
 
-        let left_trace = left.trace::<Spine<TS::OrdValBatch<I1::Key, I1::Val, I1::R>>>();
-        let right_trace = right.trace::<Spine<TS::OrdValBatch<I1::Key, I2::Val, I1::R>>>();
+        let left_trace = left.trace_with_bound::<Spine<TS::OrdValBatch<I1::Key, I1::Val, I1::R>>>(
+            self_bound.clone(),
+        );
+        let right_trace = right
+            .trace_with_bound::<Spine<TS::OrdValBatch<I1::Key, I2::Val, I1::R>>>(
+                other_bound.clone(),
+            );
 
         let left = self.circuit().add_binary_operator(
-            JoinTrace::new(join_func.clone(), Location::caller()),
+            JoinTrace::new(join_func.clone(), other_bound, Location::caller()),
             &left,
             &right_trace,
         );
@@ -292,6 +337,7 @@ where
         let right = self.circuit().add_binary_operator(
             JoinTrace::new(
                 move |k: &I1::Key, v2: &I2::Val, v1: &I1::Val| join_func(k, v1, v2),
+                self_bound,
                 Location::caller(),
             ),
             &right,
@@ -331,6 +377,136 @@ where
             )
             .clone()
     }
+
+    /// Incremental left outer join.
+    ///
+    /// Like [`Self::join`], but additionally outputs `join_func(k, v1, None)`
+    /// for each `(k, v1)` in `self` such that key `k` does not occur in
+    /// `other`.
+    ///
+    /// The operator maintains a single trace of each input stream, which is
+    /// shared by the inner join and the [`Unmatched`] operator that computes
+    /// unmatched rows.
+    #[track_caller]
+    pub fn left_join<TS, I2, F, V>(
+        &self,
+        other: &Stream<Circuit<P>, I2>,
+        join_func: F,
+    ) -> Stream<Circuit<P>, OrdZSet<V, I1::R>>
+    where
+        TS: DBTimestamp,
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        F: Fn(&I1::Key, &I1::Val, Option<&I2::Val>) -> V + Clone + 'static,
+        V: DBData,
+    {
+        let join_func_clone = join_func.clone();
+
+        self.join::<TS, _, _, _>(other, move |k, v1, v2| join_func_clone(k, v1, Some(v2)))
+            .plus(&self.unmatched::<TS, _, _, _>(other, move |k, v1| join_func(k, v1, None)))
+    }
+
+    /// Incremental right outer join.
+    ///
+    /// Like [`Self::join`], but additionally outputs `join_func(k, None, v2)`
+    /// for each `(k, v2)` in `other` such that key `k` does not occur in
+    /// `self`.
+    ///
+    /// The operator maintains a single trace of each input stream, which is
+    /// shared by the inner join and the [`Unmatched`] operator that computes
+    /// unmatched rows.
+    #[track_caller]
+    pub fn right_join<TS, I2, F, V>(
+        &self,
+        other: &Stream<Circuit<P>, I2>,
+        join_func: F,
+    ) -> Stream<Circuit<P>, OrdZSet<V, I1::R>>
+    where
+        TS: DBTimestamp,
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        F: Fn(&I1::Key, Option<&I1::Val>, &I2::Val) -> V + Clone + 'static,
+        V: DBData,
+    {
+        let join_func_clone = join_func.clone();
+
+        self.join::<TS, _, _, _>(other, move |k, v1, v2| join_func_clone(k, Some(v1), v2))
+            .plus(&other.unmatched::<TS, _, _, _>(self, move |k, v2| join_func(k, None, v2)))
+    }
+
+    /// Incremental full outer join.
+    ///
+    /// Like [`Self::join`], but additionally outputs `join_func(k, v1, None)`
+    /// for each `(k, v1)` in `self` such that key `k` does not occur in
+    /// `other` and `join_func(k, None, v2)` for each `(k, v2)` in `other`
+    /// such that key `k` does not occur in `self`.
+    ///
+    /// The operator maintains a single trace of each input stream, which is
+    /// shared by the inner join and the [`Unmatched`] operator that computes
+    /// unmatched rows.
+    #[track_caller]
+    pub fn outer_join<TS, I2, F, V>(
+        &self,
+        other: &Stream<Circuit<P>, I2>,
+        join_func: F,
+    ) -> Stream<Circuit<P>, OrdZSet<V, I1::R>>
+    where
+        TS: DBTimestamp,
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        F: Fn(&I1::Key, Option<&I1::Val>, Option<&I2::Val>) -> V + Clone + 'static,
+        V: DBData,
+    {
+        let join_func_left = join_func.clone();
+        let join_func_right = join_func.clone();
+
+        self.join::<TS, _, _, _>(other, move |k, v1, v2| join_func(k, Some(v1), Some(v2)))
+            .sum(&[
+                self.unmatched::<TS, _, _, _>(other, move |k, v1| {
+                    join_func_left(k, Some(v1), None)
+                }),
+                other.unmatched::<TS, _, _, _>(self, move |k, v2| {
+                    join_func_right(k, None, Some(v2))
+                }),
+            ])
+    }
+
+    /// Applies `unmatched_func` to each `(k, v)` in `self` such that key `k`
+    /// does not occur in `other`.
+    ///
+    /// Reads the same traces of `self` and `other` as [`Self::join_generic`]
+    /// (see [`Unmatched`]).
+    #[track_caller]
+    fn unmatched<TS, I2, F, V>(
+        &self,
+        other: &Stream<Circuit<P>, I2>,
+        unmatched_func: F,
+    ) -> Stream<Circuit<P>, OrdZSet<V, I1::R>>
+    where
+        TS: DBTimestamp,
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        F: Fn(&I1::Key, &I1::Val) -> V + 'static,
+        V: DBData,
+    {
+        let stream1 = self.shard();
+        let stream2 = other.shard();
+
+        let trace1 = stream1
+            .trace::<Spine<TS::OrdValBatch<I1::Key, I1::Val, I1::R>>>()
+            .delay_trace();
+        let trace2 = stream2
+            .trace::<Spine<TS::OrdValBatch<I1::Key, I2::Val, I1::R>>>()
+            .delay_trace();
+
+        self.circuit().add_quaternary_operator(
+            Unmatched::new(
+                unmatched_func,
+                self.circuit().root_scope(),
+                Location::caller(),
+            ),
+            &stream1,
+            &stream2,
+            &trace1,
+            &trace2,
+        )
+    }
 }
 
 /// Join two streams of batches.
@@ -531,6 +707,9 @@ where
     Z: IndexedZSet,
 {
     join_func: F,
+    // Keys below this bound are not joined (see
+    // `Stream::join_generic_with_bounds`).
+    lower_key_bound: TraceBound<T::Key>,
     location: &'static Location<'static>,
     // TODO: not needed once timekeeping is handled by the circuit.
     time: T::Time,
@@ -550,9 +729,14 @@ where
     T: BatchReader,
     Z: IndexedZSet,
 {
-    pub fn new(join_func: F, location: &'static Location<'static>) -> Self {
+    pub fn new(
+        join_func: F,
+        lower_key_bound: TraceBound<T::Key>,
+        location: &'static Location<'static>,
+    ) -> Self {
         Self {
             join_func,
+            lower_key_bound,
             location,
             time: <T::Time as Timestamp>::clock_start(),
             output_batchers: HashMap::new(),
@@ -595,6 +779,24 @@ where
         self.time = self.time.advance(scope + 1);
     }
 
+    // Batchers that accumulate future outputs are not serializable.  This is
+    // only a problem for joins inside nested circuits, since top-level joins
+    // never compute updates ahead of time.
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        if !self.output_batchers.is_empty() {
+            Err(unsupported())
+        } else if self.time == <T::Time as Timestamp>::clock_start() {
+            Ok(None)
+        } else {
+            encode_data(&self.time).map(Some)
+        }
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.time = decode_data(state)?;
+        Ok(())
+    }
+
     fn metadata(&self, meta: &mut OperatorMeta) {
         let total_size: usize = self
             .output_batchers
@@ -693,6 +895,12 @@ where
         let mut index_cursor = index.cursor();
         let mut trace_cursor = trace.cursor();
 
+        // Skip keys below the bound, whether or not they have already been
+        // discarded from the trace.
+        if let Some(bound) = self.lower_key_bound.get() {
+            index_cursor.seek_key(&bound);
+        }
+
         while index_cursor.key_valid() && trace_cursor.key_valid() {
             match index_cursor.key().cmp(trace_cursor.key()) {
                 Ordering::Less => index_cursor.seek_key(trace_cursor.key()),
@@ -809,11 +1017,442 @@ where
     }
 }
 
+/// Computes the part of an outer join that contains rows of the first input
+/// without matching keys in the second input.
+///
+/// See [`Stream::left_join`](`crate::circuit::Stream::left_join`).
+///
+/// # Inputs
+///
+/// * `delta1`, `delta2` - changes to the first and second input collections
+///   at the current clock cycle.
+/// * `trace1`, `trace2` - traces of the first and second inputs up to, but
+///   not including, the current clock cycle.  These are the same traces that
+///   are used by the inner join, so the operator does not maintain any state
+///   other than the set of keys it must reevaluate in the future.
+///
+/// # Computing output
+///
+/// For each key `k` the output of the operator at time `t` is equal to
+/// `U(t) = {f(k, v) | v ∈ A(t)[k]}` if the key has no values in `B(t)`
+/// and is empty otherwise, where `A(t)` and `B(t)` are the contents of the
+/// inputs at time `t`.  The operator outputs changes to `U`, which in a
+/// nested circuit are computed by inclusion–exclusion over the times
+/// `t.recede(s)` for all subsets of scopes `s`:
+///
+/// ```text
+/// delta(t) = U(t) - U(t.recede(0)) - U(t.recede(1)) + U(t.recede(0).recede(1))
+/// ```
+///
+/// (in the root scope this is simply `U(t) - U(t.recede(0))`, where
+/// `U(t.recede(0))` is computed from the traces without the current deltas).
+///
+/// If the key is either present or absent in `B` at all of these times, the
+/// change is equal to the change in `delta1` (or zero), and the operator
+/// does not need to scan `trace1`.  Only when the key count in `B` goes
+/// between zero and non-zero, all values of the key in `trace1` are added
+/// to or removed from the output.
+///
+/// # Keys of interest
+///
+/// Inside nested scopes, an update to a key during the current clock epoch
+/// can change the output at future times when the key was updated during
+/// previous epochs, even if there are no new updates to the key at that time.
+/// Similar to [`Stream::aggregate`](`crate::circuit::Stream::aggregate`),
+/// the operator records such keys in `keys_of_interest` and reevaluates them
+/// at the corresponding time.
+pub struct Unmatched<F, I1, I2, T1, T2>
+where
+    T1: BatchReader,
+{
+    unmatched_func: F,
+    location: &'static Location<'static>,
+    root_scope: Scope,
+    // TODO: not needed once timekeeping is handled by the circuit.
+    time: T1::Time,
+    // Keys that may need updating at future times.
+    keys_of_interest: BTreeMap<T1::Time, BTreeSet<T1::Key>>,
+    // True if empty input batches were received at the current clock cycle.
+    empty_input: bool,
+    // True if empty output was produced at the current clock cycle.
+    empty_output: bool,
+    _types: PhantomData<(I1, I2, T1, T2)>,
+}
+
+impl<F, I1, I2, T1, T2> Unmatched<F, I1, I2, T1, T2>
+where
+    T1: BatchReader,
+{
+    pub fn new(unmatched_func: F, root_scope: Scope, location: &'static Location<'static>) -> Self {
+        Self {
+            unmatched_func,
+            location,
+            root_scope,
+            time: <T1::Time as Timestamp>::clock_start(),
+            keys_of_interest: BTreeMap::new(),
+            empty_input: false,
+            empty_output: false,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<F, I1, I2, T1, T2, V> Unmatched<F, I1, I2, T1, T2>
+where
+    F: Fn(&I1::Key, &I1::Val) -> V,
+    I1: IndexedZSet,
+    I1::R: ZRingValue,
+    I2: IndexedZSet<Key = I1::Key, R = I1::R>,
+    T1: Trace<Key = I1::Key, Val = I1::Val, R = I1::R> + Clone,
+    T2: Trace<Key = I1::Key, Val = I2::Val, Time = T1::Time, R = I1::R> + Clone,
+    V: DBData,
+{
+    /// Times at which the output is evaluated to compute the change at the
+    /// current time, along with the sign of each term.
+    ///
+    /// The first element is always the current time.  `None` denotes a time
+    /// before the start of the clock epoch, when the output is empty.
+    fn times(&self) -> Vec<(Option<T1::Time>, bool)> {
+        let num_scopes = self.root_scope as usize + 1;
+
+        (0..1usize << num_scopes)
+            .map(|scopes| {
+                let mut time = Some(self.time.clone());
+                for scope in 0..num_scopes {
+                    if scopes & (1 << scope) != 0 {
+                        // The timestamp of the outermost scope only distinguishes
+                        // the current clock cycle from all earlier ones and
+                        // never underflows.
+                        time = time.filter(|time| {
+                            scope == self.root_scope as usize
+                                || time != &time.epoch_start(scope as Scope)
+                        });
+                        time = time.map(|time| time.recede(scope as Scope));
+                    }
+                }
+                (time, scopes.count_ones() % 2 == 1)
+            })
+            .collect()
+    }
+
+    /// Accumulate the weight of the current value under `cursor` at each of
+    /// `times` in `weights`.
+    fn value_weights<'s, C, V2>(
+        cursor: &mut C,
+        times: &[(Option<T1::Time>, bool)],
+        weights: &mut [I1::R],
+    ) where
+        C: TraceCursor<'s, I1::Key, V2, T1::Time, I1::R>,
+    {
+        cursor.map_times(|ts, w| {
+            for ((time, _), weight) in times.iter().zip(weights.iter_mut()) {
+                if matches!(time, Some(time) if ts.less_equal(time)) {
+                    weight.add_assign_by_ref(w);
+                }
+            }
+        });
+    }
+
+    /// Smallest time after the current time when updates under `cursor` may
+    /// change the output for the current key.
+    fn time_of_interest<'s, C, V2>(&self, cursor: &mut C) -> Option<T1::Time>
+    where
+        C: TraceCursor<'s, I1::Key, V2, T1::Time, I1::R>,
+    {
+        let mut time_of_interest = None;
+
+        while cursor.val_valid() {
+            time_of_interest = cursor.fold_times(time_of_interest, |time_of_interest, time, _| {
+                if !time.less_equal(&self.time) {
+                    let time = self.time.join(time);
+                    match time_of_interest {
+                        Some(time_of_interest) if time_of_interest < time => Some(time_of_interest),
+                        _ => Some(time),
+                    }
+                } else {
+                    time_of_interest
+                }
+            });
+            cursor.step_val();
+        }
+        cursor.rewind_vals();
+
+        time_of_interest
+    }
+
+    /// Compute changes to the output for `key`.
+    #[allow(clippy::too_many_arguments)]
+    fn eval_key(
+        &mut self,
+        key: &I1::Key,
+        times: &[(Option<T1::Time>, bool)],
+        delta1_cursor: &mut I1::Cursor<'_>,
+        delta2_cursor: &mut I2::Cursor<'_>,
+        trace1_cursor: &mut T1::Cursor<'_>,
+        trace2_cursor: &mut T2::Cursor<'_>,
+        output: &mut Vec<(V, I1::R)>,
+    ) {
+        delta1_cursor.seek_key(key);
+        let delta1_valid = delta1_cursor.key_valid() && delta1_cursor.key() == key;
+        delta2_cursor.seek_key(key);
+        let delta2_valid = delta2_cursor.key_valid() && delta2_cursor.key() == key;
+        trace1_cursor.seek_key(key);
+        let trace1_valid = trace1_cursor.key_valid() && trace1_cursor.key() == key;
+        trace2_cursor.seek_key(key);
+        let trace2_valid = trace2_cursor.key_valid() && trace2_cursor.key() == key;
+
+        // Determine if `key` is present in the second input at each of `times`.
+        let mut present = vec![false; times.len()];
+        let mut weights = vec![I1::R::zero(); times.len()];
+
+        while (delta2_valid && delta2_cursor.val_valid())
+            || (trace2_valid && trace2_cursor.val_valid())
+        {
+            weights.fill(I1::R::zero());
+
+            let ordering = match (
+                delta2_valid && delta2_cursor.val_valid(),
+                trace2_valid && trace2_cursor.val_valid(),
+            ) {
+                (true, true) => delta2_cursor.val().cmp(trace2_cursor.val()),
+                (true, false) => Ordering::Less,
+                _ => Ordering::Greater,
+            };
+
+            if ordering != Ordering::Greater {
+                weights[0].add_assign_by_ref(&delta2_cursor.weight());
+                delta2_cursor.step_val();
+            }
+            if ordering != Ordering::Less {
+                Self::value_weights(trace2_cursor, times, &mut weights);
+                trace2_cursor.step_val();
+            }
+
+            for (present, weight) in present.iter_mut().zip(weights.iter()) {
+                *present = *present || !weight.is_zero();
+            }
+        }
+
+        if present.iter().all(|present| *present) {
+            // The key is matched at all times: no changes to the output.
+        } else if present.iter().all(|present| !*present) {
+            // The key is unmatched at all times: output changes to the first
+            // input.
+            if delta1_valid {
+                while delta1_cursor.val_valid() {
+                    output.push((
+                        (self.unmatched_func)(key, delta1_cursor.val()),
+                        delta1_cursor.weight(),
+                    ));
+                    delta1_cursor.step_val();
+                }
+            }
+        } else {
+            // Some of the values of the key in the first input become matched
+            // or unmatched.
+            while (delta1_valid && delta1_cursor.val_valid())
+                || (trace1_valid && trace1_cursor.val_valid())
+            {
+                weights.fill(I1::R::zero());
+
+                let ordering = match (
+                    delta1_valid && delta1_cursor.val_valid(),
+                    trace1_valid && trace1_cursor.val_valid(),
+                ) {
+                    (true, true) => delta1_cursor.val().cmp(trace1_cursor.val()),
+                    (true, false) => Ordering::Less,
+                    _ => Ordering::Greater,
+                };
+
+                let val = if ordering == Ordering::Greater {
+                    trace1_cursor.val()
+                } else {
+                    delta1_cursor.val()
+                };
+                let val = (self.unmatched_func)(key, val);
+
+                if ordering != Ordering::Greater {
+                    weights[0].add_assign_by_ref(&delta1_cursor.weight());
+                    delta1_cursor.step_val();
+                }
+                if ordering != Ordering::Less {
+                    Self::value_weights(trace1_cursor, times, &mut weights);
+                    trace1_cursor.step_val();
+                }
+
+                let mut weight = I1::R::zero();
+                for (((_, negative), present), w) in
+                    times.iter().zip(present.iter()).zip(weights.iter())
+                {
+                    if !present {
+                        if *negative {
+                            weight.add_assign_by_ref(&w.neg_by_ref());
+                        } else {
+                            weight.add_assign_by_ref(w);
+                        }
+                    }
+                }
+
+                if !weight.is_zero() {
+                    output.push((val, weight));
+                }
+            }
+        }
+
+        // Skip this computation when running in the root scope using unit
+        // timestamps (`T1::Time = ()`), where updates never affect the
+        // output at future times.
+        if TypeId::of::<T1::Time>() != TypeId::of::<()>() {
+            let mut time_of_interest = None;
+
+            if trace1_valid {
+                trace1_cursor.rewind_vals();
+                time_of_interest = self.time_of_interest(trace1_cursor);
+            }
+            if trace2_valid {
+                trace2_cursor.rewind_vals();
+                time_of_interest = match (time_of_interest, self.time_of_interest(trace2_cursor)) {
+                    (Some(time1), Some(time2)) => Some(min(time1, time2)),
+                    (time1, time2) => time1.or(time2),
+                };
+            }
+
+            if let Some(time) = time_of_interest {
+                self.keys_of_interest
+                    .entry(time)
+                    .or_default()
+                    .insert(key.clone());
+            }
+        }
+    }
+}
+
+impl<F, I1, I2, T1, T2> Operator for Unmatched<F, I1, I2, T1, T2>
+where
+    F: 'static,
+    I1: 'static,
+    I2: 'static,
+    T1: BatchReader + 'static,
+    T2: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("Unmatched")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn clock_start(&mut self, scope: Scope) {
+        if scope == 0 {
+            self.empty_input = false;
+            self.empty_output = false;
+        }
+    }
+
+    fn clock_end(&mut self, scope: Scope) {
+        debug_assert!(self
+            .keys_of_interest
+            .keys()
+            .all(|ts| !ts.less_equal(&self.time.epoch_end(scope))));
+
+        self.time = self.time.advance(scope + 1);
+    }
+
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        if self.time == <T1::Time as Timestamp>::clock_start() && self.keys_of_interest.is_empty() {
+            Ok(None)
+        } else {
+            encode_data(&(self.time.clone(), self.keys_of_interest.clone())).map(Some)
+        }
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        (self.time, self.keys_of_interest) = decode_data(state)?;
+        Ok(())
+    }
+
+    fn fixedpoint(&self, scope: Scope) -> bool {
+        let epoch_end = self.time.epoch_end(scope);
+
+        self.empty_input
+            && self.empty_output
+            && self
+                .keys_of_interest
+                .keys()
+                .all(|ts| !ts.less_equal(&epoch_end))
+    }
+}
+
+impl<F, I1, I2, T1, T2, V> QuaternaryOperator<I1, I2, T1, T2, OrdZSet<V, I1::R>>
+    for Unmatched<F, I1, I2, T1, T2>
+where
+    F: Fn(&I1::Key, &I1::Val) -> V + 'static,
+    I1: IndexedZSet,
+    I1::R: ZRingValue,
+    I2: IndexedZSet<Key = I1::Key, R = I1::R>,
+    T1: Trace<Key = I1::Key, Val = I1::Val, R = I1::R> + Clone,
+    T2: Trace<Key = I1::Key, Val = I2::Val, Time = T1::Time, R = I1::R> + Clone,
+    V: DBData,
+{
+    fn eval<'a>(
+        &mut self,
+        delta1: Cow<'a, I1>,
+        delta2: Cow<'a, I2>,
+        trace1: Cow<'a, T1>,
+        trace2: Cow<'a, T2>,
+    ) -> OrdZSet<V, I1::R> {
+        self.empty_input = delta1.is_empty() && delta2.is_empty();
+
+        let times = self.times();
+
+        // Keys in both deltas and previously encountered keys that may affect
+        // the output at the current time.
+        let mut keys = self.keys_of_interest.remove(&self.time).unwrap_or_default();
+
+        let mut delta1_cursor = delta1.cursor();
+        while delta1_cursor.key_valid() {
+            keys.insert(delta1_cursor.key().clone());
+            delta1_cursor.step_key();
+        }
+        delta1_cursor.rewind_keys();
+
+        let mut delta2_cursor = delta2.cursor();
+        while delta2_cursor.key_valid() {
+            keys.insert(delta2_cursor.key().clone());
+            delta2_cursor.step_key();
+        }
+        delta2_cursor.rewind_keys();
+
+        let mut trace1_cursor = trace1.cursor();
+        let mut trace2_cursor = trace2.cursor();
+
+        let mut output = Vec::new();
+        for key in keys.iter() {
+            self.eval_key(
+                key,
+                &times,
+                &mut delta1_cursor,
+                &mut delta2_cursor,
+                &mut trace1_cursor,
+                &mut trace2_cursor,
+                &mut output,
+            );
+        }
+
+        let result = OrdZSet::from_keys((), output);
+        self.empty_output = result.is_empty();
+        self.time = self.time.advance(0);
+
+        result
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
         indexed_zset,
-        operator::{DelayedFeedback, FilterMap, Generator},
+        operator::{DelayedFeedback, FilterMap, Generator, TraceBound},
         time::{NestedTimestamp32, Product},
         trace::{
             ord::{OrdIndexedZSet, OrdZSet},
@@ -825,6 +1464,7 @@ mod test {
     use std::{
         fmt::{Display, Formatter},
         hash::Hash,
+        iter::once,
         sync::{Arc, Mutex},
         vec,
     };
@@ -1336,4 +1976,280 @@ mod test {
 
         circuit.kill().unwrap();
     }
+
+    // Keys below the bound of one input are never joined with updates to the
+    // other input, whether or not they have been discarded from its trace.
+    #[test]
+    fn join_with_bounds_test() {
+        let output = Arc::new(Mutex::new(OrdZSet::empty(())));
+        let output_clone = output.clone();
+
+        let (circuit, (mut input1, mut input2, bound1, bound2)) = Circuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+
+            let bound1 = TraceBound::new();
+            let bound2 = TraceBound::new();
+
+            input1
+                .join_generic_with_bounds::<(), _, _, OrdZSet<(usize, usize, usize), isize>, _>(
+                    &input2,
+                    |k, v1, v2| once(((*k, *v1, *v2), ())),
+                    bound1.clone(),
+                    bound2.clone(),
+                )
+                .inspect(move |batch| *output_clone.lock().unwrap() = batch.clone());
+
+            (input_handle1, input_handle2, bound1, bound2)
+        })
+        .unwrap();
+
+        input1.append(&mut vec![(1, (1, 1)), (5, (1, 1))]);
+        input2.append(&mut vec![(1, (2, 1)), (5, (2, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            &*output.lock().unwrap(),
+            &zset! { (1, 1, 2) => 1, (5, 1, 2) => 1 }
+        );
+
+        // New updates to `input1` are not joined with keys below the bound of
+        // `input2` and vice versa.
+        bound1.set(5);
+        bound2.set(5);
+        input1.append(&mut vec![(1, (3, 1)), (5, (3, 1))]);
+        input2.append(&mut vec![(1, (4, 1)), (5, (4, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            &*output.lock().unwrap(),
+            &zset! { (5, 1, 4) => 1, (5, 3, 2) => 1, (5, 3, 4) => 1 }
+        );
+    }
+
+    type LeftJoinOutput = OrdZSet<(usize, usize, Option<usize>), isize>;
+    type RightJoinOutput = OrdZSet<(usize, Option<usize>, usize), isize>;
+    type OuterJoinOutput = OrdZSet<(usize, Option<usize>, Option<usize>), isize>;
+
+    #[test]
+    fn outer_join_test() {
+        let output = Arc::new(Mutex::new((
+            LeftJoinOutput::empty(()),
+            RightJoinOutput::empty(()),
+            OuterJoinOutput::empty(()),
+        )));
+        let output_clone = output.clone();
+
+        let (mut circuit, (mut input1, mut input2)) = Runtime::init_circuit(4, move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+
+            let output = output_clone.clone();
+            input1
+                .left_join::<(), _, _, _>(&input2, |&k, &v1, v2| (k, v1, v2.cloned()))
+                .gather(0)
+                .inspect(move |batch| {
+                    if Runtime::worker_index() == 0 {
+                        output.lock().unwrap().0 = batch.clone();
+                    }
+                });
+
+            let output = output_clone.clone();
+            input1
+                .right_join::<(), _, _, _>(&input2, |&k, v1, &v2| (k, v1.cloned(), v2))
+                .gather(0)
+                .inspect(move |batch| {
+                    if Runtime::worker_index() == 0 {
+                        output.lock().unwrap().1 = batch.clone();
+                    }
+                });
+
+            let output = output_clone.clone();
+            input1
+                .outer_join::<(), _, _, _>(&input2, |&k, v1, v2| (k, v1.cloned(), v2.cloned()))
+                .gather(0)
+                .inspect(move |batch| {
+                    if Runtime::worker_index() == 0 {
+                        output.lock().unwrap().2 = batch.clone();
+                    }
+                });
+
+            (input_handle1, input_handle2)
+        })
+        .unwrap();
+
+        input1.append(&mut vec![(1, (0, 1)), (2, (0, 1))]);
+        input2.append(&mut vec![(2, (10, 1)), (3, (20, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            *output.lock().unwrap(),
+            (
+                zset! { (1, 0, None) => 1, (2, 0, Some(10)) => 1 },
+                zset! { (2, Some(0), 10) => 1, (3, None, 20) => 1 },
+                zset! { (1, Some(0), None) => 1, (2, Some(0), Some(10)) => 1, (3, None, Some(20)) => 1 },
+            )
+        );
+
+        input1.append(&mut vec![(3, (1, 1))]);
+        input2.append(&mut vec![(1, (11, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            *output.lock().unwrap(),
+            (
+                zset! { (1, 0, None) => -1, (1, 0, Some(11)) => 1, (3, 1, Some(20)) => 1 },
+                zset! { (1, Some(0), 11) => 1, (3, None, 20) => -1, (3, Some(1), 20) => 1 },
+                zset! { (1, Some(0), None) => -1, (1, Some(0), Some(11)) => 1, (3, None, Some(20)) => -1, (3, Some(1), Some(20)) => 1 },
+            )
+        );
+
+        input2.append(&mut vec![(2, (10, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            *output.lock().unwrap(),
+            (
+                zset! { (2, 0, Some(10)) => -1, (2, 0, None) => 1 },
+                zset! { (2, Some(0), 10) => -1 },
+                zset! { (2, Some(0), Some(10)) => -1, (2, Some(0), None) => 1 },
+            )
+        );
+
+        input1.append(&mut vec![(1, (5, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            *output.lock().unwrap(),
+            (
+                zset! { (1, 5, Some(11)) => 1 },
+                zset! { (1, Some(5), 11) => 1 },
+                zset! { (1, Some(5), Some(11)) => 1 },
+            )
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    // Check that outer join evaluated in a nested circuit produces the same
+    // changes as at the top level.
+    #[test]
+    fn outer_join_nested_test() {
+        let (mut circuit, (input1, input2)) = Runtime::init_circuit(4, move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+
+            let expected = input1
+                .outer_join::<(), _, _, _>(&input2, |&k, v1, v2| (k, v1.cloned(), v2.cloned()));
+
+            let actual = circuit
+                .fixedpoint(|child| {
+                    let input1 = input1.delta0(child);
+                    let input2 = input2.delta0(child);
+
+                    Ok(input1
+                        .outer_join::<NestedTimestamp32, _, _, _>(&input2, |&k, v1, v2| {
+                            (k, v1.cloned(), v2.cloned())
+                        })
+                        .integrate_trace()
+                        .export())
+                })
+                .unwrap()
+                .consolidate();
+
+            expected.apply2(&actual, |expected: &OuterJoinOutput, actual| {
+                assert_eq!(expected, actual)
+            });
+
+            (input_handle1, input_handle2)
+        })
+        .unwrap();
+
+        for step in 0..20 {
+            for i in 0..5 {
+                let weight = if (step + i) % 3 == 0 { -1 } else { 1 };
+                input1.push((step * 7 + i) % 10, (i % 3, weight));
+                input2.push((step * 3 + i) % 10, (i % 2, weight));
+            }
+            circuit.step().unwrap();
+        }
+
+        circuit.kill().unwrap();
+    }
+
+    // Outer join inside a recursive computation, where both inputs change
+    // at multiple nested timestamps.  Compares the output with an equivalent
+    // expression built from inner joins.
+    #[test]
+    fn outer_join_recursive_test() {
+        let (mut circuit, input) = Runtime::init_circuit(4, move |circuit| {
+            let (edges, input_handle) = circuit.add_input_zset::<(usize, usize), isize>();
+
+            circuit
+                .recursive(|child, paths: Stream<_, OrdZSet<(usize, usize), isize>>| {
+                    let edges = edges.delta0(child);
+
+                    let by_dst = paths.index_with(|&(from, to)| (to, from));
+                    let by_src = paths.index();
+
+                    let actual = by_dst
+                        .outer_join::<NestedTimestamp32, _, _, _>(&by_src, |&k, v1, v2| {
+                            (k, v1.cloned(), v2.cloned())
+                        });
+
+                    // Keys of each input, with weight 1.
+                    let dst_keys = by_dst
+                        .map(|(&k, _)| k)
+                        .distinct_trace()
+                        .index_with(|&k| (k, ()));
+                    let src_keys = by_src
+                        .map(|(&k, _)| k)
+                        .distinct_trace()
+                        .index_with(|&k| (k, ()));
+
+                    let expected = by_dst
+                        .join::<NestedTimestamp32, _, _, _>(&by_src, |&k, &v1, &v2| {
+                            (k, Some(v1), Some(v2))
+                        })
+                        .plus(&by_dst.map(|(&k, &v1)| (k, Some(v1), None)))
+                        .minus(
+                            &by_dst.join::<NestedTimestamp32, _, _, _>(&src_keys, |&k, &v1, _| {
+                                (k, Some(v1), None)
+                            }),
+                        )
+                        .plus(&by_src.map(|(&k, &v2)| (k, None, Some(v2))))
+                        .minus(
+                            &by_src.join::<NestedTimestamp32, _, _, _>(&dst_keys, |&k, &v2, _| {
+                                (k, None, Some(v2))
+                            }),
+                        );
+
+                    actual
+                        .minus(&expected)
+                        .shard()
+                        .inspect(|diff: &OuterJoinOutput| assert!(diff.is_empty()));
+
+                    Ok(edges.plus(
+                        &by_dst.join::<NestedTimestamp32, _, _, _>(&by_src, |_via, &from, &to| {
+                            (from, to)
+                        }),
+                    ))
+                })
+                .unwrap();
+
+            input_handle
+        })
+        .unwrap();
+
+        let mut edges = Vec::new();
+        for step in 0..30 {
+            // Insert a new edge and delete an old one at each step.
+            let edge = ((step * 7) % 11, (step * 5 + 3) % 11);
+            if !edges.contains(&edge) {
+                input.push(edge, 1);
+                edges.push(edge);
+            }
+            if step % 3 == 2 {
+                let edge = edges.remove((step * 13) % edges.len());
+                input.push(edge, -1);
+            }
+            circuit.step().unwrap();
+        }
+
+        circuit.kill().unwrap();
+    }
 }
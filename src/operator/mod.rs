@@ -30,12 +30,16 @@ mod semijoin;
 mod stream_fold;
 mod sum;
 pub mod time_series;
+mod topk;
 mod trace;
 mod z1;
 
 #[cfg(feature = "with-csv")]
 pub use self::csv::CsvSource;
-pub use aggregate::{Aggregator, Avg, Fold, Max, MaxSemigroup, Min, MinSemigroup};
+pub use aggregate::{
+    Aggregator, Average, Avg, Count, Fold, InvertibleAggregator, Max, MaxSemigroup, Min,
+    MinSemigroup, Var, Variance, WeightedSum,
+};
 pub use apply::Apply;
 pub use condition::Condition;
 pub use delta0::Delta0;
@@ -46,10 +50,11 @@ pub use index::Index;
 use input::Mailbox;
 pub use input::{CollectionHandle, InputHandle, UpsertHandle};
 pub use inspect::Inspect;
-pub use join::Join;
+pub use join::{Join, Unmatched};
 pub use join_range::StreamJoinRange;
 pub use neg::UnaryMinus;
 pub use output::OutputHandle;
 pub use plus::{Minus, Plus};
 pub use sum::Sum;
+pub use trace::TraceBound;
 pub use z1::{DelayedFeedback, DelayedNestedFeedback, Z1Nested, Z1};
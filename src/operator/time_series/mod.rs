@@ -4,6 +4,7 @@ mod range;
 mod rolling_aggregate;
 mod watermark;
 mod window;
+mod window_aggregate;
 
 pub use partitioned::{
     OrdPartitionedIndexedZSet, PartitionCursor, PartitionedBatch, PartitionedBatchReader,
@@ -120,6 +120,10 @@ where
         self.cursor.step_val();
     }
 
+    fn step_val_reverse(&mut self) {
+        unimplemented!()
+    }
+
     fn seek_val(&mut self, _val: &V) {
         unimplemented!()
     }
@@ -139,6 +143,10 @@ where
     fn rewind_vals(&mut self) {
         unimplemented!()
     }
+
+    fn fast_forward_vals(&mut self) {
+        unimplemented!()
+    }
 }
 
 pub type OrdPartitionedIndexedZSet<PK, TS, V, R> = OrdIndexedZSet<PK, (TS, V), R>;
@@ -10,7 +10,7 @@ use crate::{
         time_series::{
             PartitionCursor, PartitionedBatch, PartitionedBatchReader, PartitionedIndexedZSet,
         },
-        trace::{DelayedTraceId, IntegrateTraceId, UntimedTraceAppend, Z1Trace},
+        trace::{DelayedTraceId, IntegrateTraceId, TraceBounds, UntimedTraceAppend, Z1Trace},
         Aggregator,
     },
     trace::{Builder, Cursor, Spine},
@@ -170,9 +170,12 @@ where
                             //                                                    └────────────────────────────────┤Z1Trace│◄───────────┘
                             //                                                          output_trace_delayed       └───────┘
                             // ```
-                            let (output_trace_delayed, z1feedback) = circuit.add_feedback(
-                                <Z1Trace<Spine<O>>>::new(false, self.circuit().root_scope()),
-                            );
+                            let (output_trace_delayed, z1feedback) =
+                                circuit.add_feedback(<Z1Trace<Spine<O>>>::new(
+                                    false,
+                                    self.circuit().root_scope(),
+                                    TraceBounds::new(),
+                                ));
                             output_trace_delayed.mark_sharded();
 
                             let output = circuit
@@ -283,6 +286,10 @@ where
         panic!("")
     }
 
+    fn step_val_reverse(&mut self) {
+        panic!("")
+    }
+
     fn seek_val(&mut self, _val: &V) {}
 
     fn seek_val_with<P>(&mut self, _predicate: P)
@@ -294,6 +301,8 @@ where
     fn rewind_keys(&mut self) {}
 
     fn rewind_vals(&mut self) {}
+
+    fn fast_forward_vals(&mut self) {}
 }
 
 /// Ternary operator that implements the internals of
@@ -7,7 +7,7 @@ use crate::{
     },
     circuit_cache_key,
     operator::{
-        trace::{DelayedTraceId, IntegrateTraceId, UntimedTraceAppend, Z1Trace},
+        trace::{DelayedTraceId, IntegrateTraceId, TraceBounds, UntimedTraceAppend, Z1Trace},
         Aggregator,
     },
     trace::{Batch, BatchReader, Builder, Spine},
@@ -107,6 +107,7 @@ where
                             circuit.add_feedback(<Z1Trace<Spine<O>>>::new(
                                 false,
                                 self.circuit().root_scope(),
+                                TraceBounds::new(),
                             ));
 
                         let output = circuit.add_ternary_operator(
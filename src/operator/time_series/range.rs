@@ -265,6 +265,10 @@ where
         self.cursor.step_val();
     }
 
+    fn step_val_reverse(&mut self) {
+        self.cursor.step_val_reverse();
+    }
+
     fn seek_val(&mut self, val: &V) {
         self.cursor.seek_val(val)
     }
@@ -283,6 +287,10 @@ where
     fn rewind_vals(&mut self) {
         self.cursor.rewind_vals()
     }
+
+    fn fast_forward_vals(&mut self) {
+        self.cursor.fast_forward_vals()
+    }
 }
 
 #[cfg(test)]
@@ -11,7 +11,7 @@ use crate::{
             OrdPartitionedIndexedZSet, PartitionCursor, PartitionedBatchReader,
             PartitionedIndexedZSet,
         },
-        trace::{DelayedTraceId, IntegrateTraceId, UntimedTraceAppend, Z1Trace},
+        trace::{DelayedTraceId, IntegrateTraceId, TraceBounds, UntimedTraceAppend, Z1Trace},
         Aggregator,
     },
     trace::{Builder, Cursor, Spine},
@@ -167,8 +167,9 @@ impl<B> Stream<Circuit<()>, B> {
                 .integrate_trace();
             let input_trace = stream.integrate_trace();
 
-            let (output_trace_delayed, z1feedback) =
-                circuit.add_feedback(<Z1Trace<Spine<O>>>::new(false, self.circuit().root_scope()));
+            let (output_trace_delayed, z1feedback) = circuit.add_feedback(
+                <Z1Trace<Spine<O>>>::new(false, self.circuit().root_scope(), TraceBounds::new()),
+            );
             output_trace_delayed.mark_sharded();
 
             let output = circuit
@@ -1,4 +1,5 @@
 use crate::{
+    circuit::checkpoint::Checkpointable,
     trace::{cursor::Cursor, BatchReader},
     Circuit, NumEntries, Runtime, Stream,
 };
@@ -32,7 +33,7 @@ where
     pub fn watermark_monotonic<W, TS>(&self, watermark_func: W) -> Stream<Circuit<()>, TS>
     where
         W: Fn(&B::Key) -> TS + 'static,
-        TS: Ord + Clone + Default + SizeOf + NumEntries + Send + 'static,
+        TS: Ord + Clone + Default + SizeOf + NumEntries + Send + Checkpointable + 'static,
     {
         let local_watermark = self.stream_fold(TS::default(), move |old_watermark, batch| {
             let mut cursor = batch.cursor();
@@ -74,7 +75,17 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::Runtime;
+    use crate::{
+        operator::{FilterMap, TraceBound},
+        trace::{cursor::Cursor, BatchReader, Spine},
+        OrdIndexedZSet, OrdZSet, Runtime,
+    };
+    use std::{
+        cmp::min,
+        collections::BTreeMap,
+        iter::once,
+        sync::{Arc, Mutex},
+    };
 
     fn test_watermark_monotonic(workers: usize) {
         let mut expected_watermarks = vec![115, 115, 125, 145].into_iter();
@@ -116,4 +127,190 @@ mod tests {
     fn test_watermark_monotonic4() {
         test_watermark_monotonic(4);
     }
+
+    const LATENESS: u64 = 10;
+
+    // Add one key per step to a trace that discards keys more than
+    // `LATENESS` behind the watermark.  Check that the trace retains all keys
+    // within `LATENESS` of the watermark, while its size stays bounded.
+    fn test_integrate_trace_with_watermark(workers: usize) {
+        // Number of keys within the lateness bound and total trace size,
+        // indexed by worker.
+        let stats = Arc::new(Mutex::new(BTreeMap::new()));
+        let stats_clone = stats.clone();
+
+        let (mut dbsp, input_handle) = Runtime::init_circuit(workers, move |circuit| {
+            let stats = stats_clone.clone();
+
+            let (stream, handle) = circuit.add_input_zset::<u64, isize>();
+            let watermark = stream.watermark_monotonic(|ts| *ts);
+            stream
+                .integrate_trace_with_watermark(&watermark, |ts| ts.saturating_sub(LATENESS))
+                .apply(move |trace| {
+                    let mut cursor = trace.cursor();
+                    let mut recent_keys = 0;
+                    if let Some(last_key) = cursor.last_key().cloned() {
+                        cursor.seek_key(&last_key.saturating_sub(LATENESS));
+                        while cursor.key_valid() {
+                            recent_keys += 1;
+                            cursor.step_key();
+                        }
+                    }
+                    stats
+                        .lock()
+                        .unwrap()
+                        .insert(Runtime::worker_index(), (recent_keys, trace.len()));
+                });
+            handle
+        })
+        .unwrap();
+
+        for ts in 0..1000 {
+            input_handle.push(ts, 1);
+            dbsp.step().unwrap();
+
+            let stats = stats.lock().unwrap();
+            let (recent_keys, len) = stats
+                .values()
+                .fold((0, 0), |(k1, l1), (k2, l2)| (k1 + k2, l1 + l2));
+            if workers == 1 {
+                assert_eq!(recent_keys, min(ts, LATENESS) + 1);
+            }
+            assert!(len < 100, "trace size {len} at step {ts}");
+        }
+
+        dbsp.kill().unwrap();
+    }
+
+    #[test]
+    fn test_integrate_trace_with_watermark1() {
+        test_integrate_trace_with_watermark(1);
+    }
+
+    #[test]
+    fn test_integrate_trace_with_watermark4() {
+        test_integrate_trace_with_watermark(4);
+    }
+
+    // A consumer of the trace that does not attach a bound prevents the trace
+    // from discarding any keys.
+    #[test]
+    fn test_shared_trace_with_watermark() {
+        let sizes = Arc::new(Mutex::new(BTreeMap::new()));
+        let sizes_clone = sizes.clone();
+
+        let (mut dbsp, input_handle) = Runtime::init_circuit(4, move |circuit| {
+            let sizes = sizes_clone.clone();
+
+            let (stream, handle) = circuit.add_input_zset::<u64, isize>();
+            let watermark = stream.watermark_monotonic(|ts| *ts);
+            stream.integrate_trace_with_watermark(&watermark, |ts| ts.saturating_sub(LATENESS));
+            stream.integrate_trace().apply(move |trace| {
+                sizes
+                    .lock()
+                    .unwrap()
+                    .insert(Runtime::worker_index(), trace.len());
+            });
+            handle
+        })
+        .unwrap();
+
+        for ts in 0..1000 {
+            input_handle.push(ts, 1);
+            dbsp.step().unwrap();
+
+            let len: usize = sizes.lock().unwrap().values().sum();
+            assert_eq!(len as u64, ts + 1);
+        }
+
+        dbsp.kill().unwrap();
+    }
+
+    // Join two time series with bounds derived from their watermarks.  Records
+    // arrive at most one step late, within the lateness bound, so the output
+    // must match the output of the unbounded join, while the traces of the
+    // bounded join stay small.
+    fn test_join_with_watermark(workers: usize) {
+        let sizes = Arc::new(Mutex::new(BTreeMap::new()));
+        let sizes_clone = sizes.clone();
+
+        let (mut dbsp, (input1, input2)) = Runtime::init_circuit(workers, move |circuit| {
+            let sizes = sizes_clone.clone();
+
+            let (stream1, handle1) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (stream2, handle2) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            let bound1 = TraceBound::new();
+            let bound1_clone = bound1.clone();
+            stream1
+                .watermark_monotonic(|ts| *ts)
+                .inspect(move |watermark| bound1_clone.set(watermark.saturating_sub(LATENESS)));
+
+            let bound2 = TraceBound::new();
+            let bound2_clone = bound2.clone();
+            stream2
+                .watermark_monotonic(|ts| *ts)
+                .inspect(move |watermark| bound2_clone.set(watermark.saturating_sub(LATENESS)));
+
+            let bounded = stream1
+                .join_generic_with_bounds::<(), _, _, OrdZSet<(u64, u64, u64), isize>, _>(
+                    &stream2,
+                    |ts, v1, v2| once(((*ts, *v1, *v2), ())),
+                    bound1.clone(),
+                    bound2,
+                );
+
+            // Join copies of the input streams, so that the unbounded join
+            // does not share traces with the bounded one.
+            let expected = stream1
+                .map_index(|(ts, v)| (*ts, *v))
+                .join::<(), _, _, _>(&stream2.map_index(|(ts, v)| (*ts, *v)), |ts, v1, v2| {
+                    (*ts, *v1, *v2)
+                });
+
+            bounded
+                .integrate()
+                .apply2(&expected.integrate(), |bounded, expected| {
+                    assert_eq!(bounded, expected)
+                });
+
+            // Attaching the same bound again returns the bounded trace.
+            stream1
+                .shard()
+                .trace_with_bound::<Spine<OrdIndexedZSet<u64, u64, isize>>>(bound1)
+                .apply(move |trace| {
+                    sizes
+                        .lock()
+                        .unwrap()
+                        .insert(Runtime::worker_index(), trace.len());
+                });
+
+            (handle1, handle2)
+        })
+        .unwrap();
+
+        for ts in 0..1000 {
+            input1.push(ts, (ts, 1));
+            input2.push(ts, (ts + 1, 1));
+            if ts > 0 {
+                input2.push(ts - 1, (ts, 1));
+            }
+            dbsp.step().unwrap();
+
+            let len: usize = sizes.lock().unwrap().values().sum();
+            assert!(len < 100, "trace size {len} at step {ts}");
+        }
+
+        dbsp.kill().unwrap();
+    }
+
+    #[test]
+    fn test_join_with_watermark1() {
+        test_join_with_watermark(1);
+    }
+
+    #[test]
+    fn test_join_with_watermark4() {
+        test_join_with_watermark(4);
+    }
 }
@@ -3,10 +3,13 @@
 use crate::{
     algebra::{IndexedZSet, NegByRef},
     circuit::{
+        checkpoint::{decode_data, encode_data},
         operator_traits::{Operator, TernaryOperator},
         Circuit, OwnershipPreference, Scope, Stream,
     },
+    operator::TraceBound,
     trace::{cursor::Cursor, ord::OrdZSet, Batch, BatchReader, Spine},
+    Error,
 };
 use std::{borrow::Cow, cmp::max, marker::PhantomData};
 
@@ -51,6 +54,11 @@ where
     /// earlier inputs that fall within the new range, but not the previous
     /// range.
     ///
+    /// Since the lower bound of the window only moves forward, values that
+    /// fall behind it are never accessed by the operator again.  The operator
+    /// allows the trace of the input stream to discard them (see
+    /// [`Stream::integrate_trace_with_bound`]).
+    ///
     /// # Circuit
     ///
     /// ```text
@@ -70,7 +78,15 @@ where
         &self,
         bounds: &Stream<Circuit<P>, (B::Key, B::Key)>,
     ) -> Stream<Circuit<P>, OrdZSet<B::Val, B::R>> {
-        let trace = self.integrate_trace().delay_trace();
+        // The operator reads values starting from the lower bound of the
+        // window at the previous clock cycle.  The delayed trace it reads at
+        // the next clock cycle is truncated at the end of the current cycle,
+        // so the current lower bound is safe to use.
+        let bound = TraceBound::new();
+        let bound_clone = bound.clone();
+        bounds.inspect(move |(start, _end)| bound_clone.set(start.clone()));
+
+        let trace = self.integrate_trace_with_bound(bound).delay_trace();
         self.circuit()
             .add_ternary_operator(<Window<B>>::new(), &trace, self, bounds)
     }
@@ -110,6 +126,18 @@ where
         self.window = None;
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        match &self.window {
+            None => Ok(None),
+            Some(window) => encode_data(window).map(Some),
+        }
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.window = Some(decode_data(state)?);
+        Ok(())
+    }
+
     fn fixedpoint(&self, _scope: Scope) -> bool {
         // Windows can currently only be used in top-level circuits.
         // Do we have meaningful examples of using windows inside nested scopes?
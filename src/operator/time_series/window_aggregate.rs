@@ -0,0 +1,1006 @@
+//! Tumbling, hopping, and session window aggregates.
+//!
+//! Operators in this module take a partitioned time series stream and
+//! aggregate its contents over time windows in each partition.  Their outputs
+//! are indexed Z-sets that map `(window_start, window_end, key)` tuples to
+//! aggregates, where `[window_start..window_end)` is a right-open time
+//! range and `key` is the partition key.  Empty windows are not included in
+//! the output.
+//!
+//! All operators are incremental: they compute aggregates using the radix tree
+//! over each partition (see
+//! [`partitioned_tree_aggregate`](`crate::Stream::partitioned_tree_aggregate`))
+//! and only update windows affected by the changes in the input stream.  In
+//! particular, a data point that arrives late updates the aggregates of
+//! windows it belongs to, no matter how old these windows are.
+
+use crate::{
+    algebra::{HasOne, HasZero, IndexedZSet, ZRingValue},
+    circuit::{
+        operator_traits::{Operator, QuaternaryOperator, TernaryOperator},
+        OwnershipPreference, Scope,
+    },
+    operator::{
+        time_series::{
+            radix_tree::{PartitionedRadixTreeReader, RadixTreeCursor},
+            range::{Range, RangeCursor, Ranges},
+            OrdPartitionedIndexedZSet, PartitionCursor, PartitionedBatchReader,
+            PartitionedIndexedZSet,
+        },
+        trace::{DelayedTraceId, IntegrateTraceId, TraceBounds, UntimedTraceAppend, Z1Trace},
+        Aggregator, FilterMap,
+    },
+    trace::{Builder, Cursor, Spine},
+    Circuit, DBData, OrdIndexedZSet, Stream,
+};
+use num::PrimInt;
+use std::{
+    borrow::Cow,
+    cmp::{max, min},
+    marker::PhantomData,
+    ops::Neg,
+};
+
+/// Stream of window aggregates indexed by `(window_start, window_end, key)`.
+pub type OrdWindowAggregateStream<PK, TS, A, R> =
+    Stream<Circuit<()>, OrdIndexedZSet<(TS, TS, PK), A, R>>;
+
+/// Internal representation of window aggregates: a partitioned collection
+/// indexed by one of the window bounds, which stores the other bound along
+/// with the aggregate.
+type OrdPartitionedWindows<PK, TS, A, R> = OrdPartitionedIndexedZSet<PK, TS, (TS, A), R>;
+
+type OrdPartitionedWindowsStream<PK, TS, A, R> =
+    Stream<Circuit<()>, OrdPartitionedWindows<PK, TS, A, R>>;
+
+impl<B> Stream<Circuit<()>, B> {
+    /// Tumbling window aggregate of a partitioned stream.
+    ///
+    /// Splits the timeline into non-overlapping windows of size `size`, i.e.,
+    /// `[0..size)`, `[size..2*size)`, etc.  For each partition in the input
+    /// stream, applies `aggregator` to the contents of each non-empty window.
+    ///
+    /// This is a special case of [`Self::hopping_aggregate`] with
+    /// `hop == size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is not positive.
+    pub fn tumbling_aggregate<TS, V, Agg>(
+        &self,
+        aggregator: Agg,
+        size: TS,
+    ) -> OrdWindowAggregateStream<B::Key, TS, Agg::Output, B::R>
+    where
+        B: PartitionedIndexedZSet<TS, V>,
+        B::R: ZRingValue,
+        Agg: Aggregator<V, (), B::R>,
+        Agg::Accumulator: Default,
+        TS: DBData + PrimInt,
+        V: DBData,
+    {
+        self.tumbling_aggregate_generic::<TS, V, Agg, _>(aggregator, size)
+    }
+
+    /// Like [`Self::tumbling_aggregate`], but can return any batch type.
+    pub fn tumbling_aggregate_generic<TS, V, Agg, O>(
+        &self,
+        aggregator: Agg,
+        size: TS,
+    ) -> Stream<Circuit<()>, O>
+    where
+        B: PartitionedIndexedZSet<TS, V>,
+        B::R: ZRingValue,
+        Agg: Aggregator<V, (), B::R>,
+        Agg::Accumulator: Default,
+        O: IndexedZSet<Key = (TS, TS, B::Key), Val = Agg::Output, R = B::R>,
+        TS: DBData + PrimInt,
+        V: DBData,
+    {
+        self.hopping_aggregate_generic::<TS, V, Agg, O>(aggregator, size, size)
+    }
+
+    /// Hopping window aggregate of a partitioned stream.
+    ///
+    /// Covers the timeline with windows of size `size` that start every `hop`
+    /// time units, i.e., `[0..size)`, `[hop..hop+size)`, `[2*hop..2*hop+size)`,
+    /// etc.  Windows overlap when `hop < size`.  For each partition in the
+    /// input stream, applies `aggregator` to the contents of each non-empty
+    /// window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` or `hop` is not positive.
+    pub fn hopping_aggregate<TS, V, Agg>(
+        &self,
+        aggregator: Agg,
+        size: TS,
+        hop: TS,
+    ) -> OrdWindowAggregateStream<B::Key, TS, Agg::Output, B::R>
+    where
+        B: PartitionedIndexedZSet<TS, V>,
+        B::R: ZRingValue,
+        Agg: Aggregator<V, (), B::R>,
+        Agg::Accumulator: Default,
+        TS: DBData + PrimInt,
+        V: DBData,
+    {
+        self.hopping_aggregate_generic::<TS, V, Agg, _>(aggregator, size, hop)
+    }
+
+    /// Like [`Self::hopping_aggregate`], but can return any batch type.
+    pub fn hopping_aggregate_generic<TS, V, Agg, O>(
+        &self,
+        aggregator: Agg,
+        size: TS,
+        hop: TS,
+    ) -> Stream<Circuit<()>, O>
+    where
+        B: PartitionedIndexedZSet<TS, V>,
+        B::R: ZRingValue,
+        Agg: Aggregator<V, (), B::R>,
+        Agg::Accumulator: Default,
+        O: IndexedZSet<Key = (TS, TS, B::Key), Val = Agg::Output, R = B::R>,
+        TS: DBData + PrimInt,
+        V: DBData,
+    {
+        assert!(size > TS::zero(), "window size must be positive");
+        assert!(hop > TS::zero(), "window hop must be positive");
+
+        // ```
+        //      ┌──────────────────────────────────────────────┐
+        //      │                                              │
+        //      │                                              ▼
+        // self │    ┌──────────────────────────┐  tree  ┌────────────────┐ windows    ┌─────────┐
+        // ─────┴───►│partitioned_tree_aggregate├───────►│HoppingAggregate├─────┬────►│map_index├───►
+        //           └──────────────────────────┘        └────────────────┘     │     └─────────┘
+        //                                                        ▲             │
+        //                                                        │             ▼
+        //                                                        │    ┌──────────────────┐
+        //                                                        │    │UntimedTraceAppend├──┐
+        //                                                        │    └──────────────────┘  │
+        //                                                        │      ▲                   │
+        //                                                        │    ┌─┴──┐                │
+        //                                                        └────┤Z^-1│◄───────────────┘
+        //                                        output_trace_delayed └────┘
+        // ```
+        self.circuit().region("hopping_aggregate", || {
+            let circuit = self.circuit();
+            let stream = self.shard();
+
+            let tree = stream
+                .partitioned_tree_aggregate::<TS, V, Agg>(aggregator.clone())
+                .integrate_trace();
+
+            let windows: OrdPartitionedWindowsStream<B::Key, TS, Agg::Output, B::R> =
+                add_window_feedback(circuit, |output_trace_delayed| {
+                    circuit.add_ternary_operator(
+                        <HoppingAggregate<TS, V, Agg>>::new(size, hop, aggregator),
+                        &stream,
+                        &tree,
+                        output_trace_delayed,
+                    )
+                });
+
+            // Windows are indexed by start time.
+            windows.map_index_generic(|(key, (start, (end, agg)))| {
+                ((*start, *end, key.clone()), agg.clone())
+            })
+        })
+    }
+
+    /// Session window aggregate of a partitioned stream.
+    ///
+    /// Groups the contents of each partition into sessions, i.e., maximal
+    /// sets of values where the distance between consecutive timestamps is
+    /// less than `gap`, and applies `aggregator` to each session.  A session
+    /// whose first and last timestamps are `first` and `last` is represented
+    /// by the window `[first..last+gap)`.
+    ///
+    /// When a new value is within `gap` from two existing sessions, the
+    /// operator retracts both sessions and outputs the merged session.
+    /// Likewise, deleting a value can split a session in two.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gap` is not positive.
+    pub fn session_window<TS, V, Agg>(
+        &self,
+        aggregator: Agg,
+        gap: TS,
+    ) -> OrdWindowAggregateStream<B::Key, TS, Agg::Output, B::R>
+    where
+        B: PartitionedIndexedZSet<TS, V>,
+        B::R: ZRingValue,
+        Agg: Aggregator<V, (), B::R>,
+        Agg::Accumulator: Default,
+        TS: DBData + PrimInt,
+        V: DBData,
+    {
+        self.session_window_generic::<TS, V, Agg, _>(aggregator, gap)
+    }
+
+    /// Like [`Self::session_window`], but can return any batch type.
+    pub fn session_window_generic<TS, V, Agg, O>(
+        &self,
+        aggregator: Agg,
+        gap: TS,
+    ) -> Stream<Circuit<()>, O>
+    where
+        B: PartitionedIndexedZSet<TS, V>,
+        B::R: ZRingValue,
+        Agg: Aggregator<V, (), B::R>,
+        Agg::Accumulator: Default,
+        O: IndexedZSet<Key = (TS, TS, B::Key), Val = Agg::Output, R = B::R>,
+        TS: DBData + PrimInt,
+        V: DBData,
+    {
+        assert!(gap > TS::zero(), "session gap must be positive");
+
+        // ```
+        //                  ┌───────────────┐   input_trace
+        //      ┌──────────►│integrate_trace├──────────────┐
+        //      │           └───────────────┘              │
+        //      │                                          ▼
+        // self │    ┌──────────────────────────┐  tree  ┌─────────────┐  windows   ┌─────────┐
+        // ─────┼───►│partitioned_tree_aggregate├───────►│SessionWindow├─────┬─────►│map_index├───►
+        //      │    └──────────────────────────┘        └─────────────┘     │      └─────────┘
+        //      │                                          ▲         ▲       │
+        //      └──────────────────────────────────────────┘         │       ▼
+        //                                                           │  ┌──────────────────┐
+        //                                                           │  │UntimedTraceAppend├──┐
+        //                                                           │  └──────────────────┘  │
+        //                                                           │    ▲                   │
+        //                                                           │  ┌─┴──┐                │
+        //                                                           └──┤Z^-1│◄───────────────┘
+        //                                          output_trace_delayed└────┘
+        // ```
+        self.circuit().region("session_window", || {
+            let circuit = self.circuit();
+            let stream = self.shard();
+
+            let tree = stream
+                .partitioned_tree_aggregate::<TS, V, Agg>(aggregator.clone())
+                .integrate_trace();
+            let input_trace = stream.integrate_trace();
+
+            let windows: OrdPartitionedWindowsStream<B::Key, TS, Agg::Output, B::R> =
+                add_window_feedback(circuit, |output_trace_delayed| {
+                    circuit.add_quaternary_operator(
+                        <SessionWindow<TS, V, Agg>>::new(gap, aggregator),
+                        &stream,
+                        &input_trace,
+                        &tree,
+                        output_trace_delayed,
+                    )
+                });
+
+            // Sessions are indexed by end time.
+            windows.map_index_generic(|(key, (end, (start, agg)))| {
+                ((*start, *end, key.clone()), agg.clone())
+            })
+        })
+    }
+}
+
+/// Adds an operator created by `add_operator` that computes window aggregates,
+/// along with a feedback loop that supplies the operator with the trace of its
+/// previous outputs, used to compute retractions.
+fn add_window_feedback<O, F>(circuit: &Circuit<()>, add_operator: F) -> Stream<Circuit<()>, O>
+where
+    O: IndexedZSet,
+    F: FnOnce(&Stream<Circuit<()>, Spine<O>>) -> Stream<Circuit<()>, O>,
+{
+    let (output_trace_delayed, z1feedback) = circuit.add_feedback(<Z1Trace<Spine<O>>>::new(
+        false,
+        circuit.root_scope(),
+        TraceBounds::new(),
+    ));
+    output_trace_delayed.mark_sharded();
+
+    let output = add_operator(&output_trace_delayed).mark_sharded();
+
+    let output_trace = circuit
+        .add_binary_operator_with_preference(
+            <UntimedTraceAppend<Spine<O>>>::new(),
+            (
+                &output_trace_delayed,
+                OwnershipPreference::STRONGLY_PREFER_OWNED,
+            ),
+            (&output, OwnershipPreference::PREFER_OWNED),
+        )
+        .mark_sharded();
+
+    z1feedback.connect_with_preference(&output_trace, OwnershipPreference::STRONGLY_PREFER_OWNED);
+
+    circuit.cache_insert(
+        DelayedTraceId::new(output_trace.origin_node_id().clone()),
+        output_trace_delayed,
+    );
+    circuit.cache_insert(
+        IntegrateTraceId::new(output.origin_node_id().clone()),
+        output_trace,
+    );
+
+    output
+}
+
+/// Returns the largest multiple of `hop` that is less than or equal to `ts`.
+fn align_down<TS>(ts: TS, hop: TS) -> TS
+where
+    TS: PrimInt,
+{
+    let rem = ts % hop;
+    if rem < TS::zero() {
+        (ts - rem).saturating_sub(hop)
+    } else {
+        ts - rem
+    }
+}
+
+/// Ternary operator that implements the internals of `hopping_aggregate`.
+///
+/// * Input stream 1: updates to the time series.  Used to identify affected
+///   partitions and windows.
+/// * Input stream 2: trace containing the partitioned radix tree over the input
+///   time series.
+/// * Input stream 3: trace of previously produced outputs.  Used to compute
+///   retractions.
+///
+/// Outputs window aggregates partitioned by key and indexed by window start
+/// time.
+struct HoppingAggregate<TS, V, Agg> {
+    size: TS,
+    hop: TS,
+    aggregator: Agg,
+    phantom: PhantomData<V>,
+}
+
+impl<TS, V, Agg> HoppingAggregate<TS, V, Agg>
+where
+    TS: PrimInt,
+{
+    fn new(size: TS, hop: TS, aggregator: Agg) -> Self {
+        Self {
+            size,
+            hop,
+            aggregator,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the range of start times of windows that contain `ts` or `None`
+    /// if `ts` does not belong to any window, which can happen when
+    /// `hop > size`.
+    fn windows_of(&self, ts: &TS) -> Option<Range<TS>> {
+        let last = align_down(*ts, self.hop);
+        let lower = ts.saturating_sub(self.size - TS::one());
+        let mut first = align_down(lower, self.hop);
+        if first < lower {
+            first = first.checked_add(&self.hop)?;
+        }
+
+        (first <= last).then(|| Range::new(first, last))
+    }
+
+    /// Returns start times of all windows affected by updates in
+    /// `delta_cursor`.
+    fn affected_windows<'a, R, C>(&self, delta_cursor: &mut C) -> Ranges<TS>
+    where
+        C: Cursor<'a, TS, V, (), R>,
+    {
+        let mut affected_windows = Ranges::new();
+
+        while delta_cursor.key_valid() {
+            if let Some(range) = self.windows_of(delta_cursor.key()) {
+                affected_windows.push_monotonic(range);
+            }
+            delta_cursor.step_key();
+        }
+
+        affected_windows
+    }
+}
+
+impl<TS, V, Agg> Operator for HoppingAggregate<TS, V, Agg>
+where
+    TS: 'static,
+    V: 'static,
+    Agg: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("HoppingAggregate")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<TS, V, Agg, B, RT, OT, O> TernaryOperator<B, RT, OT, O> for HoppingAggregate<TS, V, Agg>
+where
+    TS: DBData + PrimInt,
+    V: DBData,
+    Agg: Aggregator<V, (), B::R>,
+    B: PartitionedBatchReader<TS, V> + Clone,
+    B::R: ZRingValue,
+    RT: PartitionedRadixTreeReader<TS, Agg::Accumulator, Key = B::Key> + Clone,
+    OT: PartitionedBatchReader<TS, (TS, Agg::Output), Key = B::Key, R = B::R> + Clone,
+    O: IndexedZSet<Key = B::Key, Val = (TS, (TS, Agg::Output)), R = B::R>,
+{
+    fn eval<'a>(
+        &mut self,
+        input_delta: Cow<'a, B>,
+        radix_tree: Cow<'a, RT>,
+        output_trace: Cow<'a, OT>,
+    ) -> O {
+        let mut delta_cursor = input_delta.cursor();
+        let mut tree_cursor = radix_tree.cursor();
+        let mut output_trace_cursor = output_trace.cursor();
+
+        let mut retraction_builder = O::Builder::new_builder(());
+        let mut insertion_builder = O::Builder::with_capacity((), input_delta.len());
+
+        // Iterate over affected partitions.
+        while delta_cursor.key_valid() {
+            // Start times of affected windows.  Since windows start at multiples of
+            // `self.hop`, so do the bounds of these ranges.
+            let windows = self.affected_windows(&mut PartitionCursor::new(&mut delta_cursor));
+            let key = delta_cursor.key();
+
+            output_trace_cursor.seek_key(key);
+            let mut output_partition_cursor =
+                if output_trace_cursor.key_valid() && output_trace_cursor.key() == key {
+                    Some(PartitionCursor::new(&mut output_trace_cursor))
+                } else {
+                    None
+                };
+
+            tree_cursor.seek_key(key);
+            let mut tree_partition_cursor = if tree_cursor.key_valid() && tree_cursor.key() == key {
+                Some(PartitionCursor::new(&mut tree_cursor))
+            } else {
+                None
+            };
+
+            for i in 0..windows.len() {
+                let range = windows.range(i);
+                let mut start = range.from;
+
+                loop {
+                    // Clear the old aggregate of the window.
+                    if let Some(output_partition_cursor) = &mut output_partition_cursor {
+                        output_partition_cursor.seek_key(&start);
+                        if output_partition_cursor.key_valid()
+                            && output_partition_cursor.key() == &start
+                        {
+                            while output_partition_cursor.val_valid() {
+                                let weight = output_partition_cursor.weight();
+                                if !weight.is_zero() {
+                                    retraction_builder.push((
+                                        O::item_from(
+                                            key.clone(),
+                                            (start, output_partition_cursor.val().clone()),
+                                        ),
+                                        weight.neg(),
+                                    ));
+                                }
+                                output_partition_cursor.step_val();
+                            }
+                        }
+                    }
+
+                    // Compute the new aggregate using the radix tree.
+                    if let Some(tree_partition_cursor) = &mut tree_partition_cursor {
+                        tree_partition_cursor.rewind_keys();
+
+                        let window = Range::new(start, start.saturating_add(self.size - TS::one()));
+                        if let Some(acc) =
+                            tree_partition_cursor.aggregate_range::<Agg::Semigroup>(&window)
+                        {
+                            let end = start.saturating_add(self.size);
+                            insertion_builder.push((
+                                O::item_from(
+                                    key.clone(),
+                                    (start, (end, self.aggregator.finalize(acc))),
+                                ),
+                                HasOne::one(),
+                            ));
+                        }
+                    }
+
+                    match start.checked_add(&self.hop) {
+                        Some(next) if next <= range.to => start = next,
+                        _ => break,
+                    }
+                }
+            }
+
+            delta_cursor.step_key();
+        }
+
+        let retractions = retraction_builder.done();
+        let insertions = insertion_builder.done();
+        retractions.add(insertions)
+    }
+}
+
+/// Quaternary operator that implements the internals of `session_window`.
+///
+/// * Input stream 1: updates to the time series.  Used to identify affected
+///   partitions and sessions.
+/// * Input stream 2: trace containing the accumulated time series data.  Used
+///   to compute session boundaries.
+/// * Input stream 3: trace containing the partitioned radix tree over the input
+///   time series.
+/// * Input stream 4: trace of previously produced outputs.  Used to compute
+///   retractions.
+///
+/// Outputs session aggregates partitioned by key and indexed by session end
+/// time.
+///
+/// # Design
+///
+/// A change at time `ts` can only affect sessions that overlap with the range
+/// `[ts..ts+gap)`: inserting a value can merge sessions on both sides of `ts`,
+/// while deleting a value can split the session that contains it.  For each
+/// partition, we retract all such sessions and compute the union of their time
+/// ranges with `[ts..ts+gap)` for all `ts` in the input batch.  Any value
+/// outside of the resulting ranges is at least `gap` away from values inside
+/// them, so we compute new sessions by scanning the input trace within these
+/// ranges only.
+///
+/// Indexing sessions by end time allows finding all sessions that contain `ts`
+/// or start after `ts` by seeking to `ts+1` in the output trace.
+struct SessionWindow<TS, V, Agg> {
+    gap: TS,
+    aggregator: Agg,
+    phantom: PhantomData<V>,
+}
+
+impl<TS, V, Agg> SessionWindow<TS, V, Agg> {
+    fn new(gap: TS, aggregator: Agg) -> Self {
+        Self {
+            gap,
+            aggregator,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<TS, V, Agg> Operator for SessionWindow<TS, V, Agg>
+where
+    TS: 'static,
+    V: 'static,
+    Agg: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("SessionWindow")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<TS, V, Agg, B, T, RT, OT, O> QuaternaryOperator<B, T, RT, OT, O> for SessionWindow<TS, V, Agg>
+where
+    TS: DBData + PrimInt,
+    V: DBData,
+    Agg: Aggregator<V, (), B::R>,
+    B: PartitionedBatchReader<TS, V> + Clone,
+    B::R: ZRingValue,
+    T: PartitionedBatchReader<TS, V, Key = B::Key, R = B::R> + Clone,
+    RT: PartitionedRadixTreeReader<TS, Agg::Accumulator, Key = B::Key> + Clone,
+    OT: PartitionedBatchReader<TS, (TS, Agg::Output), Key = B::Key, R = B::R> + Clone,
+    O: IndexedZSet<Key = B::Key, Val = (TS, (TS, Agg::Output)), R = B::R>,
+{
+    fn eval<'a>(
+        &mut self,
+        input_delta: Cow<'a, B>,
+        input_trace: Cow<'a, T>,
+        radix_tree: Cow<'a, RT>,
+        output_trace: Cow<'a, OT>,
+    ) -> O {
+        let mut delta_cursor = input_delta.cursor();
+        let mut input_trace_cursor = input_trace.cursor();
+        let mut tree_cursor = radix_tree.cursor();
+        let mut output_trace_cursor = output_trace.cursor();
+
+        let mut retraction_builder = O::Builder::new_builder(());
+        let mut insertion_builder = O::Builder::with_capacity((), input_delta.len());
+
+        // Iterate over affected partitions.
+        while delta_cursor.key_valid() {
+            let key = delta_cursor.key().clone();
+
+            output_trace_cursor.seek_key(&key);
+            let mut output_partition_cursor =
+                if output_trace_cursor.key_valid() && output_trace_cursor.key() == &key {
+                    Some(PartitionCursor::new(&mut output_trace_cursor))
+                } else {
+                    None
+                };
+
+            // Retract affected sessions and compute time ranges that may contain
+            // new sessions (see "Design" above).
+            let mut ranges = Ranges::new();
+            let mut delta_partition_cursor = PartitionCursor::new(&mut delta_cursor);
+
+            while delta_partition_cursor.key_valid() {
+                let ts = *delta_partition_cursor.key();
+                let last_affected = ts.saturating_add(self.gap - TS::one());
+                let mut range = Range::new(ts, last_affected);
+
+                if let Some(output_partition_cursor) = &mut output_partition_cursor {
+                    output_partition_cursor.seek_key(&ts.saturating_add(TS::one()));
+
+                    'sessions: while output_partition_cursor.key_valid() {
+                        let end = *output_partition_cursor.key();
+
+                        while output_partition_cursor.val_valid() {
+                            let weight = output_partition_cursor.weight();
+                            if !weight.is_zero() {
+                                let start = output_partition_cursor.val().0;
+                                if start > last_affected {
+                                    break 'sessions;
+                                }
+
+                                retraction_builder.push((
+                                    O::item_from(
+                                        key.clone(),
+                                        (end, output_partition_cursor.val().clone()),
+                                    ),
+                                    weight.neg(),
+                                ));
+                                range.from = min(range.from, start);
+                                range.to = max(range.to, end - TS::one());
+                            }
+                            output_partition_cursor.step_val();
+                        }
+                        output_partition_cursor.step_key();
+                    }
+                }
+
+                ranges.push_monotonic(range);
+                delta_partition_cursor.step_key();
+            }
+
+            // Compute new sessions.
+            input_trace_cursor.seek_key(&key);
+            tree_cursor.seek_key(&key);
+
+            if input_trace_cursor.key_valid() && input_trace_cursor.key() == &key {
+                debug_assert!(tree_cursor.key_valid());
+                debug_assert_eq!(tree_cursor.key(), &key);
+
+                let mut tree_partition_cursor = PartitionCursor::new(&mut tree_cursor);
+                let mut input_range_cursor =
+                    RangeCursor::new(PartitionCursor::new(&mut input_trace_cursor), ranges);
+
+                // Computes the aggregate of the session with the given first and
+                // last timestamps using the radix tree.
+                let mut push_session = |first: TS, last: TS| {
+                    tree_partition_cursor.rewind_keys();
+
+                    if let Some(acc) = tree_partition_cursor
+                        .aggregate_range::<Agg::Semigroup>(&Range::new(first, last))
+                    {
+                        let end = last.saturating_add(self.gap);
+                        insertion_builder.push((
+                            O::item_from(
+                                key.clone(),
+                                (end, (first, self.aggregator.finalize(acc))),
+                            ),
+                            HasOne::one(),
+                        ));
+                    }
+                };
+
+                // First and last timestamps of the current session.
+                let mut session: Option<(TS, TS)> = None;
+
+                while input_range_cursor.key_valid() {
+                    let ts = *input_range_cursor.key();
+
+                    let mut present = false;
+                    while input_range_cursor.val_valid() {
+                        if !input_range_cursor.weight().le0() {
+                            present = true;
+                            break;
+                        }
+                        input_range_cursor.step_val();
+                    }
+
+                    if present {
+                        session = match session {
+                            Some((first, last)) if ts - last < self.gap => Some((first, ts)),
+                            Some((first, last)) => {
+                                push_session(first, last);
+                                Some((ts, ts))
+                            }
+                            None => Some((ts, ts)),
+                        };
+                    }
+
+                    input_range_cursor.step_key();
+                }
+
+                if let Some((first, last)) = session {
+                    push_session(first, last);
+                }
+            }
+
+            delta_cursor.step_key();
+        }
+
+        let retractions = retraction_builder.done();
+        let insertions = insertion_builder.done();
+        retractions.add(insertions)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::DefaultSemigroup,
+        indexed_zset,
+        operator::Fold,
+        trace::{Batch, BatchReader, Cursor},
+        Circuit, CollectionHandle, DBSPHandle, OrdIndexedZSet, OutputHandle, Runtime, Stream,
+    };
+    use std::collections::{BTreeMap, BTreeSet};
+
+    type DataBatch = OrdIndexedZSet<u64, (u64, i64), isize>;
+    type DataStream = Stream<Circuit<()>, DataBatch>;
+    type OutputBatch = OrdIndexedZSet<(u64, u64, u64), i64, isize>;
+    type OutputStream = Stream<Circuit<()>, OutputBatch>;
+
+    // Contents of a batch as a map from partition to the sum of values with
+    // each timestamp.
+    fn partitions(batch: &DataBatch) -> BTreeMap<u64, BTreeMap<u64, i64>> {
+        let mut partitions: BTreeMap<u64, BTreeMap<u64, i64>> = BTreeMap::new();
+
+        let mut cursor = batch.cursor();
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                let (ts, val) = *cursor.val();
+                *partitions
+                    .entry(*cursor.key())
+                    .or_default()
+                    .entry(ts)
+                    .or_default() += val * cursor.weight() as i64;
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        partitions
+    }
+
+    // Reference implementation of `hopping_aggregate` for testing.
+    fn hopping_aggregate_slow(stream: &DataStream, size: u64, hop: u64) -> OutputStream {
+        stream
+            .gather(0)
+            .integrate()
+            .apply(move |batch: &DataBatch| {
+                let mut tuples = Vec::new();
+
+                for (partition, contents) in partitions(batch) {
+                    let mut starts = BTreeSet::new();
+                    for ts in contents.keys() {
+                        let mut start = ts / hop * hop;
+                        while start + size > *ts {
+                            starts.insert(start);
+                            if start < hop {
+                                break;
+                            }
+                            start -= hop;
+                        }
+                    }
+
+                    for start in starts {
+                        let agg = contents.range(start..start + size).map(|(_, v)| v).sum();
+                        tuples.push((((start, start + size, partition), agg), 1));
+                    }
+                }
+
+                OutputBatch::from_tuples((), tuples)
+            })
+    }
+
+    // Reference implementation of `session_window` for testing.
+    fn session_window_slow(stream: &DataStream, gap: u64) -> OutputStream {
+        stream
+            .gather(0)
+            .integrate()
+            .apply(move |batch: &DataBatch| {
+                let mut tuples = Vec::new();
+
+                for (partition, contents) in partitions(batch) {
+                    let mut session: Option<(u64, u64, i64)> = None;
+
+                    for (ts, val) in contents {
+                        session = match session {
+                            Some((first, last, agg)) if ts - last < gap => {
+                                Some((first, ts, agg + val))
+                            }
+                            Some((first, last, agg)) => {
+                                tuples.push((((first, last + gap, partition), agg), 1));
+                                Some((ts, ts, val))
+                            }
+                            None => Some((ts, ts, val)),
+                        };
+                    }
+
+                    if let Some((first, last, agg)) = session {
+                        tuples.push((((first, last + gap, partition), agg), 1));
+                    }
+                }
+
+                OutputBatch::from_tuples((), tuples)
+            })
+    }
+
+    type WindowHandle = CollectionHandle<u64, ((u64, i64), isize)>;
+
+    fn window_aggregate_circuit() -> (DBSPHandle, (WindowHandle, OutputHandle<OutputBatch>)) {
+        Runtime::init_circuit(4, |circuit| {
+            let (input_stream, input_handle) =
+                circuit.add_input_indexed_zset::<u64, (u64, i64), isize>();
+
+            let aggregator = <Fold<_, DefaultSemigroup<_>, _, _>>::new(
+                0i64,
+                |agg: &mut i64, val: &i64, w: isize| *agg += val * (w as i64),
+            );
+
+            let expected = hopping_aggregate_slow(&input_stream, 100, 100);
+            let output = input_stream
+                .tumbling_aggregate::<u64, i64, _>(aggregator.clone(), 100)
+                .gather(0)
+                .integrate();
+            expected.apply2(&output, |expected, actual| assert_eq!(expected, actual));
+
+            let expected = hopping_aggregate_slow(&input_stream, 100, 30);
+            let output = input_stream
+                .hopping_aggregate::<u64, i64, _>(aggregator.clone(), 100, 30)
+                .gather(0)
+                .integrate();
+            expected.apply2(&output, |expected, actual| assert_eq!(expected, actual));
+
+            // Windows with gaps between them.
+            let expected = hopping_aggregate_slow(&input_stream, 20, 50);
+            let output = input_stream
+                .hopping_aggregate::<u64, i64, _>(aggregator.clone(), 20, 50)
+                .gather(0)
+                .integrate();
+            expected.apply2(&output, |expected, actual| assert_eq!(expected, actual));
+
+            let expected = session_window_slow(&input_stream, 50);
+            let sessions = input_stream.session_window::<u64, i64, _>(aggregator, 50);
+            let output = sessions.gather(0).integrate();
+            expected.apply2(&output, |expected, actual| assert_eq!(expected, actual));
+
+            (input_handle, sessions.output())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_window_aggregate() {
+        let (mut circuit, (mut input, sessions)) = window_aggregate_circuit();
+
+        input.append(&mut vec![
+            (0, ((10, 1), 1)),
+            (0, ((40, 2), 1)),
+            (0, ((120, 3), 1)),
+            (1, ((20, 4), 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            sessions.consolidate(),
+            indexed_zset! {
+                (10, 90, 0) => { 3 => 1 },
+                (120, 170, 0) => { 3 => 1 },
+                (20, 70, 1) => { 4 => 1 },
+            }
+        );
+
+        // A late value merges two sessions.
+        input.append(&mut vec![(0, ((80, 5), 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            sessions.consolidate(),
+            indexed_zset! {
+                (10, 90, 0) => { 3 => -1 },
+                (120, 170, 0) => { 3 => -1 },
+                (10, 170, 0) => { 11 => 1 },
+            }
+        );
+
+        // Deleting the value splits the session again.
+        input.append(&mut vec![(0, ((80, 5), -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            sessions.consolidate(),
+            indexed_zset! {
+                (10, 170, 0) => { 11 => -1 },
+                (10, 90, 0) => { 3 => 1 },
+                (120, 170, 0) => { 3 => 1 },
+            }
+        );
+
+        // Late values and deletions in multiple windows.
+        input.append(&mut vec![
+            (0, ((10, 1), -1)),
+            (0, ((5, 7), 1)),
+            (1, ((1000, 1), 1)),
+            (1, ((0, 8), 1)),
+        ]);
+        circuit.step().unwrap();
+
+        input.append(&mut vec![
+            (0, ((5, 7), -1)),
+            (0, ((40, 2), -1)),
+            (0, ((120, 3), -1)),
+        ]);
+        circuit.step().unwrap();
+
+        circuit.kill().unwrap();
+    }
+
+    use proptest::{collection, prelude::*};
+
+    type InputTuple = (u64, ((u64, i64), isize));
+    type InputBatch = Vec<InputTuple>;
+
+    fn input_tuple(partitions: u64, epoch: u64) -> impl Strategy<Value = InputTuple> {
+        ((0..partitions), ((0..epoch, 100..101i64), 1..2isize))
+    }
+    fn input_batch(
+        partitions: u64,
+        epoch: u64,
+        max_batch_size: usize,
+    ) -> impl Strategy<Value = InputBatch> {
+        collection::vec(input_tuple(partitions, epoch), 0..max_batch_size)
+    }
+    fn input_trace(
+        partitions: u64,
+        epoch: u64,
+        max_batch_size: usize,
+        max_batches: usize,
+    ) -> impl Strategy<Value = Vec<InputBatch>> {
+        collection::vec(
+            input_batch(partitions, epoch, max_batch_size),
+            0..max_batches,
+        )
+    }
+
+    proptest! {
+        #[test]
+        #[cfg_attr(feature = "persistence", ignore = "takes a long time?")]
+        fn proptest_window_aggregate_sparse(trace in input_trace(5, 100_000, 20, 20)) {
+            let (mut circuit, (mut input, _sessions)) = window_aggregate_circuit();
+
+            for mut batch in trace {
+                input.append(&mut batch);
+                circuit.step().unwrap();
+            }
+
+            circuit.kill().unwrap();
+        }
+
+        #[test]
+        #[cfg_attr(feature = "persistence", ignore = "takes a long time?")]
+        fn proptest_window_aggregate_dense(trace in input_trace(5, 1_000, 50, 20)) {
+            let (mut circuit, (mut input, _sessions)) = window_aggregate_circuit();
+
+            for mut batch in trace {
+                input.append(&mut batch);
+                circuit.step().unwrap();
+            }
+
+            circuit.kill().unwrap();
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! Top-K operators.
+
+use crate::{
+    algebra::{HasZero, IndexedZSet, ZRingValue},
+    circuit::{
+        operator_traits::{Operator, TernaryOperator},
+        OwnershipPreference, Scope,
+    },
+    operator::trace::{TraceBounds, UntimedTraceAppend, Z1Trace},
+    trace::{BatchReader, Cursor, Spine},
+    Circuit, OrdIndexedZSet, Stream,
+};
+use std::{borrow::Cow, marker::PhantomData, ops::Neg};
+
+impl<Z> Stream<Circuit<()>, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue,
+{
+    /// Incrementally computes the `k` smallest values for each key in an
+    /// indexed Z-set.
+    ///
+    /// Given a stream of changes to an indexed Z-set, outputs a stream of
+    /// changes to its subset that contains, for each key, the `k` smallest
+    /// values with positive weight.  Each value occupies one of the `k` slots
+    /// regardless of its weight and is output with its weight in the input
+    /// Z-set.
+    ///
+    /// The operator maintains the traces of its input and output streams.
+    /// At each step, it only recomputes the top-`k` values of keys that occur
+    /// in the input batch and outputs the difference between their new and
+    /// old top-`k` values.
+    pub fn topk_asc(&self, k: usize) -> Self {
+        self.topk_generic(k, false, |_rank, val| val.clone())
+    }
+
+    /// Incrementally computes the `k` largest values for each key in an
+    /// indexed Z-set.
+    ///
+    /// See [`Self::topk_asc`] for details.
+    pub fn topk_desc(&self, k: usize) -> Self {
+        self.topk_generic(k, true, |_rank, val| val.clone())
+    }
+
+    /// Like [`Self::topk_asc`], but additionally outputs the rank of each
+    /// value, i.e., its 1-based position among the top-`k` values of its key,
+    /// similar to SQL `ROW_NUMBER() OVER (PARTITION BY key ORDER BY val)`.
+    ///
+    /// Since ranks of all values below an inserted or deleted value change,
+    /// each update can affect up to `k` output records.
+    #[allow(clippy::type_complexity)]
+    pub fn topk_rank_asc(
+        &self,
+        k: usize,
+    ) -> Stream<Circuit<()>, OrdIndexedZSet<Z::Key, (usize, Z::Val), Z::R>> {
+        self.topk_generic(k, false, |rank, val| (rank, val.clone()))
+    }
+
+    /// Like [`Self::topk_desc`], but additionally outputs the rank of each
+    /// value, similar to SQL
+    /// `ROW_NUMBER() OVER (PARTITION BY key ORDER BY val DESC)`.
+    ///
+    /// See [`Self::topk_rank_asc`] for details.
+    #[allow(clippy::type_complexity)]
+    pub fn topk_rank_desc(
+        &self,
+        k: usize,
+    ) -> Stream<Circuit<()>, OrdIndexedZSet<Z::Key, (usize, Z::Val), Z::R>> {
+        self.topk_generic(k, true, |rank, val| (rank, val.clone()))
+    }
+
+    /// Computes top-`k` values for each key, using `output_func` to map each
+    /// value and its rank to an output value.
+    fn topk_generic<F, O>(
+        &self,
+        k: usize,
+        descending: bool,
+        output_func: F,
+    ) -> Stream<Circuit<()>, O>
+    where
+        F: Fn(usize, &Z::Val) -> O::Val + 'static,
+        O: IndexedZSet<Key = Z::Key, R = Z::R>,
+    {
+        let circuit = self.circuit();
+        let stream = self.shard();
+
+        circuit.region("topk", || {
+            // We construct the following circuit.  See `TopK` documentation
+            // for details.
+            //
+            // ```
+            //          ┌──────────────────────────────────┐
+            //          │                                  │                         output
+            //          │                                  │               ┌──────────────────────────────►
+            //          │                                  ▼               │
+            //    stream│     ┌───────────────┐       ┌────────┐           │     ┌──────────────────┐
+            // ─────────┴─────┤integrate_trace├──────►│  TopK  ├───────────┴────►│UntimedTraceAppend├──┐
+            //                └───────────────┘       └────────┘                 └──────────────────┘  │
+            //                                             ▲                              ▲            │output_trace
+            //                                             │                              │            │
+            //                                             │                          ┌───┴───┐        │
+            //                                             └──────────────────────────┤Z1Trace│◄───────┘
+            //                                              output_trace_delayed      └───────┘
+            // ```
+            let (output_trace_delayed, z1feedback) = circuit.add_feedback(
+                <Z1Trace<Spine<O>>>::new(false, circuit.root_scope(), TraceBounds::new()),
+            );
+
+            let output = circuit.add_ternary_operator(
+                TopK::new(k, descending, output_func),
+                &stream,
+                &stream.integrate_trace(),
+                &output_trace_delayed,
+            );
+
+            let output_trace = circuit.add_binary_operator_with_preference(
+                <UntimedTraceAppend<Spine<O>>>::new(),
+                (
+                    &output_trace_delayed,
+                    OwnershipPreference::STRONGLY_PREFER_OWNED,
+                ),
+                (&output, OwnershipPreference::PREFER_OWNED),
+            );
+            z1feedback
+                .connect_with_preference(&output_trace, OwnershipPreference::STRONGLY_PREFER_OWNED);
+
+            output.mark_sharded()
+        })
+    }
+}
+
+/// Ternary operator that implements the internals of `topk_*` methods.
+///
+/// * Input stream 1: updates to the input collection.  Only used to identify
+///   affected keys.
+/// * Input stream 2: trace containing the accumulated input collection.
+/// * Input stream 3: trace containing the current top-k values of each key.
+///
+/// For each key in the input batch, the operator computes its new top-k values
+/// from the input trace and outputs them along with retractions of the old
+/// top-k values from the output trace.
+struct TopK<Z, IT, OT, F, O> {
+    k: usize,
+    descending: bool,
+    output_func: F,
+    phantom: PhantomData<(Z, IT, OT, O)>,
+}
+
+impl<Z, IT, OT, F, O> TopK<Z, IT, OT, F, O> {
+    pub fn new(k: usize, descending: bool, output_func: F) -> Self {
+        Self {
+            k,
+            descending,
+            output_func,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Z, IT, OT, F, O> Operator for TopK<Z, IT, OT, F, O>
+where
+    Z: 'static,
+    IT: 'static,
+    OT: 'static,
+    F: 'static,
+    O: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("TopK")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z, IT, OT, F, O> TernaryOperator<Z, IT, OT, O> for TopK<Z, IT, OT, F, O>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue,
+    IT: BatchReader<Key = Z::Key, Val = Z::Val, Time = (), R = Z::R> + Clone,
+    OT: BatchReader<Key = Z::Key, Val = O::Val, Time = (), R = Z::R> + Clone,
+    F: Fn(usize, &Z::Val) -> O::Val + 'static,
+    O: IndexedZSet<Key = Z::Key, R = Z::R>,
+{
+    fn eval<'a>(
+        &mut self,
+        delta: Cow<'a, Z>,
+        input_trace: Cow<'a, IT>,
+        output_trace: Cow<'a, OT>,
+    ) -> O {
+        let mut delta_cursor = delta.cursor();
+        let mut input_cursor = input_trace.cursor();
+        let mut output_cursor = output_trace.cursor();
+
+        let mut tuples = Vec::new();
+        let mut topk = Vec::new();
+
+        while delta_cursor.key_valid() {
+            let key = delta_cursor.key();
+
+            // Retract old top-k values.
+            output_cursor.seek_key(key);
+            if output_cursor.key_valid() && output_cursor.key() == key {
+                while output_cursor.val_valid() {
+                    let weight = output_cursor.weight();
+                    if !weight.is_zero() {
+                        tuples.push((
+                            O::item_from(key.clone(), output_cursor.val().clone()),
+                            weight.neg(),
+                        ));
+                    }
+                    output_cursor.step_val();
+                }
+            }
+
+            // Compute new top-k values.
+            input_cursor.seek_key(key);
+            if input_cursor.key_valid() && input_cursor.key() == key {
+                // In descending mode, iterate over values starting from the
+                // largest one.
+                if self.descending {
+                    input_cursor.fast_forward_vals();
+                }
+
+                while input_cursor.val_valid() && topk.len() < self.k {
+                    let weight = input_cursor.weight();
+                    if weight.ge0() && !weight.is_zero() {
+                        topk.push((input_cursor.val().clone(), weight));
+                    }
+                    if self.descending {
+                        input_cursor.step_val_reverse();
+                    } else {
+                        input_cursor.step_val();
+                    }
+                }
+            }
+
+            for (rank, (val, weight)) in topk.drain(..).enumerate() {
+                tuples.push((
+                    O::item_from(key.clone(), (self.output_func)(rank + 1, &val)),
+                    weight,
+                ));
+            }
+
+            delta_cursor.step_key();
+        }
+
+        O::from_tuples((), tuples)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, trace::Batch, OrdIndexedZSet, Runtime};
+    use std::sync::{Arc, Mutex};
+
+    type TopKOutput = OrdIndexedZSet<usize, usize, isize>;
+    type RankOutput = OrdIndexedZSet<usize, (usize, usize), isize>;
+
+    #[test]
+    fn topk_test() {
+        let output = Arc::new(Mutex::new((
+            TopKOutput::empty(()),
+            TopKOutput::empty(()),
+            RankOutput::empty(()),
+            RankOutput::empty(()),
+        )));
+        let output_clone = output.clone();
+
+        let (mut circuit, mut input) = Runtime::init_circuit(4, move |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+
+            let output = output_clone.clone();
+            input.topk_asc(2).gather(0).inspect(move |batch| {
+                if Runtime::worker_index() == 0 {
+                    output.lock().unwrap().0 = batch.clone();
+                }
+            });
+
+            let output = output_clone.clone();
+            input.topk_desc(2).gather(0).inspect(move |batch| {
+                if Runtime::worker_index() == 0 {
+                    output.lock().unwrap().1 = batch.clone();
+                }
+            });
+
+            let output = output_clone.clone();
+            input.topk_rank_asc(2).gather(0).inspect(move |batch| {
+                if Runtime::worker_index() == 0 {
+                    output.lock().unwrap().2 = batch.clone();
+                }
+            });
+
+            let output = output_clone.clone();
+            input.topk_rank_desc(2).gather(0).inspect(move |batch| {
+                if Runtime::worker_index() == 0 {
+                    output.lock().unwrap().3 = batch.clone();
+                }
+            });
+
+            input_handle
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, (5, 1)),
+            (1, (3, 1)),
+            (1, (8, 2)),
+            (2, (1, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            *output.lock().unwrap(),
+            (
+                indexed_zset! { 1 => { 3 => 1, 5 => 1 }, 2 => { 1 => 1 } },
+                indexed_zset! { 1 => { 5 => 1, 8 => 2 }, 2 => { 1 => 1 } },
+                indexed_zset! { 1 => { (1, 3) => 1, (2, 5) => 1 }, 2 => { (1, 1) => 1 } },
+                indexed_zset! { 1 => { (1, 8) => 2, (2, 5) => 1 }, 2 => { (1, 1) => 1 } },
+            )
+        );
+
+        // Values outside of the top-2 do not change the output.
+        input.append(&mut vec![(1, (6, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            *output.lock().unwrap(),
+            (
+                indexed_zset! {},
+                indexed_zset! { 1 => { 5 => -1, 6 => 1 } },
+                indexed_zset! {},
+                indexed_zset! { 1 => { (2, 5) => -1, (2, 6) => 1 } },
+            )
+        );
+
+        input.append(&mut vec![(1, (4, 1)), (1, (7, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            *output.lock().unwrap(),
+            (
+                indexed_zset! { 1 => { 4 => 1, 5 => -1 } },
+                indexed_zset! { 1 => { 6 => -1, 7 => 1 } },
+                indexed_zset! { 1 => { (2, 4) => 1, (2, 5) => -1 } },
+                indexed_zset! { 1 => { (2, 6) => -1, (2, 7) => 1 } },
+            )
+        );
+
+        // Deleting a top-k value brings in the next value.
+        input.append(&mut vec![(1, (3, -1)), (1, (8, -2))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            *output.lock().unwrap(),
+            (
+                indexed_zset! { 1 => { 3 => -1, 5 => 1 } },
+                indexed_zset! { 1 => { 6 => 1, 8 => -2 } },
+                indexed_zset! { 1 => { (1, 3) => -1, (1, 4) => 1, (2, 4) => -1, (2, 5) => 1 } },
+                indexed_zset! { 1 => { (1, 7) => 1, (1, 8) => -2, (2, 6) => 1, (2, 7) => -1 } },
+            )
+        );
+
+        input.append(&mut vec![(2, (1, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            *output.lock().unwrap(),
+            (
+                indexed_zset! { 2 => { 1 => -1 } },
+                indexed_zset! { 2 => { 1 => -1 } },
+                indexed_zset! { 2 => { (1, 1) => -1 } },
+                indexed_zset! { 2 => { (1, 1) => -1 } },
+            )
+        );
+
+        circuit.kill().unwrap();
+    }
+}
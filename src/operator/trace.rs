@@ -1,19 +1,96 @@
 use crate::{
     circuit::{
+        checkpoint::{decode_data, encode_data},
         metadata::{MetaItem, OperatorMeta},
         operator_traits::{BinaryOperator, Operator, StrictOperator, StrictUnaryOperator},
         Circuit, ExportId, ExportStream, GlobalNodeId, OwnershipPreference, Scope, Stream,
     },
     circuit_cache_key,
     trace::{cursor::Cursor, Batch, BatchReader, Builder, Spine, Trace},
-    Timestamp,
+    Error, Timestamp,
 };
 use size_of::SizeOf;
-use std::{borrow::Cow, marker::PhantomData};
+use std::{borrow::Cow, cell::RefCell, collections::BTreeMap, marker::PhantomData, rc::Rc};
 
 circuit_cache_key!(TraceId<B, D>(GlobalNodeId => Stream<B, D>));
 circuit_cache_key!(DelayedTraceId<B, D>(GlobalNodeId => Stream<B, D>));
 circuit_cache_key!(IntegrateTraceId<B, D>(GlobalNodeId => Stream<B, D>));
+circuit_cache_key!(TraceBoundsId<K>(GlobalNodeId => TraceBounds<K>));
+circuit_cache_key!(UntimedTraceBoundsId<K>(GlobalNodeId => TraceBounds<K>));
+
+/// Lower bound on the keys retained by a trace.
+///
+/// A bound is attached to the trace of a stream using
+/// [`Stream::integrate_trace_with_bound`] or [`Stream::trace_with_bound`] and
+/// can be updated by the operator that owns it at any time using
+/// [`TraceBound::set`].  Keys below the bound are discarded by the trace (see
+/// [`Trace::truncate_keys_below`]).  Until the bound is set for the first
+/// time, the trace retains all keys.
+pub struct TraceBound<K>(Rc<RefCell<Option<K>>>);
+
+impl<K> Clone for TraceBound<K> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<K> Default for TraceBound<K> {
+    fn default() -> Self {
+        Self(Rc::new(RefCell::new(None)))
+    }
+}
+
+impl<K> TraceBound<K>
+where
+    K: Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the bound to `bound`.
+    pub fn set(&self, bound: K) {
+        *self.0.borrow_mut() = Some(bound);
+    }
+
+    /// Returns the current value of the bound.
+    pub fn get(&self) -> Option<K> {
+        self.0.borrow().clone()
+    }
+}
+
+/// All bounds attached to a trace.
+///
+/// A trace can be shared by multiple operators, e.g., an aggregate and a
+/// window over the same stream.  Every consumer of the trace registers a
+/// bound; consumers that do not need to discard any keys register a bound
+/// that is never set.  The trace only discards keys below all of them.
+pub struct TraceBounds<K>(Rc<RefCell<Vec<TraceBound<K>>>>);
+
+impl<K> Clone for TraceBounds<K> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<K> TraceBounds<K>
+where
+    K: Ord + Clone,
+{
+    pub(crate) fn new() -> Self {
+        Self(Rc::new(RefCell::new(Vec::new())))
+    }
+
+    fn add_bound(&self, bound: TraceBound<K>) {
+        self.0.borrow_mut().push(bound);
+    }
+
+    /// The smallest of all bounds or `None` if there are no bounds or some
+    /// of them are not set yet.
+    fn effective_bound(&self) -> Option<K> {
+        self.0.borrow().iter().map(TraceBound::get).min().flatten()
+    }
+}
 
 // TODO: add infrastructure to compact the trace during slack time.
 
@@ -69,13 +146,38 @@ where
         B: BatchReader<Time = ()>,
         T: Trace<Key = B::Key, Val = B::Val, R = B::R> + Clone,
     {
+        self.trace_with_bound(TraceBound::new())
+    }
+
+    /// Like [`Self::trace`], but allows the trace to discard keys below
+    /// `lower_key_bound`.
+    ///
+    /// See [`Self::integrate_trace_with_bound`] for details.
+    pub fn trace_with_bound<T>(&self, lower_key_bound: TraceBound<B::Key>) -> Stream<Circuit<P>, T>
+    where
+        B: BatchReader<Time = ()>,
+        T: Trace<Key = B::Key, Val = B::Val, R = B::R> + Clone,
+    {
+        let bounds = self
+            .circuit()
+            .cache_get_or_insert_with(
+                TraceBoundsId::new(self.origin_node_id().clone()),
+                TraceBounds::new,
+            )
+            .clone();
+        bounds.add_bound(lower_key_bound);
+
         self.circuit()
             .cache_get_or_insert_with(TraceId::new(self.origin_node_id().clone()), || {
                 let circuit = self.circuit();
 
                 circuit.region("trace", || {
-                    let (ExportStream { local, export }, z1feedback) =
-                        circuit.add_feedback_with_export(Z1Trace::new(false, circuit.root_scope()));
+                    let (ExportStream { local, export }, z1feedback) = circuit
+                        .add_feedback_with_export(Z1Trace::new(
+                            false,
+                            circuit.root_scope(),
+                            bounds.clone(),
+                        ));
                     let trace = circuit.add_binary_operator_with_preference(
                         <TraceAppend<T, B>>::new(),
                         (&local, OwnershipPreference::STRONGLY_PREFER_OWNED),
@@ -109,13 +211,50 @@ where
         B: Batch,
         Spine<B>: SizeOf,
     {
+        self.integrate_trace_with_bound(TraceBound::new())
+    }
+
+    /// Like [`Self::integrate_trace`], but allows the trace to discard keys
+    /// below `lower_key_bound`.
+    ///
+    /// The trace of a stream is shared by all operators that use it, e.g.,
+    /// aggregates and windows over the stream.  Each of them attaches its
+    /// own bound; [`Self::integrate_trace`] attaches a bound that is never
+    /// set.  The trace only discards keys below all attached bounds, i.e.,
+    /// keys are only discarded if none of the consumers of the trace need
+    /// them.
+    ///
+    /// Keys are discarded lazily as the trace merges its batches, so
+    /// operators reading the trace may still observe keys below the bound.
+    #[track_caller]
+    pub fn integrate_trace_with_bound(
+        &self,
+        lower_key_bound: TraceBound<B::Key>,
+    ) -> Stream<Circuit<P>, Spine<B>>
+    where
+        B: Batch,
+        Spine<B>: SizeOf,
+    {
+        let bounds = self
+            .circuit()
+            .cache_get_or_insert_with(
+                UntimedTraceBoundsId::new(self.origin_node_id().clone()),
+                TraceBounds::new,
+            )
+            .clone();
+        bounds.add_bound(lower_key_bound);
+
         self.circuit()
             .cache_get_or_insert_with(IntegrateTraceId::new(self.origin_node_id().clone()), || {
                 let circuit = self.circuit();
 
                 circuit.region("integrate_trace", || {
-                    let (ExportStream { local, export }, z1feedback) =
-                        circuit.add_feedback_with_export(Z1Trace::new(true, circuit.root_scope()));
+                    let (ExportStream { local, export }, z1feedback) = circuit
+                        .add_feedback_with_export(Z1Trace::new(
+                            true,
+                            circuit.root_scope(),
+                            bounds.clone(),
+                        ));
 
                     let trace = circuit.add_binary_operator_with_preference(
                         UntimedTraceAppend::<Spine<B>>::new(),
@@ -145,6 +284,33 @@ where
             })
             .clone()
     }
+
+    /// Like [`Self::integrate_trace`], but discards keys that fall behind the
+    /// watermark.
+    ///
+    /// `lower_key_bound` maps each value of the `watermark` stream to the
+    /// smallest key that must be retained by the trace, e.g., `|ts| ts -
+    /// lateness` for a trace indexed by timestamp, where `lateness` is the
+    /// maximal delay of late records relative to the watermark.  See
+    /// [`Self::integrate_trace_with_bound`] for details.
+    #[track_caller]
+    pub fn integrate_trace_with_watermark<W, F>(
+        &self,
+        watermark: &Stream<Circuit<P>, W>,
+        lower_key_bound: F,
+    ) -> Stream<Circuit<P>, Spine<B>>
+    where
+        B: Batch,
+        Spine<B>: SizeOf,
+        W: Clone + 'static,
+        F: Fn(&W) -> B::Key + 'static,
+    {
+        let bound = TraceBound::new();
+        let bound_clone = bound.clone();
+        watermark.inspect(move |watermark| bound_clone.set(lower_key_bound(watermark)));
+
+        self.integrate_trace_with_bound(bound)
+    }
 }
 
 impl<P, T> Stream<Circuit<P>, T>
@@ -277,6 +443,17 @@ where
     fn clock_end(&mut self, scope: Scope) {
         self.time = self.time.advance(scope + 1);
     }
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        if self.time == T::Time::clock_start() {
+            Ok(None)
+        } else {
+            encode_data(&self.time).map(Some)
+        }
+    }
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.time = decode_data(state)?;
+        Ok(())
+    }
     fn fixedpoint(&self, _scope: Scope) -> bool {
         true
     }
@@ -324,6 +501,7 @@ where
 pub struct Z1Trace<T: Trace> {
     time: T::Time,
     trace: Option<T>,
+    bounds: TraceBounds<T::Key>,
     // `dirty[scope]` is `true` iff at least one non-empty update was added to the trace
     // since the previous clock cycle at level `scope`.
     dirty: Vec<bool>,
@@ -335,15 +513,74 @@ impl<T> Z1Trace<T>
 where
     T: Trace,
 {
-    pub fn new(reset_on_clock_start: bool, root_scope: Scope) -> Self {
+    pub fn new(reset_on_clock_start: bool, root_scope: Scope, bounds: TraceBounds<T::Key>) -> Self {
         Self {
             time: T::Time::clock_start(),
             trace: None,
+            bounds,
             dirty: vec![false; root_scope as usize + 1],
             root_scope,
             reset_on_clock_start,
         }
     }
+
+    /// `true` if the operator is in the same state as a freshly created
+    /// operator, i.e., it has nothing to checkpoint.
+    fn is_initial(&self) -> bool {
+        self.time == T::Time::clock_start()
+            && self
+                .trace
+                .as_ref()
+                .map(|trace| trace.is_empty())
+                .unwrap_or(true)
+    }
+
+    /// Serialize the contents of the trace as a list of
+    /// `(key, value, time, weight)` tuples, along with the current time.
+    fn encode_state(&self) -> Result<Vec<u8>, Error> {
+        let mut updates = Vec::new();
+
+        if let Some(trace) = &self.trace {
+            let mut cursor = trace.cursor();
+            while cursor.key_valid() {
+                while cursor.val_valid() {
+                    let key = cursor.key().clone();
+                    let val = cursor.val().clone();
+                    cursor.map_times(|time, weight| {
+                        updates.push((key.clone(), val.clone(), time.clone(), weight.clone()))
+                    });
+                    cursor.step_val();
+                }
+                cursor.step_key();
+            }
+        }
+
+        encode_data(&(self.time.clone(), updates))
+    }
+
+    /// Rebuild the trace from the output of [`Self::encode_state`].
+    fn decode_state(&mut self, state: &[u8]) -> Result<(), Error> {
+        #[allow(clippy::type_complexity)]
+        let (time, updates): (T::Time, Vec<(T::Key, T::Val, T::Time, T::R)>) = decode_data(state)?;
+
+        // Group updates by timestamp and insert a batch per timestamp.
+        let mut batches = BTreeMap::<_, Vec<_>>::new();
+        for (key, val, ts, weight) in updates {
+            batches
+                .entry(ts)
+                .or_default()
+                .push((T::Batch::item_from(key, val), weight));
+        }
+
+        let mut trace = T::new(None);
+        for (ts, tuples) in batches {
+            trace.insert(T::Batch::from_tuples(ts, tuples));
+        }
+
+        self.time = time;
+        self.trace = Some(trace);
+        Ok(())
+    }
 }
 
 impl<T> Operator for Z1Trace<T>
@@ -394,6 +631,18 @@ where
         });
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        if self.is_initial() {
+            Ok(None)
+        } else {
+            self.encode_state().map(Some)
+        }
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.decode_state(state)
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         !self.dirty[scope as usize]
     }
@@ -426,9 +675,18 @@ where
         unimplemented!()
     }
 
-    fn eval_strict_owned(&mut self, i: T) {
+    fn eval_strict_owned(&mut self, mut i: T) {
         self.time = self.time.advance(0);
 
+        if let Some(bound) = self.bounds.effective_bound() {
+            // Discarding keys below the bound only saves space: readers of the
+            // trace must tolerate such keys anyway (see
+            // `Trace::truncate_keys_below`), so a failure doesn't affect the
+            // output of the circuit.  The bound is applied again at the next
+            // clock cycle.
+            let _ = i.truncate_keys_below(&bound);
+        }
+
         let dirty = i.dirty();
         self.trace = Some(i);
 
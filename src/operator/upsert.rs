@@ -1,15 +1,16 @@
 use crate::{
     algebra::{AddAssignByRef, HasOne, HasZero, PartialOrder, ZRingValue},
     circuit::{
+        checkpoint::{decode_data, encode_data},
         operator_traits::{BinaryOperator, Operator},
         ExportId, ExportStream, OwnershipPreference, Scope,
     },
-    operator::trace::{DelayedTraceId, TraceAppend, TraceId, Z1Trace},
+    operator::trace::{DelayedTraceId, TraceAppend, TraceBounds, TraceId, Z1Trace},
     trace::{
         consolidation::consolidate, cursor::Cursor, Batch, BatchReader, Builder, Spine, Trace,
     },
     utils::VecExt,
-    Circuit, DBData, DBTimestamp, Stream, Timestamp,
+    Circuit, DBData, DBTimestamp, Error, Stream, Timestamp,
 };
 use std::{borrow::Cow, marker::PhantomData, ops::Neg};
 
@@ -64,8 +65,9 @@ where
         //                    z1trace             └───────┘
         // ```
         circuit.region("upsert", || {
-            let (ExportStream { local, export }, z1feedback) =
-                circuit.add_feedback_with_export(Z1Trace::new(false, circuit.root_scope()));
+            let (ExportStream { local, export }, z1feedback) = circuit.add_feedback_with_export(
+                Z1Trace::new(false, circuit.root_scope(), TraceBounds::new()),
+            );
             local.mark_sharded_if(self);
 
             let delta = circuit.add_binary_operator(
@@ -134,6 +136,17 @@ where
     fn clock_end(&mut self, scope: Scope) {
         self.time = self.time.advance(scope + 1);
     }
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        if self.time == T::Time::clock_start() {
+            Ok(None)
+        } else {
+            encode_data(&self.time).map(Some)
+        }
+    }
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.time = decode_data(state)?;
+        Ok(())
+    }
     fn fixedpoint(&self, _scope: Scope) -> bool {
         true
     }
@@ -3,12 +3,13 @@
 use crate::{
     algebra::HasZero,
     circuit::{
+        checkpoint::{decode_data, encode_data, Checkpointable},
         metadata::{MetaItem, OperatorMeta},
         operator_traits::{Operator, StrictOperator, StrictUnaryOperator, UnaryOperator},
         Circuit, ExportId, ExportStream, FeedbackConnector, GlobalNodeId, OwnershipPreference,
         Scope, Stream,
     },
-    circuit_cache_key, NumEntries,
+    circuit_cache_key, Error, NumEntries,
 };
 use size_of::{Context, SizeOf};
 use std::{borrow::Cow, mem::replace};
@@ -30,7 +31,7 @@ pub struct DelayedFeedback<P, D> {
 impl<P, D> DelayedFeedback<P, D>
 where
     P: Clone + 'static,
-    D: Eq + SizeOf + NumEntries + Clone + HasZero + 'static,
+    D: Eq + SizeOf + NumEntries + Clone + HasZero + Checkpointable + 'static,
 {
     /// Create a feedback loop with `Z1` operator.  Use [`Self::connect`] to
     /// close the loop.
@@ -79,7 +80,7 @@ pub struct DelayedNestedFeedback<P, D> {
 impl<P, D> DelayedNestedFeedback<P, D>
 where
     P: Clone + 'static,
-    D: Eq + SizeOf + NumEntries + Clone + 'static,
+    D: Eq + SizeOf + NumEntries + Clone + Checkpointable + 'static,
 {
     /// Create a feedback loop with `Z1` operator.  Use [`Self::connect`] to
     /// close the loop.
@@ -111,7 +112,7 @@ impl<P, D> Stream<Circuit<P>, D> {
     pub fn delay(&self) -> Stream<Circuit<P>, D>
     where
         P: Clone + 'static,
-        D: Eq + SizeOf + NumEntries + Clone + HasZero + 'static,
+        D: Eq + SizeOf + NumEntries + Clone + HasZero + Checkpointable + 'static,
     {
         self.circuit()
             .cache_get_or_insert_with(DelayedId::new(self.origin_node_id().clone()), || {
@@ -124,7 +125,7 @@ impl<P, D> Stream<Circuit<P>, D> {
     pub fn delay_nested(&self) -> Stream<Circuit<P>, D>
     where
         P: Clone + 'static,
-        D: Eq + Clone + HasZero + SizeOf + NumEntries + 'static,
+        D: Eq + Clone + HasZero + SizeOf + NumEntries + Checkpointable + 'static,
     {
         self.circuit()
             .cache_get_or_insert_with(NestedDelayedId::new(self.origin_node_id().clone()), || {
@@ -177,7 +178,7 @@ where
 
 impl<T> Operator for Z1<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Checkpointable + 'static,
 {
     fn name(&self) -> Cow<'static, str> {
         Cow::from("Z^-1")
@@ -200,6 +201,19 @@ where
         });
     }
 
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        if self.values == self.zero {
+            Ok(None)
+        } else {
+            encode_data(&self.values).map(Some)
+        }
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.values = decode_data(state)?;
+        Ok(())
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         if scope == 0 {
             self.values.num_entries_shallow() == 0 && self.empty_output
@@ -211,7 +225,7 @@ where
 
 impl<T> UnaryOperator<T, T> for Z1<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Checkpointable + 'static,
 {
     fn eval(&mut self, i: &T) -> T {
         replace(&mut self.values, i.clone())
@@ -228,7 +242,7 @@ where
 
 impl<T> StrictOperator<T> for Z1<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Checkpointable + 'static,
 {
     fn get_output(&mut self) -> T {
         self.empty_output = self.values.num_entries_shallow() == 0;
@@ -242,7 +256,7 @@ where
 
 impl<T> StrictUnaryOperator<T, T> for Z1<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Checkpointable + 'static,
 {
     fn eval_strict(&mut self, i: &T) {
         self.values = i.clone();
@@ -314,7 +328,7 @@ impl<T> Z1Nested<T> {
 
 impl<T> Operator for Z1Nested<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Checkpointable + 'static,
 {
     fn name(&self) -> Cow<'static, str> {
         Cow::from("Z^-1 (nested)")
@@ -364,6 +378,22 @@ where
         });
     }
 
+    // Checkpoints are taken between clock cycles of the root circuit, when
+    // `timestamp` is always 0.
+    fn checkpoint(&self) -> Result<Option<Vec<u8>>, Error> {
+        if self.values.iter().all(|v| *v == self.zero) {
+            Ok(None)
+        } else {
+            encode_data(&self.values).map(Some)
+        }
+    }
+
+    fn restore(&mut self, state: &[u8]) -> Result<(), Error> {
+        self.values = decode_data(state)?;
+        self.timestamp = 0;
+        Ok(())
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         if scope == 0 {
             self.values
@@ -378,7 +408,7 @@ where
 
 impl<T> UnaryOperator<T, T> for Z1Nested<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Checkpointable + 'static,
 {
     fn eval(&mut self, i: &T) -> T {
         debug_assert!(self.timestamp <= self.values.len());
@@ -417,7 +447,7 @@ where
 
 impl<T> StrictOperator<T> for Z1Nested<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Checkpointable + 'static,
 {
     fn get_output(&mut self) -> T {
         if self.timestamp >= self.values.len() {
@@ -441,7 +471,7 @@ where
 
 impl<T> StrictUnaryOperator<T, T> for Z1Nested<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Checkpointable + 'static,
 {
     fn eval_strict(&mut self, i: &T) {
         debug_assert!(self.timestamp < self.values.len());
@@ -15,7 +15,7 @@ use std::{
 };
 
 /// Per-operator CPU profile.
-#[derive(Clone, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct OperatorCPUProfile {
     invocations: usize,
     total_time: Duration,
@@ -98,4 +98,17 @@ impl CPUProfiler {
             None
         }
     }
+
+    /// Returns CPU usage information of all circuit nodes whose activations
+    /// have been observed by the profiler.
+    pub fn operator_profiles(&self) -> Vec<(GlobalNodeId, OperatorCPUProfile)> {
+        if let Ok(this) = self.0.try_borrow() {
+            this.operators
+                .iter()
+                .map(|(node, profile)| (node.clone(), profile.clone()))
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
 }
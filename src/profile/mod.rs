@@ -12,7 +12,20 @@ use crate::{
 use std::{borrow::Cow, collections::HashMap, fmt::Write};
 
 mod cpu;
-pub use cpu::CPUProfiler;
+pub use cpu::{CPUProfiler, OperatorCPUProfile};
+
+/// CPU usage of a circuit node (operator or subcircuit) in one worker.
+#[derive(Clone, Debug)]
+pub struct OperatorProfile {
+    /// Global id of the node.
+    pub node_id: GlobalNodeId,
+
+    /// Node name.
+    pub name: Cow<'static, str>,
+
+    /// CPU usage of the node.
+    pub cpu: OperatorCPUProfile,
+}
 
 /// Rudimentary circuit profiler.
 ///
@@ -46,6 +59,29 @@ impl Profiler {
         self.cpu_profiler.attach(&self.circuit, "cpu_profiler");
     }
 
+    /// CPU usage of all nodes in the circuit observed by the CPU profiler.
+    ///
+    /// Returns an empty vector if CPU profiling is disabled.
+    pub fn cpu_profile(&self) -> Vec<OperatorProfile> {
+        let mut names = HashMap::<GlobalNodeId, Cow<'static, str>>::new();
+        self.circuit.map_nodes_recursive(&mut |node: &dyn Node| {
+            names.insert(node.global_id().clone(), node.name());
+        });
+
+        let mut profile = self
+            .cpu_profiler
+            .operator_profiles()
+            .into_iter()
+            .map(|(node_id, cpu)| OperatorProfile {
+                name: names.remove(&node_id).unwrap_or(Cow::Borrowed("")),
+                node_id,
+                cpu,
+            })
+            .collect::<Vec<_>>();
+        profile.sort_by(|p1, p2| p1.node_id.cmp(&p2.node_id));
+        profile
+    }
+
     /// Dump profile in graphviz format.
     pub fn dump_profile(&self) -> String {
         let mut metadata = HashMap::<GlobalNodeId, OperatorMeta>::new();
@@ -103,6 +103,10 @@ where
         self.val_valid = false;
     }
 
+    fn step_val_reverse(&mut self) {
+        self.val_valid = false;
+    }
+
     fn seek_val(&mut self, _val: &()) {}
 
     fn seek_val_with<P>(&mut self, predicate: P)
@@ -121,4 +125,8 @@ where
     fn rewind_vals(&mut self) {
         self.val_valid = true;
     }
+
+    fn fast_forward_vals(&mut self) {
+        self.val_valid = true;
+    }
 }
@@ -9,8 +9,9 @@ use std::marker::PhantomData;
 /// Provides a cursor interface over a list of cursors.
 ///
 /// The `CursorList` tracks the indices of cursors with the minimum key, and the
-/// the indices of cursors with the minimum key and minimum value. It performs
-/// no clever management of these sets otherwise.
+/// the indices of cursors with the minimum key and minimum value (or maximum
+/// value when iterating over values in reverse order). It performs no clever
+/// management of these sets otherwise.
 #[derive(Debug)]
 pub struct CursorList<'s, K, V, T, R, C: Cursor<'s, K, V, T, R>> {
     cursors: Vec<C>,
@@ -92,6 +93,30 @@ where
             }
         }
     }
+
+    // Initialize min_val with the indices of minimum key cursors with the maximum
+    // value.
+    //
+    // Used instead of `minimize_vals()` when iterating over values in reverse
+    // order.
+    fn maximize_vals(&mut self) {
+        self.min_val.clear();
+
+        // Determine the index of the cursor with maximum value.
+        let mut max_val: Option<&V> = None;
+        for &index in self.min_key.iter() {
+            let val = self.cursors[index].get_val();
+            if val.is_some() {
+                if max_val.is_none() || val.gt(&max_val) {
+                    max_val = val;
+                    self.min_val.clear();
+                }
+                if val.eq(&max_val) {
+                    self.min_val.push(index);
+                }
+            }
+        }
+    }
 }
 
 impl<'s, K, V, T, R, C: Cursor<'s, K, V, T, R>> Cursor<'s, K, V, T, R>
@@ -185,6 +210,13 @@ where
         self.minimize_vals();
     }
 
+    fn step_val_reverse(&mut self) {
+        for &index in self.min_val.iter() {
+            self.cursors[index].step_val_reverse();
+        }
+        self.maximize_vals();
+    }
+
     fn seek_val(&mut self, val: &V) {
         for &index in self.min_key.iter() {
             self.cursors[index].seek_val(val);
@@ -215,4 +247,11 @@ where
         }
         self.minimize_vals();
     }
+
+    fn fast_forward_vals(&mut self) {
+        for &index in self.min_key.iter() {
+            self.cursors[index].fast_forward_vals();
+        }
+        self.maximize_vals();
+    }
 }
@@ -17,7 +17,8 @@ pub struct CursorPair<C1, C2> {
     key_order: Ordering, /* Invalid keys are `Greater` than all other keys. `Equal` implies both
                           * valid. */
     val_order: Ordering, /* Invalid vals are `Greater` than all other vals. `Equal` implies both
-                          * valid. */
+                          * valid. When iterating over values in reverse order,
+                          * the order of valid vals is reversed. */
 }
 
 impl<'s, K, V, T, R, C1, C2> Cursor<'s, K, V, T, R> for CursorPair<C1, C2>
@@ -163,6 +164,25 @@ where
             Ordering::Greater => self.cursor2.step_val(),
         }
     }
+    fn step_val_reverse(&mut self) {
+        match self.key_order {
+            Ordering::Less => self.cursor1.step_val_reverse(),
+            Ordering::Equal => {
+                if self.val_order != Ordering::Greater {
+                    self.cursor1.step_val_reverse();
+                }
+                if self.val_order != Ordering::Less {
+                    self.cursor2.step_val_reverse();
+                }
+                self.val_order = match (self.cursor1.val_valid(), self.cursor2.val_valid()) {
+                    (false, _) => Ordering::Greater,
+                    (_, false) => Ordering::Less,
+                    (true, true) => self.cursor2.val().cmp(self.cursor1.val()),
+                };
+            }
+            Ordering::Greater => self.cursor2.step_val_reverse(),
+        }
+    }
     fn seek_val(&mut self, val: &V) {
         match self.key_order {
             Ordering::Less => self.cursor1.seek_val(val),
@@ -211,4 +231,19 @@ where
             self.cursor2.rewind_vals();
         }
     }
+    fn fast_forward_vals(&mut self) {
+        match self.key_order {
+            Ordering::Less => self.cursor1.fast_forward_vals(),
+            Ordering::Equal => {
+                self.cursor1.fast_forward_vals();
+                self.cursor2.fast_forward_vals();
+                self.val_order = match (self.cursor1.val_valid(), self.cursor2.val_valid()) {
+                    (false, _) => Ordering::Greater,
+                    (_, false) => Ordering::Less,
+                    (true, true) => self.cursor2.val().cmp(self.cursor1.val()),
+                };
+            }
+            Ordering::Greater => self.cursor2.fast_forward_vals(),
+        }
+    }
 }
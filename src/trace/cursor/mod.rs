@@ -119,6 +119,14 @@ pub trait Cursor<'s, K, V, T, R> {
     /// Advances the cursor to the next value.
     fn step_val(&mut self);
 
+    /// Moves the cursor to the previous value.
+    ///
+    /// The cursor becomes invalid (`val_valid()` returns `false`) after
+    /// stepping past the first value.  Used together with
+    /// [`fast_forward_vals`](`Self::fast_forward_vals`) to iterate over the
+    /// values of a key in descending order.
+    fn step_val_reverse(&mut self);
+
     /// Advances the cursor to the specified value.
     fn seek_val(&mut self, val: &V);
 
@@ -133,6 +141,9 @@ pub trait Cursor<'s, K, V, T, R> {
 
     /// Rewinds the cursor to the first value for current key.
     fn rewind_vals(&mut self);
+
+    /// Moves the cursor to the last value for current key.
+    fn fast_forward_vals(&mut self);
 }
 
 /// A cursor for traversing unordered values
@@ -96,6 +96,14 @@ where
         }
     }
 
+    fn step_reverse(&mut self) {
+        if self.pos > self.bounds.0 {
+            self.pos -= 1;
+        } else {
+            self.pos = self.bounds.1;
+        }
+    }
+
     fn seek<'a>(&mut self, key: Self::Key<'a>)
     where
         's: 'a,
@@ -124,6 +132,14 @@ where
         self.pos = self.bounds.0;
     }
 
+    fn fast_forward(&mut self) {
+        self.pos = if self.bounds.1 > self.bounds.0 {
+            self.bounds.1 - 1
+        } else {
+            self.bounds.1
+        };
+    }
+
     fn reposition(&mut self, lower: usize, upper: usize) {
         self.pos = lower;
         self.bounds = (lower, upper);
@@ -115,6 +115,14 @@ where
         }
     }
 
+    fn step_reverse(&mut self) {
+        if self.current > self.bounds.0 {
+            self.current -= 1;
+        } else {
+            self.current = self.bounds.1;
+        }
+    }
+
     fn seek<'a>(&mut self, (key, _): Self::Key<'a>)
     where
         's: 'a,
@@ -142,6 +150,14 @@ where
         self.current = self.bounds.0;
     }
 
+    fn fast_forward(&mut self) {
+        self.current = if self.bounds.1 > self.bounds.0 {
+            self.bounds.1 - 1
+        } else {
+            self.bounds.1
+        };
+    }
+
     fn reposition(&mut self, lower: usize, upper: usize) {
         self.current = lower;
         self.bounds = (lower, upper);
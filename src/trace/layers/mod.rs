@@ -176,6 +176,10 @@ pub trait Cursor<'s> {
     /// Advances the cursor by one element.
     fn step(&mut self);
 
+    /// Moves the cursor back by one element.  The cursor becomes invalid after
+    /// stepping past the first element.
+    fn step_reverse(&mut self);
+
     /// Advances the cursor until the location where `key` would be expected.
     // FIXME: Attempted to allow `key` to have an arbitrary lifetime but
     //        ran into some rather weird lifetime errors and gave up
@@ -193,6 +197,9 @@ pub trait Cursor<'s> {
     /// Rewinds the cursor to its initial state.
     fn rewind(&mut self);
 
+    /// Moves the cursor to the last element.
+    fn fast_forward(&mut self);
+
     /// Repositions the cursor to a different range of values.
     fn reposition(&mut self, lower: usize, upper: usize);
 }
@@ -313,6 +320,7 @@ impl<'s> Cursor<'s> for () {
     }
     fn values(&self) {}
     fn step(&mut self) {}
+    fn step_reverse(&mut self) {}
 
     fn seek<'a>(&mut self, _key: Self::Key<'a>)
     where
@@ -328,5 +336,6 @@ impl<'s> Cursor<'s> for () {
         false
     }
     fn rewind(&mut self) {}
+    fn fast_forward(&mut self) {}
     fn reposition(&mut self, _lower: usize, _upper: usize) {}
 }
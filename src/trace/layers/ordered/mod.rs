@@ -743,6 +743,18 @@ where
         }
     }
 
+    fn step_reverse(&mut self) {
+        if self.pos > self.bounds.0 {
+            self.pos -= 1;
+            self.child.reposition(
+                self.storage.offs[self.pos].into_usize(),
+                self.storage.offs[self.pos + 1].into_usize(),
+            );
+        } else {
+            self.pos = self.bounds.1;
+        }
+    }
+
     fn seek<'a>(&mut self, key: Self::Key<'a>)
     where
         's: 'a,
@@ -781,6 +793,18 @@ where
         }
     }
 
+    fn fast_forward(&mut self) {
+        if self.bounds.1 > self.bounds.0 {
+            self.pos = self.bounds.1 - 1;
+            self.child.reposition(
+                self.storage.offs[self.pos].into_usize(),
+                self.storage.offs[self.pos + 1].into_usize(),
+            );
+        } else {
+            self.pos = self.bounds.1;
+        }
+    }
+
     fn reposition(&mut self, lower: usize, upper: usize) {
         self.pos = lower;
         self.bounds = (lower, upper);
@@ -4,7 +4,7 @@ use crate::trace::{
     layers::{
         column_layer::ColumnLayerBuilder,
         ordered::{OrderedLayerConsumer, UnorderedBuilder},
-        Builder, TupleBuilder,
+        Builder, Cursor, Trie, TupleBuilder,
     },
     Consumer, ValueConsumer,
 };
@@ -71,6 +71,42 @@ fn remaining_values() {
     }
 }
 
+#[test]
+fn reverse_values() {
+    let mut builder = UnorderedBuilder::<usize, ColumnLayerBuilder<usize, isize>, usize>::new();
+    for key in 0..5 {
+        for val in 0..key {
+            builder.push_tuple((key, (val, 1)));
+        }
+    }
+    let layer = builder.done();
+
+    let mut cursor = layer.cursor();
+    while cursor.valid() {
+        let key = *cursor.key();
+
+        let mut vals = Vec::new();
+        cursor.child.fast_forward();
+        while cursor.child.valid() {
+            vals.push(*cursor.child.key().0);
+            cursor.child.step_reverse();
+        }
+        assert_eq!(vals, (0..key).rev().collect::<Vec<_>>());
+
+        cursor.step();
+    }
+
+    // Step back through keys.
+    let mut keys = Vec::new();
+    cursor.fast_forward();
+    while cursor.valid() {
+        keys.push(*cursor.key());
+        assert_eq!(cursor.child.keys(), *cursor.key());
+        cursor.step_reverse();
+    }
+    assert_eq!(keys, vec![4, 3, 2, 1]);
+}
+
 #[test]
 #[should_panic]
 fn next_empty_consumer() {
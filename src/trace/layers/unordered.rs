@@ -109,15 +109,27 @@ impl<'s, K, R> Cursor<'s> for UnorderedCursor<'s, K, R> {
     }
 
     fn key(&self) -> Self::Key<'s> {
-        todo!()
+        &self.leaf.keys()[self.current]
     }
 
     fn values(&self) -> <Self::ValueStorage as Trie>::Cursor<'s> {}
 
     fn step(&mut self) {
-        todo!()
+        if self.current < self.end {
+            self.current += 1;
+        }
     }
 
+    fn step_reverse(&mut self) {
+        if self.current > self.start {
+            self.current -= 1;
+        } else {
+            self.current = self.end;
+        }
+    }
+
+    // Keys in an unordered leaf are not sorted, so there's no way to seek to
+    // a key.
     fn seek<'a>(&mut self, _key: Self::Key<'a>)
     where
         's: 'a,
@@ -126,19 +138,33 @@ impl<'s, K, R> Cursor<'s> for UnorderedCursor<'s, K, R> {
     }
 
     fn last_key(&mut self) -> Option<Self::Key<'s>> {
-        todo!()
+        if self.end > self.start {
+            Some(&self.leaf.keys()[self.end - 1])
+        } else {
+            None
+        }
     }
 
     fn valid(&self) -> bool {
-        todo!()
+        self.current < self.end
     }
 
     fn rewind(&mut self) {
-        todo!()
+        self.current = self.start;
     }
 
-    fn reposition(&mut self, _lower: usize, _upper: usize) {
-        todo!()
+    fn fast_forward(&mut self) {
+        self.current = if self.end > self.start {
+            self.end - 1
+        } else {
+            self.end
+        };
+    }
+
+    fn reposition(&mut self, lower: usize, upper: usize) {
+        self.current = lower;
+        self.start = lower;
+        self.end = upper;
     }
 }
 
@@ -29,9 +29,9 @@ use crate::{
     algebra::{HasZero, MonoidValue},
     circuit::Activator,
     time::{AntichainRef, Timestamp},
-    NumEntries,
+    Error, NumEntries,
 };
-#[cfg(feature = "persistence")]
+#[cfg(feature = "checkpoint")]
 use bincode::{Decode, Encode};
 use size_of::SizeOf;
 use std::{fmt::Debug, hash::Hash};
@@ -43,22 +43,28 @@ use std::{fmt::Debug, hash::Hash};
 /// must be generic over any relational data, it is sufficient to impose
 /// `DBData` as a trait bound on types.  Conversely, a trait bound of the form
 /// `B: BatchReader` implies `B::Key: DBData` and `B::Val: DBData`.
-#[cfg(feature = "persistence")]
+///
+/// When the crate is compiled with the `checkpoint` feature (implied by
+/// `persistence`), `DBData` types must also be serializable with `bincode`,
+/// which is used to checkpoint the state of stateful operators (see
+/// [`DBSPHandle::checkpoint`](crate::DBSPHandle::checkpoint)) and to store
+/// data in persistent traces.
+#[cfg(feature = "checkpoint")]
 pub trait DBData:
     Clone + Eq + Ord + Hash + SizeOf + Send + Debug + Decode + Encode + 'static
 {
 }
 
-#[cfg(not(feature = "persistence"))]
+#[cfg(not(feature = "checkpoint"))]
 pub trait DBData: Clone + Eq + Ord + Hash + SizeOf + Send + Debug + 'static {}
 
-#[cfg(feature = "persistence")]
+#[cfg(feature = "checkpoint")]
 impl<T> DBData for T where
     T: Clone + Eq + Ord + Hash + SizeOf + Send + Debug + Decode + Encode + 'static
 {
 }
 
-#[cfg(not(feature = "persistence"))]
+#[cfg(not(feature = "checkpoint"))]
 impl<T> DBData for T where T: Clone + Eq + Ord + Hash + SizeOf + Send + Debug + 'static {}
 
 /// Trait for data types used as weights.
@@ -122,6 +128,20 @@ pub trait Trace: BatchReader {
     /// Merge all updates in a trace into a single batch.
     fn consolidate(self) -> Option<Self::Batch>;
 
+    /// Informs the trace that keys below `lower_bound` will no longer be
+    /// accessed and can be discarded.
+    ///
+    /// This allows operators that only need recent data, e.g., data above
+    /// the watermark of a time series, to bound the size of the trace.
+    /// Implementations are not required to discard such keys right away: the
+    /// [`Spine`] drops them lazily while merging batches, so cursors may
+    /// still observe keys below the bound.  Bounds only move forward: a
+    /// bound smaller than the current one is ignored.
+    ///
+    /// Fails if the trace cannot discard the keys, e.g., because of a storage
+    /// error.  The trace remains valid in this case.
+    fn truncate_keys_below(&mut self, lower_bound: &Self::Key) -> Result<(), Error>;
+
     /// Introduces a batch of updates to the trace.
     ///
     /// Batches describe the time intervals they contain, and they should be
@@ -257,7 +277,7 @@ where
     fn merge(&self, other: &Self) -> Self {
         let mut fuel = isize::max_value();
         let mut merger = Self::Merger::new_merger(self, other);
-        merger.work(self, other, &mut fuel);
+        merger.work(self, other, &None, &mut fuel);
         merger.done()
     }
 
@@ -354,7 +374,17 @@ where
     ///
     /// If `fuel` is non-zero after the call, the merging is complete and
     /// one should call `done` to extract the merged results.
-    fn work(&mut self, source1: &Output, source2: &Output, fuel: &mut isize);
+    ///
+    /// Keys below `lower_key_bound`, if specified, are dropped from the
+    /// result (see [`Trace::truncate_keys_below`]).  The bound may grow
+    /// between calls, but must not decrease.
+    fn work(
+        &mut self,
+        source1: &Output,
+        source2: &Output,
+        lower_key_bound: &Option<K>,
+        fuel: &mut isize,
+    );
 
     /// Extracts merged results.
     ///
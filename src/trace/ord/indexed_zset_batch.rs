@@ -1,8 +1,11 @@
+#[cfg(feature = "checkpoint")]
+use crate::circuit::checkpoint::{decode_batch, encode_batch};
 use crate::{
     algebra::{AddAssignByRef, AddByRef, MonoidValue, NegByRef},
     time::AntichainRef,
     trace::{
         layers::{
+            advance,
             column_layer::{ColumnLayer, ColumnLayerBuilder},
             ordered::{
                 OrderedBuilder, OrderedCursor, OrderedLayer, OrderedLayerConsumer,
@@ -16,6 +19,13 @@ use crate::{
     },
     DBData, DBWeight, NumEntries,
 };
+#[cfg(feature = "checkpoint")]
+use bincode::{
+    de::{BorrowDecoder, Decoder},
+    enc::Encoder,
+    error::{DecodeError, EncodeError},
+    BorrowDecode, Decode, Encode,
+};
 use size_of::SizeOf;
 use std::{
     cmp::max,
@@ -70,6 +80,45 @@ where
     }
 }
 
+#[cfg(feature = "checkpoint")]
+impl<K, V, R, O> Encode for OrdIndexedZSet<K, V, R, O>
+where
+    K: DBData,
+    V: DBData,
+    R: DBWeight,
+    O: OrdOffset,
+{
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        encode_batch(self, encoder)
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl<K, V, R, O> Decode for OrdIndexedZSet<K, V, R, O>
+where
+    K: DBData,
+    V: DBData,
+    R: DBWeight,
+    O: OrdOffset,
+{
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        decode_batch(decoder)
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl<'de, K, V, R, O> BorrowDecode<'de> for OrdIndexedZSet<K, V, R, O>
+where
+    K: DBData,
+    V: DBData,
+    R: DBWeight,
+    O: OrdOffset,
+{
+    fn borrow_decode<D: BorrowDecoder<'de>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        decode_batch(decoder)
+    }
+}
+
 impl<K, V, R, O> From<Layers<K, V, R, O>> for OrdIndexedZSet<K, V, R, O>
 where
     K: Ord,
@@ -218,7 +267,8 @@ where
     type Val = V;
     type Time = ();
     type R = R;
-    type Cursor<'s> = OrdIndexedZSetCursor<'s, K, V, R, O>
+    type Cursor<'s>
+        = OrdIndexedZSetCursor<'s, K, V, R, O>
     where
         V: 's,
         O: 's;
@@ -347,11 +397,21 @@ where
         &mut self,
         source1: &OrdIndexedZSet<K, V, R, O>,
         source2: &OrdIndexedZSet<K, V, R, O>,
+        lower_key_bound: &Option<K>,
         fuel: &mut isize,
     ) {
-        *fuel -= self
-            .result
-            .push_merge(source1.layer.cursor(), source2.layer.cursor()) as isize;
+        // Skip keys below `lower_key_bound`.
+        let (lower1, lower2) = match lower_key_bound {
+            Some(bound) => (
+                advance(&source1.layer.keys, |k| k < bound),
+                advance(&source2.layer.keys, |k| k < bound),
+            ),
+            None => (0, 0),
+        };
+        *fuel -= self.result.push_merge(
+            source1.layer.cursor_from(lower1, source1.layer.keys.len()),
+            source2.layer.cursor_from(lower2, source2.layer.keys.len()),
+        ) as isize;
         *fuel = max(*fuel, 1);
     }
 }
@@ -430,6 +490,10 @@ where
         self.cursor.child.step();
     }
 
+    fn step_val_reverse(&mut self) {
+        self.cursor.child.step_reverse();
+    }
+
     fn seek_val(&mut self, val: &V) {
         self.cursor.child.seek_key(val);
     }
@@ -448,6 +512,10 @@ where
     fn rewind_vals(&mut self) {
         self.cursor.child.rewind();
     }
+
+    fn fast_forward_vals(&mut self) {
+        self.cursor.child.fast_forward();
+    }
 }
 
 type IndexBuilder<K, V, R, O> = OrderedBuilder<K, ColumnLayerBuilder<V, R>, O>;
@@ -516,7 +584,8 @@ impl<K, V, R, O> Consumer<K, V, R, ()> for OrdIndexedZSetConsumer<K, V, R, O>
 where
     O: OrdOffset,
 {
-    type ValueConsumer<'a> = OrdIndexedZSetValueConsumer<'a, K, V,  R, O>
+    type ValueConsumer<'a>
+        = OrdIndexedZSetValueConsumer<'a, K, V, R, O>
     where
         Self: 'a;
 
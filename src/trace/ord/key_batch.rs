@@ -3,6 +3,7 @@ use crate::{
     time::{Antichain, AntichainRef},
     trace::{
         layers::{
+            advance,
             column_layer::{ColumnLayer, ColumnLayerBuilder},
             ordered::{
                 OrderedBuilder, OrderedCursor, OrderedLayer, OrderedLayerConsumer,
@@ -253,8 +254,15 @@ where
         &mut self,
         source1: &OrdKeyBatch<K, T, R, O>,
         source2: &OrdKeyBatch<K, T, R, O>,
+        lower_key_bound: &Option<K>,
         fuel: &mut isize,
     ) {
+        // Skip keys below the bound.
+        if let Some(bound) = lower_key_bound {
+            self.lower1 += advance(&source1.layer.keys[self.lower1..self.upper1], |k| k < bound);
+            self.lower2 += advance(&source2.layer.keys[self.lower2..self.upper2], |k| k < bound);
+        }
+
         let starting_updates = self.result.vals.len();
         let mut effort = 0isize;
 
@@ -414,6 +422,10 @@ where
         self.valid = false;
     }
 
+    fn step_val_reverse(&mut self) {
+        self.valid = false;
+    }
+
     fn seek_val(&mut self, _val: &()) {}
     fn seek_val_with<P>(&mut self, predicate: P)
     where
@@ -431,6 +443,10 @@ where
     fn rewind_vals(&mut self) {
         self.valid = true;
     }
+
+    fn fast_forward_vals(&mut self) {
+        self.valid = true;
+    }
 }
 
 type RawOrdKeyBuilder<K, T, R, O> = OrderedBuilder<K, ColumnLayerBuilder<T, R>, O>;
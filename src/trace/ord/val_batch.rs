@@ -3,6 +3,7 @@ use crate::{
     time::{Antichain, AntichainRef},
     trace::{
         layers::{
+            advance,
             column_layer::{ColumnLayer, ColumnLayerBuilder},
             ordered::{OrderedBuilder, OrderedCursor, OrderedLayer},
             Builder as TrieBuilder, Cursor as TrieCursor, MergeBuilder, OrdOffset, Trie,
@@ -327,8 +328,15 @@ where
         &mut self,
         source1: &OrdValBatch<K, V, T, R, O>,
         source2: &OrdValBatch<K, V, T, R, O>,
+        lower_key_bound: &Option<K>,
         fuel: &mut isize,
     ) {
+        // Skip keys below the bound.
+        if let Some(bound) = lower_key_bound {
+            self.lower1 += advance(&source1.layer.keys[self.lower1..self.upper1], |k| k < bound);
+            self.lower2 += advance(&source2.layer.keys[self.lower2..self.upper2], |k| k < bound);
+        }
+
         let starting_updates = self.result.vals.vals.len();
         let mut effort = 0isize;
 
@@ -475,6 +483,10 @@ where
     fn step_val(&mut self) {
         self.cursor.child.step();
     }
+
+    fn step_val_reverse(&mut self) {
+        self.cursor.child.step_reverse();
+    }
     fn seek_val(&mut self, val: &V) {
         self.cursor.child.seek(val);
     }
@@ -490,6 +502,10 @@ where
     fn rewind_vals(&mut self) {
         self.cursor.child.rewind();
     }
+
+    fn fast_forward_vals(&mut self) {
+        self.cursor.child.fast_forward();
+    }
 }
 
 type RawOrdValBuilder<K, V, T, R, O> =
@@ -1,8 +1,11 @@
+#[cfg(feature = "checkpoint")]
+use crate::circuit::checkpoint::{decode_batch, encode_batch};
 use crate::{
     algebra::{AddAssignByRef, AddByRef, MonoidValue, NegByRef},
     time::AntichainRef,
     trace::{
         layers::{
+            advance,
             column_layer::{
                 ColumnLayer, ColumnLayerBuilder, ColumnLayerConsumer, ColumnLayerCursor,
                 ColumnLayerValues,
@@ -14,6 +17,13 @@ use crate::{
     },
     DBData, DBWeight, NumEntries,
 };
+#[cfg(feature = "checkpoint")]
+use bincode::{
+    de::{BorrowDecoder, Decoder},
+    enc::Encoder,
+    error::{DecodeError, EncodeError},
+    BorrowDecode, Decode, Encode,
+};
 use size_of::SizeOf;
 use std::{
     cmp::max,
@@ -60,6 +70,39 @@ where
     }
 }
 
+#[cfg(feature = "checkpoint")]
+impl<K, R> Encode for OrdZSet<K, R>
+where
+    K: DBData,
+    R: DBWeight,
+{
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        encode_batch(self, encoder)
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl<K, R> Decode for OrdZSet<K, R>
+where
+    K: DBData,
+    R: DBWeight,
+{
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        decode_batch(decoder)
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl<'de, K, R> BorrowDecode<'de> for OrdZSet<K, R>
+where
+    K: DBData,
+    R: DBWeight,
+{
+    fn borrow_decode<D: BorrowDecoder<'de>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        decode_batch(decoder)
+    }
+}
+
 impl<K, R> From<ColumnLayer<K, R>> for OrdZSet<K, R> {
     fn from(layer: ColumnLayer<K, R>) -> Self {
         Self { layer }
@@ -280,10 +323,25 @@ where
         }
     }
 
-    fn work(&mut self, source1: &OrdZSet<K, R>, source2: &OrdZSet<K, R>, fuel: &mut isize) {
-        *fuel -= self
-            .result
-            .push_merge(source1.layer.cursor(), source2.layer.cursor()) as isize;
+    fn work(
+        &mut self,
+        source1: &OrdZSet<K, R>,
+        source2: &OrdZSet<K, R>,
+        lower_key_bound: &Option<K>,
+        fuel: &mut isize,
+    ) {
+        // Skip keys below `lower_key_bound`.
+        let (lower1, lower2) = match lower_key_bound {
+            Some(bound) => (
+                advance(source1.layer.keys(), |k| k < bound),
+                advance(source2.layer.keys(), |k| k < bound),
+            ),
+            None => (0, 0),
+        };
+        *fuel -= self.result.push_merge(
+            source1.layer.cursor_from(lower1, source1.layer.len()),
+            source2.layer.cursor_from(lower2, source2.layer.len()),
+        ) as isize;
         *fuel = max(*fuel, 1);
     }
 }
@@ -361,6 +419,10 @@ where
         self.valid = false;
     }
 
+    fn step_val_reverse(&mut self) {
+        self.valid = false;
+    }
+
     fn seek_val(&mut self, _val: &()) {}
 
     fn seek_val_with<P>(&mut self, predicate: P)
@@ -380,6 +442,10 @@ where
     fn rewind_vals(&mut self) {
         self.valid = true;
     }
+
+    fn fast_forward_vals(&mut self) {
+        self.valid = true;
+    }
 }
 
 /// A builder for creating layers from unsorted update tuples.
@@ -436,7 +502,8 @@ pub struct OrdZSetConsumer<K, R> {
 }
 
 impl<K, R> Consumer<K, (), R, ()> for OrdZSetConsumer<K, R> {
-    type ValueConsumer<'a> = OrdZSetValueConsumer<'a, K, R>
+    type ValueConsumer<'a>
+        = OrdZSetValueConsumer<'a, K, R>
     where
         Self: 'a;
 
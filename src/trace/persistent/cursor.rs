@@ -265,6 +265,14 @@ impl<'s, B: Batch> Cursor<'s, B::Key, B::Val, B::Time, B::R> for PersistentTrace
         self.val_idx += 1;
     }
 
+    fn step_val_reverse(&mut self) {
+        // Move past the end of `cur_vals` after stepping past the first value.
+        self.val_idx = match self.val_idx {
+            0 => self.cur_vals.as_ref().map_or(0, |vals| vals.len()),
+            idx => idx - 1,
+        };
+    }
+
     fn seek_val(&mut self, val: &B::Val) {
         while self.val_valid() && self.val() < val {
             self.step_val();
@@ -294,4 +302,11 @@ impl<'s, B: Batch> Cursor<'s, B::Key, B::Val, B::Time, B::R> for PersistentTrace
     fn rewind_vals(&mut self) {
         self.val_idx = 0;
     }
+
+    fn fast_forward_vals(&mut self) {
+        self.val_idx = self
+            .cur_vals
+            .as_ref()
+            .map_or(0, |vals| vals.len().saturating_sub(1));
+    }
 }
@@ -20,7 +20,7 @@ use crate::trace::{
     AntichainRef, Batch, BatchReader, Builder, Consumer, DBData, DBTimestamp, DBWeight, HasZero,
     Trace, ValueConsumer,
 };
-use crate::NumEntries;
+use crate::{Error, NumEntries};
 
 /// A persistent trace implementation.
 ///
@@ -629,6 +629,30 @@ where
         Some(builder.done())
     }
 
+    fn truncate_keys_below(&mut self, lower_bound: &Self::Key) -> Result<(), Error> {
+        // Unlike the `Spine`, which truncates lazily during merges, we delete
+        // keys right away and let RocksDB reclaim the space during compaction.
+        // All deletions are applied in a single write, so the trace is left
+        // unmodified on error.
+        let mut tmp_key = ReusableEncodeBuffer::default();
+
+        let mut deletions = WriteBatch::default();
+        let mut cursor = self.cursor();
+        while cursor.key_valid() && cursor.key() < lower_bound {
+            let key = cursor.key();
+            let encoded_key = tmp_key
+                .encode(&key)
+                .map_err(|e| Error::Custom(format!("failed to encode trace key: {e}")))?;
+
+            deletions.delete_cf(&self.cf, encoded_key);
+            cursor.step_key();
+        }
+
+        ROCKS_DB_INSTANCE
+            .write(deletions)
+            .map_err(|e| Error::Custom(format!("failed to truncate persistent trace: {e}")))
+    }
+
     fn insert(&mut self, batch: Self::Batch) {
         assert!(batch.lower() != batch.upper());
 
@@ -147,6 +147,10 @@ where
         self.cursor.step_val()
     }
 
+    fn step_val_reverse(&mut self) {
+        self.cursor.step_val_reverse()
+    }
+
     fn seek_val(&mut self, val: &B::Val) {
         self.cursor.seek_val(val)
     }
@@ -165,6 +169,10 @@ where
     fn rewind_vals(&mut self) {
         self.cursor.rewind_vals()
     }
+
+    fn fast_forward_vals(&mut self) {
+        self.cursor.fast_forward_vals()
+    }
 }
 
 pub struct RcBatchConsumer<B>
@@ -382,8 +390,14 @@ where
         }
     }
 
-    fn work(&mut self, source1: &Rc<B>, source2: &Rc<B>, fuel: &mut isize) {
-        self.merger.work(source1, source2, fuel)
+    fn work(
+        &mut self,
+        source1: &Rc<B>,
+        source2: &Rc<B>,
+        lower_key_bound: &Option<B::Key>,
+        fuel: &mut isize,
+    ) {
+        self.merger.work(source1, source2, lower_key_bound, fuel)
     }
 
     fn done(self) -> Rc<B> {
@@ -87,7 +87,7 @@ use crate::{
         rc_batch::RcBatchCursor,
         Batch, BatchReader, Consumer, Merger, Trace, ValueConsumer,
     },
-    NumEntries,
+    Error, NumEntries,
 };
 use size_of::SizeOf;
 use std::{
@@ -115,6 +115,9 @@ where
     effort: usize,
     activator: Option<Activator>,
     dirty: bool,
+    /// Keys below this bound are discarded during merges (see
+    /// [`Trace::truncate_keys_below`]).
+    lower_key_bound: Option<B::Key>,
 }
 
 impl<B> Display for Spine<B>
@@ -371,6 +374,10 @@ where
         self.cursor.step_val();
     }
 
+    fn step_val_reverse(&mut self) {
+        self.cursor.step_val_reverse();
+    }
+
     fn seek_val(&mut self, val: &B::Val) {
         self.cursor.seek_val(val);
     }
@@ -389,6 +396,10 @@ where
     fn rewind_vals(&mut self) {
         self.cursor.rewind_vals();
     }
+
+    fn fast_forward_vals(&mut self) {
+        self.cursor.fast_forward_vals();
+    }
 }
 
 pub struct SpineConsumer<B>
@@ -510,7 +521,9 @@ where
         while !self.reduced() {
             self.exert(&mut fuel);
         }
-        // Return the sole remaining batch (if one exists).
+        // Return the sole remaining batch (if one exists).  Note that this
+        // batch may still contain keys below `lower_key_bound` if it has not
+        // been merged since the bound was set.
         for merging in self.merging.into_iter() {
             if let MergeState::Single(Some(batch)) = merging {
                 if !batch.is_empty() {
@@ -530,6 +543,13 @@ where
         None
     }
 
+    fn truncate_keys_below(&mut self, lower_bound: &Self::Key) -> Result<(), Error> {
+        if !matches!(&self.lower_key_bound, Some(bound) if bound >= lower_bound) {
+            self.lower_key_bound = Some(lower_bound.clone());
+        }
+        Ok(())
+    }
+
     // Ideally, this method acts as insertion of `batch`, even if we are not yet
     // able to begin merging the batch. This means it is a good time to perform
     // amortized work proportional to the size of batch.
@@ -630,6 +650,7 @@ where
             effort,
             activator,
             dirty: false,
+            lower_key_bound: None,
         }
     }
 
@@ -770,7 +791,7 @@ where
             // Give each level independent fuel, for now.
             let mut fuel = *fuel;
             // Pass along various logging stuffs, in case we need to report success.
-            self.merging[index].work(&mut fuel);
+            self.merging[index].work(&self.lower_key_bound, &mut fuel);
             // `fuel` could have a deficit at this point, meaning we over-spent when
             // we took a merge step. We could ignore this, or maintain the deficit
             // and account future fuel against it before spending again. It isn't
@@ -815,7 +836,7 @@ where
 
     /// Completes and extracts what ever is at layer `index`.
     fn complete_at(&mut self, index: usize) -> Option<Rc<B>> {
-        self.merging[index].complete()
+        self.merging[index].complete(&self.lower_key_bound)
     }
 
     /// Attempts to draw down large layers to size appropriate layers.
@@ -885,7 +906,7 @@ where
         for merge_state in self.merging.iter_mut() {
             if merge_state.is_inprogress() {
                 let mut fuel = isize::max_value();
-                merge_state.work(&mut fuel);
+                merge_state.work(&self.lower_key_bound, &mut fuel);
             }
         }
         assert!(self.merging.iter().all(|m| !m.is_inprogress()));
@@ -968,11 +989,11 @@ where
     /// which should be done with the `is_complete()` method.
     ///
     /// There is the additional option of input batches.
-    fn complete(&mut self) -> Option<B> {
+    fn complete(&mut self, lower_key_bound: &Option<B::Key>) -> Option<B> {
         match replace(self, MergeState::Vacant) {
             MergeState::Vacant => None,
             MergeState::Single(batch) => batch,
-            MergeState::Double(variant) => variant.complete(),
+            MergeState::Double(variant) => variant.complete(lower_key_bound),
         }
     }
 
@@ -991,10 +1012,10 @@ where
     /// If the merge completes, the resulting batch is returned.
     /// If a batch is returned, it is the obligation of the caller
     /// to correctly install the result.
-    fn work(&mut self, fuel: &mut isize) {
+    fn work(&mut self, lower_key_bound: &Option<B::Key>, fuel: &mut isize) {
         // We only perform work for merges in progress.
         if let MergeState::Double(layer) = self {
-            layer.work(fuel)
+            layer.work(lower_key_bound, fuel)
         }
     }
 
@@ -1065,9 +1086,9 @@ where
     ///
     /// The result is either `None`, for structurally empty batches,
     /// or a batch and optionally input batches from which it derived.
-    fn complete(mut self) -> Option<B> {
+    fn complete(mut self, lower_key_bound: &Option<B::Key>) -> Option<B> {
         let mut fuel = isize::max_value();
-        self.work(&mut fuel);
+        self.work(lower_key_bound, &mut fuel);
         if let MergeVariant::Complete(batch) = self {
             batch
         } else {
@@ -1079,10 +1100,10 @@ where
     ///
     /// In case the work completes, the source batches are returned.
     /// This allows the caller to manage the released resources.
-    fn work(&mut self, fuel: &mut isize) {
+    fn work(&mut self, lower_key_bound: &Option<B::Key>, fuel: &mut isize) {
         let variant = replace(self, MergeVariant::Complete(None));
         if let MergeVariant::InProgress(b1, b2, mut merge) = variant {
-            merge.work(&b1, &b2, fuel);
+            merge.work(&b1, &b2, lower_key_bound, fuel);
             if *fuel > 0 {
                 *self = MergeVariant::Complete(Some(merge.done()));
             } else {